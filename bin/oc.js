@@ -196,7 +196,7 @@ folderCmd
   .description('Create a folder')
   .action(
     handle((pathArg, options) => {
-      const result = store.createFolder({ path: pathArg, description: options.desc || '' });
+      const result = store.createFolder({ path: pathArg, description: options.desc || '', source: 'cli' });
       console.log(`Folder ready at "${result.rel_path}".`);
     })
   );
@@ -208,7 +208,7 @@ folderCmd
   .description('Rename a folder')
   .action(
     handle((oldPath, newName) => {
-      const result = store.renameFolder({ path: oldPath, newName });
+      const result = store.renameFolder({ path: oldPath, newName, source: 'cli' });
       console.log(`Renamed folder "${result.old_path}" → "${result.new_path}".`);
     })
   );
@@ -220,7 +220,7 @@ folderCmd
   .description('Remove a folder')
   .action(
     handle((pathArg, options) => {
-      const result = store.removeFolder({ path: pathArg, force: Boolean(options.force) });
+      const result = store.removeFolder({ path: pathArg, force: Boolean(options.force), source: 'cli' });
       console.log(`Removed folder "${result.removed}".`);
     })
   );
@@ -254,7 +254,7 @@ docCmd
   .description('Create a new document')
   .action(
     handle((folderPath, name, options) => {
-      const result = store.createDoc({ folderPath, name, description: options.desc || '' });
+      const result = store.createDoc({ folderPath, name, description: options.desc || '', source: 'cli' });
       console.log(`Created doc "${result.rel_path}".`);
       if (options.open) {
         openInEditor(result.abs_path);
@@ -269,7 +269,7 @@ docCmd
   .description('Move a document to another folder')
   .action(
     handle((docPath, destFolderPath) => {
-      const result = store.moveDoc({ docPath, destFolderPath });
+      const result = store.moveDoc({ docPath, destFolderPath, source: 'cli' });
       console.log(`Moved doc "${result.old_path}" → "${result.new_path}".`);
     })
   );
@@ -281,7 +281,7 @@ docCmd
   .description('Rename a document')
   .action(
     handle((docPath, newName) => {
-      const result = store.renameDoc({ docPath, newName });
+      const result = store.renameDoc({ docPath, newName, source: 'cli' });
       console.log(`Renamed doc "${result.old_path}" → "${result.new_path}".`);
     })
   );
@@ -292,7 +292,7 @@ docCmd
   .description('Delete a document')
   .action(
     handle((docPath) => {
-      const result = store.removeDoc({ docPath });
+      const result = store.removeDoc({ docPath, source: 'cli' });
       console.log(`Deleted doc "${result.removed}".`);
     })
   );
@@ -304,7 +304,7 @@ docCmd
   .description('Update a document description')
   .action(
     handle((docPath, desc) => {
-      const result = store.setDocDescription({ docPath, description: desc });
+      const result = store.setDocDescription({ docPath, description: desc, source: 'cli' });
       console.log(`Updated description for "${result.rel_path}".`);
     })
   );
@@ -0,0 +1,194 @@
+//! Read tracking and pinned documents.
+//!
+//! Every `get_doc_content*` call logs a row in `doc_access` tagged with the
+//! reading client (desktop, cli, mcp, ...); pins live in `doc_pins`. Both are
+//! keyed by the doc row id, so they follow a doc through renames and moves.
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use serde::Serialize;
+
+use crate::{
+    doc_not_found, folder_not_found, manifest, normalize_doc_path, normalize_folder_path, now_iso,
+    row_to_doc, CoreError, CoreResult, Doc, OpenContext,
+};
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// `ORDER BY` term placing pinned docs first in a query over `docs`
+pub(crate) const PINNED_FIRST: &str =
+    "EXISTS (SELECT 1 FROM doc_pins p WHERE p.doc_id = docs.id) DESC, ";
+
+/// Optional parameters for `recent_docs` and `frequent_docs`
+#[derive(Debug, Clone, Default)]
+pub struct DocAccessOptions {
+    /// Maximum number of docs (default 20)
+    pub limit: Option<usize>,
+    /// Only count reads from this source
+    pub source: Option<String>,
+    /// Only count reads at or after this RFC 3339 timestamp or `YYYY-MM-DD`
+    /// date
+    pub since: Option<String>,
+    /// Only include docs under this folder
+    pub folder_path: Option<String>,
+}
+
+/// A document with its read statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessedDoc {
+    #[serde(flatten)]
+    pub doc: Doc,
+    /// Reads matching the query options
+    pub access_count: i64,
+    pub last_accessed_at: Option<String>,
+    pub pinned: bool,
+}
+
+impl OpenContext {
+    /// Record a read of a document by `source`, for clients that display
+    /// docs without going through `get_doc_content`.
+    pub fn record_doc_access(&self, doc_path: &str, source: Option<&str>) -> CoreResult<()> {
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        self.with_conn(|conn| record_access(conn, doc.id, source))
+    }
+
+    /// Docs by most recent read.
+    pub fn recent_docs(&self, options: &DocAccessOptions) -> CoreResult<Vec<AccessedDoc>> {
+        self.accessed_docs(options, "MAX(a.id) DESC")
+    }
+
+    /// Docs by number of reads, most read first.
+    pub fn frequent_docs(&self, options: &DocAccessOptions) -> CoreResult<Vec<AccessedDoc>> {
+        self.accessed_docs(options, "COUNT(a.id) DESC, MAX(a.id) DESC")
+    }
+
+    /// Pin a document. Returns false if it was already pinned.
+    pub fn pin_doc(&self, doc_path: &str) -> CoreResult<bool> {
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        self.with_conn(|conn| {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO doc_pins (doc_id, pinned_at) VALUES (?1, ?2)",
+                params![doc.id, now_iso()],
+            )?;
+            Ok(inserted > 0)
+        })
+    }
+
+    /// Unpin a document. Returns false if it was not pinned.
+    pub fn unpin_doc(&self, doc_path: &str) -> CoreResult<bool> {
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        self.with_conn(|conn| {
+            let removed = conn.execute("DELETE FROM doc_pins WHERE doc_id = ?1", [doc.id])?;
+            Ok(removed > 0)
+        })
+    }
+
+    /// Pinned docs in the order they were pinned, with their total reads.
+    pub fn pinned_docs(&self) -> CoreResult<Vec<AccessedDoc>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT d.id, d.folder_id, d.name, d.rel_path, d.abs_path, d.description, d.stable_id, d.created_at, d.updated_at,
+                        (SELECT COUNT(*) FROM doc_access a WHERE a.doc_id = d.id),
+                        (SELECT MAX(accessed_at) FROM doc_access a WHERE a.doc_id = d.id),
+                        1
+                 FROM doc_pins p JOIN docs d ON d.id = p.doc_id
+                 ORDER BY p.id",
+            )?;
+            let docs = stmt
+                .query_map([], accessed_doc_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(docs)
+        })
+    }
+
+    fn accessed_docs(
+        &self,
+        options: &DocAccessOptions,
+        order_by: &str,
+    ) -> CoreResult<Vec<AccessedDoc>> {
+        let limit = match options.limit {
+            Some(0) => {
+                return Err(CoreError::Message(
+                    "limit must be a positive integer".into(),
+                ))
+            }
+            Some(limit) => limit,
+            None => DEFAULT_LIMIT,
+        };
+        let prefix = match options.folder_path.as_deref() {
+            Some(path) => {
+                let rel_path = normalize_folder_path(Some(path))?;
+                self.find_folder(&rel_path)?
+                    .ok_or_else(|| folder_not_found(&rel_path))?;
+                if rel_path.is_empty() {
+                    String::new()
+                } else {
+                    format!("{rel_path}/")
+                }
+            }
+            None => String::new(),
+        };
+        let mut sql = String::from(
+            "SELECT d.id, d.folder_id, d.name, d.rel_path, d.abs_path, d.description, d.stable_id, d.created_at, d.updated_at,
+                    COUNT(a.id), MAX(a.accessed_at),
+                    EXISTS (SELECT 1 FROM doc_pins p WHERE p.doc_id = d.id)
+             FROM doc_access a JOIN docs d ON d.id = a.doc_id
+             WHERE substr(d.rel_path, 1, length(?1)) = ?1",
+        );
+        let mut values = vec![Value::Text(prefix)];
+        if let Some(source) = options.source.as_deref().and_then(normalize_source) {
+            sql.push_str(" AND a.source = ?");
+            values.push(Value::Text(source));
+        }
+        if let Some(since) = options.since.as_deref() {
+            sql.push_str(" AND a.accessed_at >= ?");
+            values.push(Value::Text(manifest::normalize_since("since", since)?));
+        }
+        sql.push_str(&format!(
+            " GROUP BY d.id ORDER BY {order_by}, d.rel_path LIMIT ?"
+        ));
+        values.push(Value::Integer(limit as i64));
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let docs = stmt
+                .query_map(params_from_iter(values), accessed_doc_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(docs)
+        })
+    }
+}
+
+/// Log one read of `doc_id`.
+pub(crate) fn record_access(
+    conn: &Connection,
+    doc_id: i64,
+    source: Option<&str>,
+) -> CoreResult<()> {
+    conn.execute(
+        "INSERT INTO doc_access (doc_id, source, accessed_at) VALUES (?1, ?2, ?3)",
+        params![doc_id, source.and_then(normalize_source), now_iso()],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn normalize_source(source: &str) -> Option<String> {
+    let source = source.trim().to_lowercase();
+    (!source.is_empty()).then_some(source)
+}
+
+fn accessed_doc_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AccessedDoc> {
+    Ok(AccessedDoc {
+        doc: row_to_doc(row)?,
+        access_count: row.get(9)?,
+        last_accessed_at: row.get(10)?,
+        pinned: row.get(11)?,
+    })
+}
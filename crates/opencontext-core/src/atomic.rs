@@ -0,0 +1,177 @@
+//! Crash-safe filesystem helpers for catalog mutations.
+//!
+//! Mutations that touch both the contexts directory and SQLite run the
+//! catalog updates and the filesystem renames under one transaction:
+//!
+//! 1. apply the SQLite updates inside an open transaction,
+//! 2. perform the renames, recording each one in an [`FsJournal`],
+//! 3. commit.
+//!
+//! A failure in step 1 rolls back the transaction before anything on disk
+//! changed. A failure in step 2 or 3 undoes the recorded renames in reverse
+//! order and drops the transaction. Callers emit events only after
+//! `commit_with_renames` returns `Ok`.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use rusqlite::{Connection, Transaction};
+
+use crate::{CoreError, CoreResult};
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write `content` to `path` by writing a sibling temp file, syncing it and
+/// renaming it over the target. Readers see either the old or the new
+/// content, never a partial write.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> CoreResult<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| CoreError::Message(format!("Invalid path \"{}\".", path.display())))?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp = dir.join(format!(
+        ".{name}.tmp-{}-{}",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fail_point("fs.write")?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Filesystem changes made so far, undone in reverse order on rollback.
+#[derive(Debug, Default)]
+pub(crate) struct FsJournal {
+    renames: Vec<(PathBuf, PathBuf)>,
+    created_dirs: Vec<PathBuf>,
+}
+
+impl FsJournal {
+    /// Rename `from` to `to`, creating missing parent directories of `to`.
+    pub(crate) fn rename(&mut self, from: &Path, to: &Path) -> CoreResult<()> {
+        if let Some(parent) = to.parent() {
+            self.create_dir_all(parent)?;
+        }
+        fail_point("fs.rename")?;
+        fs::rename(from, to)?;
+        self.renames.push((from.to_path_buf(), to.to_path_buf()));
+        Ok(())
+    }
+
+    fn create_dir_all(&mut self, dir: &Path) -> CoreResult<()> {
+        let mut missing = Vec::new();
+        let mut current = Some(dir);
+        while let Some(path) = current {
+            if path.exists() {
+                break;
+            }
+            missing.push(path.to_path_buf());
+            current = path.parent();
+        }
+        fs::create_dir_all(dir)?;
+        // Outermost first, so rollback removes the innermost first.
+        self.created_dirs.extend(missing.into_iter().rev());
+        Ok(())
+    }
+
+    /// Best-effort undo of every recorded change.
+    pub(crate) fn rollback(self) {
+        for (from, to) in self.renames.into_iter().rev() {
+            let _ = fs::rename(&to, &from);
+        }
+        for dir in self.created_dirs.into_iter().rev() {
+            let _ = fs::remove_dir(&dir);
+        }
+    }
+}
+
+/// Apply `update` and the filesystem `renames` as one unit.
+///
+/// `update` runs inside a transaction first; the renames happen only once
+/// it succeeds, and the transaction is committed only once every rename
+/// succeeded. Any failure leaves both disk and catalog as they were.
+pub(crate) fn commit_with_renames<T, F>(
+    conn: &Connection,
+    renames: &[(PathBuf, PathBuf)],
+    update: F,
+) -> CoreResult<T>
+where
+    F: FnOnce(&Transaction<'_>) -> CoreResult<T>,
+{
+    let tx = conn.unchecked_transaction()?;
+    let value = update(&tx)?;
+    fail_point("db.update")?;
+
+    let mut journal = FsJournal::default();
+    for (from, to) in renames {
+        if let Err(err) = journal.rename(from, to) {
+            journal.rollback();
+            return Err(err);
+        }
+    }
+
+    let committed = fail_point("db.commit").and_then(|_| Ok(tx.commit()?));
+    if let Err(err) = committed {
+        journal.rollback();
+        return Err(err);
+    }
+    Ok(value)
+}
+
+#[cfg(not(test))]
+#[inline]
+fn fail_point(_name: &str) -> CoreResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+thread_local! {
+    static FAIL_AT: std::cell::RefCell<Option<&'static str>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Fail with an injected error when the test armed `name`.
+#[cfg(test)]
+fn fail_point(name: &str) -> CoreResult<()> {
+    FAIL_AT.with(|f| {
+        if f.borrow().as_deref() == Some(name) {
+            Err(CoreError::Io(std::io::Error::other(format!(
+                "injected failure at {name}"
+            ))))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Arm the fail point `name` on this thread until the guard is dropped.
+#[cfg(test)]
+pub(crate) fn fail_at(name: &'static str) -> FailGuard {
+    FAIL_AT.with(|f| *f.borrow_mut() = Some(name));
+    FailGuard
+}
+
+#[cfg(test)]
+pub(crate) struct FailGuard;
+
+#[cfg(test)]
+impl Drop for FailGuard {
+    fn drop(&mut self) {
+        FAIL_AT.with(|f| *f.borrow_mut() = None);
+    }
+}
@@ -0,0 +1,436 @@
+//! Images and other files attached to documents.
+//!
+//! Attachments are stored under `<contexts_root>/.assets/<attachment_id>/`
+//! and catalogued in `attachments`. Docs embed them with ordinary relative
+//! markdown links (`![diagram](../.assets/<id>/diagram.png)`); the targets
+//! of those links are extracted on every save into `attachment_refs`, keyed
+//! by the stable id of the linking doc like `doc_links`, so trashed docs
+//! keep their attachments alive until the trash is purged.
+//!
+//! Moving a doc or folder changes its depth below `contexts_root`, so the
+//! relative links of the moved docs are rewritten to keep resolving.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use regex::{Captures, Regex};
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+
+use crate::{
+    doc_not_found, generate_stable_id, manifest, normalize_doc_path, now_iso, CoreError,
+    CoreResult, Doc, OpenContext, SaveDocOptions,
+};
+
+/// Directory below `contexts_root` holding attachment files
+pub(crate) const ASSETS_DIR: &str = ".assets";
+
+/// A file attached to a document
+#[derive(Debug, Clone, Serialize)]
+pub struct Attachment {
+    pub attachment_id: String,
+    pub file_name: String,
+    /// Path relative to `contexts_root`, e.g. `.assets/<id>/diagram.png`
+    pub rel_path: String,
+    pub abs_path: PathBuf,
+    pub mime_type: String,
+    pub size: i64,
+    /// Stable id of the doc the file was added to
+    pub doc_stable_id: String,
+    /// Number of docs linking to the file, including trashed ones
+    pub ref_count: i64,
+    pub created_at: String,
+}
+
+/// Result of `add_attachment`
+#[derive(Debug, Clone, Serialize)]
+pub struct AttachmentAdded {
+    pub attachment: Attachment,
+    /// Link target relative to the doc's folder
+    pub link: String,
+    /// Markdown embedding the attachment, ready to paste into the doc
+    pub markdown: String,
+}
+
+/// Optional parameters for `gc_attachments`
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentGcOptions {
+    /// Only collect attachments added before this RFC 3339 timestamp or
+    /// `YYYY-MM-DD` date, sparing files that were just added and are not
+    /// linked yet
+    pub older_than: Option<String>,
+    /// Report what would be removed without removing anything
+    pub dry_run: bool,
+}
+
+impl OpenContext {
+    /// Store `bytes` as an attachment of the doc at `doc_path`.
+    ///
+    /// The file name is reduced to a single safe path segment. The returned
+    /// link is not written into the doc; until some doc links to it the
+    /// attachment is unreferenced and `gc_attachments` may remove it.
+    pub fn add_attachment(
+        &self,
+        doc_path: &str,
+        bytes: &[u8],
+        file_name: &str,
+    ) -> CoreResult<AttachmentAdded> {
+        let rel_doc_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_doc_path)?
+            .ok_or_else(|| doc_not_found(&rel_doc_path))?;
+        let file_name = sanitize_file_name(file_name)?;
+        let attachment_id = self.with_conn(generate_stable_id)?;
+        let rel_path = format!("{ASSETS_DIR}/{attachment_id}/{file_name}");
+        let abs_path = self.contained_path(&rel_path)?;
+        if let Some(dir) = abs_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&abs_path, bytes)?;
+
+        let mime_type = mime_type(&file_name);
+        let inserted = self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO attachments (attachment_id, doc_stable_id, file_name, rel_path, mime_type, size, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    attachment_id,
+                    doc.stable_id,
+                    file_name,
+                    rel_path,
+                    mime_type,
+                    bytes.len() as i64,
+                    now_iso()
+                ],
+            )?;
+            query_attachments(
+                conn,
+                &self.contexts_root,
+                "WHERE a.attachment_id = ?1", params![attachment_id])
+        });
+        let attachment = match inserted {
+            Ok(mut rows) if !rows.is_empty() => rows.remove(0),
+            Ok(_) => return Err(CoreError::Message("Failed to record attachment.".into())),
+            Err(err) => {
+                let _ = abs_path.parent().map(fs::remove_dir_all);
+                return Err(err);
+            }
+        };
+
+        let link = relative_link(&doc.rel_path, &rel_path);
+        let label = file_name.replace(']', "\\]");
+        let markdown = if mime_type.starts_with("image/") {
+            format!("![{label}]({link})")
+        } else {
+            format!("[{label}]({link})")
+        };
+        Ok(AttachmentAdded {
+            attachment,
+            link,
+            markdown,
+        })
+    }
+
+    /// Attachments added to the doc at `doc_path` or linked from it.
+    pub fn list_attachments(&self, doc_path: &str) -> CoreResult<Vec<Attachment>> {
+        let rel_doc_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_doc_path)?
+            .ok_or_else(|| doc_not_found(&rel_doc_path))?;
+        self.with_conn(|conn| {
+            query_attachments(
+                conn,
+                &self.contexts_root,
+                "WHERE a.doc_stable_id = ?1
+                    OR a.rel_path IN (SELECT rel_path FROM attachment_refs WHERE source_stable_id = ?1)
+                 ORDER BY a.id",
+                params![doc.stable_id],
+            )
+        })
+    }
+
+    /// Delete attachments no doc links to. Returns the collected
+    /// attachments.
+    pub fn gc_attachments(&self, options: &AttachmentGcOptions) -> CoreResult<Vec<Attachment>> {
+        let older_than = options
+            .older_than
+            .as_deref()
+            .map(|value| manifest::normalize_since("older_than", value))
+            .transpose()?;
+        let unreferenced = self.with_conn(|conn| {
+            query_attachments(
+                conn,
+                &self.contexts_root,
+                "WHERE NOT EXISTS (SELECT 1 FROM attachment_refs r WHERE r.rel_path = a.rel_path)
+                   AND (?1 IS NULL OR a.created_at < ?1)
+                 ORDER BY a.id",
+                params![older_than],
+            )
+        })?;
+        if options.dry_run {
+            return Ok(unreferenced);
+        }
+        for attachment in &unreferenced {
+            if let Some(dir) = attachment.abs_path.parent() {
+                if dir.exists() {
+                    fs::remove_dir_all(dir)?;
+                }
+            }
+            self.with_conn(|conn| {
+                conn.execute(
+                    "DELETE FROM attachments WHERE attachment_id = ?1",
+                    [&attachment.attachment_id],
+                )?;
+                Ok(())
+            })?;
+        }
+        Ok(unreferenced)
+    }
+
+    /// Rewrite attachment links in docs that moved from `old_path` to
+    /// `new_path` (a doc, or a folder and everything below it), so they
+    /// resolve from the new location. Rewritten docs are saved with
+    /// `source = "attachment-relink"`.
+    pub(crate) fn relink_attachments(&self, old_path: &str, new_path: &str) -> CoreResult<()> {
+        let like_pattern = format!("{new_path}/%");
+        let docs = self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT DISTINCT d.rel_path FROM docs d
+                 JOIN attachment_refs r ON r.source_stable_id = d.stable_id
+                 WHERE d.rel_path = ?1 OR d.rel_path LIKE ?2",
+            )?;
+            let rows = stmt
+                .query_map(params![new_path, like_pattern], |row| {
+                    row.get::<_, String>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })?;
+        for doc_path in docs {
+            // LIKE is case-insensitive and treats `_` as a wildcard.
+            let Some(suffix) = doc_path.strip_prefix(new_path) else {
+                continue;
+            };
+            if !suffix.is_empty() && !suffix.starts_with('/') {
+                continue;
+            }
+            let old_doc_path = format!("{old_path}{suffix}");
+            let content = self.read_doc_content(&doc_path)?;
+            let updated = relink_content(&content, &old_doc_path, &doc_path);
+            if updated != content {
+                self.save_doc_content_with(
+                    &doc_path,
+                    &updated,
+                    SaveDocOptions {
+                        source: Some("attachment-relink".into()),
+                        ..Default::default()
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace the stored attachment references of `doc` with the attachment
+/// links found in `content`.
+pub(crate) fn index_attachment_refs(conn: &Connection, doc: &Doc, content: &str) -> CoreResult<()> {
+    conn.execute(
+        "DELETE FROM attachment_refs WHERE source_stable_id = ?1",
+        [&doc.stable_id],
+    )?;
+    let mut targets = Vec::new();
+    for_each_link(content, |target| {
+        if let Some(asset) = resolve_asset(&doc.rel_path, target) {
+            targets.push(asset);
+        }
+    });
+    for asset in targets {
+        conn.execute(
+            "INSERT OR IGNORE INTO attachment_refs (source_stable_id, rel_path) VALUES (?1, ?2)",
+            params![doc.stable_id, asset],
+        )?;
+    }
+    Ok(())
+}
+
+/// Rewrite the attachment links of a doc moving from `old_doc_path` to
+/// `new_doc_path`.
+pub(crate) fn relink_content(content: &str, old_doc_path: &str, new_doc_path: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence || !line.contains(ASSETS_DIR) {
+            out.push_str(line);
+            continue;
+        }
+        let rewritten = link_regex().replace_all(line, |caps: &Captures| {
+            let (open, target) = (&caps[1], &caps[2]);
+            let unchanged = format!("{open}{target}");
+            let Some(asset) = resolve_asset(old_doc_path, target) else {
+                return unchanged;
+            };
+            if resolve_asset(new_doc_path, target).as_deref() == Some(asset.as_str()) {
+                return unchanged;
+            }
+            let link = relative_link(new_doc_path, &asset);
+            if target.starts_with('<') {
+                format!("{open}<{link}>")
+            } else {
+                format!("{open}{link}")
+            }
+        });
+        out.push_str(&rewritten);
+    }
+    out
+}
+
+/// `[label](target` and `![alt](target`, with the target in group 2
+fn link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(!?\[[^\]\n]*\]\(\s*)(<[^>\n]*>|[^)\s]+)").expect("valid regex"))
+}
+
+/// Call `f` with the target of every markdown link outside fenced code
+/// blocks.
+fn for_each_link(content: &str, mut f: impl FnMut(&str)) {
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || !line.contains(ASSETS_DIR) {
+            continue;
+        }
+        for caps in link_regex().captures_iter(line) {
+            f(caps.get(2).map_or("", |m| m.as_str()));
+        }
+    }
+}
+
+/// Resolve a link target written in the doc at `doc_path` to the
+/// `contexts_root`-relative path of an attachment, if it points into the
+/// assets directory.
+fn resolve_asset(doc_path: &str, target: &str) -> Option<String> {
+    let target = target.trim_start_matches('<').trim_end_matches('>');
+    if target.contains("://") || target.starts_with('/') || target.starts_with('#') {
+        return None;
+    }
+    let target = target.split(['#', '?']).next().unwrap_or(target);
+    let target = urlencoding::decode(target).ok()?;
+    let mut parts: Vec<&str> = doc_path.split('/').collect();
+    parts.pop();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            segment => parts.push(segment),
+        }
+    }
+    let resolved = parts.join("/");
+    resolved
+        .starts_with(&format!("{ASSETS_DIR}/"))
+        .then_some(resolved)
+}
+
+/// Link from the doc at `doc_path` to the attachment at `asset_path`.
+fn relative_link(doc_path: &str, asset_path: &str) -> String {
+    let depth = doc_path.matches('/').count();
+    format!("{}{asset_path}", "../".repeat(depth))
+}
+
+/// Reduce a file name to one path segment that needs no escaping in a
+/// markdown link.
+fn sanitize_file_name(file_name: &str) -> CoreResult<String> {
+    let base = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+    let cleaned: String = base
+        .trim()
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            ':' | '*' | '?' | '"' | '<' | '>' | '|' | '(' | ')' | '[' | ']' | '#' | '%' => '-',
+            c if c.is_whitespace() => '-',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+    if cleaned.is_empty() {
+        return Err(CoreError::Message(format!(
+            "Invalid attachment file name \"{file_name}\"."
+        )));
+    }
+    Ok(cleaned.to_string())
+}
+
+fn mime_type(file_name: &str) -> &'static str {
+    let ext = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        _ => "application/octet-stream",
+    }
+}
+
+fn query_attachments(
+    conn: &Connection,
+    contexts_root: &Path,
+    clause: &str,
+    params: impl rusqlite::Params,
+) -> CoreResult<Vec<Attachment>> {
+    let sql = format!(
+        "SELECT a.attachment_id, a.file_name, a.rel_path, a.mime_type, a.size, a.doc_stable_id, a.created_at,
+                (SELECT COUNT(*) FROM attachment_refs r WHERE r.rel_path = a.rel_path)
+         FROM attachments a
+         {clause}"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(params, |row| row_to_attachment(row, contexts_root))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn row_to_attachment(row: &Row<'_>, contexts_root: &Path) -> rusqlite::Result<Attachment> {
+    let rel_path: String = row.get(2)?;
+    Ok(Attachment {
+        attachment_id: row.get(0)?,
+        file_name: row.get(1)?,
+        abs_path: contexts_root.join(&rel_path),
+        rel_path,
+        mime_type: row.get(3)?,
+        size: row.get(4)?,
+        doc_stable_id: row.get(5)?,
+        created_at: row.get(6)?,
+        ref_count: row.get(7)?,
+    })
+}
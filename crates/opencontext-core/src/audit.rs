@@ -0,0 +1,313 @@
+//! Append-only log of catalog mutations.
+//!
+//! Every create, save, rename, move, copy, remove, trash restore and purge
+//! and bundle import is recorded in `audit_log` with the source of the
+//! `OpenContext` that made it (see `with_source`), or the source passed to
+//! `save_doc_content_with`. Folder
+//! operations also list the docs they touched in `audit_log_docs`, so a
+//! doc's history includes renames of its folders.
+//! Triggers reject updates and deletes on both tables.
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use serde::Serialize;
+
+use crate::{
+    access::normalize_source, manifest, normalize_doc_path, normalize_folder_path, now_iso,
+    CoreError, CoreResult, OpenContext,
+};
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// What an audit entry is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditTarget {
+    Doc,
+    Folder,
+}
+
+impl AuditTarget {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuditTarget::Doc => "doc",
+            AuditTarget::Folder => "folder",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "folder" => AuditTarget::Folder,
+            _ => AuditTarget::Doc,
+        }
+    }
+}
+
+/// One recorded mutation
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Name of the core method, e.g. `save_doc_content` or `move_folder`
+    pub action: String,
+    pub target: AuditTarget,
+    /// Client that made the change (desktop, cli, mcp, an agent name, ...)
+    pub source: Option<String>,
+    /// Path of the doc or folder before the change
+    pub path: String,
+    /// Path after a rename, move or copy
+    pub new_path: Option<String>,
+    /// Stable id of the doc, for doc entries
+    pub stable_id: Option<String>,
+    /// Docs touched by a folder operation
+    pub docs: Vec<AuditedDoc>,
+    pub created_at: String,
+}
+
+/// A doc touched by a folder operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditedDoc {
+    pub stable_id: String,
+    pub path: String,
+    pub new_path: Option<String>,
+}
+
+/// Filters for `audit_log`
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogOptions {
+    /// Only entries about this doc, by path or stable id. Entries of folder
+    /// operations that touched the doc are included.
+    pub doc: Option<String>,
+    /// Only entries at or below this folder
+    pub folder_path: Option<String>,
+    /// Only entries from this source
+    pub source: Option<String>,
+    /// Only this action
+    pub action: Option<String>,
+    /// Only entries at or after this RFC 3339 timestamp or `YYYY-MM-DD` date
+    pub since: Option<String>,
+    /// Only entries before this RFC 3339 timestamp or `YYYY-MM-DD` date
+    pub until: Option<String>,
+    /// Maximum number of entries (default 100)
+    pub limit: Option<usize>,
+}
+
+impl OpenContext {
+    /// Recorded mutations matching `options`, newest first.
+    pub fn audit_log(&self, options: &AuditLogOptions) -> CoreResult<Vec<AuditEntry>> {
+        let limit = match options.limit {
+            Some(0) => {
+                return Err(CoreError::Message(
+                    "limit must be a positive integer".into(),
+                ))
+            }
+            Some(limit) => limit,
+            None => DEFAULT_LIMIT,
+        };
+        let mut sql = String::from(
+            "SELECT a.id, a.action, a.target, a.source, a.path, a.new_path, a.stable_id, a.created_at
+             FROM audit_log a WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(doc) = options.doc.as_deref() {
+            let path = normalize_doc_path(Some(doc))?;
+            let (stable_id, path) = match self.find_doc(&path)? {
+                Some(found) => (found.stable_id, path),
+                None => match self.get_doc_by_stable_id(doc.trim()) {
+                    Ok(found) => (found.stable_id, found.rel_path),
+                    Err(_) => (doc.trim().to_string(), path),
+                },
+            };
+            sql.push_str(
+                " AND (a.stable_id = ?1 OR a.path = ?2 OR a.new_path = ?2
+                   OR EXISTS (SELECT 1 FROM audit_log_docs d WHERE d.audit_id = a.id
+                              AND (d.stable_id = ?1 OR d.path = ?2 OR d.new_path = ?2)))",
+            );
+            values.push(Value::Text(stable_id));
+            values.push(Value::Text(path));
+        }
+        let next = |values: &Vec<Value>| values.len() + 1;
+        if let Some(folder) = options.folder_path.as_deref() {
+            let folder = normalize_folder_path(Some(folder))?;
+            if !folder.is_empty() {
+                let (f, p) = (next(&values), next(&values) + 1);
+                sql.push_str(&format!(
+                    " AND (a.path = ?{f} OR a.new_path = ?{f}
+                       OR substr(a.path, 1, length(?{p})) = ?{p}
+                       OR substr(a.new_path, 1, length(?{p})) = ?{p})"
+                ));
+                values.push(Value::Text(folder.clone()));
+                values.push(Value::Text(format!("{folder}/")));
+            }
+        }
+        if let Some(source) = options.source.as_deref().and_then(normalize_source) {
+            sql.push_str(&format!(" AND a.source = ?{}", next(&values)));
+            values.push(Value::Text(source));
+        }
+        if let Some(action) = options.action.as_deref() {
+            sql.push_str(&format!(" AND a.action = ?{}", next(&values)));
+            values.push(Value::Text(action.trim().to_string()));
+        }
+        if let Some(since) = options.since.as_deref() {
+            sql.push_str(&format!(" AND a.created_at >= ?{}", next(&values)));
+            values.push(Value::Text(manifest::normalize_since("since", since)?));
+        }
+        if let Some(until) = options.until.as_deref() {
+            sql.push_str(&format!(" AND a.created_at < ?{}", next(&values)));
+            values.push(Value::Text(manifest::normalize_since("until", until)?));
+        }
+        sql.push_str(&format!(" ORDER BY a.id DESC LIMIT ?{}", next(&values)));
+        values.push(Value::Integer(limit as i64));
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let mut entries = stmt
+                .query_map(params_from_iter(values), |row| {
+                    Ok(AuditEntry {
+                        id: row.get(0)?,
+                        action: row.get(1)?,
+                        target: AuditTarget::parse(&row.get::<_, String>(2)?),
+                        source: row.get(3)?,
+                        path: row.get(4)?,
+                        new_path: row.get(5)?,
+                        stable_id: row.get(6)?,
+                        docs: Vec::new(),
+                        created_at: row.get(7)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut stmt = conn.prepare(
+                "SELECT stable_id, path, new_path FROM audit_log_docs
+                 WHERE audit_id = ?1 ORDER BY id",
+            )?;
+            for entry in entries
+                .iter_mut()
+                .filter(|e| e.target == AuditTarget::Folder)
+            {
+                entry.docs = stmt
+                    .query_map([entry.id], |row| {
+                        Ok(AuditedDoc {
+                            stable_id: row.get(0)?,
+                            path: row.get(1)?,
+                            new_path: row.get(2)?,
+                        })
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+            }
+            Ok(entries)
+        })
+    }
+
+    /// Record a mutation of one doc. `source` overrides the context's
+    /// source for this call.
+    pub(crate) fn audit_doc(
+        &self,
+        action: &str,
+        source: Option<&str>,
+        path: &str,
+        new_path: Option<&str>,
+        stable_id: &str,
+    ) -> CoreResult<()> {
+        let source = source.or(self.source.as_deref());
+        self.with_conn(|conn| {
+            insert_entry(
+                conn,
+                action,
+                AuditTarget::Doc,
+                source,
+                path,
+                new_path,
+                Some(stable_id),
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Record a mutation of a folder and the docs it touched.
+    pub(crate) fn audit_folder(
+        &self,
+        action: &str,
+        path: &str,
+        new_path: Option<&str>,
+        docs: &[AuditedDoc],
+    ) -> CoreResult<()> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let id = insert_entry(
+                &tx,
+                action,
+                AuditTarget::Folder,
+                self.source.as_deref(),
+                path,
+                new_path,
+                None,
+            )?;
+            for doc in docs {
+                tx.execute(
+                    "INSERT INTO audit_log_docs (audit_id, stable_id, path, new_path)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![id, doc.stable_id, doc.path, doc.new_path],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Docs below the folder at `rel_path`, as `(stable_id, rel_path)`.
+    pub(crate) fn docs_below(&self, rel_path: &str) -> CoreResult<Vec<(String, String)>> {
+        let prefix = format!("{rel_path}/");
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT stable_id, rel_path FROM docs
+                 WHERE substr(rel_path, 1, length(?1)) = ?1 ORDER BY rel_path",
+            )?;
+            let rows = stmt
+                .query_map([prefix], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// Record a folder rename or move after the catalog was updated.
+    pub(crate) fn audit_folder_move(
+        &self,
+        action: &str,
+        old_path: &str,
+        new_path: &str,
+    ) -> CoreResult<()> {
+        let docs: Vec<AuditedDoc> = self
+            .docs_below(new_path)?
+            .into_iter()
+            .map(|(stable_id, rel_path)| AuditedDoc {
+                stable_id,
+                path: format!("{old_path}{}", &rel_path[new_path.len()..]),
+                new_path: Some(rel_path),
+            })
+            .collect();
+        self.audit_folder(action, old_path, Some(new_path), &docs)
+    }
+}
+
+fn insert_entry(
+    conn: &Connection,
+    action: &str,
+    target: AuditTarget,
+    source: Option<&str>,
+    path: &str,
+    new_path: Option<&str>,
+    stable_id: Option<&str>,
+) -> CoreResult<i64> {
+    conn.execute(
+        "INSERT INTO audit_log (action, target, source, path, new_path, stable_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            action,
+            target.as_str(),
+            source.and_then(normalize_source),
+            path,
+            new_path,
+            stable_id,
+            now_iso()
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
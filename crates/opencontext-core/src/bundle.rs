@@ -0,0 +1,498 @@
+//! Portable export/import bundles.
+//!
+//! A bundle is an uncompressed tar archive holding:
+//!
+//! - `manifest.json`: format version, the exported folder's name and
+//!   description, and every folder and doc below it with stable ids,
+//!   descriptions, tags and timestamps (paths relative to the folder);
+//! - `docs/<rel_path>`: the markdown files.
+//!
+//! Absolute paths are never stored, so a bundle can be imported under any
+//! folder of any library.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atomic, audit::AuditedDoc, folder_not_found, fulltext, generate_stable_id,
+    normalize_folder_path, now_iso, parent_rel_path, paths, tags::doc_tags, trash::suffixed_name,
+    ConflictPolicy, CoreError, CoreResult, OpenContext, RenameResult, SaveDocOptions,
+};
+
+#[cfg(feature = "search")]
+use crate::events::DocEvent;
+
+const BUNDLE_FORMAT: &str = "opencontext-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DOCS_PREFIX: &str = "docs/";
+/// Version history source recorded for imported docs
+const IMPORT_SOURCE: &str = "import";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    format: String,
+    version: u32,
+    exported_at: String,
+    /// Name of the exported folder (empty for a whole-library export)
+    root_name: String,
+    #[serde(default)]
+    root_description: String,
+    folders: Vec<BundleFolder>,
+    docs: Vec<BundleDoc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleFolder {
+    rel_path: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleDoc {
+    rel_path: String,
+    stable_id: String,
+    #[serde(default)]
+    description: String,
+    created_at: String,
+    updated_at: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Result of `export_bundle`
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleExported {
+    pub bundle_path: PathBuf,
+    pub folder_count: usize,
+    pub doc_count: usize,
+}
+
+/// Result of `import_bundle`
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleImported {
+    /// Folder the bundle was imported into
+    pub rel_path: String,
+    /// Paths of the imported docs
+    pub docs: Vec<String>,
+    /// Docs saved under a suffixed name because the path was taken
+    pub renamed: Vec<RenameResult>,
+    /// Docs whose stable id was already in use and got a new one
+    pub reassigned_stable_ids: usize,
+}
+
+impl OpenContext {
+    /// Write `folder_path` and everything below it to a bundle at
+    /// `bundle_path`. An empty folder path exports the whole library.
+    pub fn export_bundle(
+        &self,
+        folder_path: &str,
+        bundle_path: &Path,
+    ) -> CoreResult<BundleExported> {
+        let rel_path = normalize_folder_path(Some(folder_path))?;
+        let (root_name, root_description, prefix) = if rel_path.is_empty() {
+            (String::new(), String::new(), String::new())
+        } else {
+            let folder = self
+                .find_folder(&rel_path)?
+                .ok_or_else(|| folder_not_found(&rel_path))?;
+            (folder.name, folder.description, format!("{rel_path}/"))
+        };
+        let strip = |path: &str| -> String {
+            if rel_path.is_empty() {
+                path.to_string()
+            } else {
+                path[rel_path.len() + 1..].to_string()
+            }
+        };
+
+        let (folders, docs) = self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT rel_path, description FROM folders
+                 WHERE substr(rel_path, 1, length(?1)) = ?1 AND rel_path != '' ORDER BY rel_path",
+            )?;
+            let folders = stmt
+                .query_map([&prefix], |row| {
+                    Ok(BundleFolder {
+                        rel_path: strip(&row.get::<_, String>(0)?),
+                        description: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT id, rel_path, abs_path, stable_id, description, created_at, updated_at
+                 FROM docs WHERE substr(rel_path, 1, length(?1)) = ?1 ORDER BY rel_path",
+            )?;
+            let rows = stmt
+                .query_map([&prefix], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        BundleDoc {
+                            rel_path: String::new(),
+                            stable_id: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                            description: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                            created_at: row.get(5)?,
+                            updated_at: row.get(6)?,
+                            tags: Vec::new(),
+                        },
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut docs = Vec::with_capacity(rows.len());
+            for (id, doc_rel, abs_path, mut doc) in rows {
+                doc.rel_path = strip(&doc_rel);
+                doc.tags = doc_tags(conn, id)?;
+                docs.push((doc, abs_path));
+            }
+            Ok((folders, docs))
+        })?;
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut manifest_docs = Vec::with_capacity(docs.len());
+        for (doc, abs_path) in docs {
+            let content = fs::read(&abs_path)?;
+            append_entry(
+                &mut builder,
+                &format!("{DOCS_PREFIX}{}", doc.rel_path),
+                &content,
+            )?;
+            manifest_docs.push(doc);
+        }
+        let manifest = BundleManifest {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            exported_at: now_iso(),
+            root_name,
+            root_description,
+            folders,
+            docs: manifest_docs,
+        };
+        let manifest_json =
+            serde_json::to_vec_pretty(&manifest).map_err(|e| CoreError::Message(e.to_string()))?;
+        append_entry(&mut builder, MANIFEST_ENTRY, &manifest_json)?;
+        let bytes = builder.into_inner()?;
+
+        if let Some(parent) = bundle_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let temp = bundle_path.with_extension("tmp");
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, bundle_path)?;
+
+        Ok(BundleExported {
+            bundle_path: bundle_path.to_path_buf(),
+            folder_count: manifest.folders.len(),
+            doc_count: manifest.docs.len(),
+        })
+    }
+
+    /// Import a bundle under `dest_folder`. The exported folder is recreated
+    /// as `dest_folder/<name>` and merged with an existing folder of that
+    /// name; `conflict_policy` decides what happens to docs whose path is
+    /// already taken. Stable ids are kept unless already in use.
+    pub fn import_bundle(
+        &self,
+        bundle_path: &Path,
+        dest_folder: &str,
+        conflict_policy: ConflictPolicy,
+    ) -> CoreResult<BundleImported> {
+        let (manifest, mut files) = read_bundle(bundle_path)?;
+        let dest_rel = normalize_folder_path(Some(dest_folder))?;
+        if !dest_rel.is_empty() {
+            self.find_folder(&dest_rel)?
+                .ok_or_else(|| folder_not_found(&dest_rel))?;
+        }
+        let base = join_rel(&dest_rel, &manifest.root_name);
+        if base.is_empty() {
+            return Err(CoreError::Message(
+                "Root is not supported. Please import into a folder under contexts/.".into(),
+            ));
+        }
+
+        // Plan target paths and stable ids before touching anything.
+        let mut planned: HashSet<String> = HashSet::new();
+        let mut renamed = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut plan = Vec::with_capacity(manifest.docs.len());
+        let mut id_map: HashMap<String, String> = HashMap::new();
+        for doc in &manifest.docs {
+            let target = join_rel(&base, &doc.rel_path);
+            let mut final_path = target.clone();
+            if self.path_taken(&final_path)? || planned.contains(&final_path) {
+                match conflict_policy {
+                    ConflictPolicy::Fail => {
+                        conflicts.push(target.clone());
+                        continue;
+                    }
+                    ConflictPolicy::Rename => {
+                        let parent = parent_rel_path(&target).unwrap_or_default();
+                        let name = target.split('/').next_back().unwrap_or(&target);
+                        let mut n = 1;
+                        loop {
+                            let candidate = join_rel(&parent, &suffixed_name(name, n));
+                            if !self.path_taken(&candidate)? && !planned.contains(&candidate) {
+                                final_path = candidate;
+                                break;
+                            }
+                            n += 1;
+                        }
+                        renamed.push(RenameResult {
+                            old_path: target.clone(),
+                            new_path: final_path.clone(),
+                        });
+                    }
+                }
+            }
+            planned.insert(final_path.clone());
+            plan.push((doc, final_path));
+        }
+        if !conflicts.is_empty() {
+            return Err(CoreError::Message(format!(
+                "Documents already exist: {}.",
+                conflicts.join(", ")
+            )));
+        }
+
+        self.with_conn(|conn| {
+            let mut seen = HashSet::new();
+            for doc in &manifest.docs {
+                let in_use: Option<i64> = conn
+                    .query_row(
+                        "SELECT id FROM docs WHERE stable_id = ?1",
+                        [&doc.stable_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if doc.stable_id.is_empty() || in_use.is_some() || !seen.insert(&doc.stable_id) {
+                    id_map.insert(doc.stable_id.clone(), generate_stable_id(conn)?);
+                }
+            }
+            Ok(())
+        })?;
+
+        // Folders: reuse existing ones, create the rest with the bundle's
+        // descriptions.
+        let mut folder_paths: Vec<(String, &str)> =
+            vec![(base.clone(), &manifest.root_description)];
+        folder_paths.extend(
+            manifest
+                .folders
+                .iter()
+                .map(|f| (join_rel(&base, &f.rel_path), f.description.as_str())),
+        );
+        for (_, final_path) in &plan {
+            if let Some(parent) = parent_rel_path(final_path) {
+                folder_paths.push((parent, ""));
+            }
+        }
+        for (rel_path, description) in folder_paths {
+            if self.find_folder(&rel_path)?.is_none() {
+                self.ensure_folder_record(&rel_path)?;
+                if !description.is_empty() {
+                    self.update_folder_description(&rel_path, description)?;
+                }
+            }
+        }
+
+        // Files first, then one transaction for the catalog rows; written
+        // files are removed again if the transaction fails.
+        let mut contents = Vec::with_capacity(plan.len());
+        let mut written: Vec<PathBuf> = Vec::new();
+        let write_result = (|| -> CoreResult<()> {
+            for (doc, final_path) in &plan {
+                let mut content = files.remove(&doc.rel_path).ok_or_else(|| {
+                    bundle_error(&format!("missing file for \"{}\"", doc.rel_path))
+                })?;
+                for (old_id, new_id) in &id_map {
+                    content = content
+                        .replace(&format!("oc://doc/{old_id}"), &format!("oc://doc/{new_id}"));
+                }
+                let abs_path = self.contained_path(final_path)?;
+                atomic::write_atomic(&abs_path, &content)?;
+                written.push(abs_path);
+                contents.push(content);
+            }
+            Ok(())
+        })();
+        let db_result = write_result.and_then(|_| {
+            self.with_conn(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                let ts = now_iso();
+                for ((doc, final_path), content) in plan.iter().zip(&contents) {
+                    let name = final_path.split('/').next_back().unwrap_or(final_path);
+                    let folder_rel = parent_rel_path(final_path).unwrap_or_default();
+                    let stable_id = id_map.get(&doc.stable_id).unwrap_or(&doc.stable_id);
+                    tx.execute(
+                        "INSERT INTO docs (folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at)
+                         VALUES ((SELECT id FROM folders WHERE rel_path = ?1), ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        params![
+                            folder_rel,
+                            name,
+                            final_path,
+                            self.contexts_root.join(final_path).to_string_lossy(),
+                            doc.description,
+                            stable_id,
+                            doc.created_at,
+                            doc.updated_at
+                        ],
+                    )?;
+                    let doc_id = tx.last_insert_rowid();
+                    for tag in &doc.tags {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO doc_tags (doc_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                            params![doc_id, tag, ts],
+                        )?;
+                    }
+                    fulltext::index_doc_text(&tx, doc_id, content)?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+        });
+        if let Err(err) = db_result {
+            for path in written {
+                let _ = fs::remove_file(path);
+            }
+            return Err(err);
+        }
+
+        let options = SaveDocOptions {
+            source: Some(IMPORT_SOURCE.to_string()),
+            ..Default::default()
+        };
+        let mut docs = Vec::with_capacity(plan.len());
+        let mut audited = Vec::with_capacity(plan.len());
+        for ((_, final_path), content) in plan.iter().zip(&contents) {
+            if let Some(doc) = self.find_doc(final_path)? {
+                self.record_doc_version(&doc, None, content, &options)?;
+                self.index_doc_links(&doc, content)?;
+                audited.push(AuditedDoc {
+                    stable_id: doc.stable_id,
+                    path: final_path.clone(),
+                    new_path: None,
+                });
+            }
+            #[cfg(feature = "search")]
+            self.emit_doc_event(DocEvent::Created {
+                rel_path: final_path.clone(),
+            });
+            docs.push(final_path.clone());
+        }
+        self.audit_folder("import_bundle", &base, None, &audited)?;
+
+        Ok(BundleImported {
+            rel_path: base,
+            docs,
+            renamed,
+            reassigned_stable_ids: id_map.len(),
+        })
+    }
+
+    fn path_taken(&self, rel_path: &str) -> CoreResult<bool> {
+        Ok(self.find_doc(rel_path)?.is_some() || self.contexts_root.join(rel_path).exists())
+    }
+}
+
+fn append_entry(builder: &mut tar::Builder<Vec<u8>>, path: &str, data: &[u8]) -> CoreResult<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_entry_type(tar::EntryType::Regular);
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+/// Read the manifest and the doc files (keyed by path relative to the
+/// bundle root) from a bundle, validating every path.
+fn read_bundle(bundle_path: &Path) -> CoreResult<(BundleManifest, HashMap<String, String>)> {
+    let file = fs::File::open(bundle_path)?;
+    let mut archive = tar::Archive::new(file);
+    let mut manifest: Option<BundleManifest> = None;
+    let mut files = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let name = safe_bundle_path(&path)?;
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        if name == MANIFEST_ENTRY {
+            manifest = Some(
+                serde_json::from_str(&content)
+                    .map_err(|e| bundle_error(&format!("invalid manifest: {e}")))?,
+            );
+        } else if let Some(rel) = name.strip_prefix(DOCS_PREFIX) {
+            files.insert(rel.to_string(), content);
+        }
+    }
+    let manifest = manifest.ok_or_else(|| bundle_error("missing manifest.json"))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(bundle_error("not an OpenContext bundle"));
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err(bundle_error(&format!(
+            "unsupported version {}",
+            manifest.version
+        )));
+    }
+    if manifest.root_name.contains('/')
+        || (!manifest.root_name.is_empty()
+            && paths::check_name(&manifest.root_name, "Folder name").is_err())
+    {
+        return Err(bundle_error("invalid root name"));
+    }
+    for path in manifest
+        .folders
+        .iter()
+        .map(|f| &f.rel_path)
+        .chain(manifest.docs.iter().map(|d| &d.rel_path))
+    {
+        safe_bundle_path(Path::new(path))?;
+    }
+    Ok((manifest, files))
+}
+
+/// Reject absolute paths and `.`/`..` segments so a bundle cannot write
+/// outside the import folder.
+fn safe_bundle_path(path: &Path) -> CoreResult<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            _ => return Err(bundle_error(&format!("unsafe path \"{}\"", path.display()))),
+        }
+    }
+    if parts.is_empty() {
+        return Err(bundle_error("empty path"));
+    }
+    paths::clean_rel_path(&parts.join("/"), "Bundle path")
+        .map_err(|_| bundle_error(&format!("unsafe path \"{}\"", path.display())))
+}
+
+pub(crate) fn join_rel(base: &str, rel: &str) -> String {
+    match (base.is_empty(), rel.is_empty()) {
+        (true, _) => rel.to_string(),
+        (_, true) => base.to_string(),
+        _ => format!("{base}/{rel}"),
+    }
+}
+
+fn bundle_error(detail: &str) -> CoreError {
+    CoreError::Message(format!("Invalid bundle: {detail}."))
+}
@@ -0,0 +1,323 @@
+//! Prompt-ready context packs.
+//!
+//! A pack concatenates several docs into one markdown bundle under a token
+//! budget, so an agent can load a project's context in a single call
+//! instead of reading docs one by one. Docs are taken in priority order:
+//! manifest order for a folder, the given order for stable ids.
+//!
+//! - `PackStrategy::Truncate` adds docs in full while they fit, cuts the
+//!   first one that does not, and omits the rest.
+//! - `PackStrategy::Summarize` first makes room for a short summary of
+//!   every doc (description, first paragraph and outline), then upgrades
+//!   docs to full content in priority order while the budget allows.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fulltext, manifest::estimate_tokens, strip_frontmatter, CoreError, CoreResult,
+    DocManifestEntry, ManifestOptions, ManifestSort, OpenContext, Properties,
+};
+
+const DEFAULT_TOKEN_BUDGET: usize = 8000;
+/// Truncating a doc below this many body tokens is not worth it
+const MIN_TRUNCATED_TOKENS: usize = 32;
+const TRUNCATED_MARKER: &str = "\n\n…(truncated)";
+const SUMMARY_PARAGRAPH_CHARS: usize = 300;
+
+/// Which docs go into a context pack
+#[derive(Debug, Clone)]
+pub enum ContextPackSource {
+    /// Every doc under a folder, recursively
+    Folder(String),
+    /// These docs, in this order
+    StableIds(Vec<String>),
+}
+
+/// What to do with lower-priority docs once the budget runs out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackStrategy {
+    #[default]
+    Truncate,
+    Summarize,
+}
+
+/// Optional parameters for `build_context_pack`
+#[derive(Debug, Clone, Default)]
+pub struct ContextPackOptions {
+    /// Estimated token budget for the whole pack (default 8000)
+    pub token_budget: Option<usize>,
+    pub strategy: PackStrategy,
+    /// Priority order of docs in a folder (default by path)
+    pub sort: ManifestSort,
+}
+
+/// How much of a doc made it into the pack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackInclusion {
+    Full,
+    Truncated,
+    Summary,
+    Omitted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackedDoc {
+    pub rel_path: String,
+    pub stable_id: String,
+    pub inclusion: PackInclusion,
+    /// Estimated tokens this doc takes in the pack
+    pub estimated_tokens: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextPack {
+    /// The markdown bundle
+    pub content: String,
+    pub estimated_tokens: usize,
+    pub docs: Vec<PackedDoc>,
+}
+
+struct Candidate {
+    entry: DocManifestEntry,
+    header: String,
+    body: String,
+}
+
+impl OpenContext {
+    /// Concatenate docs into one markdown bundle that fits `token_budget`.
+    pub fn build_context_pack(
+        &self,
+        source: &ContextPackSource,
+        options: &ContextPackOptions,
+    ) -> CoreResult<ContextPack> {
+        let budget = match options.token_budget {
+            Some(0) => {
+                return Err(CoreError::Message(
+                    "token_budget must be a positive integer".into(),
+                ))
+            }
+            Some(budget) => budget,
+            None => DEFAULT_TOKEN_BUDGET,
+        };
+        let (title, entries) = match source {
+            ContextPackSource::Folder(folder_path) => {
+                let entries = self.generate_manifest_with(
+                    folder_path,
+                    &ManifestOptions {
+                        sort: options.sort,
+                        ..Default::default()
+                    },
+                )?;
+                let title = match folder_path.trim().trim_matches('/') {
+                    "" => "Context pack".to_string(),
+                    folder => format!("Context pack: {folder}"),
+                };
+                (title, entries)
+            }
+            ContextPackSource::StableIds(ids) => {
+                let mut seen = HashSet::new();
+                let mut entries = Vec::new();
+                for id in ids {
+                    if !seen.insert(id.trim()) {
+                        continue;
+                    }
+                    let doc = self.get_doc_by_stable_id(id)?;
+                    entries.push(DocManifestEntry {
+                        doc_name: doc.name,
+                        rel_path: doc.rel_path,
+                        abs_path: doc.abs_path,
+                        stable_id: doc.stable_id,
+                        description: doc.description,
+                        updated_at: doc.updated_at,
+                        tags: Vec::new(),
+                        properties: Properties::new(),
+                    });
+                }
+                ("Context pack".to_string(), entries)
+            }
+        };
+
+        let mut candidates = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let content = self.read_doc_content(&entry.rel_path)?;
+            candidates.push(Candidate {
+                header: render_header(&entry),
+                body: strip_frontmatter(&content).trim().to_string(),
+                entry,
+            });
+        }
+
+        let mut content = format!("# {title}\n\n");
+        let available = budget.saturating_sub(estimate_tokens(&content));
+        let sections = match options.strategy {
+            PackStrategy::Truncate => pack_truncated(&candidates, available),
+            PackStrategy::Summarize => pack_summarized(&candidates, available),
+        };
+
+        let mut docs = Vec::with_capacity(candidates.len());
+        for (candidate, (inclusion, section)) in candidates.iter().zip(sections) {
+            let estimated_tokens = section.as_deref().map(estimate_tokens).unwrap_or(0);
+            if let Some(section) = section {
+                content.push_str(&section);
+            }
+            docs.push(PackedDoc {
+                rel_path: candidate.entry.rel_path.clone(),
+                stable_id: candidate.entry.stable_id.clone(),
+                inclusion,
+                estimated_tokens,
+            });
+        }
+        Ok(ContextPack {
+            estimated_tokens: estimate_tokens(&content),
+            content,
+            docs,
+        })
+    }
+}
+
+type Section = (PackInclusion, Option<String>);
+
+fn pack_truncated(candidates: &[Candidate], mut remaining: usize) -> Vec<Section> {
+    let mut sections = Vec::with_capacity(candidates.len());
+    let mut exhausted = false;
+    for candidate in candidates {
+        if exhausted {
+            sections.push((PackInclusion::Omitted, None));
+            continue;
+        }
+        let full = full_section(candidate);
+        let tokens = estimate_tokens(&full);
+        if tokens <= remaining {
+            remaining -= tokens;
+            sections.push((PackInclusion::Full, Some(full)));
+            continue;
+        }
+        exhausted = true;
+        let overhead = estimate_tokens(&candidate.header) + estimate_tokens(TRUNCATED_MARKER) + 1;
+        match remaining.checked_sub(overhead) {
+            Some(body_budget) if body_budget >= MIN_TRUNCATED_TOKENS => {
+                let body = truncate_to_tokens(&candidate.body, body_budget);
+                let section = format!("{}{body}{TRUNCATED_MARKER}\n\n", candidate.header);
+                sections.push((PackInclusion::Truncated, Some(section)));
+            }
+            _ => sections.push((PackInclusion::Omitted, None)),
+        }
+    }
+    sections
+}
+
+fn pack_summarized(candidates: &[Candidate], mut remaining: usize) -> Vec<Section> {
+    let summaries: Vec<String> = candidates
+        .iter()
+        .map(|c| format!("{}{}\n\n", c.header, summarize(&c.body)))
+        .collect();
+    // Reserve room for the summaries of as many docs as fit, in priority order.
+    let mut included = 0;
+    for summary in &summaries {
+        let tokens = estimate_tokens(summary);
+        if tokens > remaining {
+            break;
+        }
+        remaining -= tokens;
+        included += 1;
+    }
+    // Spend what is left upgrading summaries to full content.
+    let mut sections = Vec::with_capacity(candidates.len());
+    for (i, candidate) in candidates.iter().enumerate() {
+        if i >= included {
+            sections.push((PackInclusion::Omitted, None));
+            continue;
+        }
+        let full = full_section(candidate);
+        let extra = estimate_tokens(&full).saturating_sub(estimate_tokens(&summaries[i]));
+        if extra <= remaining {
+            remaining -= extra;
+            sections.push((PackInclusion::Full, Some(full)));
+        } else {
+            sections.push((PackInclusion::Summary, Some(summaries[i].clone())));
+        }
+    }
+    sections
+}
+
+fn render_header(entry: &DocManifestEntry) -> String {
+    let mut header = format!(
+        "## {}\n\n- stable_id: {}\n- updated_at: {}\n",
+        entry.rel_path, entry.stable_id, entry.updated_at
+    );
+    let description = entry.description.trim();
+    if !description.is_empty() {
+        header.push_str(&format!(
+            "- description: {}\n",
+            description.replace('\n', " ")
+        ));
+    }
+    header.push('\n');
+    header
+}
+
+fn full_section(candidate: &Candidate) -> String {
+    format!("{}{}\n\n", candidate.header, candidate.body)
+}
+
+/// First paragraph (capped) and the heading outline of a markdown body.
+fn summarize(body: &str) -> String {
+    let mut out = String::from("> Summary only: full content left out to fit the token budget.");
+    let paragraph: Vec<&str> = body
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty() || line.starts_with('#'))
+        .take_while(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    if !paragraph.is_empty() {
+        let paragraph = paragraph.join(" ");
+        let mut chars = paragraph.chars();
+        let capped: String = chars.by_ref().take(SUMMARY_PARAGRAPH_CHARS).collect();
+        out.push_str("\n\n");
+        out.push_str(&capped);
+        if chars.next().is_some() {
+            out.push('…');
+        }
+    }
+    let outline: Vec<String> = body
+        .lines()
+        .filter_map(|line| {
+            let trimmed = line.trim_start();
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            let title = trimmed[level..].trim();
+            (level > 0 && level <= 6 && trimmed[level..].starts_with(' ') && !title.is_empty())
+                .then(|| format!("{}- {title}", "  ".repeat(level - 1)))
+        })
+        .collect();
+    if !outline.is_empty() {
+        out.push_str("\n\nOutline:\n");
+        out.push_str(&outline.join("\n"));
+    }
+    out
+}
+
+/// Longest prefix of whole lines within `max_tokens`, or of characters when
+/// even the first line does not fit.
+fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let (mut cjk, mut other) = (0usize, 0usize);
+    let mut line_end = 0;
+    for (i, c) in text.char_indices() {
+        if c == '\n' {
+            line_end = i;
+        }
+        if fulltext::is_cjk(c) {
+            cjk += 1;
+        } else {
+            other += 1;
+        }
+        if cjk + other.div_ceil(4) > max_tokens {
+            let cut = if line_end > 0 { line_end } else { i };
+            return text[..cut].to_string();
+        }
+    }
+    text.to_string()
+}
@@ -0,0 +1,373 @@
+//! Copying documents and folder trees.
+//!
+//! Copies are new catalog entries: every copied doc gets a fresh stable id
+//! and carries over its description, tags and properties; copied folders
+//! keep their descriptions, properties and default template. Version
+//! history, reads and pins stay with the original.
+
+use std::{collections::HashMap, fs};
+
+use rusqlite::{params, Transaction};
+use serde::Serialize;
+
+use crate::{
+    atomic, attachments, audit::AuditedDoc, bundle::join_rel, doc_not_found, folder_not_found,
+    fulltext, generate_stable_id, normalize_doc_path, normalize_folder_path, now_iso,
+    parent_rel_path, paths, trash::suffixed_name, ConflictPolicy, CoreError, CoreResult,
+    OpenContext, SaveDocOptions,
+};
+
+#[cfg(feature = "search")]
+use crate::events::{DocEvent, FolderEvent};
+
+/// Version history source recorded for copied docs
+const COPY_SOURCE: &str = "copy";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocCopied {
+    pub source_path: String,
+    pub rel_path: String,
+    pub stable_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderCopied {
+    pub source_path: String,
+    pub rel_path: String,
+    pub docs: Vec<DocCopied>,
+}
+
+struct PlannedDoc {
+    id: i64,
+    source_path: String,
+    rel_path: String,
+    description: String,
+    stable_id: String,
+    content: String,
+}
+
+impl OpenContext {
+    /// Copy a document into `dest_folder`, as `new_name` or under its own
+    /// name. `conflict_policy` decides what happens when the name is taken.
+    pub fn copy_doc(
+        &self,
+        doc_path: &str,
+        dest_folder: &str,
+        new_name: Option<&str>,
+        conflict_policy: ConflictPolicy,
+    ) -> CoreResult<DocCopied> {
+        let rel_doc_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_doc_path)?
+            .ok_or_else(|| doc_not_found(&rel_doc_path))?;
+        let dest_rel = normalize_folder_path(Some(dest_folder))?;
+        let dest = self
+            .find_folder(&dest_rel)?
+            .ok_or_else(|| folder_not_found(&dest_rel))?;
+        let name = new_name.map(str::trim).unwrap_or(&doc.name);
+        if name.is_empty() || name.contains('/') {
+            return Err(CoreError::Message(
+                "New name must be a single file name without \"/\".".into(),
+            ));
+        }
+        paths::check_name(name, "Document name")?;
+        let target = self.free_path(&join_rel(&dest.rel_path, name), conflict_policy)?;
+
+        let content = attachments::relink_content(
+            &self.read_doc_content(&rel_doc_path)?,
+            &rel_doc_path,
+            &target,
+        );
+        let planned = PlannedDoc {
+            id: doc.id,
+            source_path: rel_doc_path,
+            rel_path: target,
+            description: doc.description,
+            stable_id: self.with_conn(generate_stable_id)?,
+            content,
+        };
+        let abs_path = self.contained_path(&planned.rel_path)?;
+        atomic::write_atomic(&abs_path, &planned.content)?;
+        let inserted = self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            insert_doc_copy(&tx, &self.contexts_root, &planned, &now_iso())?;
+            tx.commit()?;
+            Ok(())
+        });
+        if let Err(err) = inserted {
+            let _ = fs::remove_file(&abs_path);
+            return Err(err);
+        }
+        self.finish_doc_copies(std::slice::from_ref(&planned))?;
+        self.audit_doc(
+            "copy_doc",
+            None,
+            &planned.source_path,
+            Some(&planned.rel_path),
+            &planned.stable_id,
+        )?;
+
+        Ok(DocCopied {
+            source_path: planned.source_path,
+            rel_path: planned.rel_path,
+            stable_id: planned.stable_id,
+        })
+    }
+
+    /// Copy a folder with everything below it into `dest_folder` ("" for
+    /// the top level). `conflict_policy` decides what happens when the
+    /// folder name is taken. `oc://doc/` links between docs of the folder
+    /// are pointed at the copies.
+    pub fn copy_folder(
+        &self,
+        path: &str,
+        dest_folder: &str,
+        conflict_policy: ConflictPolicy,
+    ) -> CoreResult<FolderCopied> {
+        let rel_path = normalize_folder_path(Some(path))?;
+        if rel_path.is_empty() {
+            return Err(CoreError::Message(
+                "Cannot copy the root contexts directory.".into(),
+            ));
+        }
+        let folder = self
+            .find_folder(&rel_path)?
+            .ok_or_else(|| folder_not_found(&rel_path))?;
+        let dest_rel = normalize_folder_path(Some(dest_folder))?;
+        if dest_rel == rel_path || dest_rel.starts_with(&format!("{rel_path}/")) {
+            return Err(CoreError::Message(
+                "Cannot copy a folder into itself or its descendants.".into(),
+            ));
+        }
+        if !dest_rel.is_empty() {
+            self.find_folder(&dest_rel)?
+                .ok_or_else(|| folder_not_found(&dest_rel))?;
+        }
+        let target = self.free_path(&join_rel(&dest_rel, &folder.name), conflict_policy)?;
+        let retarget = |source: &str| format!("{target}{}", &source[rel_path.len()..]);
+
+        // Plan every folder and doc, with new stable ids, before writing.
+        let prefix = format!("{rel_path}/");
+        let (folders, rows) = self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, rel_path, description FROM folders
+                 WHERE rel_path = ?1 OR substr(rel_path, 1, length(?2)) = ?2
+                 ORDER BY rel_path",
+            )?;
+            let folders = stmt
+                .query_map(params![rel_path, prefix], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut stmt = conn.prepare(
+                "SELECT id, rel_path, description, stable_id FROM docs
+                 WHERE substr(rel_path, 1, length(?1)) = ?1 ORDER BY rel_path",
+            )?;
+            let rows = stmt
+                .query_map([&prefix], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((folders, rows))
+        })?;
+        let id_map = self.with_conn(|conn| {
+            rows.iter()
+                .map(|(_, _, _, stable_id)| Ok((stable_id.clone(), generate_stable_id(conn)?)))
+                .collect::<CoreResult<HashMap<_, _>>>()
+        })?;
+        let mut docs = Vec::with_capacity(rows.len());
+        for (id, source_path, description, stable_id) in rows {
+            let mut content = self.read_doc_content(&source_path)?;
+            for (old_id, new_id) in &id_map {
+                content =
+                    content.replace(&format!("oc://doc/{old_id}"), &format!("oc://doc/{new_id}"));
+            }
+            let rel_path = retarget(&source_path);
+            let content = attachments::relink_content(&content, &source_path, &rel_path);
+            docs.push(PlannedDoc {
+                id,
+                rel_path,
+                source_path,
+                description,
+                stable_id: id_map[&stable_id].clone(),
+                content,
+            });
+        }
+
+        // Files first, then one transaction for the catalog rows; the copy
+        // is removed again if the transaction fails.
+        let target_abs = self.contained_path(&target)?;
+        let result = (|| -> CoreResult<()> {
+            for (_, source, _) in &folders {
+                fs::create_dir_all(self.contexts_root.join(retarget(source)))?;
+            }
+            for doc in &docs {
+                atomic::write_atomic(&self.contexts_root.join(&doc.rel_path), &doc.content)?;
+            }
+            self.with_conn(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                let ts = now_iso();
+                for (id, source, description) in &folders {
+                    let copy = retarget(source);
+                    let name = copy.split('/').next_back().unwrap_or(&copy);
+                    tx.execute(
+                        "INSERT INTO folders (parent_id, name, rel_path, abs_path, description, created_at, updated_at)
+                         VALUES ((SELECT id FROM folders WHERE rel_path = ?1), ?2, ?3, ?4, ?5, ?6, ?6)",
+                        params![
+                            parent_rel_path(&copy),
+                            name,
+                            copy,
+                            self.contexts_root.join(&copy).to_string_lossy(),
+                            description,
+                            ts
+                        ],
+                    )?;
+                    let folder_id = tx.last_insert_rowid();
+                    tx.execute(
+                        "INSERT INTO folder_properties (folder_id, key, value_type, value, updated_at)
+                         SELECT ?1, key, value_type, value, ?2 FROM folder_properties WHERE folder_id = ?3",
+                        params![folder_id, ts, id],
+                    )?;
+                    tx.execute(
+                        "INSERT INTO folder_templates (folder_id, template)
+                         SELECT ?1, template FROM folder_templates WHERE folder_id = ?2",
+                        params![folder_id, id],
+                    )?;
+                }
+                for doc in &docs {
+                    insert_doc_copy(&tx, &self.contexts_root, doc, &ts)?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+        })();
+        if let Err(err) = result {
+            let _ = fs::remove_dir_all(&target_abs);
+            return Err(err);
+        }
+
+        #[cfg(feature = "search")]
+        self.emit_folder_event(FolderEvent::Created {
+            rel_path: target.clone(),
+        });
+        self.finish_doc_copies(&docs)?;
+        let audited: Vec<AuditedDoc> = docs
+            .iter()
+            .map(|d| AuditedDoc {
+                stable_id: d.stable_id.clone(),
+                path: d.source_path.clone(),
+                new_path: Some(d.rel_path.clone()),
+            })
+            .collect();
+        self.audit_folder("copy_folder", &rel_path, Some(&target), &audited)?;
+
+        Ok(FolderCopied {
+            source_path: rel_path,
+            rel_path: target,
+            docs: docs
+                .into_iter()
+                .map(|d| DocCopied {
+                    source_path: d.source_path,
+                    rel_path: d.rel_path,
+                    stable_id: d.stable_id,
+                })
+                .collect(),
+        })
+    }
+
+    /// `rel_path`, or a suffixed sibling if it is taken and the policy
+    /// allows renaming.
+    fn free_path(&self, rel_path: &str, policy: ConflictPolicy) -> CoreResult<String> {
+        let taken = |rel: &str| -> CoreResult<bool> {
+            Ok(self.find_doc(rel)?.is_some()
+                || self.find_folder(rel)?.is_some()
+                || self.contexts_root.join(rel).exists())
+        };
+        if !taken(rel_path)? {
+            return Ok(rel_path.to_string());
+        }
+        match policy {
+            ConflictPolicy::Fail => Err(CoreError::Message(format!(
+                "\"{rel_path}\" already exists."
+            ))),
+            ConflictPolicy::Rename => {
+                let parent = parent_rel_path(rel_path).unwrap_or_default();
+                let name = rel_path.split('/').next_back().unwrap_or(rel_path);
+                let mut n = 1;
+                loop {
+                    let candidate = join_rel(&parent, &suffixed_name(name, n));
+                    if !taken(&candidate)? {
+                        return Ok(candidate);
+                    }
+                    n += 1;
+                }
+            }
+        }
+    }
+
+    /// Record the first version and links of freshly copied docs and
+    /// announce them.
+    fn finish_doc_copies(&self, docs: &[PlannedDoc]) -> CoreResult<()> {
+        let options = SaveDocOptions {
+            source: Some(COPY_SOURCE.to_string()),
+            ..Default::default()
+        };
+        for planned in docs {
+            if let Some(doc) = self.find_doc(&planned.rel_path)? {
+                self.record_doc_version(&doc, None, &planned.content, &options)?;
+                self.index_doc_links(&doc, &planned.content)?;
+            }
+            #[cfg(feature = "search")]
+            self.emit_doc_event(DocEvent::Created {
+                rel_path: planned.rel_path.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Insert the catalog row of a copied doc with the tags and properties of
+/// its original, and index its text.
+fn insert_doc_copy(
+    tx: &Transaction,
+    contexts_root: &std::path::Path,
+    doc: &PlannedDoc,
+    ts: &str,
+) -> CoreResult<()> {
+    let name = doc.rel_path.split('/').next_back().unwrap_or(&doc.rel_path);
+    tx.execute(
+        "INSERT INTO docs (folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at)
+         VALUES ((SELECT id FROM folders WHERE rel_path = ?1), ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![
+            parent_rel_path(&doc.rel_path).unwrap_or_default(),
+            name,
+            doc.rel_path,
+            contexts_root.join(&doc.rel_path).to_string_lossy(),
+            doc.description,
+            doc.stable_id,
+            ts
+        ],
+    )?;
+    let doc_id = tx.last_insert_rowid();
+    tx.execute(
+        "INSERT INTO doc_tags (doc_id, tag, created_at)
+         SELECT ?1, tag, ?2 FROM doc_tags WHERE doc_id = ?3",
+        params![doc_id, ts, doc.id],
+    )?;
+    tx.execute(
+        "INSERT INTO doc_properties (doc_id, key, value_type, value, updated_at)
+         SELECT ?1, key, value_type, value, ?2 FROM doc_properties WHERE doc_id = ?3",
+        params![doc_id, ts, doc.id],
+    )?;
+    fulltext::index_doc_text(tx, doc_id, &doc.content)?;
+    Ok(())
+}
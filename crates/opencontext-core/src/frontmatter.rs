@@ -0,0 +1,117 @@
+//! YAML frontmatter at the top of markdown documents.
+//!
+//! A frontmatter block starts with a `---` line on the first line of the
+//! file and ends with the next `---` (or `...`) line:
+//!
+//! ```markdown
+//! ---
+//! title: Payments ADR
+//! tags: [decision, payments]
+//! status: accepted
+//! ---
+//! # Body starts here
+//! ```
+
+use serde_json::{Map, Value};
+
+/// Parsed frontmatter fields, in document order
+pub type FrontmatterFields = Map<String, Value>;
+
+/// Frontmatter key mirrored to the catalog description when sync is on
+pub const DESCRIPTION_KEY: &str = "description";
+
+/// Split `content` into `(yaml, body, body_line_offset)`.
+///
+/// `yaml` is the text between the delimiters, `body` the rest of the
+/// document and `body_line_offset` the number of lines taken up by the
+/// frontmatter block. Without frontmatter the whole content is the body.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str, usize) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content, 0);
+    };
+    let mut offset = 0;
+    let mut lines = 1;
+    for line in rest.split_inclusive('\n') {
+        lines += 1;
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(yaml), body, lines);
+        }
+        offset += line.len();
+    }
+    // Unterminated block: treat the whole file as body.
+    (None, content, 0)
+}
+
+/// Parse the frontmatter of `content` into a JSON object.
+///
+/// Returns `None` when there is no frontmatter, it is not valid YAML or it
+/// is not a mapping.
+pub fn parse_frontmatter(content: &str) -> Option<FrontmatterFields> {
+    let (yaml, _, _) = split_frontmatter(content);
+    let yaml = yaml?;
+    if yaml.trim().is_empty() {
+        return Some(Map::new());
+    }
+    match serde_yaml::from_str::<Value>(yaml) {
+        Ok(Value::Object(fields)) => Some(fields),
+        _ => None,
+    }
+}
+
+/// Document body with any frontmatter removed
+pub fn strip_frontmatter(content: &str) -> &str {
+    split_frontmatter(content).1
+}
+
+/// Set a top-level string field in existing frontmatter, keeping the other
+/// lines as written. Returns `None` when `content` has no frontmatter.
+pub(crate) fn set_frontmatter_field(content: &str, key: &str, value: &str) -> Option<String> {
+    let (yaml, body, _) = split_frontmatter(content);
+    let yaml = yaml?;
+    let rendered = render_field(key, value);
+
+    let mut out_lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    let mut skipping = false;
+    for line in yaml.lines() {
+        if skipping {
+            // Continuation lines of the old value (block scalars, lists).
+            if line.starts_with(' ') || line.starts_with('\t') || line.is_empty() {
+                continue;
+            }
+            skipping = false;
+        }
+        if !replaced && is_key_line(line, key) {
+            out_lines.push(rendered.clone());
+            replaced = true;
+            skipping = true;
+            continue;
+        }
+        out_lines.push(line.to_string());
+    }
+    if !replaced {
+        out_lines.push(rendered);
+    }
+    Some(format!("---\n{}\n---\n{}", out_lines.join("\n"), body))
+}
+
+fn is_key_line(line: &str, key: &str) -> bool {
+    line.strip_prefix(key)
+        .map(|rest| rest.trim_start().starts_with(':'))
+        .unwrap_or(false)
+}
+
+fn render_field(key: &str, value: &str) -> String {
+    let mut map = serde_yaml::Mapping::new();
+    map.insert(key.into(), value.into());
+    serde_yaml::to_string(&map)
+        .unwrap_or_else(|_| format!("{key}: {value:?}\n"))
+        .trim_end()
+        .to_string()
+}
@@ -0,0 +1,265 @@
+//! SQLite FTS5 full-text index over document names, descriptions and
+//! content.
+//!
+//! The `docs_fts` table shares its rowid with `docs.id`. Deleting a doc row
+//! drops its index row through a trigger; content, names and descriptions
+//! are written from Rust so CJK text can be segmented first.
+//!
+//! The `unicode61` tokenizer keeps an unbroken run of CJK characters as a
+//! single token, so every CJK character is stored as its own token
+//! (separated by U+200B) and queries are matched as phrases of those
+//! characters.
+
+use std::fs;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::{folder_not_found, normalize_folder_path, CoreError, CoreResult, OpenContext};
+
+/// Separator inserted around CJK characters in indexed text
+const CJK_SEPARATOR: char = '\u{200B}';
+const SNIPPET_OPEN: &str = "<mark>";
+const SNIPPET_CLOSE: &str = "</mark>";
+const DEFAULT_LIMIT: usize = 20;
+
+/// Optional parameters for `full_text_search`
+#[derive(Debug, Clone, Default)]
+pub struct FullTextSearchOptions {
+    /// Maximum number of hits (default 20)
+    pub limit: Option<usize>,
+    /// Only search docs under this folder
+    pub folder_path: Option<String>,
+    /// Rank pinned docs above the others, each group by relevance
+    pub pinned_first: bool,
+}
+
+/// A document matching a full-text query
+#[derive(Debug, Clone, Serialize)]
+pub struct FullTextHit {
+    pub rel_path: String,
+    pub stable_id: String,
+    pub name: String,
+    pub description: String,
+    /// Content excerpt with matches wrapped in `<mark>` tags
+    pub snippet: String,
+    /// BM25 relevance, higher is better
+    pub score: f64,
+}
+
+impl OpenContext {
+    /// Search document names, descriptions and content.
+    ///
+    /// Whitespace-separated terms must all match; each term is matched as a
+    /// phrase, so FTS5 query syntax in `query` is treated as plain text.
+    pub fn full_text_search(
+        &self,
+        query: &str,
+        options: &FullTextSearchOptions,
+    ) -> CoreResult<Vec<FullTextHit>> {
+        let match_expr = build_match_query(query)
+            .ok_or_else(|| CoreError::Message("Search query cannot be empty.".into()))?;
+        let limit = match options.limit {
+            Some(0) => {
+                return Err(CoreError::Message(
+                    "limit must be a positive integer".into(),
+                ))
+            }
+            Some(l) => l,
+            None => DEFAULT_LIMIT,
+        };
+        let prefix = match options.folder_path.as_deref() {
+            Some(path) => {
+                let rel_path = normalize_folder_path(Some(path))?;
+                self.find_folder(&rel_path)?
+                    .ok_or_else(|| folder_not_found(&rel_path))?;
+                if rel_path.is_empty() {
+                    String::new()
+                } else {
+                    format!("{rel_path}/")
+                }
+            }
+            None => String::new(),
+        };
+        let pinned_first = if options.pinned_first {
+            "EXISTS (SELECT 1 FROM doc_pins p WHERE p.doc_id = d.id) DESC,"
+        } else {
+            ""
+        };
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT d.rel_path, d.stable_id, d.name, d.description,
+                        snippet(docs_fts, 2, ?1, ?2, '…', 24),
+                        bm25(docs_fts, 5.0, 2.0, 1.0)
+                 FROM docs_fts JOIN docs d ON d.id = docs_fts.rowid
+                 WHERE docs_fts MATCH ?3 AND substr(d.rel_path, 1, length(?4)) = ?4
+                 ORDER BY {pinned_first} bm25(docs_fts, 5.0, 2.0, 1.0)
+                 LIMIT ?5"
+            ))?;
+            let hits = stmt
+                .query_map(
+                    params![
+                        SNIPPET_OPEN,
+                        SNIPPET_CLOSE,
+                        match_expr,
+                        prefix,
+                        limit as i64
+                    ],
+                    |row| {
+                        Ok(FullTextHit {
+                            rel_path: row.get(0)?,
+                            stable_id: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                            name: row.get(2)?,
+                            description: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                            snippet: unsegment(&row.get::<_, String>(4)?),
+                            score: -row.get::<_, f64>(5)?,
+                        })
+                    },
+                )?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(hits)
+        })
+    }
+
+    /// Re-read every document from disk into the full-text index.
+    /// Returns the number of indexed docs.
+    pub fn rebuild_full_text_index(&self) -> CoreResult<usize> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute("DELETE FROM docs_fts", [])?;
+            let count = index_missing_docs(&tx)?;
+            tx.commit()?;
+            Ok(count)
+        })
+    }
+}
+
+/// Replace the index row of one doc with `content` and its current name
+/// and description.
+pub(crate) fn index_doc_text(conn: &Connection, doc_id: i64, content: &str) -> CoreResult<()> {
+    let row: Option<(String, Option<String>)> = conn
+        .query_row(
+            "SELECT name, description FROM docs WHERE id = ?1",
+            [doc_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((name, description)) = row else {
+        return Ok(());
+    };
+    conn.execute("DELETE FROM docs_fts WHERE rowid = ?1", [doc_id])?;
+    conn.execute(
+        "INSERT INTO docs_fts (rowid, name, description, content) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            doc_id,
+            segment(&name),
+            segment(description.as_deref().unwrap_or("")),
+            segment(content)
+        ],
+    )?;
+    Ok(())
+}
+
+/// Index one doc using the content currently on disk.
+pub(crate) fn index_doc_file(conn: &Connection, doc_id: i64) -> CoreResult<()> {
+    let abs_path: Option<String> = conn
+        .query_row("SELECT abs_path FROM docs WHERE id = ?1", [doc_id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    let content = abs_path
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    index_doc_text(conn, doc_id, &content)
+}
+
+/// Refresh the name and description of an indexed doc, keeping its content.
+pub(crate) fn index_doc_fields(conn: &Connection, doc_id: i64) -> CoreResult<()> {
+    let row: Option<(String, Option<String>)> = conn
+        .query_row(
+            "SELECT name, description FROM docs WHERE id = ?1",
+            [doc_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((name, description)) = row else {
+        return Ok(());
+    };
+    let updated = conn.execute(
+        "UPDATE docs_fts SET name = ?1, description = ?2 WHERE rowid = ?3",
+        params![
+            segment(&name),
+            segment(description.as_deref().unwrap_or("")),
+            doc_id
+        ],
+    )?;
+    if updated == 0 {
+        index_doc_file(conn, doc_id)?;
+    }
+    Ok(())
+}
+
+/// Index every doc that has no index row yet. Used to backfill catalogs
+/// created before the index existed.
+pub(crate) fn index_missing_docs(conn: &Connection) -> CoreResult<usize> {
+    let mut stmt =
+        conn.prepare("SELECT id FROM docs WHERE id NOT IN (SELECT rowid FROM docs_fts)")?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in &ids {
+        index_doc_file(conn, *id)?;
+    }
+    Ok(ids.len())
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // Hiragana, Katakana
+        | 0x3400..=0x4DBF    // CJK Extension A
+        | 0x4E00..=0x9FFF    // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF    // Hangul syllables
+        | 0xF900..=0xFAFF    // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F) // CJK Extensions B-F, Compatibility Supplement
+}
+
+/// Surround every CJK character with `CJK_SEPARATOR` so the tokenizer sees
+/// one token per character.
+fn segment(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 2);
+    let mut prev_cjk = false;
+    for c in text.chars() {
+        let cjk = is_cjk(c);
+        if (cjk || prev_cjk) && !out.is_empty() && !c.is_whitespace() {
+            out.push(CJK_SEPARATOR);
+        }
+        out.push(c);
+        prev_cjk = cjk;
+    }
+    out
+}
+
+/// Undo `segment` on text read back from the index.
+fn unsegment(text: &str) -> String {
+    text.chars().filter(|c| *c != CJK_SEPARATOR).collect()
+}
+
+/// Turn free text into an FTS5 query: each whitespace-separated term
+/// becomes a quoted phrase, and all phrases must match.
+fn build_match_query(query: &str) -> Option<String> {
+    let phrases: Vec<String> = query
+        .split_whitespace()
+        .map(|term| {
+            let tokens: String = segment(term)
+                .chars()
+                .map(|c| if c == CJK_SEPARATOR { ' ' } else { c })
+                .collect();
+            format!("\"{}\"", tokens.replace('"', "\"\""))
+        })
+        .collect();
+    if phrases.is_empty() {
+        None
+    } else {
+        Some(phrases.join(" "))
+    }
+}
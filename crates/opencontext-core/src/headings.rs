@@ -0,0 +1,153 @@
+//! Markdown heading paths and the anchors derived from them.
+//!
+//! The search chunker labels chunks with the same heading path that
+//! `oc://` URIs use for their `#anchor`, so a search hit can be cited as a
+//! section.
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+use crate::split_frontmatter;
+
+/// Headings enclosing the current position in a markdown document
+#[derive(Debug, Default)]
+pub(crate) struct HeadingPath {
+    headings: Vec<(HeadingLevel, String)>,
+}
+
+impl HeadingPath {
+    /// Enter a heading, leaving headings at the same or a deeper level.
+    pub(crate) fn enter(&mut self, level: HeadingLevel, text: &str) {
+        while let Some((last_level, _)) = self.headings.last() {
+            if *last_level >= level {
+                self.headings.pop();
+            } else {
+                break;
+            }
+        }
+        self.headings.push((level, text.trim().to_string()));
+    }
+
+    /// Heading titles joined with " > "
+    pub(crate) fn joined(&self) -> String {
+        self.headings
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+
+    /// Slugs of the heading titles joined with "/"
+    pub(crate) fn anchor(&self) -> String {
+        self.headings
+            .iter()
+            .map(|(_, text)| slugify(text))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// A heading and the lines it spans, subsections included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Section {
+    pub heading_path: String,
+    /// Unique within the document; repeated heading paths get a `-1`,
+    /// `-2`, ... suffix
+    pub anchor: String,
+    pub title: String,
+    /// 1-based line of the heading in the original file
+    pub start_line: usize,
+    /// 1-based last non-blank line before the next heading at the same or
+    /// a higher level
+    pub end_line: usize,
+}
+
+/// Sections of a markdown document in document order. Frontmatter is
+/// skipped; line numbers refer to the original file.
+pub(crate) fn sections(content: &str) -> Vec<Section> {
+    let (_, body, frontmatter_lines) = split_frontmatter(content);
+    let line_of = |offset: usize| frontmatter_lines + body[..offset].matches('\n').count() + 1;
+
+    let mut path = HeadingPath::default();
+    let mut found: Vec<(HeadingLevel, Section)> = Vec::new();
+    let mut heading: Option<(HeadingLevel, usize, String)> = None;
+    for (event, range) in Parser::new(body).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((level, line_of(range.start), String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, start_line, text)) = heading.take() {
+                    path.enter(level, &text);
+                    found.push((
+                        level,
+                        Section {
+                            heading_path: path.joined(),
+                            anchor: path.anchor(),
+                            title: text.trim().to_string(),
+                            start_line,
+                            end_line: start_line,
+                        },
+                    ));
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, buf)) = heading.as_mut() {
+                    buf.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((_, _, buf)) = heading.as_mut() {
+                    buf.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut seen: Vec<String> = Vec::new();
+    let mut sections = Vec::with_capacity(found.len());
+    for (i, (level, section)) in found.iter().enumerate() {
+        let next = found[i + 1..]
+            .iter()
+            .find(|(next_level, _)| next_level <= level)
+            .map(|(_, next)| next.start_line - 1)
+            .unwrap_or(lines.len());
+        let mut end_line = next.max(section.start_line);
+        while end_line > section.start_line && lines[end_line - 1].trim().is_empty() {
+            end_line -= 1;
+        }
+        let repeats = seen.iter().filter(|a| **a == section.anchor).count();
+        seen.push(section.anchor.clone());
+        let anchor = if repeats == 0 {
+            section.anchor.clone()
+        } else {
+            format!("{}-{repeats}", section.anchor)
+        };
+        sections.push(Section {
+            anchor,
+            end_line,
+            ..section.clone()
+        });
+    }
+    sections
+}
+
+/// Lowercase `text`, keep letters, digits and `_`, turn whitespace and `-`
+/// runs into a single `-` and drop everything else.
+pub(crate) fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
@@ -0,0 +1,246 @@
+//! Document version history.
+//!
+//! Every `save_doc_content` records a snapshot of the written content in the
+//! `doc_versions` table, keyed by the doc's stable id so history follows the
+//! document across renames and moves.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use similar::{ChangeTag, TextDiff};
+
+use crate::{now_iso, CoreError, CoreResult, Doc, DocSaved, OpenContext, SaveDocOptions};
+
+/// Source recorded for content found on disk that was never saved through
+/// OpenContext (e.g. edited in another editor).
+pub(crate) const EXTERNAL_SOURCE: &str = "external";
+
+/// Source recorded for snapshots written by `restore_doc_version`.
+const RESTORE_SOURCE: &str = "restore";
+
+/// Metadata of a single recorded version
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DocVersion {
+    pub version_id: i64,
+    pub stable_id: String,
+    /// Document path at the time the version was recorded
+    pub rel_path: String,
+    pub content_hash: String,
+    pub size: i64,
+    pub author: Option<String>,
+    pub source: Option<String>,
+    pub created_at: String,
+}
+
+/// A recorded version together with its content
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DocVersionContent {
+    #[serde(flatten)]
+    pub version: DocVersion,
+    pub content: String,
+}
+
+/// Kind of change for a diff line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// One line of a version diff
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Line diff between two versions of a document
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DocVersionDiff {
+    pub stable_id: String,
+    pub from_version: i64,
+    pub to_version: i64,
+    pub lines: Vec<DiffLine>,
+    /// The same diff rendered in unified format
+    pub unified: String,
+}
+
+impl OpenContext {
+    /// List recorded versions of a document, newest first.
+    pub fn list_doc_versions(&self, stable_id: &str) -> CoreResult<Vec<DocVersion>> {
+        let doc = self.get_doc_by_stable_id(stable_id)?;
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, stable_id, rel_path, content_hash, LENGTH(CAST(content AS BLOB)), author, source, created_at
+                 FROM doc_versions WHERE stable_id = ?1 ORDER BY id DESC",
+            )?;
+            let rows = stmt
+                .query_map([&doc.stable_id], row_to_version)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// Fetch a single recorded version including its content.
+    pub fn get_doc_version(
+        &self,
+        stable_id: &str,
+        version_id: i64,
+    ) -> CoreResult<DocVersionContent> {
+        let cleaned = stable_id.trim();
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, stable_id, rel_path, content_hash, LENGTH(CAST(content AS BLOB)), author, source, created_at, content
+                 FROM doc_versions WHERE stable_id = ?1 AND id = ?2",
+            )?;
+            stmt.query_row(params![cleaned, version_id], |row| {
+                Ok(DocVersionContent {
+                    version: row_to_version(row)?,
+                    content: row.get(8)?,
+                })
+            })
+            .optional()?
+            .ok_or_else(|| version_not_found(cleaned, version_id))
+        })
+    }
+
+    /// Compute a line diff from `from_version` to `to_version`.
+    pub fn diff_doc_versions(
+        &self,
+        stable_id: &str,
+        from_version: i64,
+        to_version: i64,
+    ) -> CoreResult<DocVersionDiff> {
+        let from = self.get_doc_version(stable_id, from_version)?;
+        let to = self.get_doc_version(stable_id, to_version)?;
+        let diff = TextDiff::from_lines(&from.content, &to.content);
+        let lines = diff
+            .iter_all_changes()
+            .map(|change| DiffLine {
+                op: match change.tag() {
+                    ChangeTag::Equal => DiffOp::Equal,
+                    ChangeTag::Insert => DiffOp::Insert,
+                    ChangeTag::Delete => DiffOp::Delete,
+                },
+                text: change.value().trim_end_matches('\n').to_string(),
+            })
+            .collect();
+        let unified = diff
+            .unified_diff()
+            .header(
+                &format!("{}@{}", from.version.rel_path, from_version),
+                &format!("{}@{}", to.version.rel_path, to_version),
+            )
+            .to_string();
+        Ok(DocVersionDiff {
+            stable_id: from.version.stable_id,
+            from_version,
+            to_version,
+            lines,
+            unified,
+        })
+    }
+
+    /// Write a recorded version back to the document.
+    ///
+    /// The restore is itself saved as a new version, so it can be undone.
+    pub fn restore_doc_version(&self, stable_id: &str, version_id: i64) -> CoreResult<DocSaved> {
+        let version = self.get_doc_version(stable_id, version_id)?;
+        let doc = self.get_doc_by_stable_id(stable_id)?;
+        self.save_doc_content_with(
+            &doc.rel_path,
+            &version.content,
+            SaveDocOptions {
+                source: Some(RESTORE_SOURCE.to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Record a snapshot of `content` for `doc`.
+    ///
+    /// If the file on disk differs from the latest recorded version (or no
+    /// version exists yet), the on-disk content is snapshotted first so that
+    /// external edits are never lost by an overwrite.
+    pub(crate) fn record_doc_version(
+        &self,
+        doc: &Doc,
+        previous: Option<&str>,
+        content: &str,
+        options: &SaveDocOptions,
+    ) -> CoreResult<()> {
+        let ts = now_iso();
+        self.with_conn(|conn| {
+            let latest_hash: Option<String> = conn
+                .query_row(
+                    "SELECT content_hash FROM doc_versions WHERE stable_id = ?1 ORDER BY id DESC LIMIT 1",
+                    [&doc.stable_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(previous) = previous {
+                let previous_hash = content_hash(previous);
+                let untracked = match latest_hash.as_deref() {
+                    Some(hash) => hash != previous_hash,
+                    None => !previous.is_empty(),
+                };
+                if untracked {
+                    insert_version(conn, doc, previous, &previous_hash, None, Some(EXTERNAL_SOURCE), &ts)?;
+                }
+            }
+            let hash = content_hash(content);
+            insert_version(
+                conn,
+                doc,
+                content,
+                &hash,
+                options.author.as_deref(),
+                options.source.as_deref(),
+                &ts,
+            )
+        })
+    }
+}
+
+/// Hex-encoded SHA-256 of the document content.
+pub(crate) fn content_hash(content: &str) -> String {
+    let digest = Sha256::digest(content.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn insert_version(
+    conn: &Connection,
+    doc: &Doc,
+    content: &str,
+    hash: &str,
+    author: Option<&str>,
+    source: Option<&str>,
+    ts: &str,
+) -> CoreResult<()> {
+    conn.execute(
+        "INSERT INTO doc_versions (stable_id, rel_path, content, content_hash, author, source, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![doc.stable_id, doc.rel_path, content, hash, author, source, ts],
+    )?;
+    Ok(())
+}
+
+fn row_to_version(row: &rusqlite::Row<'_>) -> rusqlite::Result<DocVersion> {
+    Ok(DocVersion {
+        version_id: row.get(0)?,
+        stable_id: row.get(1)?,
+        rel_path: row.get(2)?,
+        content_hash: row.get(3)?,
+        size: row.get(4)?,
+        author: row.get(5)?,
+        source: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+fn version_not_found(stable_id: &str, version_id: i64) -> CoreError {
+    CoreError::Message(format!(
+        "Version {version_id} of document \"{stable_id}\" not found."
+    ))
+}
@@ -0,0 +1,400 @@
+//! Ideas: short entries kept in thread docs under `.ideas/<box>/`.
+//!
+//! A thread is a markdown doc at
+//! `.ideas/<box>/<YYYY>/<MM>/<YYYYMMDD>-<slug>-<ts>.md` holding one or more
+//! entries, each introduced by a hidden marker line:
+//!
+//! ```text
+//! [//]: # (idea:id=<uuid> created_at=<ISO 8601> [is_ai=true])
+//! ```
+//!
+//! Threads directly under `.ideas/<YYYY>/` predate boxes and belong to the
+//! `inbox` box. Every write goes through `save_doc_content_with` or
+//! `remove_doc`, so the usual doc events keep search indexing incremental.
+
+use chrono::{Local, Utc};
+use serde::Serialize;
+
+use crate::{generate_stable_id, now_iso, CoreError, CoreResult, OpenContext, SaveDocOptions};
+
+pub const IDEAS_ROOT: &str = ".ideas";
+pub const DEFAULT_IDEA_BOX: &str = "inbox";
+
+const MARKER_PREFIX: &str = "[//]: # (";
+const SLUG_MAX_CHARS: usize = 30;
+
+/// One idea entry
+#[derive(Debug, Clone, Serialize)]
+pub struct Idea {
+    pub id: String,
+    pub created_at: String,
+    pub text: String,
+    pub is_ai: bool,
+    #[serde(rename = "box")]
+    pub idea_box: String,
+    /// Thread doc holding the entry
+    pub rel_path: String,
+}
+
+/// Optional parameters for `add_idea_with`
+#[derive(Debug, Clone, Default)]
+pub struct AddIdeaOptions {
+    /// Append to this thread instead of starting a new one
+    pub thread_path: Option<String>,
+    /// Written by an agent rather than the user
+    pub is_ai: bool,
+}
+
+/// Inclusive `YYYY-MM-DD` bounds on an idea's UTC creation date
+#[derive(Debug, Clone, Default)]
+pub struct IdeaDateRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// An entry parsed from a thread doc
+#[derive(Debug, Clone)]
+pub(crate) struct IdeaEntry {
+    pub(crate) id: String,
+    pub(crate) created_at: String,
+    pub(crate) is_ai: bool,
+    pub(crate) content: String,
+}
+
+impl OpenContext {
+    /// Start a new thread in `idea_box` with one idea.
+    pub fn add_idea(&self, idea_box: &str, text: &str) -> CoreResult<Idea> {
+        self.add_idea_with(idea_box, text, &AddIdeaOptions::default())
+    }
+
+    /// Add an idea, to a new thread in `idea_box` or appended to
+    /// `options.thread_path`.
+    pub fn add_idea_with(
+        &self,
+        idea_box: &str,
+        text: &str,
+        options: &AddIdeaOptions,
+    ) -> CoreResult<Idea> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(CoreError::Message("Idea text cannot be empty.".into()));
+        }
+        let entry = IdeaEntry {
+            id: self.with_conn(generate_stable_id)?,
+            created_at: now_iso(),
+            is_ai: options.is_ai,
+            content: text.to_string(),
+        };
+        match options.thread_path.as_deref() {
+            Some(thread_path) => {
+                let (rel_path, mut entries) = self.read_thread(thread_path)?;
+                entries.push(entry.clone());
+                self.write_thread(&rel_path, &entries)?;
+                Ok(to_idea(&rel_path, entry))
+            }
+            None => {
+                let rel_path = self.create_thread(idea_box, &entry)?;
+                Ok(to_idea(&rel_path, entry))
+            }
+        }
+    }
+
+    /// Ideas in `idea_box` (all boxes when `None`) created within `range`,
+    /// newest first.
+    pub fn list_ideas(
+        &self,
+        idea_box: Option<&str>,
+        range: &IdeaDateRange,
+    ) -> CoreResult<Vec<Idea>> {
+        if let Some(name) = idea_box {
+            validate_box(name)?;
+        }
+        let mut ideas = Vec::new();
+        for rel_path in self.thread_paths()? {
+            let thread_box = extract_idea_box(&rel_path).unwrap_or_default();
+            if idea_box.is_some_and(|b| b != thread_box) {
+                continue;
+            }
+            let content = self.read_doc_content(&rel_path)?;
+            for entry in parse_idea_entries(&content) {
+                let date = entry.created_at.get(0..10).unwrap_or("");
+                if range.from.as_deref().is_some_and(|from| date < from)
+                    || range.to.as_deref().is_some_and(|to| date > to)
+                {
+                    continue;
+                }
+                ideas.push(to_idea(&rel_path, entry));
+            }
+        }
+        ideas.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(ideas)
+    }
+
+    pub fn get_idea(&self, id: &str) -> CoreResult<Idea> {
+        let (rel_path, entries, index) = self.find_idea(id)?;
+        Ok(to_idea(&rel_path, entries[index].clone()))
+    }
+
+    /// Replace the text of an idea, keeping its id and creation time.
+    pub fn update_idea(&self, id: &str, text: &str) -> CoreResult<Idea> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(CoreError::Message("Idea text cannot be empty.".into()));
+        }
+        let (rel_path, mut entries, index) = self.find_idea(id)?;
+        entries[index].content = text.to_string();
+        self.write_thread(&rel_path, &entries)?;
+        Ok(to_idea(&rel_path, entries[index].clone()))
+    }
+
+    /// Delete an idea. A thread left without ideas is removed.
+    pub fn delete_idea(&self, id: &str) -> CoreResult<()> {
+        let (rel_path, mut entries, index) = self.find_idea(id)?;
+        entries.remove(index);
+        self.write_or_remove_thread(&rel_path, &entries)
+    }
+
+    /// Move an idea into a new thread in `to_box`, keeping its id and
+    /// creation time.
+    pub fn move_idea(&self, id: &str, to_box: &str) -> CoreResult<Idea> {
+        validate_box(to_box)?;
+        let (rel_path, mut entries, index) = self.find_idea(id)?;
+        if extract_idea_box(&rel_path).as_deref() == Some(to_box) {
+            return Ok(to_idea(&rel_path, entries[index].clone()));
+        }
+        let entry = entries.remove(index);
+        let new_path = self.create_thread(to_box, &entry)?;
+        self.write_or_remove_thread(&rel_path, &entries)?;
+        Ok(to_idea(&new_path, entry))
+    }
+
+    fn create_thread(&self, idea_box: &str, entry: &IdeaEntry) -> CoreResult<String> {
+        let idea_box = if idea_box.trim().is_empty() {
+            DEFAULT_IDEA_BOX
+        } else {
+            idea_box.trim()
+        };
+        validate_box(idea_box)?;
+        let now = Local::now();
+        let folder = format!("{IDEAS_ROOT}/{idea_box}/{}", now.format("%Y/%m"));
+        let title: String = entry.content.chars().take(20).collect();
+        let name = format!(
+            "{}-{}-{}.md",
+            now.format("%Y%m%d"),
+            slugify(&title),
+            to_base36(Utc::now().timestamp_millis().max(0) as u64)
+        );
+        self.ensure_folder_record(&folder)?;
+        let created = self.create_empty_doc(&folder, &name, None)?;
+        self.write_thread(&created.rel_path, std::slice::from_ref(entry))?;
+        Ok(created.rel_path)
+    }
+
+    fn read_thread(&self, thread_path: &str) -> CoreResult<(String, Vec<IdeaEntry>)> {
+        let rel_path = thread_path.trim().trim_start_matches("./").to_string();
+        if extract_idea_box(&rel_path).is_none() {
+            return Err(CoreError::Message(format!(
+                "\"{rel_path}\" is not an idea thread."
+            )));
+        }
+        let content = self.read_doc_content(&rel_path)?;
+        Ok((rel_path, parse_idea_entries(&content)))
+    }
+
+    fn write_thread(&self, rel_path: &str, entries: &[IdeaEntry]) -> CoreResult<()> {
+        self.save_doc_content_with(
+            rel_path,
+            &serialize_idea_entries(entries),
+            SaveDocOptions {
+                source: Some("ideas".into()),
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+
+    fn write_or_remove_thread(&self, rel_path: &str, entries: &[IdeaEntry]) -> CoreResult<()> {
+        if entries.is_empty() {
+            self.remove_doc(rel_path)?;
+            Ok(())
+        } else {
+            self.write_thread(rel_path, entries)
+        }
+    }
+
+    fn find_idea(&self, id: &str) -> CoreResult<(String, Vec<IdeaEntry>, usize)> {
+        let id = id.trim();
+        for rel_path in self.thread_paths()? {
+            let content = self.read_doc_content(&rel_path)?;
+            let entries = parse_idea_entries(&content);
+            if let Some(index) = entries.iter().position(|e| e.id == id) {
+                return Ok((rel_path, entries, index));
+            }
+        }
+        Err(CoreError::Message(format!("Idea \"{id}\" not found.")))
+    }
+
+    /// Markdown docs under `.ideas/`
+    fn thread_paths(&self) -> CoreResult<Vec<String>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT rel_path FROM docs WHERE rel_path LIKE ?1 AND rel_path LIKE '%.md'
+                 ORDER BY rel_path",
+            )?;
+            let paths = stmt
+                .query_map([format!("{IDEAS_ROOT}/%")], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            Ok(paths)
+        })
+    }
+}
+
+fn to_idea(rel_path: &str, entry: IdeaEntry) -> Idea {
+    Idea {
+        id: entry.id,
+        created_at: entry.created_at,
+        text: entry.content,
+        is_ai: entry.is_ai,
+        idea_box: extract_idea_box(rel_path).unwrap_or_default(),
+        rel_path: rel_path.to_string(),
+    }
+}
+
+fn parse_idea_marker(line: &str) -> Option<(String, String, bool)> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with(MARKER_PREFIX) || !trimmed.ends_with(')') {
+        return None;
+    }
+    let inner = trimmed.strip_prefix(MARKER_PREFIX)?.strip_suffix(')')?;
+    let mut id = None;
+    let mut created_at = None;
+    let mut is_ai = false;
+    for part in inner.split_whitespace() {
+        if let Some(value) = part.strip_prefix("idea:id=") {
+            id = Some(value.to_string());
+        } else if let Some(value) = part.strip_prefix("created_at=") {
+            created_at = Some(value.to_string());
+        } else if let Some(value) = part.strip_prefix("is_ai=") {
+            is_ai = value == "true";
+        }
+    }
+    match (id, created_at) {
+        (Some(id), Some(created_at)) => Some((id, created_at, is_ai)),
+        _ => None,
+    }
+}
+
+/// Split a thread doc into its entries. Text before the first marker is
+/// ignored.
+pub(crate) fn parse_idea_entries(content: &str) -> Vec<IdeaEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<(String, String, bool)> = None;
+    let mut buffer: Vec<&str> = Vec::new();
+
+    for line in content.lines() {
+        if let Some(marker) = parse_idea_marker(line) {
+            if let Some((id, created_at, is_ai)) = current.take() {
+                entries.push(IdeaEntry {
+                    id,
+                    created_at,
+                    is_ai,
+                    content: buffer.join("\n").trim().to_string(),
+                });
+            }
+            buffer.clear();
+            current = Some(marker);
+            continue;
+        }
+        if current.is_some() {
+            buffer.push(line);
+        }
+    }
+
+    if let Some((id, created_at, is_ai)) = current {
+        entries.push(IdeaEntry {
+            id,
+            created_at,
+            is_ai,
+            content: buffer.join("\n").trim().to_string(),
+        });
+    }
+
+    entries
+}
+
+/// Inverse of `parse_idea_entries`, in the format the desktop app writes.
+pub(crate) fn serialize_idea_entries(entries: &[IdeaEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let ai = if entry.is_ai { " is_ai=true" } else { "" };
+            format!(
+                "{MARKER_PREFIX}idea:id={} created_at={}{ai})\n{}",
+                entry.id, entry.created_at, entry.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Box of a path under `.ideas/`, or `None` for other paths.
+pub(crate) fn extract_idea_box(rel_path: &str) -> Option<String> {
+    let normalized = rel_path.trim_start_matches("./");
+    let rest = normalized.strip_prefix(IDEAS_ROOT)?.strip_prefix('/')?;
+    match rest.split('/').find(|p| !p.is_empty()) {
+        Some(seg) if !is_year(seg) => Some(seg.to_string()),
+        _ => Some(DEFAULT_IDEA_BOX.to_string()),
+    }
+}
+
+fn is_year(segment: &str) -> bool {
+    segment.len() == 4 && segment.chars().all(|c| c.is_ascii_digit())
+}
+
+fn validate_box(name: &str) -> CoreResult<()> {
+    let valid = !name.trim().is_empty()
+        && !name.contains(['/', '\\'])
+        && !name.starts_with('.')
+        && !is_year(name);
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::Message(format!("Invalid idea box \"{name}\".")))
+    }
+}
+
+/// Lowercase letters, digits and dashes, as the desktop app names threads.
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug
+        .trim_end_matches('-')
+        .chars()
+        .take(SLUG_MAX_CHARS)
+        .collect();
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug
+    }
+}
+
+fn to_base36(mut value: u64) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut out = Vec::new();
+    loop {
+        out.push(DIGITS[(value % 36) as usize]);
+        value /= 36;
+        if value == 0 {
+            break;
+        }
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap_or_default()
+}
@@ -5,7 +5,7 @@ mod tests;
 
 use chrono::{SecondsFormat, Utc};
 use parking_lot::Mutex;
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
 use std::{env, fs, path::PathBuf, sync::Arc};
 use thiserror::Error;
 
@@ -17,9 +17,60 @@ pub mod events;
 #[cfg(feature = "search")]
 pub mod search;
 
+// Filesystem watcher (enabled with "watch" feature)
+#[cfg(feature = "watch")]
+pub mod watcher;
+
 #[cfg(feature = "search")]
 use events::{DocEvent, FolderEvent, SharedEventBus};
 
+mod access;
+mod atomic;
+mod attachments;
+mod audit;
+mod bundle;
+mod context_pack;
+mod copy;
+mod frontmatter;
+mod fulltext;
+mod headings;
+mod history;
+mod ideas;
+mod links;
+mod manifest;
+mod migrations;
+mod paths;
+mod properties;
+mod reconcile;
+mod tags;
+mod templates;
+mod trash;
+mod uri;
+mod workspace;
+
+pub use access::{AccessedDoc, DocAccessOptions};
+pub use attachments::{Attachment, AttachmentAdded, AttachmentGcOptions};
+pub use audit::{AuditEntry, AuditLogOptions, AuditTarget, AuditedDoc};
+pub use bundle::{BundleExported, BundleImported};
+pub use context_pack::{
+    ContextPack, ContextPackOptions, ContextPackSource, PackInclusion, PackStrategy, PackedDoc,
+};
+pub use copy::{DocCopied, FolderCopied};
+pub use frontmatter::{parse_frontmatter, split_frontmatter, strip_frontmatter, FrontmatterFields};
+pub use fulltext::{FullTextHit, FullTextSearchOptions};
+pub use history::{DiffLine, DiffOp, DocVersion, DocVersionContent, DocVersionDiff};
+pub use ideas::{AddIdeaOptions, Idea, IdeaDateRange, DEFAULT_IDEA_BOX, IDEAS_ROOT};
+pub use links::{DocLink, LinkKind, LinkRewrite};
+pub use manifest::{render_manifest_entries, ManifestFormat, ManifestSort};
+pub use migrations::SCHEMA_VERSION;
+pub use properties::{Properties, PropertyFilter, PropertyOp, PropertySort, PropertyValue};
+pub use reconcile::{ReconcileOptions, ReconcileReport};
+pub use tags::{TagCount, TagMatch};
+pub use templates::TemplateInfo;
+pub use trash::{ConflictPolicy, TrashEntry, TrashKind, TrashRestored};
+pub use uri::ResolvedUri;
+pub use workspace::{Workspace, WorkspaceManager, DEFAULT_WORKSPACE};
+
 #[derive(Debug, Error)]
 pub enum CoreError {
     #[error("{0}")]
@@ -28,6 +79,12 @@ pub enum CoreError {
     Db(#[from] rusqlite::Error),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    /// The document changed since the caller read it
+    #[error("Document \"{rel_path}\" was modified since it was read (current revision {current_revision}).")]
+    Conflict {
+        rel_path: String,
+        current_revision: String,
+    },
 }
 
 pub type CoreResult<T> = Result<T, CoreError>;
@@ -36,7 +93,13 @@ pub type CoreResult<T> = Result<T, CoreError>;
 pub struct OpenContext {
     contexts_root: PathBuf,
     db_path: PathBuf,
+    templates_root: PathBuf,
     conn: Arc<Mutex<Connection>>,
+    /// Mirror frontmatter `description` and the catalog description
+    frontmatter_sync: bool,
+    /// Client recorded in the audit log for changes made through this
+    /// context
+    source: Option<String>,
     #[cfg(feature = "search")]
     event_bus: Option<SharedEventBus>,
 }
@@ -52,6 +115,7 @@ pub struct EnvOverrides {
 pub struct EnvInfo {
     pub contexts_root: PathBuf,
     pub db_path: PathBuf,
+    pub templates_root: PathBuf,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -77,6 +141,12 @@ pub struct Doc {
     pub stable_id: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Parsed YAML frontmatter (only filled by `get_doc_meta`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frontmatter: Option<FrontmatterFields>,
+    /// Content hash of the file on disk (only filled by `get_doc_meta`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -87,6 +157,8 @@ pub struct DocManifestEntry {
     pub stable_id: String,
     pub description: String,
     pub updated_at: String,
+    pub tags: Vec<String>,
+    pub properties: Properties,
 }
 
 impl OpenContext {
@@ -110,46 +182,42 @@ impl OpenContext {
             fs::create_dir_all(parent)?;
         }
 
-        let conn = Connection::open(&db_path)?;
+        let mut conn = Connection::open(&db_path)?;
         conn.pragma_update(None, "foreign_keys", "ON")?;
-        conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS folders (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                parent_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
-                name TEXT NOT NULL,
-                rel_path TEXT NOT NULL UNIQUE,
-                abs_path TEXT NOT NULL,
-                description TEXT DEFAULT '',
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS docs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
-                name TEXT NOT NULL,
-                rel_path TEXT NOT NULL UNIQUE,
-                abs_path TEXT NOT NULL,
-                description TEXT DEFAULT '',
-                stable_id TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-        ",
-        )?;
-
-        ensure_schema_migrations(&conn)?;
+        migrations::migrate(&mut conn)?;
+        fulltext::index_missing_docs(&conn)?;
 
         Ok(Self {
             contexts_root,
             db_path,
+            templates_root: base_root.join("templates"),
             conn: Arc::new(Mutex::new(conn)),
+            frontmatter_sync: env::var("OPENCONTEXT_FRONTMATTER_SYNC")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            source: None,
             #[cfg(feature = "search")]
             event_bus: None,
         })
     }
 
+    /// Keep the frontmatter `description` field and the catalog description
+    /// in sync: saving a doc copies the field into the catalog, and
+    /// `set_doc_description` writes it back into existing frontmatter.
+    ///
+    /// Also enabled by `OPENCONTEXT_FRONTMATTER_SYNC=1`.
+    pub fn with_frontmatter_sync(mut self, enabled: bool) -> Self {
+        self.frontmatter_sync = enabled;
+        self
+    }
+
+    /// Attribute changes made through this context to `source` (desktop,
+    /// cli, mcp, an agent name, ...) in the audit log.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
     /// Set the event bus for this context
     #[cfg(feature = "search")]
     pub fn with_event_bus(mut self, event_bus: SharedEventBus) -> Self {
@@ -216,6 +284,11 @@ impl OpenContext {
                 doc.updated_at = updated;
             }
         }
+        self.ensure_contained(&doc.abs_path)?;
+        if let Ok(content) = fs::read_to_string(&doc.abs_path) {
+            doc.frontmatter = parse_frontmatter(&content);
+            doc.revision = Some(history::content_hash(&content));
+        }
         Ok(doc)
     }
 
@@ -223,6 +296,7 @@ impl OpenContext {
         EnvInfo {
             contexts_root: self.contexts_root.clone(),
             db_path: self.db_path.clone(),
+            templates_root: self.templates_root.clone(),
         }
     }
 
@@ -271,7 +345,7 @@ impl OpenContext {
             .next_back()
             .unwrap_or(&rel_path)
             .to_string();
-        let abs_path = self.contexts_root.join(&rel_path);
+        let abs_path = self.contained_path(&rel_path)?;
         fs::create_dir_all(&abs_path)?;
         self.with_conn(|conn| {
             conn.execute(
@@ -290,6 +364,7 @@ impl OpenContext {
             )?;
             Ok(())
         })?;
+        self.audit_folder("create_folder", &rel_path, None, &[])?;
         Ok(FolderSummary {
             rel_path,
             abs_path,
@@ -309,6 +384,7 @@ impl OpenContext {
                 "New name must be a single path segment.".into(),
             ));
         }
+        paths::check_name(new_name, "Folder name")?;
         let folder = self
             .find_folder(&rel_path)?
             .ok_or_else(|| folder_not_found(&rel_path))?;
@@ -327,11 +403,7 @@ impl OpenContext {
                 "Target folder \"{new_rel_path}\" already exists."
             )));
         }
-        let new_abs_path = self.contexts_root.join(&new_rel_path);
-        if let Some(parent) = new_abs_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::rename(&folder.abs_path, &new_abs_path)?;
+        let new_abs_path = self.contained_path(&new_rel_path)?;
         let ts = now_iso();
 
         // Collect affected doc paths before the transaction (for event emission)
@@ -345,9 +417,9 @@ impl OpenContext {
             Ok(paths)
         })?;
 
+        let renames = [(folder.abs_path.clone(), new_abs_path.clone())];
         self.with_conn(|conn| {
-            let tx = conn.unchecked_transaction()?;
-            {
+            atomic::commit_with_renames(conn, &renames, |tx| {
                 tx.execute(
                     "UPDATE folders SET name = ?1, rel_path = ?2, abs_path = ?3, updated_at = ?4 WHERE id = ?5",
                     params![new_name, new_rel_path, new_abs_path.to_string_lossy(), ts, folder.id],
@@ -384,9 +456,8 @@ impl OpenContext {
                         params![updated_rel, updated_abs.to_string_lossy(), ts, id],
                     )?;
                 }
-            }
-            tx.commit()?;
-            Ok(())
+                Ok(())
+            })
         })?;
 
         // Emit folder event with affected docs
@@ -407,6 +478,9 @@ impl OpenContext {
             });
         }
 
+        self.audit_folder_move("rename_folder", &rel_path, &new_rel_path)?;
+        self.relink_attachments(&rel_path, &new_rel_path)?;
+
         Ok(RenameResult {
             old_path: rel_path,
             new_path: new_rel_path,
@@ -450,11 +524,7 @@ impl OpenContext {
             )));
         }
 
-        let new_abs_path = self.contexts_root.join(&new_rel_path);
-        if let Some(parent) = new_abs_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::rename(&folder.abs_path, &new_abs_path)?;
+        let new_abs_path = self.contained_path(&new_rel_path)?;
 
         let ts = now_iso();
 
@@ -469,9 +539,9 @@ impl OpenContext {
             Ok(paths)
         })?;
 
+        let renames = [(folder.abs_path.clone(), new_abs_path.clone())];
         self.with_conn(|conn| {
-            let tx = conn.unchecked_transaction()?;
-            {
+            atomic::commit_with_renames(conn, &renames, |tx| {
                 tx.execute(
                     "UPDATE folders SET parent_id = ?1, rel_path = ?2, abs_path = ?3, updated_at = ?4 WHERE id = ?5",
                     params![
@@ -516,9 +586,8 @@ impl OpenContext {
                         params![updated_rel, updated_abs.to_string_lossy(), ts, id],
                     )?;
                 }
-            }
-            tx.commit()?;
-            Ok(())
+                Ok(())
+            })
         })?;
 
         // Emit folder event with affected docs
@@ -539,6 +608,9 @@ impl OpenContext {
             });
         }
 
+        self.audit_folder_move("move_folder", &rel_path, &new_rel_path)?;
+        self.relink_attachments(&rel_path, &new_rel_path)?;
+
         Ok(RenameResult {
             old_path: rel_path,
             new_path: new_rel_path,
@@ -570,7 +642,7 @@ impl OpenContext {
             Ok(paths)
         })?;
 
-        self.with_conn(|conn| {
+        let (child_count, doc_count) = self.with_conn(|conn| {
             let child_count: i64 = conn.query_row(
                 "SELECT COUNT(1) FROM folders WHERE parent_id = ?1",
                 params![folder.id],
@@ -581,33 +653,24 @@ impl OpenContext {
                 params![folder.id],
                 |row| row.get(0),
             )?;
-            if !force && (child_count > 0 || doc_count > 0) {
-                return Err(CoreError::Message(format!(
-                    "Folder \"{rel_path}\" is not empty. Use --force to delete recursively."
-                )));
-            }
-            let like_pattern = format!("{}/%", rel_path);
-            let tx = conn.unchecked_transaction()?;
-            tx.execute(
-                "DELETE FROM docs WHERE rel_path LIKE ?1",
-                params![like_pattern.clone()],
-            )?;
-            tx.execute(
-                "DELETE FROM folders WHERE rel_path LIKE ?1",
-                params![like_pattern.clone()],
-            )?;
-            tx.execute("DELETE FROM docs WHERE folder_id = ?1", params![folder.id])?;
-            tx.execute("DELETE FROM folders WHERE id = ?1", params![folder.id])?;
-            tx.commit()?;
-            Ok(())
+            Ok((child_count, doc_count))
         })?;
-        if folder.abs_path.exists() {
-            if force {
-                fs::remove_dir_all(&folder.abs_path)?;
-            } else {
-                fs::remove_dir(&folder.abs_path)?;
-            }
+        if !force && (child_count > 0 || doc_count > 0) {
+            return Err(CoreError::Message(format!(
+                "Folder \"{rel_path}\" is not empty. Use --force to delete recursively."
+            )));
         }
+        let audited_docs: Vec<AuditedDoc> = self
+            .docs_below(&rel_path)?
+            .into_iter()
+            .map(|(stable_id, path)| AuditedDoc {
+                stable_id,
+                path,
+                new_path: None,
+            })
+            .collect();
+        self.trash_folder(&folder)?;
+        self.audit_folder("remove_folder", &rel_path, None, &audited_docs)?;
 
         // Emit folder deleted event
         #[cfg(feature = "search")]
@@ -620,52 +683,84 @@ impl OpenContext {
     }
 
     pub fn list_docs(&self, folder_path: &str, recursive: bool) -> CoreResult<Vec<Doc>> {
+        self.list_docs_with(
+            folder_path,
+            &ListDocsOptions {
+                recursive,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// List docs in a folder, filtered and ordered by properties.
+    pub fn list_docs_with(
+        &self,
+        folder_path: &str,
+        options: &ListDocsOptions,
+    ) -> CoreResult<Vec<Doc>> {
         let rel_folder_path = normalize_folder_path(Some(folder_path))?;
         let folder = self
             .find_folder(&rel_folder_path)?
             .ok_or_else(|| folder_not_found(&rel_folder_path))?;
-        self.with_conn(|conn| {
-            if recursive {
-                let pattern = if folder.rel_path.is_empty() {
-                    "%".to_string()
-                } else {
-                    format!("{}/%", folder.rel_path)
-                };
-                let mut stmt = conn.prepare(
-                    "SELECT id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at
-                     FROM docs WHERE rel_path LIKE ?1 ORDER BY rel_path",
-                )?;
-                let rows = stmt
-                    .query_map([pattern], row_to_doc)?
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(rows)
-            } else if rel_folder_path.is_empty() {
-                let mut stmt = conn.prepare(
-                    "SELECT id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at
-                     FROM docs WHERE folder_id IS NULL ORDER BY name",
-                )?;
-                let rows = stmt
-                    .query_map([], row_to_doc)?
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(rows)
+        let mut sql = String::from(
+            "SELECT id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at
+             FROM docs WHERE ",
+        );
+        let mut values = Vec::new();
+        let default_order = if options.recursive {
+            let pattern = if folder.rel_path.is_empty() {
+                "%".to_string()
             } else {
-                let mut stmt = conn.prepare(
-                    "SELECT id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at
-                     FROM docs WHERE folder_id = ?1 ORDER BY name",
-                )?;
-                let rows = stmt
-                    .query_map([folder.id], row_to_doc)?
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(rows)
-            }
+                format!("{}/%", folder.rel_path)
+            };
+            sql.push_str("rel_path LIKE ?");
+            values.push(Value::Text(pattern));
+            "rel_path"
+        } else if rel_folder_path.is_empty() {
+            sql.push_str("folder_id IS NULL");
+            "name"
+        } else {
+            sql.push_str("folder_id = ?");
+            values.push(Value::Integer(folder.id));
+            "name"
+        };
+        for filter in &options.property_filters {
+            let (clause, filter_values) = properties::filter_sql(filter)?;
+            sql.push_str(&clause);
+            values.extend(filter_values);
+        }
+        sql.push_str(" ORDER BY ");
+        if let Some(sort) = &options.sort_by_property {
+            let (order, sort_values) = properties::sort_sql(sort)?;
+            sql.push_str(&order);
+            sql.push_str(", ");
+            values.extend(sort_values);
+        }
+        sql.push_str(default_order);
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(params_from_iter(values), row_to_doc)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
         })
     }
 
+    /// Create a doc, filled from the folder's default template if it has one.
     pub fn create_doc(
         &self,
         folder_path: &str,
         name: &str,
         description: Option<&str>,
+    ) -> CoreResult<DocCreated> {
+        self.create_doc_with_default_template(folder_path, name, description)
+    }
+
+    pub(crate) fn create_empty_doc(
+        &self,
+        folder_path: &str,
+        name: &str,
+        description: Option<&str>,
     ) -> CoreResult<DocCreated> {
         if name.is_empty() {
             return Err(CoreError::Message("Document name is required.".into()));
@@ -675,6 +770,7 @@ impl OpenContext {
                 "Document name must not contain \"/\".".into(),
             ));
         }
+        paths::check_name(name, "Document name")?;
         let rel_folder_path = normalize_folder_path(Some(folder_path))?;
         let folder = self
             .find_folder(&rel_folder_path)?
@@ -689,7 +785,7 @@ impl OpenContext {
                 "File \"{rel_path}\" already exists."
             )));
         }
-        let abs_path = self.contexts_root.join(&rel_path);
+        let abs_path = self.contained_path(&rel_path)?;
         if let Some(parent) = abs_path.parent() {
             fs::create_dir_all(parent)?;
         }
@@ -710,8 +806,10 @@ impl OpenContext {
                     ts
                 ],
             )?;
+            fulltext::index_doc_text(conn, conn.last_insert_rowid(), "")?;
             Ok(sid)
         })?;
+        self.audit_doc("create_doc", None, &rel_path, None, &stable_id)?;
 
         // Emit event
         #[cfg(feature = "search")]
@@ -746,24 +844,23 @@ impl OpenContext {
                 "Document \"{new_rel_path}\" already exists."
             )));
         }
-        let new_abs_path = self.contexts_root.join(&new_rel_path);
-        if let Some(parent) = new_abs_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::rename(&doc.abs_path, &new_abs_path)?;
+        let new_abs_path = self.contained_path(&new_rel_path)?;
+        let renames = [(doc.abs_path.clone(), new_abs_path.clone())];
         let ts = now_iso();
         self.with_conn(|conn| {
-            conn.execute(
-                "UPDATE docs SET folder_id = ?1, rel_path = ?2, abs_path = ?3, updated_at = ?4 WHERE id = ?5",
-                params![
-                    dest_folder.id,
-                    new_rel_path,
-                    new_abs_path.to_string_lossy(),
-                    ts,
-                    doc.id
-                ],
-            )?;
-            Ok(())
+            atomic::commit_with_renames(conn, &renames, |tx| {
+                tx.execute(
+                    "UPDATE docs SET folder_id = ?1, rel_path = ?2, abs_path = ?3, updated_at = ?4 WHERE id = ?5",
+                    params![
+                        dest_folder.id,
+                        new_rel_path,
+                        new_abs_path.to_string_lossy(),
+                        ts,
+                        doc.id
+                    ],
+                )?;
+                Ok(())
+            })
         })?;
 
         // Emit event
@@ -773,6 +870,15 @@ impl OpenContext {
             new_path: new_rel_path.clone(),
         });
 
+        self.audit_doc(
+            "move_doc",
+            None,
+            &rel_doc_path,
+            Some(&new_rel_path),
+            &doc.stable_id,
+        )?;
+        self.relink_attachments(&rel_doc_path, &new_rel_path)?;
+
         Ok(RenameResult {
             old_path: rel_doc_path,
             new_path: new_rel_path,
@@ -785,6 +891,7 @@ impl OpenContext {
                 "New name must be a single file name without \"/\".".into(),
             ));
         }
+        paths::check_name(new_name, "Document name")?;
         let rel_doc_path = normalize_doc_path(Some(doc_path))?;
         let doc = self
             .find_doc(&rel_doc_path)?
@@ -799,19 +906,26 @@ impl OpenContext {
                 "Document \"{new_rel_path}\" already exists."
             )));
         }
-        let new_abs_path = self.contexts_root.join(&new_rel_path);
-        if let Some(parent) = new_abs_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::rename(&doc.abs_path, &new_abs_path)?;
+        let new_abs_path = self.contained_path(&new_rel_path)?;
+        let renames = [(doc.abs_path.clone(), new_abs_path.clone())];
         let ts = now_iso();
         self.with_conn(|conn| {
-            conn.execute(
-                "UPDATE docs SET name = ?1, rel_path = ?2, abs_path = ?3, updated_at = ?4 WHERE id = ?5",
-                params![new_name, new_rel_path, new_abs_path.to_string_lossy(), ts, doc.id],
-            )?;
-            Ok(())
+            atomic::commit_with_renames(conn, &renames, |tx| {
+                tx.execute(
+                    "UPDATE docs SET name = ?1, rel_path = ?2, abs_path = ?3, updated_at = ?4 WHERE id = ?5",
+                    params![new_name, new_rel_path, new_abs_path.to_string_lossy(), ts, doc.id],
+                )?;
+                fulltext::index_doc_fields(tx, doc.id)?;
+                Ok(())
+            })
         })?;
+        self.audit_doc(
+            "rename_doc",
+            None,
+            &rel_doc_path,
+            Some(&new_rel_path),
+            &doc.stable_id,
+        )?;
 
         // Emit event
         #[cfg(feature = "search")]
@@ -831,13 +945,8 @@ impl OpenContext {
         let doc = self
             .find_doc(&rel_doc_path)?
             .ok_or_else(|| doc_not_found(&rel_doc_path))?;
-        if doc.abs_path.exists() {
-            fs::remove_file(&doc.abs_path)?;
-        }
-        self.with_conn(|conn| {
-            conn.execute("DELETE FROM docs WHERE id = ?1", params![doc.id])?;
-            Ok(())
-        })?;
+        self.trash_doc(&doc)?;
+        self.audit_doc("remove_doc", None, &rel_doc_path, None, &doc.stable_id)?;
 
         // Emit event
         #[cfg(feature = "search")]
@@ -854,14 +963,45 @@ impl OpenContext {
         let doc = self
             .find_doc(&rel_doc_path)?
             .ok_or_else(|| doc_not_found(&rel_doc_path))?;
+        if self.frontmatter_sync {
+            self.ensure_contained(&doc.abs_path)?;
+            let content = fs::read_to_string(&doc.abs_path).unwrap_or_default();
+            let current = parse_frontmatter(&content)
+                .and_then(|fields| fields.get(frontmatter::DESCRIPTION_KEY).cloned());
+            if current.as_ref().and_then(|v| v.as_str()) != Some(description) {
+                if let Some(updated) = frontmatter::set_frontmatter_field(
+                    &content,
+                    frontmatter::DESCRIPTION_KEY,
+                    description,
+                ) {
+                    self.save_doc_content_with(
+                        &rel_doc_path,
+                        &updated,
+                        SaveDocOptions {
+                            description: Some(description.to_string()),
+                            source: Some("frontmatter-sync".into()),
+                            ..Default::default()
+                        },
+                    )?;
+                }
+            }
+        }
         let ts = now_iso();
         self.with_conn(|conn| {
             conn.execute(
                 "UPDATE docs SET description = ?1, updated_at = ?2 WHERE id = ?3",
                 params![description, ts, doc.id],
             )?;
+            fulltext::index_doc_fields(conn, doc.id)?;
             Ok(())
         })?;
+        self.audit_doc(
+            "set_doc_description",
+            None,
+            &rel_doc_path,
+            None,
+            &doc.stable_id,
+        )?;
         Ok(DocSummary {
             rel_path: rel_doc_path,
             description: description.to_string(),
@@ -869,23 +1009,42 @@ impl OpenContext {
     }
 
     pub fn get_doc_content(&self, doc_path: &str) -> CoreResult<String> {
+        Ok(self
+            .get_doc_content_with(doc_path, &ReadDocOptions::default())?
+            .content)
+    }
+
+    /// Read a document together with its current revision, for use as
+    /// `SaveDocOptions::expected_revision` on the next save.
+    pub fn get_doc_content_with_revision(&self, doc_path: &str) -> CoreResult<DocContent> {
+        self.get_doc_content_with(doc_path, &ReadDocOptions::default())
+    }
+
+    /// Read a document and its revision, recording the read as coming from
+    /// `options.source`.
+    pub fn get_doc_content_with(
+        &self,
+        doc_path: &str,
+        options: &ReadDocOptions,
+    ) -> CoreResult<DocContent> {
         let rel_doc_path = normalize_doc_path(Some(doc_path))?;
         let doc = self
             .find_doc(&rel_doc_path)?
             .ok_or_else(|| doc_not_found(&rel_doc_path))?;
-        // Best-effort: sync updated_at from filesystem mtime when reading.
-        if let Ok(updated) = sync_updated_at_from_fs(&doc) {
-            if updated != doc.updated_at {
-                let ts = updated;
-                self.with_conn(|conn| {
-                    conn.execute(
-                        "UPDATE docs SET updated_at = ?1 WHERE id = ?2",
-                        params![ts, doc.id],
-                    )?;
-                    Ok(())
-                })?;
-            }
-        }
+        self.ensure_contained(&doc.abs_path)?;
+        let content = fs::read_to_string(&doc.abs_path)?;
+        self.with_conn(|conn| access::record_access(conn, doc.id, options.source.as_deref()))?;
+        let revision = history::content_hash(&content);
+        Ok(DocContent { content, revision })
+    }
+
+    /// Read a document without recording an access, for internal use.
+    pub(crate) fn read_doc_content(&self, doc_path: &str) -> CoreResult<String> {
+        let rel_doc_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_doc_path)?
+            .ok_or_else(|| doc_not_found(&rel_doc_path))?;
+        self.ensure_contained(&doc.abs_path)?;
         let content = fs::read_to_string(&doc.abs_path)?;
         Ok(content)
     }
@@ -895,15 +1054,61 @@ impl OpenContext {
         doc_path: &str,
         content: &str,
         description: Option<&str>,
+    ) -> CoreResult<DocSaved> {
+        self.save_doc_content_with(
+            doc_path,
+            content,
+            SaveDocOptions {
+                description: description.map(str::to_string),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Save document content, recording a version snapshot attributed to
+    /// the optional author/source in `options`.
+    pub fn save_doc_content_with(
+        &self,
+        doc_path: &str,
+        content: &str,
+        options: SaveDocOptions,
     ) -> CoreResult<DocSaved> {
         let rel_doc_path = normalize_doc_path(Some(doc_path))?;
         let doc = self
             .find_doc(&rel_doc_path)?
             .ok_or_else(|| doc_not_found(&rel_doc_path))?;
-        fs::write(&doc.abs_path, content)?;
+        self.ensure_contained(&doc.abs_path)?;
+        let previous = fs::read_to_string(&doc.abs_path).ok();
+        if let Some(expected) = options.expected_revision.as_deref() {
+            let current_revision = history::content_hash(previous.as_deref().unwrap_or(""));
+            let current_updated_at = sync_updated_at_from_fs(&doc).ok();
+            if expected != current_revision && Some(expected) != current_updated_at.as_deref() {
+                return Err(CoreError::Conflict {
+                    rel_path: rel_doc_path,
+                    current_revision,
+                });
+            }
+        }
+        atomic::write_atomic(&doc.abs_path, content)?;
+        self.record_doc_version(&doc, previous.as_deref(), content, &options)?;
+        self.index_doc_links(&doc, content)?;
+        let synced_description = if self.frontmatter_sync && options.description.is_none() {
+            parse_frontmatter(content).and_then(|fields| {
+                fields
+                    .get(frontmatter::DESCRIPTION_KEY)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            })
+        } else {
+            None
+        };
         let ts = now_iso();
         self.with_conn(|conn| {
-            if let Some(desc) = description {
+            if let Some(desc) = options
+                .description
+                .as_deref()
+                .or(synced_description.as_deref())
+            {
                 conn.execute(
                     "UPDATE docs SET description = ?1, updated_at = ?2 WHERE id = ?3",
                     params![desc, ts, doc.id],
@@ -914,8 +1119,16 @@ impl OpenContext {
                     params![ts, doc.id],
                 )?;
             }
+            fulltext::index_doc_text(conn, doc.id, content)?;
             Ok(())
         })?;
+        self.audit_doc(
+            "save_doc_content",
+            options.source.as_deref(),
+            &rel_doc_path,
+            None,
+            &doc.stable_id,
+        )?;
 
         // Emit event
         #[cfg(feature = "search")]
@@ -926,6 +1139,7 @@ impl OpenContext {
         Ok(DocSaved {
             rel_path: rel_doc_path,
             abs_path: doc.abs_path,
+            revision: history::content_hash(content),
         })
     }
 
@@ -934,40 +1148,82 @@ impl OpenContext {
         folder_path: &str,
         limit: Option<usize>,
     ) -> CoreResult<Vec<DocManifestEntry>> {
-        if let Some(l) = limit {
-            if l == 0 {
-                return Err(CoreError::Message(
-                    "limit must be a positive integer".into(),
-                ));
+        self.generate_manifest_with(
+            folder_path,
+            &ManifestOptions {
+                limit,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn generate_manifest_with(
+        &self,
+        folder_path: &str,
+        options: &ManifestOptions,
+    ) -> CoreResult<Vec<DocManifestEntry>> {
+        for (name, value) in [
+            ("limit", options.limit),
+            ("max_tokens", options.max_tokens),
+            ("max_chars", options.max_chars),
+        ] {
+            if value == Some(0) {
+                return Err(CoreError::Message(format!(
+                    "{name} must be a positive integer"
+                )));
             }
         }
+        let tags = tags::normalize_tags(&options.tags)?;
+        let updated_since = options
+            .updated_since
+            .as_deref()
+            .map(|since| manifest::normalize_since("updated_since", since))
+            .transpose()?;
         let rel_path = normalize_folder_path(Some(folder_path))?;
         let folder = self
             .find_folder(&rel_path)?
             .ok_or_else(|| folder_not_found(&rel_path))?;
         self.with_conn(|conn| {
-            let sql = if limit.is_some() {
-                "SELECT name, rel_path, abs_path, stable_id, description, updated_at FROM docs WHERE rel_path LIKE ?1 ORDER BY rel_path LIMIT ?2"
-            } else {
-                "SELECT name, rel_path, abs_path, stable_id, description, updated_at FROM docs WHERE rel_path LIKE ?1 ORDER BY rel_path"
-            };
             let pattern = if folder.rel_path.is_empty() {
                 "%".to_string()
             } else {
                 format!("{}/%", folder.rel_path)
             };
-            let mut stmt = conn.prepare(sql)?;
-            if let Some(limit) = limit {
-                let rows = stmt
-                    .query_map(params![pattern, limit as i64], manifest_row)?
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(rows)
-            } else {
-                let rows = stmt
-                    .query_map([pattern], manifest_row)?
-                    .collect::<Result<Vec<_>, _>>()?;
-                Ok(rows)
+            let mut sql = String::from(
+                "SELECT name, rel_path, abs_path, stable_id, description, updated_at FROM docs WHERE rel_path LIKE ?",
+            );
+            let mut values = vec![Value::Text(pattern.clone())];
+            if !tags.is_empty() {
+                sql.push_str(&format!(
+                    " AND id IN ({})",
+                    tags::tag_filter_sql(tags.len(), options.tag_match)
+                ));
+                values.extend(tags.iter().cloned().map(Value::Text));
+            }
+            if let Some(since) = updated_since {
+                sql.push_str(" AND updated_at >= ?");
+                values.push(Value::Text(since));
+            }
+            sql.push_str(" ORDER BY ");
+            if options.pinned_first {
+                sql.push_str(access::PINNED_FIRST);
+            }
+            sql.push_str(options.sort.order_by());
+            if let Some(limit) = options.limit {
+                sql.push_str(" LIMIT ?");
+                values.push(Value::Integer(limit as i64));
             }
+            let mut stmt = conn.prepare(&sql)?;
+            let mut rows = stmt
+                .query_map(params_from_iter(values), manifest_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut doc_tags = tags::tags_by_rel_path(conn, &pattern)?;
+            let mut doc_properties = properties::properties_by_rel_path(conn, &pattern)?;
+            for row in &mut rows {
+                row.tags = doc_tags.remove(&row.rel_path).unwrap_or_default();
+                row.properties = doc_properties.remove(&row.rel_path).unwrap_or_default();
+            }
+            Ok(manifest::apply_budget(rows, options))
         })
     }
 
@@ -1004,7 +1260,7 @@ impl OpenContext {
                 self.ensure_folder_record(parent_rel)?;
             }
         }
-        let abs_path = self.contexts_root.join(rel_path);
+        let abs_path = self.contained_path(rel_path)?;
         fs::create_dir_all(&abs_path)?;
         let ts = now_iso();
         let name = rel_path.split('/').next_back().unwrap_or(rel_path);
@@ -1074,10 +1330,74 @@ pub struct DocSummary {
     pub description: String,
 }
 
+/// Optional parameters for `get_doc_content_with`
+#[derive(Debug, Clone, Default)]
+pub struct ReadDocOptions {
+    /// Who is reading (desktop, cli, mcp, ...), recorded with the access
+    pub source: Option<String>,
+}
+
+/// Optional parameters for `save_doc_content_with`
+#[derive(Debug, Clone, Default)]
+pub struct SaveDocOptions {
+    /// Replace the document description
+    pub description: Option<String>,
+    /// Who wrote this version (user or agent name)
+    pub author: Option<String>,
+    /// Where the save came from (desktop, cli, mcp, ...)
+    pub source: Option<String>,
+    /// Only save if the document is still at this revision: either the
+    /// content hash (`revision`) or the `updated_at` from `get_doc_meta`.
+    /// A mismatch fails with `CoreError::Conflict`.
+    pub expected_revision: Option<String>,
+}
+
+/// Optional parameters for `list_docs_with`
+#[derive(Debug, Clone, Default)]
+pub struct ListDocsOptions {
+    /// Include docs in subfolders
+    pub recursive: bool,
+    /// Only include docs matching every filter
+    pub property_filters: Vec<PropertyFilter>,
+    /// Order by this property instead of by name (or path when recursive)
+    pub sort_by_property: Option<PropertySort>,
+}
+
+/// Optional parameters for `generate_manifest_with`
+#[derive(Debug, Clone, Default)]
+pub struct ManifestOptions {
+    /// Maximum number of entries
+    pub limit: Option<usize>,
+    /// Only include docs carrying these tags
+    pub tags: Vec<String>,
+    /// How `tags` are combined
+    pub tag_match: TagMatch,
+    /// Entry order (default by path)
+    pub sort: ManifestSort,
+    /// List pinned docs before the others, each group in `sort` order
+    pub pinned_first: bool,
+    /// Only include docs updated at or after this RFC 3339 timestamp or
+    /// `YYYY-MM-DD` date
+    pub updated_since: Option<String>,
+    /// Stop once the estimated tokens of the entries would exceed this
+    pub max_tokens: Option<usize>,
+    /// Stop once the characters of the entries would exceed this
+    pub max_chars: Option<usize>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct DocSaved {
     pub rel_path: String,
     pub abs_path: PathBuf,
+    /// Content hash of the saved content
+    pub revision: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DocContent {
+    pub content: String,
+    /// Content hash, usable as `SaveDocOptions::expected_revision`
+    pub revision: String,
 }
 
 fn now_iso() -> String {
@@ -1092,26 +1412,14 @@ fn normalize_folder_path(input: Option<&str>) -> CoreResult<String> {
     if trimmed.is_empty() || trimmed == "." || trimmed == "/" {
         return Ok(String::new());
     }
-    let normalized = trimmed
-        .replace('\\', "/")
-        .split('/')
-        .filter(|segment| !segment.is_empty())
-        .collect::<Vec<_>>()
-        .join("/");
-    Ok(normalized)
+    paths::clean_rel_path(trimmed, "Folder path")
 }
 
 fn normalize_doc_path(input: Option<&str>) -> CoreResult<String> {
     let Some(value) = input else {
         return Err(CoreError::Message("Document path is required".into()));
     };
-    let cleaned = value
-        .trim()
-        .replace('\\', "/")
-        .split('/')
-        .filter(|segment| !segment.is_empty())
-        .collect::<Vec<_>>()
-        .join("/");
+    let cleaned = paths::clean_rel_path(value, "Document path")?;
     if cleaned.is_empty() {
         return Err(CoreError::Message("Document path cannot be root".into()));
     }
@@ -1169,6 +1477,8 @@ fn row_to_doc(row: &rusqlite::Row<'_>) -> rusqlite::Result<Doc> {
         stable_id: row.get(6)?,
         created_at: row.get(7)?,
         updated_at: row.get(8)?,
+        frontmatter: None,
+        revision: None,
     })
 }
 
@@ -1180,39 +1490,11 @@ fn manifest_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DocManifestEntry> {
         stable_id: row.get(3)?,
         description: row.get(4)?,
         updated_at: row.get(5)?,
+        tags: Vec::new(),
+        properties: Properties::new(),
     })
 }
 
-fn ensure_schema_migrations(conn: &Connection) -> CoreResult<()> {
-    // Add docs.stable_id if missing.
-    let mut stmt = conn.prepare("PRAGMA table_info(docs)")?;
-    let cols = stmt
-        .query_map([], |row| row.get::<_, String>(1))?
-        .collect::<Result<Vec<_>, _>>()?;
-    let has_stable_id = cols.iter().any(|c| c == "stable_id");
-    if !has_stable_id {
-        conn.execute("ALTER TABLE docs ADD COLUMN stable_id TEXT", [])?;
-    }
-    conn.execute(
-        "CREATE UNIQUE INDEX IF NOT EXISTS idx_docs_stable_id ON docs(stable_id)",
-        [],
-    )?;
-
-    // Backfill missing stable_id.
-    let mut stmt = conn.prepare("SELECT id FROM docs WHERE stable_id IS NULL OR stable_id = ''")?;
-    let ids = stmt
-        .query_map([], |row| row.get::<_, i64>(0))?
-        .collect::<Result<Vec<_>, _>>()?;
-    for id in ids {
-        let sid = generate_stable_id(conn)?;
-        conn.execute(
-            "UPDATE docs SET stable_id = ?1 WHERE id = ?2",
-            params![sid, id],
-        )?;
-    }
-    Ok(())
-}
-
 fn generate_stable_id(conn: &Connection) -> CoreResult<String> {
     // Use SQLite's RNG via randomblob(16), then format as UUIDv4-like string.
     let bytes: Vec<u8> = conn.query_row("SELECT randomblob(16)", [], |row| row.get(0))?;
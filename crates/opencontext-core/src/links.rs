@@ -0,0 +1,473 @@
+//! Link graph between documents.
+//!
+//! Outgoing links are extracted from doc content on every save and stored in
+//! `doc_links`, keyed by the stable id of the source doc. Two link forms are
+//! recognised:
+//!
+//! - `oc://doc/<stable_id>?path=<rel_path>` (the editor's page references;
+//!   either part may be missing, and a `#anchor` is ignored)
+//! - `[[folder/doc]]` / `[[folder/doc.md|Label]]` wiki links, resolved
+//!   relative to `contexts_root`
+//!
+//! Path-based links break when their target is renamed or moved;
+//! `rewrite_links` updates the referring docs.
+
+use std::{collections::BTreeMap, fs, sync::OnceLock};
+
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachments, normalize_doc_path, row_to_doc, CoreError, CoreResult, Doc, OpenContext,
+    SaveDocOptions,
+};
+
+/// How a link was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// `oc://doc/...` reference
+    Oc,
+    /// `[[...]]` wiki link
+    Wiki,
+}
+
+impl LinkKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkKind::Oc => "oc",
+            LinkKind::Wiki => "wiki",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "wiki" => LinkKind::Wiki,
+            _ => LinkKind::Oc,
+        }
+    }
+}
+
+/// A link from one document to another
+#[derive(Debug, Clone, Serialize)]
+pub struct DocLink {
+    pub kind: LinkKind,
+    /// Link text as written in the source doc
+    pub raw: String,
+    pub source_stable_id: String,
+    pub source_path: String,
+    /// Target stable id, when written in the link or resolved on save
+    pub target_stable_id: Option<String>,
+    /// Target path as written in the link
+    pub target_path: Option<String>,
+    /// Where the target currently lives, if it can be found
+    pub resolved_path: Option<String>,
+}
+
+/// Docs changed by `rewrite_links`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkRewrite {
+    pub old_path: String,
+    pub new_path: String,
+    /// Paths of the referring docs whose content was updated
+    pub updated_docs: Vec<String>,
+    /// Number of link occurrences rewritten
+    pub rewritten_links: usize,
+}
+
+/// A link parsed from doc content, before resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParsedLink {
+    kind: LinkKind,
+    raw: String,
+    stable_id: Option<String>,
+    path: Option<String>,
+}
+
+impl OpenContext {
+    /// Links written in the doc with the given stable id.
+    pub fn get_outgoing_links(&self, stable_id: &str) -> CoreResult<Vec<DocLink>> {
+        let doc = self.get_doc_by_stable_id(stable_id)?;
+        self.query_links(
+            "WHERE l.source_stable_id = ?1 ORDER BY l.id",
+            params![doc.stable_id],
+        )
+    }
+
+    /// Links in other docs that point at the doc with the given stable id.
+    pub fn get_backlinks(&self, stable_id: &str) -> CoreResult<Vec<DocLink>> {
+        let doc = self.get_doc_by_stable_id(stable_id)?;
+        self.query_links(
+            "WHERE (l.target_stable_id = ?1 OR (l.target_stable_id IS NULL AND l.target_path = ?2))
+               AND l.source_stable_id != ?1
+             ORDER BY s.rel_path, l.id",
+            params![doc.stable_id, doc.rel_path],
+        )
+    }
+
+    /// Links whose target no longer exists at the address they are written
+    /// with: a missing stable id, or a path that nothing lives at anymore.
+    pub fn find_broken_links(&self) -> CoreResult<Vec<DocLink>> {
+        let links = self.query_links("ORDER BY s.rel_path, l.id", params![])?;
+        Ok(links.into_iter().filter(is_broken).collect())
+    }
+
+    /// Re-extract the links of every doc from disk.
+    ///
+    /// Links are normally kept current on save; this picks up docs edited
+    /// outside OpenContext. Returns the number of links recorded.
+    pub fn rebuild_links(&self) -> CoreResult<usize> {
+        let docs = self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at
+                 FROM docs ORDER BY rel_path",
+            )?;
+            let rows = stmt
+                .query_map([], row_to_doc)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })?;
+        let mut total = 0;
+        for doc in docs {
+            let content = match fs::read_to_string(&doc.abs_path) {
+                Ok(content) => content,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            total += self.index_doc_links(&doc, &content)?;
+        }
+        Ok(total)
+    }
+
+    /// Rewrite path-based links that point at `old_path` (a doc, or a folder
+    /// and everything below it) so they point at `new_path`.
+    ///
+    /// Meant to be called after `rename_doc`, `move_doc`, `rename_folder` or
+    /// `move_folder` with the paths from the returned `RenameResult`.
+    /// Referring docs are saved with `source = "link-rewrite"`.
+    pub fn rewrite_links(&self, old_path: &str, new_path: &str) -> CoreResult<LinkRewrite> {
+        let old_path = old_path.trim().trim_matches('/').to_string();
+        let new_path = new_path.trim().trim_matches('/').to_string();
+        if old_path.is_empty() || new_path.is_empty() {
+            return Err(CoreError::Message(
+                "Both old and new paths are required.".into(),
+            ));
+        }
+        let like_pattern = format!("{old_path}/%");
+        let rows = self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT s.rel_path, l.kind, l.raw, l.target_path
+                 FROM doc_links l JOIN docs s ON s.stable_id = l.source_stable_id
+                 WHERE l.target_path = ?1 OR l.target_path LIKE ?2
+                 ORDER BY s.rel_path, l.id",
+            )?;
+            let rows = stmt
+                .query_map(params![old_path, like_pattern], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        LinkKind::parse(&row.get::<_, String>(1)?),
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })?;
+
+        let mut by_source: BTreeMap<String, Vec<(LinkKind, String, String)>> = BTreeMap::new();
+        for (source_path, kind, raw, target_path) in rows {
+            // LIKE is case-insensitive and treats `_` as a wildcard.
+            if target_path != old_path && !target_path.starts_with(&format!("{old_path}/")) {
+                continue;
+            }
+            let moved = format!("{new_path}{}", &target_path[old_path.len()..]);
+            by_source
+                .entry(source_path)
+                .or_default()
+                .push((kind, raw, moved));
+        }
+
+        let mut result = LinkRewrite {
+            old_path,
+            new_path,
+            ..Default::default()
+        };
+        for (source_path, links) in by_source {
+            let content = self.read_doc_content(&source_path)?;
+            let mut updated = content.clone();
+            for (kind, raw, moved) in &links {
+                let replacement = rewrite_raw(*kind, raw, moved);
+                if replacement == *raw {
+                    continue;
+                }
+                let (rewritten, count) = replace_outside_fences(&updated, raw, &replacement);
+                updated = rewritten;
+                result.rewritten_links += count;
+            }
+            if updated != content {
+                self.save_doc_content_with(
+                    &source_path,
+                    &updated,
+                    SaveDocOptions {
+                        source: Some("link-rewrite".into()),
+                        ..Default::default()
+                    },
+                )?;
+                result.updated_docs.push(source_path);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Replace the stored outgoing links of `doc` with those found in
+    /// `content`. Returns the number of links recorded.
+    pub(crate) fn index_doc_links(&self, doc: &Doc, content: &str) -> CoreResult<usize> {
+        let links = parse_links(content);
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "DELETE FROM doc_links WHERE source_stable_id = ?1",
+                params![doc.stable_id],
+            )?;
+            for link in &links {
+                let (target_stable_id, target_path) = resolve_link(&tx, link)?;
+                tx.execute(
+                    "INSERT INTO doc_links (source_stable_id, target_stable_id, target_path, kind, raw)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        doc.stable_id,
+                        target_stable_id,
+                        target_path,
+                        link.kind.as_str(),
+                        link.raw
+                    ],
+                )?;
+            }
+            attachments::index_attachment_refs(&tx, doc, content)?;
+            tx.commit()?;
+            Ok(links.len())
+        })
+    }
+
+    fn query_links(&self, clause: &str, params: impl rusqlite::Params) -> CoreResult<Vec<DocLink>> {
+        let sql = format!(
+            "SELECT l.kind, l.raw, l.source_stable_id, s.rel_path, l.target_stable_id, l.target_path,
+                    COALESCE(t.rel_path, p.rel_path)
+             FROM doc_links l
+             JOIN docs s ON s.stable_id = l.source_stable_id
+             LEFT JOIN docs t ON l.target_stable_id IS NOT NULL AND t.stable_id = l.target_stable_id
+             LEFT JOIN docs p ON l.target_stable_id IS NULL AND p.rel_path = l.target_path
+             {clause}"
+        );
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(params, |row| {
+                    Ok(DocLink {
+                        kind: LinkKind::parse(&row.get::<_, String>(0)?),
+                        raw: row.get(1)?,
+                        source_stable_id: row.get(2)?,
+                        source_path: row.get(3)?,
+                        target_stable_id: row.get(4)?,
+                        target_path: row.get(5)?,
+                        resolved_path: row.get(6)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+}
+
+fn is_broken(link: &DocLink) -> bool {
+    match (&link.resolved_path, link.kind, &link.target_stable_id) {
+        (None, _, _) => true,
+        // Stable-id references keep working wherever the target moves.
+        (Some(_), LinkKind::Oc, Some(_)) => false,
+        (Some(resolved), _, _) => link.target_path.as_deref() != Some(resolved.as_str()),
+    }
+}
+
+/// Resolve a parsed link to `(target_stable_id, target_path)`.
+fn resolve_link(
+    conn: &Connection,
+    link: &ParsedLink,
+) -> CoreResult<(Option<String>, Option<String>)> {
+    let find_by_path = |path: &str| -> rusqlite::Result<Option<String>> {
+        conn.query_row(
+            "SELECT stable_id FROM docs WHERE rel_path = ?1",
+            [path],
+            |row| row.get(0),
+        )
+        .optional()
+    };
+    if let Some(stable_id) = &link.stable_id {
+        return Ok((Some(stable_id.clone()), link.path.clone()));
+    }
+    let Some(path) = &link.path else {
+        return Ok((None, None));
+    };
+    if link.kind == LinkKind::Wiki && !path.ends_with(".md") {
+        // `[[folder/doc]]` usually omits the extension.
+        let with_ext = format!("{path}.md");
+        if let Some(sid) = find_by_path(path)? {
+            return Ok((Some(sid), Some(path.clone())));
+        }
+        let sid = find_by_path(&with_ext)?;
+        return Ok((sid, Some(with_ext)));
+    }
+    Ok((find_by_path(path)?, Some(path.clone())))
+}
+
+fn oc_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"oc://doc/[^\s)\]>"'`]*"#).expect("valid regex"))
+}
+
+fn wiki_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\[\[([^\[\]\n]+?)\]\]").expect("valid regex"))
+}
+
+/// Extract links from markdown, skipping fenced code blocks. Duplicates are
+/// collapsed.
+fn parse_links(content: &str) -> Vec<ParsedLink> {
+    let mut links: Vec<ParsedLink> = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for m in oc_link_regex().find_iter(line) {
+            if let Some(link) = parse_oc_link(m.as_str()) {
+                if !links.contains(&link) {
+                    links.push(link);
+                }
+            }
+        }
+        for caps in wiki_link_regex().captures_iter(line) {
+            if let Some(link) = parse_wiki_link(&caps[0], &caps[1]) {
+                if !links.contains(&link) {
+                    links.push(link);
+                }
+            }
+        }
+    }
+    links
+}
+
+/// Replace `raw` with `replacement` outside fenced code blocks, returning
+/// the new content and the number of replacements.
+fn replace_outside_fences(content: &str, raw: &str, replacement: &str) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            out.push_str(line);
+            continue;
+        }
+        if in_fence {
+            out.push_str(line);
+            continue;
+        }
+        count += line.matches(raw).count();
+        out.push_str(&line.replace(raw, replacement));
+    }
+    (out, count)
+}
+
+fn parse_oc_link(raw: &str) -> Option<ParsedLink> {
+    let rest = raw.strip_prefix("oc://doc/")?;
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let (id_part, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let stable_id = id_part.trim_matches('/').trim();
+    let path = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("path="))
+        .and_then(|value| urlencoding::decode(value).ok())
+        .and_then(|value| normalize_doc_path(Some(&value)).ok());
+    if stable_id.is_empty() && path.is_none() {
+        return None;
+    }
+    Some(ParsedLink {
+        kind: LinkKind::Oc,
+        raw: raw.to_string(),
+        stable_id: (!stable_id.is_empty()).then(|| stable_id.to_string()),
+        path,
+    })
+}
+
+fn parse_wiki_link(raw: &str, inner: &str) -> Option<ParsedLink> {
+    let target = inner.split('|').next().unwrap_or(inner);
+    let target = target.split('#').next().unwrap_or(target);
+    let path = normalize_doc_path(Some(target.trim())).ok()?;
+    Some(ParsedLink {
+        kind: LinkKind::Wiki,
+        raw: raw.to_string(),
+        stable_id: None,
+        path: Some(path),
+    })
+}
+
+/// Produce the text of `raw` pointing at `new_path`, keeping labels,
+/// anchors and the original extension style.
+fn rewrite_raw(kind: LinkKind, raw: &str, new_path: &str) -> String {
+    match kind {
+        LinkKind::Wiki => {
+            let inner = &raw[2..raw.len() - 2];
+            let (target, label) = match inner.split_once('|') {
+                Some((target, label)) => (target, Some(label)),
+                None => (inner, None),
+            };
+            let (target, anchor) = match target.split_once('#') {
+                Some((target, anchor)) => (target, Some(anchor)),
+                None => (target, None),
+            };
+            let mut new_target = if target.trim().ends_with(".md") {
+                new_path.to_string()
+            } else {
+                new_path.trim_end_matches(".md").to_string()
+            };
+            if let Some(anchor) = anchor {
+                new_target = format!("{new_target}#{anchor}");
+            }
+            match label {
+                Some(label) => format!("[[{new_target}|{label}]]"),
+                None => format!("[[{new_target}]]"),
+            }
+        }
+        LinkKind::Oc => {
+            let (target, fragment) = match raw.split_once('#') {
+                Some((target, fragment)) => (target, Some(fragment)),
+                None => (raw, None),
+            };
+            let Some((base, query)) = target.split_once('?') else {
+                return raw.to_string();
+            };
+            let query = query
+                .split('&')
+                .map(|pair| {
+                    if pair.starts_with("path=") {
+                        format!("path={}", urlencoding::encode(new_path))
+                    } else {
+                        pair.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("&");
+            match fragment {
+                Some(fragment) => format!("{base}?{query}#{fragment}"),
+                None => format!("{base}?{query}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Manifest ranking, budgeting and rendering.
+//!
+//! `generate_manifest_with` filters and orders the entries; the helpers here
+//! trim them to a size budget and render them as JSON, a markdown list or an
+//! `llms.txt` file, so the CLI, MCP server and desktop app all hand agents
+//! the same payload.
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    folder_not_found, fulltext, normalize_folder_path, CoreError, CoreResult, DocManifestEntry,
+    ManifestOptions, OpenContext,
+};
+
+/// Order of manifest entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestSort {
+    /// By relative path
+    #[default]
+    Path,
+    /// Most recently updated first
+    UpdatedAt,
+    /// Most frequently read first
+    AccessCount,
+    /// Most recently read first
+    LastAccessed,
+}
+
+impl ManifestSort {
+    /// `ORDER BY` clause for a query over `docs`
+    pub(crate) fn order_by(self) -> &'static str {
+        match self {
+            ManifestSort::Path => "rel_path",
+            ManifestSort::UpdatedAt => "updated_at DESC, rel_path",
+            ManifestSort::AccessCount => {
+                "(SELECT COUNT(*) FROM doc_access a WHERE a.doc_id = docs.id) DESC, rel_path"
+            }
+            ManifestSort::LastAccessed => {
+                "(SELECT MAX(a.id) FROM doc_access a WHERE a.doc_id = docs.id) DESC NULLS LAST, rel_path"
+            }
+        }
+    }
+}
+
+/// Output format of `render_manifest`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestFormat {
+    /// Pretty-printed JSON array of entries
+    #[default]
+    Json,
+    /// One markdown list item per doc
+    Markdown,
+    /// An `llms.txt` file: title, folder summary and one section per folder
+    LlmsTxt,
+}
+
+impl OpenContext {
+    /// Generate a manifest for `folder_path` and render it as `format`.
+    pub fn render_manifest(
+        &self,
+        folder_path: &str,
+        options: &ManifestOptions,
+        format: ManifestFormat,
+    ) -> CoreResult<String> {
+        let entries = self.generate_manifest_with(folder_path, options)?;
+        let rel_path = normalize_folder_path(Some(folder_path))?;
+        let folder = self
+            .find_folder(&rel_path)?
+            .ok_or_else(|| folder_not_found(&rel_path))?;
+        let title = if rel_path.is_empty() {
+            "OpenContext"
+        } else {
+            rel_path.as_str()
+        };
+        render_manifest_entries(title, &folder.description, &entries, format)
+    }
+}
+
+/// Render manifest entries. `title` and `summary` are only used by
+/// `ManifestFormat::LlmsTxt`.
+pub fn render_manifest_entries(
+    title: &str,
+    summary: &str,
+    entries: &[DocManifestEntry],
+    format: ManifestFormat,
+) -> CoreResult<String> {
+    match format {
+        ManifestFormat::Json => {
+            serde_json::to_string_pretty(entries).map_err(|e| CoreError::Message(e.to_string()))
+        }
+        ManifestFormat::Markdown => Ok(entries
+            .iter()
+            .map(|entry| format!("{}\n", markdown_line(entry)))
+            .collect()),
+        ManifestFormat::LlmsTxt => Ok(render_llms_txt(title, summary, entries)),
+    }
+}
+
+fn render_llms_txt(title: &str, summary: &str, entries: &[DocManifestEntry]) -> String {
+    let mut out = format!("# {title}\n");
+    let summary = summary.trim();
+    if !summary.is_empty() {
+        out.push_str(&format!("\n> {}\n", summary.replace('\n', " ")));
+    }
+    // Sections keep the order in which folders first appear, so the
+    // requested sort still decides what comes first.
+    let mut sections: Vec<(&str, Vec<&DocManifestEntry>)> = Vec::new();
+    for entry in entries {
+        let folder = entry
+            .rel_path
+            .rsplit_once('/')
+            .map(|(folder, _)| folder)
+            .unwrap_or("");
+        match sections.iter_mut().find(|(name, _)| *name == folder) {
+            Some((_, items)) => items.push(entry),
+            None => sections.push((folder, vec![entry])),
+        }
+    }
+    for (folder, items) in sections {
+        let heading = if folder.is_empty() { "Docs" } else { folder };
+        out.push_str(&format!("\n## {heading}\n\n"));
+        for entry in items {
+            out.push_str(&markdown_line(entry));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `- [name](path): description (tags: a, b)`
+fn markdown_line(entry: &DocManifestEntry) -> String {
+    let target = if entry
+        .rel_path
+        .chars()
+        .any(|c| c.is_whitespace() || c == '(' || c == ')')
+    {
+        format!("<{}>", entry.rel_path)
+    } else {
+        entry.rel_path.clone()
+    };
+    let mut line = format!("- [{}]({target})", entry.doc_name.replace(']', "\\]"));
+    let description = entry.description.trim();
+    if !description.is_empty() {
+        line.push_str(": ");
+        line.push_str(&description.replace('\n', " "));
+    }
+    if !entry.tags.is_empty() {
+        line.push_str(&format!(" (tags: {})", entry.tags.join(", ")));
+    }
+    if !entry.properties.is_empty() {
+        let properties = entry
+            .properties
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        line.push_str(&format!(" (properties: {properties})"));
+    }
+    line
+}
+
+/// Rough token count: one token per CJK character and one per four other
+/// characters, which tracks common BPE tokenizers closely enough for
+/// budgeting.
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    let mut cjk = 0;
+    let mut other = 0usize;
+    for c in text.chars() {
+        if fulltext::is_cjk(c) {
+            cjk += 1;
+        } else {
+            other += 1;
+        }
+    }
+    cjk + other.div_ceil(4)
+}
+
+/// Keep leading entries while their markdown lines fit in the character
+/// and token budgets of `options`.
+pub(crate) fn apply_budget(
+    entries: Vec<DocManifestEntry>,
+    options: &ManifestOptions,
+) -> Vec<DocManifestEntry> {
+    if options.max_chars.is_none() && options.max_tokens.is_none() {
+        return entries;
+    }
+    let mut chars = 0;
+    let mut tokens = 0;
+    let mut kept = Vec::new();
+    for entry in entries {
+        let line = markdown_line(&entry);
+        chars += line.chars().count() + 1;
+        tokens += estimate_tokens(&line) + 1;
+        if options.max_chars.is_some_and(|max| chars > max)
+            || options.max_tokens.is_some_and(|max| tokens > max)
+        {
+            break;
+        }
+        kept.push(entry);
+    }
+    kept
+}
+
+/// Parse an RFC 3339 timestamp or a `YYYY-MM-DD` date into the format
+/// stored in `docs.updated_at`, so the two compare as strings. `name` is
+/// the option being parsed, for the error message.
+pub(crate) fn normalize_since(name: &str, value: &str) -> CoreResult<String> {
+    let value = value.trim();
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+        })
+        .map_err(|_| {
+            CoreError::Message(format!(
+                "Invalid {name} \"{value}\". Use an RFC 3339 timestamp or YYYY-MM-DD."
+            ))
+        })?;
+    Ok(parsed.to_rfc3339_opts(SecondsFormat::Millis, true))
+}
@@ -0,0 +1,411 @@
+//! Versioned schema migrations for `opencontext.db`.
+//!
+//! The schema version lives in `PRAGMA user_version`. Opening a database
+//! applies every migration above its version in order, each in its own
+//! transaction together with the version bump, and refuses databases
+//! written by a newer build.
+//!
+//! Databases created before versioning report version 0 but may already
+//! hold the tables of any later migration, since the schema used to be
+//! created with `IF NOT EXISTS` on every open. Migrations up to
+//! `folder_templates` are therefore idempotent; later ones can assume their
+//! predecessors ran.
+
+use rusqlite::{params, Connection, Transaction};
+
+use crate::{fulltext, generate_stable_id, CoreError, CoreResult};
+
+pub(crate) struct Migration {
+    pub(crate) version: u32,
+    pub(crate) name: &'static str,
+    pub(crate) up: fn(&Transaction) -> CoreResult<()>,
+}
+
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "folders_and_docs",
+        up: folders_and_docs,
+    },
+    Migration {
+        version: 2,
+        name: "doc_versions",
+        up: doc_versions,
+    },
+    Migration {
+        version: 3,
+        name: "trash",
+        up: trash,
+    },
+    Migration {
+        version: 4,
+        name: "doc_tags",
+        up: doc_tags,
+    },
+    Migration {
+        version: 5,
+        name: "doc_links",
+        up: doc_links,
+    },
+    Migration {
+        version: 6,
+        name: "docs_fts",
+        up: docs_fts,
+    },
+    Migration {
+        version: 7,
+        name: "doc_access",
+        up: doc_access,
+    },
+    Migration {
+        version: 8,
+        name: "folder_templates",
+        up: folder_templates,
+    },
+    Migration {
+        version: 9,
+        name: "properties",
+        up: properties,
+    },
+    Migration {
+        version: 10,
+        name: "doc_pins_and_access_source_index",
+        up: doc_pins_and_access_source_index,
+    },
+    Migration {
+        version: 11,
+        name: "attachments",
+        up: attachments,
+    },
+    Migration {
+        version: 12,
+        name: "audit_log",
+        up: audit_log,
+    },
+];
+
+/// Schema version written by this build
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Bring `conn` up to `SCHEMA_VERSION`.
+pub(crate) fn migrate(conn: &mut Connection) -> CoreResult<()> {
+    apply_migrations(conn, MIGRATIONS)
+}
+
+pub(crate) fn schema_version(conn: &Connection) -> CoreResult<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+pub(crate) fn apply_migrations(conn: &mut Connection, migrations: &[Migration]) -> CoreResult<()> {
+    let current = schema_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(CoreError::Message(format!(
+            "Database schema version {current} is newer than this build supports ({latest}). \
+             Upgrade OpenContext to open it."
+        )));
+    }
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx).map_err(|e| {
+            CoreError::Message(format!(
+                "Migration {} ({}) failed: {e}",
+                migration.version, migration.name
+            ))
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn folders_and_docs(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS folders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            parent_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            rel_path TEXT NOT NULL UNIQUE,
+            abs_path TEXT NOT NULL,
+            description TEXT DEFAULT '',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS docs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            rel_path TEXT NOT NULL UNIQUE,
+            abs_path TEXT NOT NULL,
+            description TEXT DEFAULT '',
+            stable_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        ",
+    )?;
+
+    // Catalogs from the original Node implementation predate stable ids.
+    let mut stmt = tx.prepare("PRAGMA table_info(docs)")?;
+    let cols = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if !cols.iter().any(|c| c == "stable_id") {
+        tx.execute("ALTER TABLE docs ADD COLUMN stable_id TEXT", [])?;
+    }
+    tx.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_docs_stable_id ON docs(stable_id)",
+        [],
+    )?;
+
+    let mut stmt = tx.prepare("SELECT id FROM docs WHERE stable_id IS NULL OR stable_id = ''")?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in ids {
+        let sid = generate_stable_id(tx)?;
+        tx.execute(
+            "UPDATE docs SET stable_id = ?1 WHERE id = ?2",
+            params![sid, id],
+        )?;
+    }
+    Ok(())
+}
+
+fn doc_versions(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS doc_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            stable_id TEXT NOT NULL,
+            rel_path TEXT NOT NULL,
+            content TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            author TEXT,
+            source TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_doc_versions_stable_id ON doc_versions(stable_id, id);
+        ",
+    )?;
+    Ok(())
+}
+
+fn trash(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS trash (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            original_path TEXT NOT NULL,
+            trash_path TEXT NOT NULL,
+            description TEXT DEFAULT '',
+            payload TEXT NOT NULL,
+            deleted_at TEXT NOT NULL
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+fn doc_tags(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS doc_tags (
+            doc_id INTEGER NOT NULL REFERENCES docs(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (doc_id, tag)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_doc_tags_tag ON doc_tags(tag);
+        ",
+    )?;
+    Ok(())
+}
+
+fn doc_links(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS doc_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_stable_id TEXT NOT NULL,
+            target_stable_id TEXT,
+            target_path TEXT,
+            kind TEXT NOT NULL,
+            raw TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_doc_links_source ON doc_links(source_stable_id);
+        CREATE INDEX IF NOT EXISTS idx_doc_links_target ON doc_links(target_stable_id);
+        CREATE INDEX IF NOT EXISTS idx_doc_links_target_path ON doc_links(target_path);
+        ",
+    )?;
+    Ok(())
+}
+
+fn docs_fts(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS docs_fts USING fts5(
+            name,
+            description,
+            content,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS docs_fts_delete AFTER DELETE ON docs BEGIN
+            DELETE FROM docs_fts WHERE rowid = old.id;
+        END;
+        ",
+    )?;
+    fulltext::index_missing_docs(tx)?;
+    Ok(())
+}
+
+fn doc_access(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS doc_access (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            doc_id INTEGER NOT NULL REFERENCES docs(id) ON DELETE CASCADE,
+            source TEXT,
+            accessed_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_doc_access_doc ON doc_access(doc_id);
+        ",
+    )?;
+    Ok(())
+}
+
+fn folder_templates(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS folder_templates (
+            folder_id INTEGER PRIMARY KEY REFERENCES folders(id) ON DELETE CASCADE,
+            template TEXT NOT NULL
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+fn properties(tx: &Transaction) -> CoreResult<()> {
+    // `value` is untyped so numbers and bools keep their SQLite type and
+    // compare numerically in filters and sorts.
+    tx.execute_batch(
+        "
+        CREATE TABLE doc_properties (
+            doc_id INTEGER NOT NULL REFERENCES docs(id) ON DELETE CASCADE,
+            key TEXT NOT NULL,
+            value_type TEXT NOT NULL,
+            value,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (doc_id, key)
+        );
+
+        CREATE INDEX idx_doc_properties_key ON doc_properties(key, value);
+
+        CREATE TABLE folder_properties (
+            folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+            key TEXT NOT NULL,
+            value_type TEXT NOT NULL,
+            value,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (folder_id, key)
+        );
+        ",
+    )?;
+    Ok(())
+}
+
+/// Pinned docs, and an index for the per-source queries of the
+/// `doc_access` table from v7.
+fn doc_pins_and_access_source_index(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE doc_pins (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            doc_id INTEGER NOT NULL UNIQUE REFERENCES docs(id) ON DELETE CASCADE,
+            pinned_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_doc_access_source ON doc_access(source, accessed_at);
+        ",
+    )?;
+    Ok(())
+}
+
+fn attachments(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            attachment_id TEXT NOT NULL UNIQUE,
+            doc_stable_id TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            rel_path TEXT NOT NULL UNIQUE,
+            mime_type TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_attachments_doc ON attachments(doc_stable_id);
+
+        CREATE TABLE attachment_refs (
+            source_stable_id TEXT NOT NULL,
+            rel_path TEXT NOT NULL,
+            PRIMARY KEY (source_stable_id, rel_path)
+        );
+
+        CREATE INDEX idx_attachment_refs_path ON attachment_refs(rel_path);
+        ",
+    )?;
+    Ok(())
+}
+
+fn audit_log(tx: &Transaction) -> CoreResult<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            action TEXT NOT NULL,
+            target TEXT NOT NULL,
+            source TEXT,
+            path TEXT NOT NULL,
+            new_path TEXT,
+            stable_id TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_audit_log_stable_id ON audit_log(stable_id);
+        CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
+        CREATE INDEX idx_audit_log_source ON audit_log(source);
+
+        CREATE TABLE audit_log_docs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            audit_id INTEGER NOT NULL REFERENCES audit_log(id),
+            stable_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            new_path TEXT
+        );
+
+        CREATE INDEX idx_audit_log_docs_audit ON audit_log_docs(audit_id);
+        CREATE INDEX idx_audit_log_docs_stable_id ON audit_log_docs(stable_id);
+
+        CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        CREATE TRIGGER audit_log_docs_no_update BEFORE UPDATE ON audit_log_docs
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        CREATE TRIGGER audit_log_docs_no_delete BEFORE DELETE ON audit_log_docs
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        ",
+    )?;
+    Ok(())
+}
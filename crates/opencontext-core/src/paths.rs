@@ -0,0 +1,144 @@
+//! Path safety for caller-supplied doc and folder paths.
+//!
+//! Paths arrive from the desktop app, the CLI and MCP tools and end up
+//! joined onto `contexts_root`. `clean_rel_path` and `check_name` reject
+//! anything that could leave that directory or that a filesystem treats
+//! specially: `..`, absolute and drive paths, control characters, Windows
+//! device names and the directories core keeps for itself. `ensure_within`
+//! catches symlinks inside `contexts_root` that resolve outside it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{attachments::ASSETS_DIR, trash::TRASH_DIR, CoreError, CoreResult, OpenContext};
+
+/// Top-level directories managed by core, not by callers
+const RESERVED_DIRS: [&str; 2] = [TRASH_DIR, ASSETS_DIR];
+
+/// Names Windows maps to devices, with or without an extension
+const WINDOWS_DEVICES: [&str; 6] = ["con", "prn", "aux", "nul", "conin$", "conout$"];
+
+/// Normalize a `contexts_root`-relative path: unify separators and drop
+/// empty segments, rejecting any segment that is unsafe. `what` names the
+/// path in errors ("Document path", "Folder path").
+pub(crate) fn clean_rel_path(input: &str, what: &str) -> CoreResult<String> {
+    let unified = input.trim().replace('\\', "/");
+    if unified.starts_with('/') || has_drive_prefix(&unified) {
+        return Err(unsafe_path(what, input, "absolute paths are not allowed"));
+    }
+    let segments: Vec<&str> = unified.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in &segments {
+        check_segment(segment).map_err(|reason| unsafe_path(what, input, reason))?;
+    }
+    if let Some(first) = segments.first() {
+        if RESERVED_DIRS
+            .iter()
+            .any(|dir| first.eq_ignore_ascii_case(dir))
+        {
+            return Err(unsafe_path(what, input, "this directory is reserved"));
+        }
+    }
+    Ok(segments.join("/"))
+}
+
+/// Check a single doc or folder name, as given to create and rename.
+pub(crate) fn check_name(name: &str, what: &str) -> CoreResult<()> {
+    if name.contains(['/', '\\']) {
+        return Err(CoreError::Message(format!(
+            "{what} \"{name}\" cannot contain path separators."
+        )));
+    }
+    if has_drive_prefix(name) {
+        return Err(unsafe_path(what, name, "absolute paths are not allowed"));
+    }
+    check_segment(name).map_err(|reason| unsafe_path(what, name, reason))
+}
+
+/// Fail when `path` exists (or will be created) somewhere that resolves
+/// outside `root` through a symlink.
+pub(crate) fn ensure_within(root: &Path, path: &Path) -> CoreResult<()> {
+    let root = root.canonicalize()?;
+    // The path itself, or the deepest ancestor that exists yet
+    let mut existing = path;
+    while fs::symlink_metadata(existing).is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return Ok(()),
+        }
+    }
+    let escapes = match existing.canonicalize() {
+        Ok(resolved) => !resolved.starts_with(&root),
+        // A dangling symlink could point anywhere
+        Err(_) => true,
+    };
+    if escapes {
+        return Err(CoreError::Message(format!(
+            "Unsafe path \"{}\": it resolves outside the contexts directory.",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+impl OpenContext {
+    /// Absolute path of `rel_path`, refusing one that a symlink would take
+    /// outside `contexts_root`.
+    pub(crate) fn contained_path(&self, rel_path: &str) -> CoreResult<PathBuf> {
+        let abs_path = self.contexts_root.join(rel_path);
+        ensure_within(&self.contexts_root, &abs_path)?;
+        Ok(abs_path)
+    }
+
+    /// Refuse an absolute path that resolves outside `contexts_root`.
+    pub(crate) fn ensure_contained(&self, abs_path: &Path) -> CoreResult<()> {
+        ensure_within(&self.contexts_root, abs_path)
+    }
+}
+
+fn check_segment(segment: &str) -> Result<(), &'static str> {
+    if segment == "." || segment == ".." {
+        return Err("\".\" and \"..\" segments are not allowed");
+    }
+    if segment.chars().any(char::is_control) {
+        return Err("control characters are not allowed");
+    }
+    if segment.ends_with([' ', '.']) {
+        return Err("names cannot end with a space or a dot");
+    }
+    let stem = segment.split('.').next().unwrap_or(segment).trim_end();
+    if is_windows_device(stem) {
+        return Err("this name is reserved by the operating system");
+    }
+    Ok(())
+}
+
+fn is_windows_device(stem: &str) -> bool {
+    let stem = stem.to_ascii_lowercase();
+    if WINDOWS_DEVICES.contains(&stem.as_str()) {
+        return true;
+    }
+    let (Some(prefix), Some(digit)) = (stem.get(..3), stem.get(3..)) else {
+        return false;
+    };
+    matches!(prefix, "com" | "lpt")
+        && matches!(
+            digit,
+            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" | "¹" | "²" | "³"
+        )
+}
+
+/// `C:`, `C:/...` or `C:name`
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn unsafe_path(what: &str, input: &str, reason: &str) -> CoreError {
+    CoreError::Message(format!(
+        "Unsafe {} \"{}\": {reason}.",
+        what.to_lowercase(),
+        input.escape_debug()
+    ))
+}
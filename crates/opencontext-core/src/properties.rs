@@ -0,0 +1,373 @@
+//! Typed key/value properties on docs and folders.
+//!
+//! Properties live in `doc_properties`/`folder_properties`, keyed by the row
+//! id like tags, so renames and moves carry them along. Values keep their
+//! SQLite type (text, real or integer) so filters and sorts compare numbers
+//! as numbers; dates are stored as `YYYY-MM-DD` text, which sorts by date.
+
+use std::{collections::BTreeMap, fmt};
+
+use chrono::NaiveDate;
+use rusqlite::{params, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    doc_not_found, folder_not_found, normalize_doc_path, normalize_folder_path, now_iso, CoreError,
+    CoreResult, OpenContext,
+};
+
+const MAX_KEY_LEN: usize = 64;
+
+/// A property value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum PropertyValue {
+    String(String),
+    Number(f64),
+    /// `YYYY-MM-DD`
+    Date(String),
+    Bool(bool),
+}
+
+impl PropertyValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::String(_) => "string",
+            PropertyValue::Number(_) => "number",
+            PropertyValue::Date(_) => "date",
+            PropertyValue::Bool(_) => "bool",
+        }
+    }
+
+    fn to_sql(&self) -> Value {
+        match self {
+            PropertyValue::String(s) | PropertyValue::Date(s) => Value::Text(s.clone()),
+            PropertyValue::Number(n) => Value::Real(*n),
+            PropertyValue::Bool(b) => Value::Integer(*b as i64),
+        }
+    }
+
+    fn from_sql(value_type: &str, value: Value) -> Option<Self> {
+        match (value_type, value) {
+            ("string", Value::Text(s)) => Some(PropertyValue::String(s)),
+            ("date", Value::Text(s)) => Some(PropertyValue::Date(s)),
+            ("number", Value::Real(n)) => Some(PropertyValue::Number(n)),
+            ("number", Value::Integer(n)) => Some(PropertyValue::Number(n as f64)),
+            ("bool", Value::Integer(n)) => Some(PropertyValue::Bool(n != 0)),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> CoreResult<()> {
+        match self {
+            PropertyValue::Number(n) if !n.is_finite() => Err(CoreError::Message(
+                "Number properties must be finite.".into(),
+            )),
+            PropertyValue::Date(s) if NaiveDate::parse_from_str(s, "%Y-%m-%d").is_err() => Err(
+                CoreError::Message(format!("Invalid date \"{s}\". Use YYYY-MM-DD.")),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::String(s) | PropertyValue::Date(s) => f.write_str(s),
+            PropertyValue::Number(n) => write!(f, "{n}"),
+            PropertyValue::Bool(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+/// Comparison used by a `PropertyFilter`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyOp {
+    #[default]
+    Eq,
+    /// Docs without the property also match
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    /// The property is set, whatever its value
+    Exists,
+    /// The property is not set
+    Missing,
+}
+
+/// Match docs by one property. Ordering comparisons only match values of
+/// the same type as `value`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyFilter {
+    pub key: String,
+    #[serde(default)]
+    pub op: PropertyOp,
+    /// Required by every op except `Exists` and `Missing`
+    #[serde(default)]
+    pub value: Option<PropertyValue>,
+}
+
+/// Order docs by a property; docs without it come last.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertySort {
+    pub key: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+pub type Properties = BTreeMap<String, PropertyValue>;
+
+impl OpenContext {
+    /// Set a property on a document, replacing any previous value.
+    pub fn set_doc_property(
+        &self,
+        doc_path: &str,
+        key: &str,
+        value: &PropertyValue,
+    ) -> CoreResult<Properties> {
+        let doc_id = self.find_doc_id(doc_path)?;
+        self.with_conn(|conn| {
+            set_property(conn, "doc_properties", "doc_id", doc_id, key, value)?;
+            properties(conn, "doc_properties", "doc_id", doc_id)
+        })
+    }
+
+    pub fn get_doc_property(&self, doc_path: &str, key: &str) -> CoreResult<Option<PropertyValue>> {
+        Ok(self.get_doc_properties(doc_path)?.remove(key))
+    }
+
+    /// All properties of a document, by key.
+    pub fn get_doc_properties(&self, doc_path: &str) -> CoreResult<Properties> {
+        let doc_id = self.find_doc_id(doc_path)?;
+        self.with_conn(|conn| properties(conn, "doc_properties", "doc_id", doc_id))
+    }
+
+    /// Remove a property from a document. Returns whether it was set.
+    pub fn delete_doc_property(&self, doc_path: &str, key: &str) -> CoreResult<bool> {
+        let doc_id = self.find_doc_id(doc_path)?;
+        self.with_conn(|conn| delete_property(conn, "doc_properties", "doc_id", doc_id, key))
+    }
+
+    /// Set a property on a folder, replacing any previous value.
+    pub fn set_folder_property(
+        &self,
+        folder_path: &str,
+        key: &str,
+        value: &PropertyValue,
+    ) -> CoreResult<Properties> {
+        let folder_id = self.find_folder_id(folder_path)?;
+        self.with_conn(|conn| {
+            set_property(
+                conn,
+                "folder_properties",
+                "folder_id",
+                folder_id,
+                key,
+                value,
+            )?;
+            properties(conn, "folder_properties", "folder_id", folder_id)
+        })
+    }
+
+    pub fn get_folder_property(
+        &self,
+        folder_path: &str,
+        key: &str,
+    ) -> CoreResult<Option<PropertyValue>> {
+        Ok(self.get_folder_properties(folder_path)?.remove(key))
+    }
+
+    /// All properties of a folder, by key.
+    pub fn get_folder_properties(&self, folder_path: &str) -> CoreResult<Properties> {
+        let folder_id = self.find_folder_id(folder_path)?;
+        self.with_conn(|conn| properties(conn, "folder_properties", "folder_id", folder_id))
+    }
+
+    /// Remove a property from a folder. Returns whether it was set.
+    pub fn delete_folder_property(&self, folder_path: &str, key: &str) -> CoreResult<bool> {
+        let folder_id = self.find_folder_id(folder_path)?;
+        self.with_conn(|conn| {
+            delete_property(conn, "folder_properties", "folder_id", folder_id, key)
+        })
+    }
+
+    fn find_doc_id(&self, doc_path: &str) -> CoreResult<i64> {
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        Ok(doc.id)
+    }
+
+    fn find_folder_id(&self, folder_path: &str) -> CoreResult<i64> {
+        let rel_path = normalize_folder_path(Some(folder_path))?;
+        let folder = self
+            .find_folder(&rel_path)?
+            .ok_or_else(|| folder_not_found(&rel_path))?;
+        Ok(folder.id)
+    }
+}
+
+/// Trim a property key and check it is 1-64 letters, digits, `_`, `-` or `.`.
+pub(crate) fn normalize_key(key: &str) -> CoreResult<String> {
+    let key = key.trim();
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(key.to_string())
+    } else {
+        Err(CoreError::Message(format!(
+            "Invalid property key \"{key}\". Use up to {MAX_KEY_LEN} letters, digits, '_', '-' or '.'."
+        )))
+    }
+}
+
+pub(crate) fn set_property(
+    conn: &Connection,
+    table: &str,
+    owner_col: &str,
+    owner_id: i64,
+    key: &str,
+    value: &PropertyValue,
+) -> CoreResult<()> {
+    let key = normalize_key(key)?;
+    value.validate()?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {table} ({owner_col}, key, value_type, value, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT({owner_col}, key) DO UPDATE SET
+                value_type = excluded.value_type, value = excluded.value, updated_at = excluded.updated_at"
+        ),
+        params![owner_id, key, value.type_name(), value.to_sql(), now_iso()],
+    )?;
+    Ok(())
+}
+
+fn delete_property(
+    conn: &Connection,
+    table: &str,
+    owner_col: &str,
+    owner_id: i64,
+    key: &str,
+) -> CoreResult<bool> {
+    let key = normalize_key(key)?;
+    let removed = conn.execute(
+        &format!("DELETE FROM {table} WHERE {owner_col} = ?1 AND key = ?2"),
+        params![owner_id, key],
+    )?;
+    Ok(removed > 0)
+}
+
+/// Properties of one doc or folder row.
+pub(crate) fn properties(
+    conn: &Connection,
+    table: &str,
+    owner_col: &str,
+    owner_id: i64,
+) -> CoreResult<Properties> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT key, value_type, value FROM {table} WHERE {owner_col} = ?1"
+    ))?;
+    let mut out = Properties::new();
+    for row in stmt.query_map([owner_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Value>(2)?,
+        ))
+    })? {
+        let (key, value_type, value) = row?;
+        if let Some(value) = PropertyValue::from_sql(&value_type, value) {
+            out.insert(key, value);
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn doc_properties(conn: &Connection, doc_id: i64) -> CoreResult<Properties> {
+    properties(conn, "doc_properties", "doc_id", doc_id)
+}
+
+pub(crate) fn folder_properties(conn: &Connection, folder_id: i64) -> CoreResult<Properties> {
+    properties(conn, "folder_properties", "folder_id", folder_id)
+}
+
+/// Properties of every doc whose rel_path matches `pattern`, keyed by rel_path.
+pub(crate) fn properties_by_rel_path(
+    conn: &Connection,
+    pattern: &str,
+) -> CoreResult<BTreeMap<String, Properties>> {
+    let mut stmt = conn.prepare(
+        "SELECT d.rel_path, p.key, p.value_type, p.value
+         FROM doc_properties p JOIN docs d ON d.id = p.doc_id
+         WHERE d.rel_path LIKE ?1",
+    )?;
+    let mut map: BTreeMap<String, Properties> = BTreeMap::new();
+    for row in stmt.query_map([pattern], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Value>(3)?,
+        ))
+    })? {
+        let (rel_path, key, value_type, value) = row?;
+        if let Some(value) = PropertyValue::from_sql(&value_type, value) {
+            map.entry(rel_path).or_default().insert(key, value);
+        }
+    }
+    Ok(map)
+}
+
+/// `AND ...` clause restricting a query over `docs` to matches of `filter`,
+/// with the values it binds.
+pub(crate) fn filter_sql(filter: &PropertyFilter) -> CoreResult<(String, Vec<Value>)> {
+    let key = normalize_key(&filter.key)?;
+    let comparison = match filter.op {
+        PropertyOp::Exists | PropertyOp::Missing => None,
+        PropertyOp::Eq | PropertyOp::Ne => Some("="),
+        PropertyOp::Lt => Some("<"),
+        PropertyOp::Lte => Some("<="),
+        PropertyOp::Gt => Some(">"),
+        PropertyOp::Gte => Some(">="),
+    };
+    let negated = matches!(filter.op, PropertyOp::Ne | PropertyOp::Missing);
+    let mut sql = format!(
+        " AND id {}IN (SELECT doc_id FROM doc_properties WHERE key = ?",
+        if negated { "NOT " } else { "" }
+    );
+    let mut values = vec![Value::Text(key)];
+    if let Some(comparison) = comparison {
+        let value = filter.value.as_ref().ok_or_else(|| {
+            CoreError::Message(format!(
+                "Property filter on \"{}\" needs a value.",
+                filter.key
+            ))
+        })?;
+        value.validate()?;
+        sql.push_str(&format!(" AND value_type = ? AND value {comparison} ?"));
+        values.push(Value::Text(value.type_name().to_string()));
+        values.push(value.to_sql());
+    }
+    sql.push(')');
+    Ok((sql, values))
+}
+
+/// `ORDER BY` terms placing docs by `sort`, docs without the property last.
+pub(crate) fn sort_sql(sort: &PropertySort) -> CoreResult<(String, Vec<Value>)> {
+    let key = normalize_key(&sort.key)?;
+    let value = "(SELECT value FROM doc_properties p WHERE p.doc_id = docs.id AND p.key = ?)";
+    let sql = format!(
+        "{value} IS NULL, {value} {}",
+        if sort.descending { "DESC" } else { "ASC" }
+    );
+    Ok((sql, vec![Value::Text(key.clone()), Value::Text(key)]))
+}
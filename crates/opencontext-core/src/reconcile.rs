@@ -0,0 +1,241 @@
+//! Filesystem reconciliation between `contexts_root` and the SQLite catalog.
+//!
+//! Files under `contexts_root` are routinely edited outside OpenContext
+//! (editors, git pulls, scripts). `reconcile` walks the tree, registers
+//! untracked folders/docs and flags (or removes) catalog rows whose files
+//! have disappeared.
+
+use std::{collections::HashSet, fs, path::Path};
+
+use rusqlite::params;
+
+use crate::{
+    fulltext, generate_stable_id, now_iso, parent_rel_path, CoreError, CoreResult, OpenContext,
+};
+
+#[cfg(feature = "search")]
+use crate::events::{DocEvent, FolderEvent};
+
+/// Options controlling a reconcile run
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileOptions {
+    /// Only report the differences, do not touch the catalog
+    pub dry_run: bool,
+    /// Delete catalog rows whose files no longer exist on disk
+    pub remove_orphans: bool,
+}
+
+/// Differences found (and, unless dry-run, applied) by `reconcile`
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ReconcileReport {
+    pub dry_run: bool,
+    /// Folders found on disk but missing from the catalog
+    pub added_folders: Vec<String>,
+    /// Documents found on disk but missing from the catalog
+    pub added_docs: Vec<String>,
+    /// Catalog folders whose directory no longer exists
+    pub orphaned_folders: Vec<String>,
+    /// Catalog documents whose file no longer exists
+    pub orphaned_docs: Vec<String>,
+    /// Whether orphaned rows were deleted from the catalog
+    pub orphans_removed: bool,
+}
+
+impl ReconcileReport {
+    pub fn is_clean(&self) -> bool {
+        self.added_folders.is_empty()
+            && self.added_docs.is_empty()
+            && self.orphaned_folders.is_empty()
+            && self.orphaned_docs.is_empty()
+    }
+}
+
+impl OpenContext {
+    /// Compare `contexts_root` with the catalog without changing anything.
+    pub fn reconcile_report(&self) -> CoreResult<ReconcileReport> {
+        self.reconcile(ReconcileOptions {
+            dry_run: true,
+            remove_orphans: false,
+        })
+    }
+
+    /// Bring the catalog in line with the files under `contexts_root`.
+    ///
+    /// Untracked folders and `.md` docs are registered with fresh stable ids.
+    /// Rows whose files are gone are reported, and deleted when
+    /// `remove_orphans` is set.
+    pub fn reconcile(&self, options: ReconcileOptions) -> CoreResult<ReconcileReport> {
+        let mut disk_folders = Vec::new();
+        let mut disk_docs = Vec::new();
+        scan_tree(&self.contexts_root, "", &mut disk_folders, &mut disk_docs)?;
+
+        let (known_folders, known_docs) = self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT rel_path FROM folders")?;
+            let folders = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<HashSet<_>, _>>()?;
+            let mut stmt = conn.prepare("SELECT rel_path FROM docs")?;
+            let docs = stmt
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<HashSet<_>, _>>()?;
+            Ok((folders, docs))
+        })?;
+
+        let disk_folder_set: HashSet<&str> = disk_folders.iter().map(String::as_str).collect();
+        let disk_doc_set: HashSet<&str> = disk_docs.iter().map(String::as_str).collect();
+
+        let mut report = ReconcileReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        report.added_folders = disk_folders
+            .iter()
+            .filter(|p| !known_folders.contains(*p))
+            .cloned()
+            .collect();
+        report.added_docs = disk_docs
+            .iter()
+            .filter(|p| !known_docs.contains(*p))
+            .cloned()
+            .collect();
+        report.orphaned_folders = known_folders
+            .iter()
+            .filter(|p| !disk_folder_set.contains(p.as_str()))
+            .cloned()
+            .collect();
+        report.orphaned_docs = known_docs
+            .iter()
+            .filter(|p| !disk_doc_set.contains(p.as_str()))
+            .cloned()
+            .collect();
+        report.orphaned_folders.sort();
+        report.orphaned_docs.sort();
+
+        if options.dry_run {
+            return Ok(report);
+        }
+
+        // Parents sort before children, so each folder's parent row exists
+        // by the time it is inserted.
+        let ts = now_iso();
+        for rel_path in &report.added_folders {
+            let name = rel_path.split('/').next_back().unwrap_or(rel_path);
+            let abs_path = self.contexts_root.join(rel_path);
+            self.with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO folders (parent_id, name, rel_path, abs_path, description, created_at, updated_at)
+                     VALUES (
+                        (SELECT id FROM folders WHERE rel_path = ?1),
+                        ?2, ?3, ?4, '', ?5, ?5
+                     )",
+                    params![
+                        parent_rel_path(rel_path),
+                        name,
+                        rel_path,
+                        abs_path.to_string_lossy(),
+                        ts
+                    ],
+                )?;
+                Ok(())
+            })?;
+            #[cfg(feature = "search")]
+            self.emit_folder_event(FolderEvent::Created {
+                rel_path: rel_path.clone(),
+            });
+        }
+
+        for rel_path in &report.added_docs {
+            let folder_rel = parent_rel_path(rel_path).unwrap_or_default();
+            let folder = self
+                .find_folder(&folder_rel)?
+                .ok_or_else(|| CoreError::Message(format!("Folder \"{folder_rel}\" missing.")))?;
+            let name = rel_path.split('/').next_back().unwrap_or(rel_path);
+            let abs_path = self.contexts_root.join(rel_path);
+            self.with_conn(|conn| {
+                let sid = generate_stable_id(conn)?;
+                conn.execute(
+                    "INSERT INTO docs (folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, '', ?5, ?6, ?6)",
+                    params![folder.id, name, rel_path, abs_path.to_string_lossy(), sid, ts],
+                )?;
+                fulltext::index_doc_file(conn, conn.last_insert_rowid())?;
+                Ok(())
+            })?;
+            #[cfg(feature = "search")]
+            self.emit_doc_event(DocEvent::Created {
+                rel_path: rel_path.clone(),
+            });
+        }
+
+        if options.remove_orphans {
+            self.with_conn(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                for rel_path in &report.orphaned_docs {
+                    tx.execute("DELETE FROM docs WHERE rel_path = ?1", params![rel_path])?;
+                }
+                for rel_path in &report.orphaned_folders {
+                    tx.execute("DELETE FROM folders WHERE rel_path = ?1", params![rel_path])?;
+                }
+                tx.commit()?;
+                Ok(())
+            })?;
+            report.orphans_removed = true;
+
+            #[cfg(feature = "search")]
+            {
+                for rel_path in &report.orphaned_docs {
+                    self.emit_doc_event(DocEvent::Deleted {
+                        rel_path: rel_path.clone(),
+                    });
+                }
+                for rel_path in &report.orphaned_folders {
+                    self.emit_folder_event(FolderEvent::Deleted {
+                        rel_path: rel_path.clone(),
+                        removed_docs: vec![],
+                    });
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Recursively collect folder and `.md` doc paths relative to `root`.
+///
+/// Hidden entries are skipped, except for the `.ideas` tree which the
+/// desktop app stores as regular docs.
+fn scan_tree(
+    root: &Path,
+    rel_dir: &str,
+    folders: &mut Vec<String>,
+    docs: &mut Vec<String>,
+) -> CoreResult<()> {
+    let dir = if rel_dir.is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(rel_dir)
+    };
+    let mut entries = fs::read_dir(&dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') && !(rel_dir.is_empty() && name == ".ideas") {
+            continue;
+        }
+        let rel_path = if rel_dir.is_empty() {
+            name.clone()
+        } else {
+            format!("{rel_dir}/{name}")
+        };
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            folders.push(rel_path.clone());
+            scan_tree(root, &rel_path, folders, docs)?;
+        } else if file_type.is_file() && name.ends_with(".md") && !rel_dir.is_empty() {
+            // Docs always belong to a folder; loose files in the root are ignored.
+            docs.push(rel_path);
+        }
+    }
+    Ok(())
+}
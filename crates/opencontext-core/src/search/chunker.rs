@@ -3,6 +3,7 @@
 use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
 
 use super::types::TextChunk;
+use crate::{headings::HeadingPath, split_frontmatter};
 
 /// Markdown chunker that splits documents into semantic chunks
 /// All size calculations are based on **character count**, not byte count,
@@ -33,12 +34,16 @@ impl Chunker {
     }
 
     /// Chunk a markdown document into semantic pieces
+    ///
+    /// YAML frontmatter is not prose and is left out of the chunks; line
+    /// numbers still refer to the original file.
     pub fn chunk(&self, content: &str, _file_path: &str) -> Vec<TextChunk> {
+        let (_, content, frontmatter_lines) = split_frontmatter(content);
         let mut chunks = Vec::new();
-        let mut current_heading_path: Vec<(HeadingLevel, String)> = Vec::new();
+        let mut current_heading_path = HeadingPath::default();
         let mut current_text = String::new();
-        let mut current_start_line = 1;
-        let mut line_number = 1;
+        let mut current_start_line = frontmatter_lines + 1;
+        let mut line_number = frontmatter_lines + 1;
 
         let parser = Parser::new(content);
         let mut in_heading = false;
@@ -50,7 +55,7 @@ impl Chunker {
                 Event::Start(Tag::Heading { level, .. }) => {
                     // Save current chunk before starting new heading section
                     if !current_text.trim().is_empty() {
-                        let heading_path = Self::build_heading_path(&current_heading_path);
+                        let heading_path = current_heading_path.joined();
                         chunks.push(TextChunk {
                             content: current_text.trim().to_string(),
                             heading_path,
@@ -66,15 +71,7 @@ impl Chunker {
                 }
                 Event::End(TagEnd::Heading(_)) => {
                     if let Some(level) = heading_level {
-                        // Remove headings at same or lower level
-                        while let Some((last_level, _)) = current_heading_path.last() {
-                            if *last_level >= level {
-                                current_heading_path.pop();
-                            } else {
-                                break;
-                            }
-                        }
-                        current_heading_path.push((level, heading_text.trim().to_string()));
+                        current_heading_path.enter(level, &heading_text);
                     }
 
                     in_heading = false;
@@ -118,7 +115,7 @@ impl Chunker {
 
             // Check if we need to split the chunk (using char count, not byte count)
             if current_text.chars().count() > self.max_chunk_chars {
-                let heading_path = Self::build_heading_path(&current_heading_path);
+                let heading_path = current_heading_path.joined();
                 let (chunk, remainder) = self.split_chunk(&current_text);
 
                 chunks.push(TextChunk {
@@ -135,7 +132,7 @@ impl Chunker {
 
         // Don't forget the last chunk
         if !current_text.trim().is_empty() {
-            let heading_path = Self::build_heading_path(&current_heading_path);
+            let heading_path = current_heading_path.joined();
             chunks.push(TextChunk {
                 content: current_text.trim().to_string(),
                 heading_path,
@@ -148,14 +145,6 @@ impl Chunker {
         self.post_process_chunks(chunks)
     }
 
-    fn build_heading_path(headings: &[(HeadingLevel, String)]) -> String {
-        headings
-            .iter()
-            .map(|(_, text)| text.as_str())
-            .collect::<Vec<_>>()
-            .join(" > ")
-    }
-
     /// Split text into (chunk, remainder) at a natural boundary
     /// All calculations use character indices for Unicode safety
     fn split_chunk(&self, text: &str) -> (String, String) {
@@ -301,6 +290,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_frontmatter_is_stripped() {
+        let chunker = Chunker::default();
+        let content = "---\ntitle: Secret Title\ntags: [a, b]\n---\n# Heading\n\nBody text.";
+        let chunks = chunker.chunk(content, "test.md");
+
+        assert_eq!(chunks.len(), 1);
+        assert!(!chunks[0].content.contains("Secret Title"));
+        assert!(chunks[0].content.contains("Body text."));
+        assert_eq!(chunks[0].heading_path, "Heading");
+        assert!(chunks[0].start_line > 4);
+    }
+
     #[test]
     fn test_mixed_language() {
         let chunker = Chunker::new(50, 10);
@@ -1,7 +1,7 @@
 //! Search configuration
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::error::{SearchError, SearchResult};
 
@@ -107,6 +107,10 @@ pub struct SearchBehaviorConfig {
     /// Overlap between chunks in characters
     #[serde(default = "default_chunk_overlap")]
     pub chunk_overlap: usize,
+
+    /// Frontmatter fields stored as filterable chunk metadata
+    #[serde(default = "default_frontmatter_fields")]
+    pub frontmatter_fields: Vec<String>,
 }
 
 impl Default for SearchBehaviorConfig {
@@ -115,6 +119,7 @@ impl Default for SearchBehaviorConfig {
             default_limit: default_limit(),
             chunk_size: default_chunk_size(),
             chunk_overlap: default_chunk_overlap(),
+            frontmatter_fields: default_frontmatter_fields(),
         }
     }
 }
@@ -131,6 +136,13 @@ fn default_chunk_overlap() -> usize {
     200
 }
 
+fn default_frontmatter_fields() -> Vec<String> {
+    ["title", "tags", "status", "owner"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
 /// Paths configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PathsConfig {
@@ -198,10 +210,16 @@ impl SearchConfig {
     /// Load configuration from file and environment
     /// Priority: environment variables > config.json (Node.js) > config.toml (Rust) > defaults
     pub fn load() -> SearchResult<Self> {
+        Self::load_from(&Self::config_dir())
+    }
+
+    /// Like `load`, but reads `config.toml`/`config.json` from `dir`
+    /// (used for per-workspace configuration)
+    pub fn load_from(dir: &Path) -> SearchResult<Self> {
         let mut config = Self::default();
 
         // 1. Try loading from config.toml (Rust format)
-        let toml_path = Self::toml_config_path();
+        let toml_path = dir.join("config.toml");
         if toml_path.exists() {
             if let Ok(content) = std::fs::read_to_string(&toml_path) {
                 if let Ok(toml_config) = toml::from_str::<SearchConfig>(&content) {
@@ -211,7 +229,7 @@ impl SearchConfig {
         }
 
         // 2. Try loading from config.json (Node.js format) - this takes precedence
-        let json_path = Self::json_config_path();
+        let json_path = dir.join("config.json");
         if json_path.exists() {
             if let Ok(content) = std::fs::read_to_string(&json_path) {
                 if let Ok(node_config) = serde_json::from_str::<NodeJsConfig>(&content) {
@@ -0,0 +1,549 @@
+//! Type-aware chunking for docs that are not markdown
+//!
+//! `Extractors` picks an `Extractor` by file extension: line-based chunking
+//! for source code and plain text, structural chunking for JSON and YAML,
+//! and row groups for CSV. Files with unknown extensions go through the
+//! markdown `Chunker`. The chosen extractor's `doc_type` is recorded on
+//! every chunk so searches can filter by it.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use super::chunker::Chunker;
+use super::types::TextChunk;
+
+/// Turns the content of one kind of file into chunks
+pub trait Extractor: Send + Sync {
+    /// Value recorded in `Chunk.doc_type`
+    fn doc_type(&self) -> &str;
+
+    /// Split `content` into chunks
+    fn chunk(&self, content: &str, file_path: &str) -> Vec<TextChunk>;
+}
+
+impl Extractor for Chunker {
+    fn doc_type(&self) -> &str {
+        "doc"
+    }
+
+    fn chunk(&self, content: &str, file_path: &str) -> Vec<TextChunk> {
+        Chunker::chunk(self, content, file_path)
+    }
+}
+
+/// Extractors keyed by lowercase file extension
+pub struct Extractors {
+    by_extension: HashMap<String, Arc<dyn Extractor>>,
+    markdown: Arc<dyn Extractor>,
+}
+
+impl Default for Extractors {
+    fn default() -> Self {
+        Self::new(1500, 200)
+    }
+}
+
+impl Extractors {
+    /// Built-in extractors sized like `Chunker::new`
+    pub fn new(max_chunk_chars: usize, overlap_chars: usize) -> Self {
+        let mut extractors = Self {
+            by_extension: HashMap::new(),
+            markdown: Arc::new(Chunker::new(max_chunk_chars, overlap_chars)),
+        };
+        let code: Arc<dyn Extractor> =
+            Arc::new(LineChunker::new("code", max_chunk_chars, overlap_chars));
+        for ext in [
+            "rs", "js", "mjs", "cjs", "jsx", "ts", "tsx", "py", "go", "java", "kt", "swift", "c",
+            "h", "cc", "cpp", "hpp", "cs", "rb", "php", "sh", "bash", "zsh", "sql", "lua", "scala",
+            "css", "scss", "html", "vue", "svelte", "toml", "ini",
+        ] {
+            extractors.register(ext, code.clone());
+        }
+        let text: Arc<dyn Extractor> =
+            Arc::new(LineChunker::new("text", max_chunk_chars, overlap_chars));
+        for ext in ["txt", "text", "log", "rst"] {
+            extractors.register(ext, text.clone());
+        }
+        extractors.register(
+            "json",
+            Arc::new(StructuredChunker::new(
+                StructuredFormat::Json,
+                max_chunk_chars,
+                overlap_chars,
+            )),
+        );
+        let yaml: Arc<dyn Extractor> = Arc::new(StructuredChunker::new(
+            StructuredFormat::Yaml,
+            max_chunk_chars,
+            overlap_chars,
+        ));
+        extractors.register("yaml", yaml.clone());
+        extractors.register("yml", yaml);
+        let csv: Arc<dyn Extractor> = Arc::new(CsvChunker::new(max_chunk_chars));
+        extractors.register("csv", csv.clone());
+        extractors.register("tsv", csv);
+        extractors
+    }
+
+    /// Use `extractor` for files ending in `.<extension>`
+    pub fn register(&mut self, extension: &str, extractor: Arc<dyn Extractor>) {
+        self.by_extension.insert(
+            extension.trim_start_matches('.').to_ascii_lowercase(),
+            extractor,
+        );
+    }
+
+    /// The extractor for `file_path`, falling back to markdown
+    pub fn for_path(&self, file_path: &str) -> &dyn Extractor {
+        let name = file_path.rsplit('/').next().unwrap_or(file_path);
+        name.rsplit_once('.')
+            .and_then(|(_, ext)| self.by_extension.get(&ext.to_ascii_lowercase()))
+            .unwrap_or(&self.markdown)
+            .as_ref()
+    }
+}
+
+/// Groups whole lines into chunks, for source code and plain text
+pub struct LineChunker {
+    doc_type: &'static str,
+    max_chunk_chars: usize,
+    overlap_chars: usize,
+}
+
+impl LineChunker {
+    pub fn new(doc_type: &'static str, max_chunk_chars: usize, overlap_chars: usize) -> Self {
+        Self {
+            doc_type,
+            max_chunk_chars: max_chunk_chars.max(1),
+            overlap_chars,
+        }
+    }
+}
+
+impl Extractor for LineChunker {
+    fn doc_type(&self) -> &str {
+        self.doc_type
+    }
+
+    fn chunk(&self, content: &str, _file_path: &str) -> Vec<TextChunk> {
+        // Lines longer than a chunk are cut into pieces first, so every
+        // piece fits; pieces keep the line number they came from.
+        let mut pieces: Vec<(usize, String)> = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                pieces.push((i + 1, String::new()));
+            }
+            for part in chars.chunks(self.max_chunk_chars) {
+                pieces.push((i + 1, part.iter().collect()));
+            }
+        }
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < pieces.len() {
+            let mut end = start;
+            let mut size = 0;
+            while end < pieces.len() {
+                let len = pieces[end].1.chars().count() + 1;
+                if end > start && size + len > self.max_chunk_chars {
+                    break;
+                }
+                size += len;
+                end += 1;
+            }
+            let text = pieces[start..end]
+                .iter()
+                .map(|(_, line)| line.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !text.trim().is_empty() {
+                chunks.push(TextChunk {
+                    content: text.trim_end().to_string(),
+                    heading_path: String::new(),
+                    start_line: pieces[start].0,
+                    end_line: pieces[end - 1].0,
+                });
+            }
+            if end == pieces.len() {
+                break;
+            }
+            // Step back over trailing lines worth up to `overlap_chars`,
+            // always moving forward by at least one line.
+            let mut next = end;
+            let mut overlap = 0;
+            while next > start + 1 {
+                overlap += pieces[next - 1].1.chars().count() + 1;
+                if overlap > self.overlap_chars {
+                    break;
+                }
+                next -= 1;
+            }
+            start = next;
+        }
+        chunks
+    }
+}
+
+/// Serialization of a `StructuredChunker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Yaml,
+}
+
+/// Splits JSON or YAML documents along their structure. Each chunk holds a
+/// subtree or a run of sibling entries, with its key path (`a.b[2]`) as the
+/// heading path. Documents that fail to parse are chunked by lines.
+pub struct StructuredChunker {
+    format: StructuredFormat,
+    max_chunk_chars: usize,
+    fallback: LineChunker,
+}
+
+impl StructuredChunker {
+    pub fn new(format: StructuredFormat, max_chunk_chars: usize, overlap_chars: usize) -> Self {
+        let doc_type = match format {
+            StructuredFormat::Json => "json",
+            StructuredFormat::Yaml => "yaml",
+        };
+        Self {
+            format,
+            max_chunk_chars: max_chunk_chars.max(1),
+            fallback: LineChunker::new(doc_type, max_chunk_chars, overlap_chars),
+        }
+    }
+
+    fn parse(&self, content: &str) -> Option<Value> {
+        match self.format {
+            StructuredFormat::Json => serde_json::from_str(content).ok(),
+            StructuredFormat::Yaml => serde_yaml::from_str(content).ok(),
+        }
+    }
+
+    fn render(&self, value: &Value) -> String {
+        let rendered = match self.format {
+            StructuredFormat::Json => serde_json::to_string_pretty(value).unwrap_or_default(),
+            StructuredFormat::Yaml => serde_yaml::to_string(value).unwrap_or_default(),
+        };
+        rendered.trim_end().to_string()
+    }
+
+    /// Emit `value` at `path` as one chunk if it fits, otherwise split its
+    /// children, grouping consecutive small ones.
+    fn walk(&self, path: &str, value: &Value, end_line: usize, chunks: &mut Vec<TextChunk>) {
+        let rendered = self.render(value);
+        let children: Vec<(Option<&String>, String, &Value)> = match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, child)| (Some(key), join_key(path, key), child))
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, child)| (None, format!("{path}[{i}]"), child))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if rendered.chars().count() <= self.max_chunk_chars || children.is_empty() {
+            if !rendered.trim().is_empty() {
+                chunks.push(structured_chunk(rendered, path, end_line));
+            }
+            return;
+        }
+
+        // A run of siblings is labelled with the parent path, a lone child
+        // with its own.
+        let mut group: Vec<(Option<&String>, String, &Value)> = Vec::new();
+        let mut group_chars = 0;
+        let flush = |group: &mut Vec<(Option<&String>, String, &Value)>,
+                     chunks: &mut Vec<TextChunk>| {
+            match group.len() {
+                0 => return,
+                1 => {
+                    let (_, child_path, child) = &group[0];
+                    chunks.push(structured_chunk(self.render(child), child_path, end_line));
+                }
+                _ => {
+                    let value = if value.is_object() {
+                        Value::Object(
+                            group
+                                .iter()
+                                .filter_map(|(key, _, child)| {
+                                    Some(((*key)?.clone(), (*child).clone()))
+                                })
+                                .collect(),
+                        )
+                    } else {
+                        Value::Array(group.iter().map(|(_, _, child)| (*child).clone()).collect())
+                    };
+                    chunks.push(structured_chunk(self.render(&value), path, end_line));
+                }
+            }
+            group.clear();
+        };
+        for (key, child_path, child) in children {
+            let size = self.render(child).chars().count();
+            if size > self.max_chunk_chars {
+                flush(&mut group, chunks);
+                group_chars = 0;
+                self.walk(&child_path, child, end_line, chunks);
+                continue;
+            }
+            if group_chars + size > self.max_chunk_chars {
+                flush(&mut group, chunks);
+                group_chars = 0;
+            }
+            group_chars += size;
+            group.push((key, child_path, child));
+        }
+        flush(&mut group, chunks);
+    }
+}
+
+impl Extractor for StructuredChunker {
+    fn doc_type(&self) -> &str {
+        self.fallback.doc_type
+    }
+
+    fn chunk(&self, content: &str, file_path: &str) -> Vec<TextChunk> {
+        let Some(value) = self.parse(content) else {
+            return self.fallback.chunk(content, file_path);
+        };
+        let mut chunks = Vec::new();
+        self.walk("", &value, content.lines().count().max(1), &mut chunks);
+        chunks
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Structured chunks cannot be mapped back to source lines, so they span
+/// the whole file.
+fn structured_chunk(content: String, path: &str, end_line: usize) -> TextChunk {
+    TextChunk {
+        content,
+        heading_path: path.to_string(),
+        start_line: 1,
+        end_line,
+    }
+}
+
+/// Groups CSV rows into chunks, repeating the header row in each so a chunk
+/// reads on its own. The heading path names the rows (`rows 2-40`).
+/// Rows are kept as written, so any delimiter works.
+pub struct CsvChunker {
+    max_chunk_chars: usize,
+}
+
+impl CsvChunker {
+    pub fn new(max_chunk_chars: usize) -> Self {
+        Self {
+            max_chunk_chars: max_chunk_chars.max(1),
+        }
+    }
+}
+
+impl Extractor for CsvChunker {
+    fn doc_type(&self) -> &str {
+        "csv"
+    }
+
+    fn chunk(&self, content: &str, _file_path: &str) -> Vec<TextChunk> {
+        let records = split_records(content);
+        let Some(((header_line, header), rows)) = records.split_first() else {
+            return Vec::new();
+        };
+        if rows.is_empty() {
+            return vec![TextChunk {
+                content: header.to_string(),
+                heading_path: String::new(),
+                start_line: *header_line,
+                end_line: *header_line,
+            }];
+        }
+
+        let mut chunks = Vec::new();
+        let mut current: Vec<&(usize, &str)> = Vec::new();
+        let mut size = header.chars().count();
+        let mut push = |current: &mut Vec<&(usize, &str)>| {
+            let (Some(first), Some(last)) = (current.first(), current.last()) else {
+                return;
+            };
+            let mut text = header.to_string();
+            for (_, row) in current.iter() {
+                text.push('\n');
+                text.push_str(row);
+            }
+            let heading_path = if first.0 == last.0 {
+                format!("row {}", first.0)
+            } else {
+                format!("rows {}-{}", first.0, last.0)
+            };
+            chunks.push(TextChunk {
+                content: text,
+                heading_path,
+                start_line: first.0,
+                end_line: last.0 + last.1.matches('\n').count(),
+            });
+            current.clear();
+        };
+        for row in rows {
+            let len = row.1.chars().count() + 1;
+            if !current.is_empty() && size + len > self.max_chunk_chars {
+                push(&mut current);
+                size = header.chars().count();
+            }
+            size += len;
+            current.push(row);
+        }
+        push(&mut current);
+        chunks
+    }
+}
+
+/// Split CSV text into `(line number, record)` pairs, keeping newlines
+/// inside quoted fields and dropping blank lines.
+fn split_records(content: &str) -> Vec<(usize, &str)> {
+    let mut records = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    let mut line = 1;
+    let mut start_line = 1;
+    for (i, c) in content.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\n' => {
+                line += 1;
+                if !in_quotes {
+                    let record = content[start..i].trim_end_matches('\r');
+                    if !record.trim().is_empty() {
+                        records.push((start_line, record));
+                    }
+                    start = i + 1;
+                    start_line = line;
+                }
+            }
+            _ => {}
+        }
+    }
+    let record = content[start..].trim_end_matches('\r');
+    if !record.trim().is_empty() {
+        records.push((start_line, record));
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selects_extractor_by_extension() {
+        let extractors = Extractors::default();
+        assert_eq!(extractors.for_path("notes/a.md").doc_type(), "doc");
+        assert_eq!(extractors.for_path("notes/no-extension").doc_type(), "doc");
+        assert_eq!(extractors.for_path("src/main.RS").doc_type(), "code");
+        assert_eq!(extractors.for_path("logs/run.log").doc_type(), "text");
+        assert_eq!(extractors.for_path("config/app.json").doc_type(), "json");
+        assert_eq!(extractors.for_path("config/app.yml").doc_type(), "yaml");
+        assert_eq!(extractors.for_path("exports/users.csv").doc_type(), "csv");
+        assert_eq!(extractors.for_path("v1.2/readme").doc_type(), "doc");
+
+        let mut extractors = extractors;
+        extractors.register(".MD", Arc::new(LineChunker::new("plain", 100, 0)));
+        assert_eq!(extractors.for_path("notes/a.md").doc_type(), "plain");
+    }
+
+    #[test]
+    fn test_line_chunker_keeps_whole_lines() {
+        let chunker = LineChunker::new("code", 40, 20);
+        let content = (1..=10)
+            .map(|i| format!("let value_{i} = {i};"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let chunks = chunker.chunk(&content, "src/lib.rs");
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].start_line, 1);
+        assert_eq!(chunks.last().unwrap().end_line, 10);
+        for chunk in &chunks {
+            assert!(chunk.content.chars().count() <= 40);
+            assert!(chunk.content.lines().all(|l| l.starts_with("let ")));
+        }
+        // Consecutive chunks overlap by a line.
+        assert_eq!(chunks[1].start_line, chunks[0].end_line);
+
+        let long = "x".repeat(95);
+        let chunks = LineChunker::new("text", 40, 0).chunk(&long, "a.txt");
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.start_line == 1));
+    }
+
+    #[test]
+    fn test_structured_chunker_splits_by_key_path() {
+        let chunker = StructuredChunker::new(StructuredFormat::Json, 80, 0);
+        let content = r#"{
+            "name": "demo",
+            "version": 1,
+            "dependencies": {
+                "serde": "1",
+                "regex": "1",
+                "tokio": {"version": "1", "features": ["rt-multi-thread", "macros", "sync"]}
+            }
+        }"#;
+        let chunks = chunker.chunk(content, "package.json");
+        let paths: Vec<&str> = chunks.iter().map(|c| c.heading_path.as_str()).collect();
+        assert!(paths.contains(&""));
+        assert!(paths.contains(&"dependencies"));
+        assert!(paths.contains(&"dependencies.tokio"));
+        for chunk in &chunks {
+            assert!(serde_json::from_str::<Value>(&chunk.content).is_ok());
+        }
+        let top = chunks.iter().find(|c| c.heading_path.is_empty()).unwrap();
+        assert!(top.content.contains("\"demo\""));
+
+        let yaml = StructuredChunker::new(StructuredFormat::Yaml, 30, 0);
+        let chunks = yaml.chunk(
+            "server:\n  host: localhost\n  port: 8080\nusers:\n  - alice\n  - bob\n",
+            "app.yaml",
+        );
+        assert!(chunks
+            .iter()
+            .any(|c| c.heading_path == "server" && c.content.contains("host: localhost")));
+        assert_eq!(yaml.doc_type(), "yaml");
+
+        // Invalid documents fall back to line chunking.
+        let chunks = chunker.chunk("{ not json", "broken.json");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].content, "{ not json");
+    }
+
+    #[test]
+    fn test_csv_chunker_groups_rows_under_header() {
+        let chunker = CsvChunker::new(40);
+        let mut content = String::from("id,name\n");
+        for i in 1..=6 {
+            content.push_str(&format!("{i},user number {i}\n"));
+        }
+        content.push_str("7,\"multi\nline\"\n");
+        let chunks = chunker.chunk(&content, "users.csv");
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].heading_path, "rows 2-3");
+        for chunk in &chunks {
+            assert!(chunk.content.starts_with("id,name\n"));
+        }
+        let last = chunks.last().unwrap();
+        assert!(last.content.ends_with("7,\"multi\nline\""));
+        assert_eq!(last.end_line, 9);
+    }
+}
@@ -1,99 +1,16 @@
 //! Document indexer
 
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use super::chunker::Chunker;
 use super::config::SearchConfig;
 use super::embedding::EmbeddingClient;
 use super::error::{SearchError, SearchResult};
+use super::extractor::{Extractor, Extractors};
 use super::types::Chunk;
 use super::vector_store::VectorStore;
-
-#[derive(Clone)]
-struct IdeaEntry {
-    id: String,
-    created_at: String,
-    content: String,
-}
-
-const DEFAULT_IDEA_BOX: &str = "inbox";
-
-fn parse_idea_marker(line: &str) -> Option<(String, String)> {
-    let trimmed = line.trim();
-    if !trimmed.starts_with("[//]: # (") || !trimmed.ends_with(')') {
-        return None;
-    }
-    let inner = trimmed.strip_prefix("[//]: # (")?.strip_suffix(')')?;
-    let mut id = None;
-    let mut created_at = None;
-    for part in inner.split_whitespace() {
-        if let Some(value) = part.strip_prefix("idea:id=") {
-            id = Some(value.to_string());
-        } else if let Some(value) = part.strip_prefix("created_at=") {
-            created_at = Some(value.to_string());
-        }
-    }
-    match (id, created_at) {
-        (Some(id), Some(created_at)) => Some((id, created_at)),
-        _ => None,
-    }
-}
-
-fn parse_idea_entries(content: &str) -> Vec<IdeaEntry> {
-    let mut entries = Vec::new();
-    let mut current_id: Option<String> = None;
-    let mut current_created: Option<String> = None;
-    let mut buffer: Vec<String> = Vec::new();
-
-    for line in content.lines() {
-        if let Some((id, created_at)) = parse_idea_marker(line) {
-            if let (Some(id), Some(created_at)) = (current_id.take(), current_created.take()) {
-                entries.push(IdeaEntry {
-                    id,
-                    created_at,
-                    content: buffer.join("\n").trim().to_string(),
-                });
-            }
-            buffer.clear();
-            current_id = Some(id);
-            current_created = Some(created_at);
-            continue;
-        }
-        if current_id.is_some() {
-            buffer.push(line.to_string());
-        }
-    }
-
-    if let (Some(id), Some(created_at)) = (current_id.take(), current_created.take()) {
-        entries.push(IdeaEntry {
-            id,
-            created_at,
-            content: buffer.join("\n").trim().to_string(),
-        });
-    }
-
-    entries
-}
-
-fn extract_idea_box(rel_path: &str) -> Option<String> {
-    let normalized = rel_path.trim_start_matches("./");
-    if !normalized.starts_with(".ideas/") {
-        return None;
-    }
-    let rest = &normalized[".ideas/".len()..];
-    let mut parts = rest.split('/').filter(|p| !p.is_empty());
-    let first = parts.next();
-    match first {
-        None => Some(DEFAULT_IDEA_BOX.to_string()),
-        Some(seg) => {
-            if seg.len() == 4 && seg.chars().all(|c| c.is_ascii_digit()) {
-                Some(DEFAULT_IDEA_BOX.to_string())
-            } else {
-                Some(seg.to_string())
-            }
-        }
-    }
-}
+use crate::ideas::{extract_idea_box, parse_idea_entries};
+use crate::paths;
 
 /// Index build statistics
 #[derive(Debug, Clone, serde::Serialize)]
@@ -133,7 +50,7 @@ pub struct Indexer {
     contexts_root: PathBuf,
     vector_store: VectorStore,
     embedding_client: EmbeddingClient,
-    chunker: Chunker,
+    extractors: Extractors,
     /// Whether vector_store has been re-initialized with actual dimensions
     dimensions_verified: bool,
 }
@@ -149,18 +66,24 @@ impl Indexer {
 
         let embedding_client = EmbeddingClient::new(config.embedding.clone())?;
 
-        let chunker = Chunker::new(config.search.chunk_size, config.search.chunk_overlap);
+        let extractors = Extractors::new(config.search.chunk_size, config.search.chunk_overlap);
 
         Ok(Self {
             config,
             contexts_root,
             vector_store,
             embedding_client,
-            chunker,
+            extractors,
             dimensions_verified: false,
         })
     }
 
+    /// Chunk files ending in `.<extension>` with `extractor`, replacing the
+    /// built-in choice
+    pub fn register_extractor(&mut self, extension: &str, extractor: Arc<dyn Extractor>) {
+        self.extractors.register(extension, extractor);
+    }
+
     /// Verify and update vector store dimensions based on actual embedding dimensions
     async fn verify_dimensions(&mut self) -> SearchResult<()> {
         if self.dimensions_verified {
@@ -226,6 +149,14 @@ impl Indexer {
             });
 
             for doc in batch {
+                if paths::ensure_within(&self.contexts_root, &doc.abs_path).is_err() {
+                    log::warn!(
+                        "Skipping {}: it resolves outside contexts_root",
+                        doc.rel_path
+                    );
+                    processed_docs += 1;
+                    continue;
+                }
                 let content = std::fs::read_to_string(&doc.abs_path)?;
                 if content.trim().is_empty() {
                     processed_docs += 1;
@@ -264,30 +195,13 @@ impl Indexer {
                             },
                             entry_created_at: Some(entry.created_at),
                             idea_box: idea_box.clone(),
+                            metadata: None,
                             chunk_index: i,
                             vector: vec![], // Will be filled below
                         });
                     }
                 } else {
-                    let text_chunks = self.chunker.chunk(&content, &doc.rel_path);
-
-                    for (i, text_chunk) in text_chunks.into_iter().enumerate() {
-                        let id = format!("{}#{}", doc.rel_path, i);
-                        all_chunks.push(Chunk {
-                            id,
-                            file_path: doc.rel_path.clone(),
-                            content: text_chunk.content,
-                            heading_path: text_chunk.heading_path,
-                            section_title: None,
-                            doc_type: Some("doc".to_string()),
-                            entry_id: None,
-                            entry_date: None,
-                            entry_created_at: None,
-                            idea_box: None,
-                            chunk_index: i,
-                            vector: vec![], // Will be filled below
-                        });
-                    }
+                    all_chunks.extend(self.doc_chunks(&doc.rel_path, &content));
                 }
                 processed_docs += 1;
             }
@@ -361,7 +275,11 @@ impl Indexer {
 
     /// Index a single file
     pub async fn index_file(&mut self, rel_path: &str) -> SearchResult<usize> {
+        let rel_path = &paths::clean_rel_path(rel_path, "Document path")
+            .map_err(|e| SearchError::Index(e.to_string()))?;
         let abs_path = self.contexts_root.join(rel_path);
+        paths::ensure_within(&self.contexts_root, &abs_path)
+            .map_err(|e| SearchError::Index(e.to_string()))?;
 
         if !abs_path.exists() {
             return Err(SearchError::Index(format!("File not found: {}", rel_path)));
@@ -410,29 +328,13 @@ impl Indexer {
                     },
                     entry_created_at: Some(entry.created_at),
                     idea_box: idea_box.clone(),
+                    metadata: None,
                     chunk_index: i,
                     vector: vec![],
                 });
             }
         } else {
-            let text_chunks = self.chunker.chunk(&content, rel_path);
-            for (i, text_chunk) in text_chunks.into_iter().enumerate() {
-                let id = format!("{}#{}", rel_path, i);
-                chunks.push(Chunk {
-                    id,
-                    file_path: rel_path.to_string(),
-                    content: text_chunk.content,
-                    heading_path: text_chunk.heading_path,
-                    section_title: None,
-                    doc_type: Some("doc".to_string()),
-                    entry_id: None,
-                    entry_date: None,
-                    entry_created_at: None,
-                    idea_box: None,
-                    chunk_index: i,
-                    vector: vec![],
-                });
-            }
+            chunks.extend(self.doc_chunks(rel_path, &content));
         }
 
         if chunks.is_empty() {
@@ -457,6 +359,56 @@ impl Indexer {
         Ok(count)
     }
 
+    /// Chunks of a non-idea doc, from the extractor for its extension.
+    /// Embeddings are filled in by the caller.
+    fn doc_chunks(&self, rel_path: &str, content: &str) -> Vec<Chunk> {
+        let extractor = self.extractors.for_path(rel_path);
+        let doc_type = extractor.doc_type().to_string();
+        // Frontmatter only means something in markdown.
+        let metadata = if doc_type == "doc" {
+            self.frontmatter_metadata(content)
+        } else {
+            None
+        };
+        extractor
+            .chunk(content, rel_path)
+            .into_iter()
+            .enumerate()
+            .map(|(i, text_chunk)| Chunk {
+                id: format!("{}#{}", rel_path, i),
+                file_path: rel_path.to_string(),
+                content: text_chunk.content,
+                heading_path: text_chunk.heading_path,
+                section_title: None,
+                doc_type: Some(doc_type.clone()),
+                entry_id: None,
+                entry_date: None,
+                entry_created_at: None,
+                idea_box: None,
+                metadata: metadata.clone(),
+                chunk_index: i,
+                vector: vec![],
+            })
+            .collect()
+    }
+
+    /// Configured frontmatter fields of a doc, serialized for chunk metadata
+    fn frontmatter_metadata(&self, content: &str) -> Option<String> {
+        let fields = crate::parse_frontmatter(content)?;
+        let selected: serde_json::Map<String, serde_json::Value> = self
+            .config
+            .search
+            .frontmatter_fields
+            .iter()
+            .filter_map(|key| fields.get(key).map(|v| (key.clone(), v.clone())))
+            .collect();
+        if selected.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&selected).ok()
+        }
+    }
+
     /// Remove a file from the index
     pub async fn remove_file(&mut self, rel_path: &str) -> SearchResult<()> {
         self.vector_store.delete_by_file(rel_path).await?;
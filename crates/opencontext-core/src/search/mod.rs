@@ -7,6 +7,8 @@
 //! - Vector-based semantic search using LanceDB
 //! - OpenAI Embedding API integration
 //! - Markdown-aware document chunking
+//! - Line, structural and row-group chunking for code, text, JSON, YAML
+//!   and CSV docs
 //! - Hybrid search (vector + keyword)
 //! - Event-driven index synchronization
 //!
@@ -27,6 +29,7 @@ mod chunker;
 mod config;
 mod embedding;
 mod error;
+mod extractor;
 mod index_sync;
 mod indexer;
 mod searcher;
@@ -40,6 +43,9 @@ pub use chunker::Chunker;
 pub use config::{EmbeddingConfig, SearchConfig};
 pub use embedding::EmbeddingClient;
 pub use error::{SearchError, SearchResult};
+pub use extractor::{
+    CsvChunker, Extractor, Extractors, LineChunker, StructuredChunker, StructuredFormat,
+};
 pub use index_sync::IndexSyncService;
 pub use indexer::{IndexProgress, IndexStats, Indexer};
 pub use searcher::Searcher;
@@ -85,10 +85,16 @@ impl Searcher {
             hits.retain(|hit| match filter_type {
                 "idea" => hit.doc_type.as_deref() == Some("idea"),
                 "doc" => hit.doc_type.as_deref().unwrap_or("doc") == "doc",
-                _ => true,
+                other => hit.doc_type.as_deref() == Some(other),
             });
         }
 
+        if let Some(filter) = options.metadata_filter.as_ref() {
+            if !filter.is_empty() {
+                hits.retain(|hit| hit.matches_metadata(filter));
+            }
+        }
+
         // Aggregate results
         let results = match aggregate_by {
             AggregateBy::Content => hits.into_iter().take(limit).collect(),
@@ -506,6 +512,7 @@ impl Searcher {
                     entry_date: None,
                     entry_created_at: None,
                     idea_box: doc.top_chunk.idea_box,
+                    metadata: doc.top_chunk.metadata,
                 }
             })
             .collect();
@@ -602,6 +609,7 @@ impl Searcher {
                     entry_date: None,
                     entry_created_at: None,
                     idea_box: folder.top_chunk.idea_box,
+                    metadata: None,
                 }
             })
             .collect();
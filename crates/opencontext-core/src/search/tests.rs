@@ -136,6 +136,53 @@ mod tests {
             let results = SearchResults::index_not_built("query".to_string());
             assert!(results.index_missing.unwrap_or(false));
         }
+
+        #[test]
+        fn test_search_hit_matches_metadata() {
+            let hit = SearchHit {
+                file_path: "adr.md".to_string(),
+                display_name: "adr".to_string(),
+                content: String::new(),
+                heading_path: None,
+                section_title: None,
+                line_start: None,
+                line_end: None,
+                score: 0.0,
+                matched_by: MatchType::Keyword,
+                hit_count: None,
+                doc_count: None,
+                folder_path: None,
+                aggregate_type: None,
+                doc_type: None,
+                entry_id: None,
+                entry_date: None,
+                entry_created_at: None,
+                idea_box: None,
+                metadata: Some(serde_json::json!({
+                    "status": "accepted",
+                    "tags": ["decision", "payments"],
+                    "draft": false,
+                    "priority": 2,
+                    "versions": [1, 1.5]
+                })),
+            };
+            let filter = |pairs: &[(&str, &str)]| {
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<std::collections::HashMap<_, _>>()
+            };
+
+            assert!(hit.matches_metadata(&filter(&[("status", "accepted")])));
+            assert!(hit.matches_metadata(&filter(&[("tags", "decision"), ("status", "accepted")])));
+            assert!(!hit.matches_metadata(&filter(&[("status", "draft")])));
+            assert!(!hit.matches_metadata(&filter(&[("owner", "alice")])));
+            assert!(hit.matches_metadata(&filter(&[("draft", "false")])));
+            assert!(!hit.matches_metadata(&filter(&[("draft", "no")])));
+            assert!(hit.matches_metadata(&filter(&[("priority", "2.0")])));
+            assert!(hit.matches_metadata(&filter(&[("versions", "1.5")])));
+            assert!(!hit.matches_metadata(&filter(&[("priority", "two")])));
+        }
     }
 
     mod config_tests {
@@ -1,5 +1,7 @@
 //! Common types for search module
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 /// A chunk of document content with its embedding
@@ -16,7 +18,8 @@ pub struct Chunk {
     /// Optional section title (for ideas entry title)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub section_title: Option<String>,
-    /// Document type: "doc" | "idea"
+    /// Document type: "doc" (markdown) | "idea" | "code" | "text" | "json" |
+    /// "yaml" | "csv", or the type of a registered extractor
     #[serde(skip_serializing_if = "Option::is_none")]
     pub doc_type: Option<String>,
     /// Entry id for idea chunks
@@ -31,6 +34,9 @@ pub struct Chunk {
     /// Ideas box name (e.g. "inbox")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub idea_box: Option<String>,
+    /// Selected frontmatter fields of the source doc, as a JSON object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<String>,
     /// Index of this chunk within the document
     pub chunk_index: usize,
     /// Embedding vector
@@ -99,8 +105,12 @@ pub struct SearchOptions {
     pub mode: Option<SearchMode>,
     /// Aggregation level
     pub aggregate_by: Option<AggregateBy>,
-    /// Filter by document type: "doc" | "idea"
+    /// Filter by document type: "doc" | "idea" | "code" | "text" | "json" |
+    /// "yaml" | "csv"
     pub doc_type: Option<String>,
+    /// Only keep hits whose frontmatter metadata has these values
+    /// (list fields match when they contain the value)
+    pub metadata_filter: Option<HashMap<String, String>>,
 }
 
 impl SearchOptions {
@@ -155,7 +165,7 @@ pub struct SearchHit {
     /// Aggregation type: 'doc' | 'folder'
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregate_type: Option<String>,
-    /// Document type: 'doc' | 'idea'
+    /// Document type, as in `Chunk.doc_type`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub doc_type: Option<String>,
     /// Entry id for idea hits
@@ -170,6 +180,36 @@ pub struct SearchHit {
     /// Ideas box name (e.g. "inbox")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub idea_box: Option<String>,
+    /// Selected frontmatter fields of the source doc
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl SearchHit {
+    /// Whether this hit's metadata matches every `key = value` pair
+    pub fn matches_metadata(&self, filter: &HashMap<String, String>) -> bool {
+        filter.iter().all(
+            |(key, expected)| match self.metadata.as_ref().and_then(|m| m.get(key)) {
+                Some(serde_json::Value::Array(items)) => {
+                    items.iter().any(|item| scalar_matches(item, expected))
+                }
+                Some(value) => scalar_matches(value, expected),
+                None => false,
+            },
+        )
+    }
+}
+
+/// Compare a string, bool or number metadata value with a filter value
+fn scalar_matches(value: &serde_json::Value, expected: &str) -> bool {
+    match value {
+        serde_json::Value::String(s) => s == expected,
+        serde_json::Value::Bool(b) => expected.parse::<bool>() == Ok(*b),
+        serde_json::Value::Number(n) => {
+            matches!((expected.parse::<f64>(), n.as_f64()), (Ok(e), Some(n)) if e == n)
+        }
+        _ => false,
+    }
 }
 
 /// Search results response
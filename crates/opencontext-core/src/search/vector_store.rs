@@ -4,12 +4,13 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use arrow_array::{
-    types::Float32Type, FixedSizeListArray, RecordBatch, RecordBatchIterator, StringArray,
+    types::Float32Type, Array, FixedSizeListArray, RecordBatch, RecordBatchIterator, StringArray,
     UInt32Array,
 };
 use arrow_schema::{DataType, Field, Schema};
 use futures::TryStreamExt;
 use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::table::NewColumnTransform;
 use lancedb::{connect, Connection, Table};
 
 use super::error::{SearchError, SearchResult};
@@ -63,6 +64,7 @@ impl VectorStore {
                     .execute()
                     .await
                     .map_err(SearchError::Lance)?;
+                Self::migrate_table(&table).await?;
                 self.table = Some(table);
             }
         }
@@ -70,6 +72,24 @@ impl VectorStore {
         Ok(())
     }
 
+    /// Add columns introduced after the table was created
+    async fn migrate_table(table: &Table) -> SearchResult<()> {
+        let schema = table.schema().await.map_err(SearchError::Lance)?;
+        if schema.field_with_name("metadata").is_err() {
+            table
+                .add_columns(
+                    NewColumnTransform::SqlExpressions(vec![(
+                        "metadata".to_string(),
+                        "CAST(NULL AS STRING)".to_string(),
+                    )]),
+                    None,
+                )
+                .await
+                .map_err(SearchError::Lance)?;
+        }
+        Ok(())
+    }
+
     /// Check if index exists
     pub async fn exists(&self) -> bool {
         self.table.is_some()
@@ -88,6 +108,7 @@ impl VectorStore {
             Field::new("entry_date", DataType::Utf8, true),
             Field::new("entry_created_at", DataType::Utf8, true),
             Field::new("idea_box", DataType::Utf8, true),
+            Field::new("metadata", DataType::Utf8, true),
             Field::new("chunk_index", DataType::UInt32, false),
             Field::new(
                 "vector",
@@ -169,6 +190,7 @@ impl VectorStore {
             .iter()
             .map(|c| c.idea_box.as_deref().unwrap_or(""))
             .collect();
+        let metadata: Vec<Option<&str>> = chunks.iter().map(|c| c.metadata.as_deref()).collect();
         let chunk_indices: Vec<u32> = chunks.iter().map(|c| c.chunk_index as u32).collect();
 
         let vectors_array = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
@@ -191,6 +213,7 @@ impl VectorStore {
                 Arc::new(StringArray::from(entry_dates)),
                 Arc::new(StringArray::from(entry_created_ats)),
                 Arc::new(StringArray::from(idea_boxes)),
+                Arc::new(StringArray::from(metadata)),
                 Arc::new(UInt32Array::from(chunk_indices)),
                 Arc::new(vectors_array),
             ],
@@ -260,6 +283,10 @@ impl VectorStore {
                 .column_by_name("idea_box")
                 .and_then(|c| c.as_any().downcast_ref::<StringArray>());
 
+            let metadata_values = batch
+                .column_by_name("metadata")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
             let line_starts = batch
                 .column_by_name("line_start")
                 .and_then(|c| c.as_any().downcast_ref::<arrow_array::Int64Array>());
@@ -336,6 +363,14 @@ impl VectorStore {
                     }
                 });
 
+                let metadata = metadata_values.and_then(|arr| {
+                    if arr.is_null(i) {
+                        None
+                    } else {
+                        serde_json::from_str(arr.value(i)).ok()
+                    }
+                });
+
                 let line_start = line_starts.map(|arr| arr.value(i) as usize);
                 let line_end = line_ends.map(|arr| arr.value(i) as usize);
 
@@ -386,6 +421,7 @@ impl VectorStore {
                     entry_date,
                     entry_created_at,
                     idea_box,
+                    metadata,
                 });
             }
         }
@@ -502,6 +538,10 @@ impl VectorStore {
                 .column_by_name("idea_box")
                 .and_then(|c| c.as_any().downcast_ref::<StringArray>());
 
+            let metadata_values = batch
+                .column_by_name("metadata")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
             let line_starts = batch
                 .column_by_name("line_start")
                 .and_then(|c| c.as_any().downcast_ref::<arrow_array::Int64Array>());
@@ -575,6 +615,14 @@ impl VectorStore {
                     }
                 });
 
+                let metadata = metadata_values.and_then(|arr| {
+                    if arr.is_null(i) {
+                        None
+                    } else {
+                        serde_json::from_str(arr.value(i)).ok()
+                    }
+                });
+
                 let line_start = line_starts.map(|arr| arr.value(i) as usize);
                 let line_end = line_ends.map(|arr| arr.value(i) as usize);
 
@@ -618,6 +666,7 @@ impl VectorStore {
                     entry_date,
                     entry_created_at,
                     idea_box,
+                    metadata,
                 });
             }
         }
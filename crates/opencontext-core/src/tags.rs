@@ -0,0 +1,203 @@
+//! Tags attached to documents.
+//!
+//! Tags live in `doc_tags`, keyed by the doc row id, so they follow a doc
+//! through `rename_doc`/`move_doc` and folder renames/moves.
+
+use std::collections::HashMap;
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    doc_not_found, folder_not_found, normalize_doc_path, normalize_folder_path, now_iso,
+    row_to_doc, CoreError, CoreResult, Doc, OpenContext,
+};
+
+/// How multiple tags are combined when filtering
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Docs carrying at least one of the tags
+    #[default]
+    Any,
+    /// Docs carrying every one of the tags
+    All,
+}
+
+/// A tag and the number of docs carrying it
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub doc_count: i64,
+}
+
+impl OpenContext {
+    /// Attach tags to a document. Returns the doc's full tag list.
+    pub fn add_tags(&self, doc_path: &str, tags: &[String]) -> CoreResult<Vec<String>> {
+        let tags = normalize_tags(tags)?;
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        let ts = now_iso();
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for tag in &tags {
+                tx.execute(
+                    "INSERT OR IGNORE INTO doc_tags (doc_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                    params![doc.id, tag, ts],
+                )?;
+            }
+            tx.commit()?;
+            doc_tags(conn, doc.id)
+        })
+    }
+
+    /// Detach tags from a document. Returns the doc's remaining tags.
+    pub fn remove_tags(&self, doc_path: &str, tags: &[String]) -> CoreResult<Vec<String>> {
+        let tags = normalize_tags(tags)?;
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for tag in &tags {
+                tx.execute(
+                    "DELETE FROM doc_tags WHERE doc_id = ?1 AND tag = ?2",
+                    params![doc.id, tag],
+                )?;
+            }
+            tx.commit()?;
+            doc_tags(conn, doc.id)
+        })
+    }
+
+    /// Tags of a single document, sorted.
+    pub fn get_doc_tags(&self, doc_path: &str) -> CoreResult<Vec<String>> {
+        let rel_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_path)?
+            .ok_or_else(|| doc_not_found(&rel_path))?;
+        self.with_conn(|conn| doc_tags(conn, doc.id))
+    }
+
+    /// Every tag in use, with its doc count, sorted by tag.
+    pub fn list_tags(&self) -> CoreResult<Vec<TagCount>> {
+        self.with_conn(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT tag, COUNT(*) FROM doc_tags GROUP BY tag ORDER BY tag")?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok(TagCount {
+                        tag: row.get(0)?,
+                        doc_count: row.get(1)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// Docs carrying the given tags, optionally limited to a folder subtree.
+    pub fn list_docs_by_tag(
+        &self,
+        tags: &[String],
+        mode: TagMatch,
+        folder_path: Option<&str>,
+    ) -> CoreResult<Vec<Doc>> {
+        let tags = normalize_tags(tags)?;
+        if tags.is_empty() {
+            return Err(CoreError::Message("At least one tag is required.".into()));
+        }
+        let prefix = match folder_path {
+            Some(path) => {
+                let rel_path = normalize_folder_path(Some(path))?;
+                self.find_folder(&rel_path)?
+                    .ok_or_else(|| folder_not_found(&rel_path))?;
+                if rel_path.is_empty() {
+                    String::new()
+                } else {
+                    format!("{rel_path}/")
+                }
+            }
+            None => String::new(),
+        };
+        self.with_conn(|conn| {
+            let sql = format!(
+                "SELECT id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at
+                 FROM docs WHERE substr(rel_path, 1, length(?1)) = ?1 AND id IN ({}) ORDER BY rel_path",
+                tag_filter_sql(tags.len(), mode)
+            );
+            let mut values = vec![Value::Text(prefix)];
+            values.extend(tags.into_iter().map(Value::Text));
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(params_from_iter(values), row_to_doc)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+}
+
+/// Trim and lowercase tags, dropping duplicates. Tags may not be empty or
+/// contain whitespace or commas.
+pub(crate) fn normalize_tags(tags: &[String]) -> CoreResult<Vec<String>> {
+    let mut out: Vec<String> = Vec::new();
+    for raw in tags {
+        let tag = raw.trim().to_lowercase();
+        if tag.is_empty() {
+            return Err(CoreError::Message("Tag cannot be empty.".into()));
+        }
+        if tag.chars().any(|c| c.is_whitespace() || c == ',') {
+            return Err(CoreError::Message(format!(
+                "Tag \"{tag}\" cannot contain whitespace or commas."
+            )));
+        }
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    Ok(out)
+}
+
+/// Sorted tags of one doc row.
+pub(crate) fn doc_tags(conn: &Connection, doc_id: i64) -> CoreResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM doc_tags WHERE doc_id = ?1 ORDER BY tag")?;
+    let tags = stmt
+        .query_map([doc_id], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tags)
+}
+
+/// Tags of every doc whose rel_path matches `pattern`, keyed by rel_path.
+pub(crate) fn tags_by_rel_path(
+    conn: &Connection,
+    pattern: &str,
+) -> CoreResult<HashMap<String, Vec<String>>> {
+    let mut stmt = conn.prepare(
+        "SELECT d.rel_path, t.tag FROM doc_tags t JOIN docs d ON d.id = t.doc_id
+         WHERE d.rel_path LIKE ?1 ORDER BY d.rel_path, t.tag",
+    )?;
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([pattern], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })? {
+        let (rel_path, tag) = row?;
+        map.entry(rel_path).or_default().push(tag);
+    }
+    Ok(map)
+}
+
+/// Subquery selecting the ids of docs that match `count` tags under `mode`.
+/// Binds one positional `?` per tag.
+pub(crate) fn tag_filter_sql(count: usize, mode: TagMatch) -> String {
+    let placeholders = vec!["?"; count].join(", ");
+    match mode {
+        TagMatch::Any => format!("SELECT doc_id FROM doc_tags WHERE tag IN ({placeholders})"),
+        TagMatch::All => format!(
+            "SELECT doc_id FROM doc_tags WHERE tag IN ({placeholders})
+             GROUP BY doc_id HAVING COUNT(DISTINCT tag) = {count}"
+        ),
+    }
+}
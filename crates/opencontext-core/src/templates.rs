@@ -0,0 +1,265 @@
+//! Document templates.
+//!
+//! Templates are markdown files in `<base_root>/templates/<name>.md`. A
+//! folder can name a default template (inherited by its subfolders) which
+//! `create_doc` applies to new docs; `create_doc_from_template` picks one
+//! explicitly and fills in custom variables.
+//!
+//! Placeholders are `{{key}}`. Built-in keys are `date` (UTC `YYYY-MM-DD`),
+//! `datetime`, `title` (doc name without `.md`), `name` and `folder`;
+//! caller variables take precedence. Unknown placeholders are left as-is.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use crate::{
+    atomic, folder_not_found, normalize_folder_path, now_iso, parent_rel_path, CoreError,
+    CoreResult, DocCreated, OpenContext, SaveDocOptions,
+};
+
+const TEMPLATE_EXT: &str = "md";
+
+/// A template file
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub abs_path: PathBuf,
+}
+
+impl OpenContext {
+    /// All templates, sorted by name
+    pub fn list_templates(&self) -> CoreResult<Vec<TemplateInfo>> {
+        if !self.templates_root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut templates = Vec::new();
+        for entry in fs::read_dir(&self.templates_root)? {
+            let path = entry?.path();
+            if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some(TEMPLATE_EXT) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if validate_template_name(name).is_ok() {
+                templates.push(TemplateInfo {
+                    name: name.to_string(),
+                    abs_path: path.clone(),
+                });
+            }
+        }
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    pub fn get_template(&self, name: &str) -> CoreResult<String> {
+        let path = self.template_path(name)?;
+        if !path.is_file() {
+            return Err(template_not_found(name));
+        }
+        Ok(fs::read_to_string(path)?)
+    }
+
+    /// Create or replace a template
+    pub fn save_template(&self, name: &str, content: &str) -> CoreResult<TemplateInfo> {
+        let abs_path = self.template_path(name)?;
+        fs::create_dir_all(&self.templates_root)?;
+        atomic::write_atomic(&abs_path, content)?;
+        Ok(TemplateInfo {
+            name: name.to_string(),
+            abs_path,
+        })
+    }
+
+    /// Delete a template and clear it as a folder default
+    pub fn delete_template(&self, name: &str) -> CoreResult<()> {
+        let path = self.template_path(name)?;
+        if !path.is_file() {
+            return Err(template_not_found(name));
+        }
+        fs::remove_file(path)?;
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM folder_templates WHERE template = ?1", [name])?;
+            Ok(())
+        })
+    }
+
+    /// Set (or with `None`, clear) the default template of a folder.
+    pub fn set_folder_template(&self, folder_path: &str, template: Option<&str>) -> CoreResult<()> {
+        let rel_path = normalize_folder_path(Some(folder_path))?;
+        let folder = self
+            .find_folder(&rel_path)?
+            .ok_or_else(|| folder_not_found(&rel_path))?;
+        if let Some(name) = template {
+            if !self.template_path(name)?.is_file() {
+                return Err(template_not_found(name));
+            }
+        }
+        self.with_conn(|conn| {
+            match template {
+                Some(name) => conn.execute(
+                    "INSERT INTO folder_templates (folder_id, template) VALUES (?1, ?2)
+                     ON CONFLICT(folder_id) DO UPDATE SET template = excluded.template",
+                    params![folder.id, name],
+                )?,
+                None => conn.execute(
+                    "DELETE FROM folder_templates WHERE folder_id = ?1",
+                    [folder.id],
+                )?,
+            };
+            Ok(())
+        })
+    }
+
+    /// Default template for new docs in a folder: its own, else the nearest
+    /// ancestor's.
+    pub fn get_folder_template(&self, folder_path: &str) -> CoreResult<Option<String>> {
+        let mut current = Some(normalize_folder_path(Some(folder_path))?);
+        self.with_conn(|conn| {
+            while let Some(rel_path) = current {
+                let template: Option<String> = conn
+                    .query_row(
+                        "SELECT t.template FROM folder_templates t
+                         JOIN folders f ON f.id = t.folder_id
+                         WHERE f.rel_path = ?1",
+                        [&rel_path],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if template.is_some() {
+                    return Ok(template);
+                }
+                current = parent_rel_path(&rel_path);
+            }
+            Ok(None)
+        })
+    }
+
+    /// Create a doc filled from `template`, or from the folder's default
+    /// template when `template` is `None`.
+    pub fn create_doc_from_template(
+        &self,
+        folder_path: &str,
+        name: &str,
+        template: Option<&str>,
+        vars: &HashMap<String, String>,
+    ) -> CoreResult<DocCreated> {
+        let template = match template {
+            Some(template) => template.to_string(),
+            None => self.get_folder_template(folder_path)?.ok_or_else(|| {
+                CoreError::Message(format!("Folder \"{folder_path}\" has no default template."))
+            })?,
+        };
+        // Read first so a missing template does not leave an empty doc behind.
+        let content = self.get_template(&template)?;
+        let created = self.create_empty_doc(folder_path, name, None)?;
+        self.fill_from_template(created, &content, vars)
+    }
+
+    /// `create_doc` body: apply the folder's default template if it has one
+    /// that still exists.
+    pub(crate) fn create_doc_with_default_template(
+        &self,
+        folder_path: &str,
+        name: &str,
+        description: Option<&str>,
+    ) -> CoreResult<DocCreated> {
+        let content = match self.get_folder_template(folder_path)? {
+            Some(template) => self.get_template(&template).ok(),
+            None => None,
+        };
+        let created = self.create_empty_doc(folder_path, name, description)?;
+        match content {
+            Some(content) => self.fill_from_template(created, &content, &HashMap::new()),
+            None => Ok(created),
+        }
+    }
+
+    fn fill_from_template(
+        &self,
+        mut created: DocCreated,
+        template: &str,
+        vars: &HashMap<String, String>,
+    ) -> CoreResult<DocCreated> {
+        let folder = parent_rel_path(&created.rel_path).unwrap_or_default();
+        let name = created
+            .rel_path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&created.rel_path)
+            .to_string();
+        let title = name.strip_suffix(".md").unwrap_or(&name).to_string();
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        let date = Utc::now().format("%Y-%m-%d").to_string();
+        let datetime = now_iso();
+        values.insert("date", &date);
+        values.insert("datetime", &datetime);
+        values.insert("title", &title);
+        values.insert("name", &name);
+        values.insert("folder", &folder);
+        for (key, value) in vars {
+            values.insert(key, value);
+        }
+        let content = render_template(template, &values);
+        self.save_doc_content_with(
+            &created.rel_path,
+            &content,
+            SaveDocOptions {
+                source: Some("template".into()),
+                ..Default::default()
+            },
+        )?;
+        if let Some(doc) = self.find_doc(&created.rel_path)? {
+            created.description = doc.description;
+        }
+        Ok(created)
+    }
+
+    fn template_path(&self, name: &str) -> CoreResult<PathBuf> {
+        validate_template_name(name)?;
+        Ok(self.templates_root.join(format!("{name}.{TEMPLATE_EXT}")))
+    }
+}
+
+/// Replace `{{key}}` placeholders that have a value.
+fn render_template(template: &str, values: &HashMap<&str, &str>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        match values.get(after[..end].trim()) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn validate_template_name(name: &str) -> CoreResult<()> {
+    let valid = !name.trim().is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && !name.chars().any(char::is_control);
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::Message(format!(
+            "Invalid template name \"{name}\"."
+        )))
+    }
+}
+
+fn template_not_found(name: &str) -> CoreError {
+    CoreError::Message(format!("Template \"{name}\" not found."))
+}
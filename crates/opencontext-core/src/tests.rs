@@ -623,7 +623,7 @@ mod doc_tests {
 
 #[cfg(test)]
 mod manifest_tests {
-    use crate::{EnvOverrides, OpenContext};
+    use crate::{EnvOverrides, ManifestFormat, ManifestOptions, ManifestSort, OpenContext};
     use tempfile::TempDir;
 
     fn create_test_context() -> (OpenContext, TempDir) {
@@ -729,4 +729,4375 @@ mod manifest_tests {
         assert!(!entry.updated_at.is_empty());
         assert!(entry.abs_path.to_string_lossy().contains("folder/doc.md"));
     }
+
+    fn set_updated_at(ctx: &OpenContext, rel_path: &str, ts: &str) {
+        ctx.with_conn(|conn| {
+            conn.execute(
+                "UPDATE docs SET updated_at = ?1 WHERE rel_path = ?2",
+                [ts, rel_path],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_manifest_sort_and_updated_since() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("folder", None).unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            ctx.create_doc("folder", name, None).unwrap();
+        }
+        set_updated_at(&ctx, "folder/a.md", "2024-01-01T00:00:00.000Z");
+        set_updated_at(&ctx, "folder/b.md", "2024-03-01T00:00:00.000Z");
+        set_updated_at(&ctx, "folder/c.md", "2024-02-01T00:00:00.000Z");
+
+        let paths = |options: &ManifestOptions| -> Vec<String> {
+            ctx.generate_manifest_with("folder", options)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.rel_path)
+                .collect()
+        };
+
+        let by_updated = ManifestOptions {
+            sort: ManifestSort::UpdatedAt,
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&by_updated),
+            vec!["folder/b.md", "folder/c.md", "folder/a.md"]
+        );
+
+        let since = ManifestOptions {
+            updated_since: Some("2024-02-01".into()),
+            ..Default::default()
+        };
+        assert_eq!(paths(&since), vec!["folder/b.md", "folder/c.md"]);
+        let since = ManifestOptions {
+            updated_since: Some("2024-02-15T08:00:00+08:00".into()),
+            ..Default::default()
+        };
+        assert_eq!(paths(&since), vec!["folder/b.md"]);
+        assert!(ctx
+            .generate_manifest_with(
+                "folder",
+                &ManifestOptions {
+                    updated_since: Some("last week".into()),
+                    ..Default::default()
+                },
+            )
+            .is_err());
+
+        // Reads rank docs for AccessCount, ties fall back to path
+        for _ in 0..2 {
+            ctx.get_doc_content("folder/c.md").unwrap();
+        }
+        ctx.get_doc_content("folder/b.md").unwrap();
+        let by_access = ManifestOptions {
+            sort: ManifestSort::AccessCount,
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(&by_access),
+            vec!["folder/c.md", "folder/b.md", "folder/a.md"]
+        );
+    }
+
+    #[test]
+    fn test_manifest_budget() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("folder", None).unwrap();
+        for i in 1..=5 {
+            ctx.create_doc("folder", &format!("doc{i}.md"), Some("0123456789"))
+                .unwrap();
+        }
+        // Each line is "- [docN.md](folder/docN.md): 0123456789" plus a newline
+        let line_chars = "- [doc1.md](folder/doc1.md): 0123456789".len() + 1;
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "folder",
+                &ManifestOptions {
+                    max_chars: Some(line_chars * 2 + 5),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manifest.len(), 2);
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "folder",
+                &ManifestOptions {
+                    max_tokens: Some(1),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(manifest.is_empty());
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "folder",
+                &ManifestOptions {
+                    max_tokens: Some(10_000),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manifest.len(), 5);
+
+        assert!(ctx
+            .generate_manifest_with(
+                "folder",
+                &ManifestOptions {
+                    max_chars: Some(0),
+                    ..Default::default()
+                },
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_render_manifest_formats() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("proj", Some("Project notes")).unwrap();
+        ctx.create_folder("proj/api", None).unwrap();
+        ctx.create_doc("proj", "overview.md", Some("Start here"))
+            .unwrap();
+        ctx.create_doc("proj/api", "auth flow.md", None).unwrap();
+        ctx.add_tags("proj/overview.md", &["intro".to_string()])
+            .unwrap();
+        let options = ManifestOptions::default();
+
+        let json = ctx
+            .render_manifest("proj", &options, ManifestFormat::Json)
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+
+        let markdown = ctx
+            .render_manifest("proj", &options, ManifestFormat::Markdown)
+            .unwrap();
+        assert_eq!(
+            markdown,
+            "- [auth flow.md](<proj/api/auth flow.md>)\n\
+             - [overview.md](proj/overview.md): Start here (tags: intro)\n"
+        );
+
+        let llms = ctx
+            .render_manifest("proj", &options, ManifestFormat::LlmsTxt)
+            .unwrap();
+        assert_eq!(
+            llms,
+            "# proj\n\n> Project notes\n\n\
+             ## proj/api\n\n- [auth flow.md](<proj/api/auth flow.md>)\n\n\
+             ## proj\n\n- [overview.md](proj/overview.md): Start here (tags: intro)\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod reconcile_tests {
+    use crate::{EnvOverrides, OpenContext, ReconcileOptions};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_reconcile_clean_tree() {
+        let (ctx, _temp) = create_test_context();
+
+        ctx.create_folder("project", None).unwrap();
+        ctx.create_doc("project", "doc.md", None).unwrap();
+
+        let report = ctx.reconcile_report().expect("Failed to reconcile");
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_reconcile_dry_run_does_not_modify_catalog() {
+        let (ctx, _temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+
+        fs::create_dir_all(root.join("external/nested")).unwrap();
+        fs::write(root.join("external/nested/notes.md"), "# Notes").unwrap();
+
+        let report = ctx.reconcile_report().unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.added_folders, vec!["external", "external/nested"]);
+        assert_eq!(report.added_docs, vec!["external/nested/notes.md"]);
+
+        assert!(ctx.list_folders(true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_registers_untracked_files() {
+        let (ctx, _temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+
+        ctx.create_folder("project", None).unwrap();
+        fs::create_dir_all(root.join("project/sub")).unwrap();
+        fs::write(root.join("project/sub/new.md"), "hello").unwrap();
+        fs::write(root.join("project/ignored.txt"), "not markdown").unwrap();
+        fs::write(root.join("loose.md"), "root files are ignored").unwrap();
+
+        let report = ctx.reconcile(ReconcileOptions::default()).unwrap();
+        assert_eq!(report.added_folders, vec!["project/sub"]);
+        assert_eq!(report.added_docs, vec!["project/sub/new.md"]);
+
+        let doc = ctx.get_doc_meta("project/sub/new.md").unwrap();
+        assert_eq!(doc.stable_id.len(), 36);
+        assert_eq!(ctx.get_doc_content("project/sub/new.md").unwrap(), "hello");
+
+        // A second run finds nothing left to do.
+        assert!(ctx.reconcile_report().unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_reconcile_flags_and_removes_orphans() {
+        let (ctx, _temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+
+        ctx.create_folder("project", None).unwrap();
+        ctx.create_doc("project", "gone.md", None).unwrap();
+        ctx.create_doc("project", "kept.md", None).unwrap();
+        fs::remove_file(root.join("project/gone.md")).unwrap();
+
+        let report = ctx.reconcile(ReconcileOptions::default()).unwrap();
+        assert_eq!(report.orphaned_docs, vec!["project/gone.md"]);
+        assert!(!report.orphans_removed);
+        assert!(ctx.get_doc_meta("project/gone.md").is_ok());
+
+        let report = ctx
+            .reconcile(ReconcileOptions {
+                dry_run: false,
+                remove_orphans: true,
+            })
+            .unwrap();
+        assert!(report.orphans_removed);
+        assert!(ctx.get_doc_meta("project/gone.md").is_err());
+        assert_eq!(ctx.list_docs("project", false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reconcile_includes_ideas_tree() {
+        let (ctx, _temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+
+        fs::create_dir_all(root.join(".ideas/inbox")).unwrap();
+        fs::write(root.join(".ideas/inbox/thread.md"), "idea").unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/HEAD.md"), "").unwrap();
+
+        let report = ctx.reconcile(ReconcileOptions::default()).unwrap();
+        assert_eq!(report.added_docs, vec![".ideas/inbox/thread.md"]);
+        assert!(!report.added_folders.iter().any(|f| f.starts_with(".git")));
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use crate::{DiffOp, EnvOverrides, OpenContext, SaveDocOptions};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("test-folder", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_save_records_versions() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+
+        ctx.save_doc_content("test-folder/doc.md", "v1", None)
+            .unwrap();
+        ctx.save_doc_content_with(
+            "test-folder/doc.md",
+            "v2",
+            SaveDocOptions {
+                author: Some("alice".into()),
+                source: Some("mcp".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+        assert_eq!(versions.len(), 2);
+        // Newest first
+        assert_eq!(versions[0].author.as_deref(), Some("alice"));
+        assert_eq!(versions[0].source.as_deref(), Some("mcp"));
+        assert_eq!(versions[0].size, 2);
+        assert_ne!(versions[0].content_hash, versions[1].content_hash);
+    }
+
+    #[test]
+    fn test_external_edit_is_snapshotted_before_overwrite() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+
+        ctx.save_doc_content("test-folder/doc.md", "saved", None)
+            .unwrap();
+        fs::write(&created.abs_path, "edited in vim").unwrap();
+        ctx.save_doc_content("test-folder/doc.md", "clobbered", None)
+            .unwrap();
+
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[1].source.as_deref(), Some("external"));
+        let external = ctx
+            .get_doc_version(&created.stable_id, versions[1].version_id)
+            .unwrap();
+        assert_eq!(external.content, "edited in vim");
+    }
+
+    #[test]
+    fn test_diff_doc_versions() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+
+        ctx.save_doc_content("test-folder/doc.md", "a\nb\nc\n", None)
+            .unwrap();
+        ctx.save_doc_content("test-folder/doc.md", "a\nB\nc\n", None)
+            .unwrap();
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+
+        let diff = ctx
+            .diff_doc_versions(
+                &created.stable_id,
+                versions[1].version_id,
+                versions[0].version_id,
+            )
+            .unwrap();
+        let deleted: Vec<_> = diff
+            .lines
+            .iter()
+            .filter(|l| l.op == DiffOp::Delete)
+            .map(|l| l.text.as_str())
+            .collect();
+        let inserted: Vec<_> = diff
+            .lines
+            .iter()
+            .filter(|l| l.op == DiffOp::Insert)
+            .map(|l| l.text.as_str())
+            .collect();
+        assert_eq!(deleted, vec!["b"]);
+        assert_eq!(inserted, vec!["B"]);
+        assert!(diff.unified.contains("-b"));
+        assert!(diff.unified.contains("+B"));
+    }
+
+    #[test]
+    fn test_restore_doc_version() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+
+        ctx.save_doc_content("test-folder/doc.md", "good", None)
+            .unwrap();
+        ctx.save_doc_content("test-folder/doc.md", "bad", None)
+            .unwrap();
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+
+        ctx.restore_doc_version(&created.stable_id, versions[1].version_id)
+            .expect("Failed to restore");
+
+        assert_eq!(ctx.get_doc_content("test-folder/doc.md").unwrap(), "good");
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[0].source.as_deref(), Some("restore"));
+    }
+
+    #[test]
+    fn test_history_follows_rename() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+
+        ctx.save_doc_content("test-folder/doc.md", "before", None)
+            .unwrap();
+        ctx.rename_doc("test-folder/doc.md", "renamed.md").unwrap();
+        ctx.save_doc_content("test-folder/renamed.md", "after", None)
+            .unwrap();
+
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].rel_path, "test-folder/renamed.md");
+        assert_eq!(versions[1].rel_path, "test-folder/doc.md");
+    }
+
+    #[test]
+    fn test_get_doc_version_not_found() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+
+        assert!(ctx.get_doc_version(&created.stable_id, 999).is_err());
+    }
+}
+
+#[cfg(test)]
+mod trash_tests {
+    use crate::{ConflictPolicy, EnvOverrides, OpenContext, TrashKind};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("test-folder", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_remove_doc_moves_to_trash() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx
+            .create_doc("test-folder", "doc.md", Some("Important"))
+            .unwrap();
+        ctx.save_doc_content("test-folder/doc.md", "content", None)
+            .unwrap();
+
+        ctx.remove_doc("test-folder/doc.md").unwrap();
+        assert!(!created.abs_path.exists());
+
+        let trash = ctx.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].kind, TrashKind::Doc);
+        assert_eq!(trash[0].original_path, "test-folder/doc.md");
+        assert_eq!(trash[0].description, "Important");
+        assert_eq!(
+            trash[0].stable_id.as_deref(),
+            Some(created.stable_id.as_str())
+        );
+    }
+
+    #[test]
+    fn test_restore_doc_preserves_stable_id() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx
+            .create_doc("test-folder", "doc.md", Some("Important"))
+            .unwrap();
+        ctx.save_doc_content("test-folder/doc.md", "content", None)
+            .unwrap();
+        ctx.remove_doc("test-folder/doc.md").unwrap();
+        let id = ctx.list_trash().unwrap()[0].id;
+
+        let restored = ctx
+            .restore_from_trash(id, ConflictPolicy::Fail)
+            .expect("Failed to restore");
+        assert_eq!(restored.rel_path, "test-folder/doc.md");
+
+        let doc = ctx.get_doc_by_stable_id(&created.stable_id).unwrap();
+        assert_eq!(doc.rel_path, "test-folder/doc.md");
+        assert_eq!(doc.description, "Important");
+        assert_eq!(
+            ctx.get_doc_content("test-folder/doc.md").unwrap(),
+            "content"
+        );
+        assert!(ctx.list_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_doc_conflict() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("test-folder", "doc.md", None).unwrap();
+        ctx.remove_doc("test-folder/doc.md").unwrap();
+        ctx.create_doc("test-folder", "doc.md", None).unwrap();
+        let id = ctx.list_trash().unwrap()[0].id;
+
+        assert!(ctx.restore_from_trash(id, ConflictPolicy::Fail).is_err());
+
+        let restored = ctx.restore_from_trash(id, ConflictPolicy::Rename).unwrap();
+        assert_eq!(restored.rel_path, "test-folder/doc-1.md");
+        assert_eq!(ctx.list_docs("test-folder", false).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_remove_folder_moves_tree_to_trash_and_restores() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("project/sub", Some("Sub folder"))
+            .unwrap();
+        let a = ctx.create_doc("project", "a.md", Some("A")).unwrap();
+        let b = ctx.create_doc("project/sub", "b.md", None).unwrap();
+
+        ctx.remove_folder("project", true).unwrap();
+        assert!(ctx.list_docs("test-folder", true).unwrap().is_empty());
+        assert!(ctx.get_doc_by_stable_id(&a.stable_id).is_err());
+
+        let trash = ctx.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].kind, TrashKind::Folder);
+        assert_eq!(trash[0].doc_count, 2);
+
+        let restored = ctx
+            .restore_from_trash(trash[0].id, ConflictPolicy::Fail)
+            .unwrap();
+        assert_eq!(restored.restored_docs.len(), 2);
+
+        assert_eq!(
+            ctx.get_doc_by_stable_id(&a.stable_id).unwrap().description,
+            "A"
+        );
+        assert_eq!(
+            ctx.get_doc_by_stable_id(&b.stable_id).unwrap().rel_path,
+            "project/sub/b.md"
+        );
+        let folders = ctx.list_folders(true).unwrap();
+        let sub = folders
+            .iter()
+            .find(|f| f.rel_path == "project/sub")
+            .unwrap();
+        assert_eq!(sub.description, "Sub folder");
+    }
+
+    #[test]
+    fn test_remove_folder_leaves_similarly_named_folders() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("my_notes", None).unwrap();
+        ctx.create_folder("my-notes", None).unwrap();
+        ctx.create_folder("MY_NOTES2", None).unwrap();
+        ctx.create_doc("my_notes", "a.md", None).unwrap();
+        let secret = ctx.create_doc("my-notes", "secret.md", None).unwrap();
+        ctx.create_doc("MY_NOTES2", "b.md", None).unwrap();
+
+        ctx.remove_folder("my_notes", true).unwrap();
+
+        let trash = ctx.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].doc_count, 1);
+        assert_eq!(
+            ctx.get_doc_by_stable_id(&secret.stable_id)
+                .unwrap()
+                .rel_path,
+            "my-notes/secret.md"
+        );
+        assert!(ctx.get_doc_meta("MY_NOTES2/b.md").is_ok());
+        assert!(secret.abs_path.exists());
+    }
+
+    #[test]
+    fn test_restore_recreates_missing_parent() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("gone", None).unwrap();
+        ctx.create_doc("gone", "doc.md", None).unwrap();
+        ctx.remove_doc("gone/doc.md").unwrap();
+        ctx.remove_folder("gone", false).unwrap();
+
+        let trash = ctx.list_trash().unwrap();
+        let doc_entry = trash.iter().find(|e| e.kind == TrashKind::Doc).unwrap();
+        ctx.restore_from_trash(doc_entry.id, ConflictPolicy::Fail)
+            .unwrap();
+
+        assert!(ctx.get_doc_meta("gone/doc.md").is_ok());
+    }
+
+    #[test]
+    fn test_purge_trash() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+        ctx.save_doc_content("test-folder/doc.md", "content", None)
+            .unwrap();
+        ctx.remove_doc("test-folder/doc.md").unwrap();
+
+        // Nothing is older than the epoch
+        assert_eq!(ctx.purge_trash(Some("1970-01-01T00:00:00Z")).unwrap(), 0);
+        assert_eq!(ctx.list_trash().unwrap().len(), 1);
+
+        assert_eq!(ctx.purge_trash(None).unwrap(), 1);
+        assert!(ctx.list_trash().unwrap().is_empty());
+        let trash_dir = ctx.env_info().contexts_root.join(".trash");
+        assert_eq!(std::fs::read_dir(trash_dir).unwrap().count(), 0);
+        assert!(ctx.list_doc_versions(&created.stable_id).is_err());
+    }
+
+    #[test]
+    fn test_purge_trash_rejects_invalid_cutoff() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("test-folder", "doc.md", None).unwrap();
+        ctx.remove_doc("test-folder/doc.md").unwrap();
+
+        for cutoff in ["30d", "now", ""] {
+            assert!(ctx.purge_trash(Some(cutoff)).is_err(), "{cutoff}");
+        }
+        // Offsets are converted to UTC before comparing
+        assert_eq!(
+            ctx.purge_trash(Some("1970-01-01T08:00:00+08:00")).unwrap(),
+            0
+        );
+        assert_eq!(ctx.list_trash().unwrap().len(), 1);
+        assert_eq!(ctx.purge_trash(Some("2999-01-01")).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_failed_restore_leaves_files_in_trash() {
+        let (ctx, _temp) = create_test_context();
+        let created = ctx.create_doc("test-folder", "doc.md", None).unwrap();
+        ctx.remove_doc("test-folder/doc.md").unwrap();
+        let id = ctx.list_trash().unwrap()[0].id;
+        ctx.with_conn(|conn| {
+            conn.execute_batch(
+                "CREATE TEMP TRIGGER fail_insert BEFORE INSERT ON docs
+                 BEGIN SELECT RAISE(ABORT, 'boom'); END;",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(ctx.restore_from_trash(id, ConflictPolicy::Fail).is_err());
+        assert!(!created.abs_path.exists());
+        assert_eq!(ctx.list_trash().unwrap().len(), 1);
+
+        ctx.with_conn(|conn| {
+            conn.execute_batch("DROP TRIGGER fail_insert")?;
+            Ok(())
+        })
+        .unwrap();
+        ctx.restore_from_trash(id, ConflictPolicy::Fail).unwrap();
+        assert!(created.abs_path.exists());
+        assert!(ctx.list_trash().unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tag_tests {
+    use crate::{ConflictPolicy, EnvOverrides, ManifestOptions, OpenContext, TagMatch};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("project-x", None).unwrap();
+        ctx.create_folder("project-y", None).unwrap();
+        ctx.create_doc("project-x", "adr-1.md", None).unwrap();
+        ctx.create_doc("project-x", "adr-2.md", None).unwrap();
+        ctx.create_doc("project-x", "notes.md", None).unwrap();
+        ctx.create_doc("project-y", "adr-1.md", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        let (ctx, _temp) = create_test_context();
+
+        let result = ctx
+            .add_tags(
+                "project-x/adr-1.md",
+                &tags(&["Decision", "arch", "decision"]),
+            )
+            .expect("Failed to add tags");
+        assert_eq!(result, vec!["arch", "decision"]);
+
+        let result = ctx
+            .remove_tags("project-x/adr-1.md", &tags(&["arch"]))
+            .unwrap();
+        assert_eq!(result, vec!["decision"]);
+        assert_eq!(
+            ctx.get_doc_tags("project-x/adr-1.md").unwrap(),
+            vec!["decision"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        let (ctx, _temp) = create_test_context();
+        assert!(ctx.add_tags("project-x/adr-1.md", &tags(&[" "])).is_err());
+        assert!(ctx
+            .add_tags("project-x/adr-1.md", &tags(&["two words"]))
+            .is_err());
+        assert!(ctx.add_tags("project-x/missing.md", &tags(&["a"])).is_err());
+    }
+
+    #[test]
+    fn test_list_tags_counts() {
+        let (ctx, _temp) = create_test_context();
+        ctx.add_tags("project-x/adr-1.md", &tags(&["decision", "arch"]))
+            .unwrap();
+        ctx.add_tags("project-y/adr-1.md", &tags(&["decision"]))
+            .unwrap();
+
+        let all = ctx.list_tags().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].tag, "arch");
+        assert_eq!(all[0].doc_count, 1);
+        assert_eq!(all[1].tag, "decision");
+        assert_eq!(all[1].doc_count, 2);
+    }
+
+    #[test]
+    fn test_list_docs_by_tag_any_and_all() {
+        let (ctx, _temp) = create_test_context();
+        ctx.add_tags("project-x/adr-1.md", &tags(&["decision", "arch"]))
+            .unwrap();
+        ctx.add_tags("project-x/adr-2.md", &tags(&["decision"]))
+            .unwrap();
+        ctx.add_tags("project-x/notes.md", &tags(&["arch"]))
+            .unwrap();
+
+        let any = ctx
+            .list_docs_by_tag(&tags(&["decision", "arch"]), TagMatch::Any, None)
+            .unwrap();
+        assert_eq!(any.len(), 3);
+
+        let all = ctx
+            .list_docs_by_tag(&tags(&["decision", "arch"]), TagMatch::All, None)
+            .unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].rel_path, "project-x/adr-1.md");
+
+        assert!(ctx.list_docs_by_tag(&[], TagMatch::Any, None).is_err());
+    }
+
+    #[test]
+    fn test_list_docs_by_tag_in_folder() {
+        let (ctx, _temp) = create_test_context();
+        ctx.add_tags("project-x/adr-1.md", &tags(&["decision"]))
+            .unwrap();
+        ctx.add_tags("project-y/adr-1.md", &tags(&["decision"]))
+            .unwrap();
+
+        let docs = ctx
+            .list_docs_by_tag(&tags(&["decision"]), TagMatch::Any, Some("project-y"))
+            .unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].rel_path, "project-y/adr-1.md");
+
+        // `_` in the folder name is not a wildcard
+        ctx.create_folder("project_y", None).unwrap();
+        ctx.create_doc("project_y", "adr-2.md", None).unwrap();
+        ctx.add_tags("project_y/adr-2.md", &tags(&["decision"]))
+            .unwrap();
+        let docs = ctx
+            .list_docs_by_tag(&tags(&["decision"]), TagMatch::Any, Some("project_y"))
+            .unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].rel_path, "project_y/adr-2.md");
+    }
+
+    #[test]
+    fn test_tags_survive_rename_and_move() {
+        let (ctx, _temp) = create_test_context();
+        ctx.add_tags("project-x/adr-1.md", &tags(&["decision"]))
+            .unwrap();
+
+        ctx.rename_doc("project-x/adr-1.md", "adr-001.md").unwrap();
+        ctx.move_doc("project-x/adr-001.md", "project-y").unwrap();
+        ctx.rename_folder("project-y", "project-z").unwrap();
+
+        assert_eq!(
+            ctx.get_doc_tags("project-z/adr-001.md").unwrap(),
+            vec!["decision"]
+        );
+    }
+
+    #[test]
+    fn test_tags_survive_trash_restore() {
+        let (ctx, _temp) = create_test_context();
+        ctx.add_tags("project-x/adr-1.md", &tags(&["decision"]))
+            .unwrap();
+
+        ctx.remove_folder("project-x", true).unwrap();
+        assert!(ctx.list_tags().unwrap().is_empty());
+
+        let id = ctx.list_trash().unwrap()[0].id;
+        ctx.restore_from_trash(id, ConflictPolicy::Fail).unwrap();
+        assert_eq!(
+            ctx.get_doc_tags("project-x/adr-1.md").unwrap(),
+            vec!["decision"]
+        );
+    }
+
+    #[test]
+    fn test_manifest_tag_filter() {
+        let (ctx, _temp) = create_test_context();
+        ctx.add_tags("project-x/adr-1.md", &tags(&["decision", "arch"]))
+            .unwrap();
+        ctx.add_tags("project-x/adr-2.md", &tags(&["decision"]))
+            .unwrap();
+        ctx.add_tags("project-y/adr-1.md", &tags(&["decision"]))
+            .unwrap();
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "project-x",
+                &ManifestOptions {
+                    tags: tags(&["decision"]),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest[0].tags, vec!["arch", "decision"]);
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "project-x",
+                &ManifestOptions {
+                    limit: Some(1),
+                    tags: tags(&["decision", "arch"]),
+                    tag_match: TagMatch::All,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manifest.len(), 1);
+        assert_eq!(manifest[0].rel_path, "project-x/adr-1.md");
+
+        // Unfiltered manifests still include every doc with its tags
+        let manifest = ctx.generate_manifest("project-x", None).unwrap();
+        assert_eq!(manifest.len(), 3);
+        assert!(manifest[2].tags.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod property_tests {
+    use crate::{
+        ConflictPolicy, EnvOverrides, ListDocsOptions, ManifestOptions, OpenContext,
+        PropertyFilter, PropertyOp, PropertySort, PropertyValue,
+    };
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("project", None).unwrap();
+        ctx.create_doc("project", "a.md", None).unwrap();
+        ctx.create_doc("project", "b.md", None).unwrap();
+        ctx.create_doc("project", "c.md", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    fn names(docs: &[crate::Doc]) -> Vec<&str> {
+        docs.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_set_get_and_delete_properties() {
+        let (ctx, _temp) = create_test_context();
+
+        ctx.set_doc_property(
+            "project/a.md",
+            "status",
+            &PropertyValue::String("draft".into()),
+        )
+        .unwrap();
+        ctx.set_doc_property("project/a.md", "priority", &PropertyValue::Number(2.0))
+            .unwrap();
+        ctx.set_doc_property(
+            "project/a.md",
+            "due",
+            &PropertyValue::Date("2026-03-01".into()),
+        )
+        .unwrap();
+        let props = ctx
+            .set_doc_property("project/a.md", "done", &PropertyValue::Bool(false))
+            .unwrap();
+        assert_eq!(props.len(), 4);
+        assert_eq!(props["priority"], PropertyValue::Number(2.0));
+        assert_eq!(props["done"], PropertyValue::Bool(false));
+
+        // Setting again replaces the value and may change its type.
+        ctx.set_doc_property(
+            "project/a.md",
+            "priority",
+            &PropertyValue::String("high".into()),
+        )
+        .unwrap();
+        assert_eq!(
+            ctx.get_doc_property("project/a.md", "priority").unwrap(),
+            Some(PropertyValue::String("high".into()))
+        );
+
+        assert!(ctx.delete_doc_property("project/a.md", "priority").unwrap());
+        assert!(!ctx.delete_doc_property("project/a.md", "priority").unwrap());
+        assert_eq!(
+            ctx.get_doc_property("project/a.md", "priority").unwrap(),
+            None
+        );
+
+        ctx.set_folder_property("project", "owner", &PropertyValue::String("ana".into()))
+            .unwrap();
+        assert_eq!(
+            ctx.get_folder_property("project", "owner").unwrap(),
+            Some(PropertyValue::String("ana".into()))
+        );
+        assert!(ctx.delete_folder_property("project", "owner").unwrap());
+        assert!(ctx.get_folder_properties("project").unwrap().is_empty());
+
+        // Invalid keys and values are rejected.
+        for key in ["", "has space", &"k".repeat(65)] {
+            assert!(ctx
+                .set_doc_property("project/a.md", key, &PropertyValue::Bool(true))
+                .is_err());
+        }
+        assert!(ctx
+            .set_doc_property(
+                "project/a.md",
+                "due",
+                &PropertyValue::Date("03/01/2026".into())
+            )
+            .is_err());
+        assert!(ctx
+            .set_doc_property("project/a.md", "n", &PropertyValue::Number(f64::NAN))
+            .is_err());
+        assert!(ctx
+            .set_doc_property("project/missing.md", "k", &PropertyValue::Bool(true))
+            .is_err());
+    }
+
+    #[test]
+    fn test_list_docs_filters_and_sorts_by_property() {
+        let (ctx, _temp) = create_test_context();
+        ctx.set_doc_property("project/a.md", "priority", &PropertyValue::Number(10.0))
+            .unwrap();
+        ctx.set_doc_property("project/b.md", "priority", &PropertyValue::Number(2.0))
+            .unwrap();
+        ctx.set_doc_property(
+            "project/a.md",
+            "status",
+            &PropertyValue::String("done".into()),
+        )
+        .unwrap();
+        ctx.set_doc_property(
+            "project/c.md",
+            "status",
+            &PropertyValue::String("draft".into()),
+        )
+        .unwrap();
+
+        let list = |filters: Vec<PropertyFilter>, sort: Option<PropertySort>| {
+            ctx.list_docs_with(
+                "project",
+                &ListDocsOptions {
+                    recursive: false,
+                    property_filters: filters,
+                    sort_by_property: sort,
+                },
+            )
+            .unwrap()
+        };
+        let filter = |key: &str, op, value: Option<PropertyValue>| PropertyFilter {
+            key: key.into(),
+            op,
+            value,
+        };
+
+        // Numbers compare numerically, not as text.
+        let docs = list(
+            vec![filter(
+                "priority",
+                PropertyOp::Gt,
+                Some(PropertyValue::Number(5.0)),
+            )],
+            None,
+        );
+        assert_eq!(names(&docs), vec!["a.md"]);
+
+        let docs = list(
+            vec![filter(
+                "status",
+                PropertyOp::Ne,
+                Some(PropertyValue::String("done".into())),
+            )],
+            None,
+        );
+        assert_eq!(names(&docs), vec!["b.md", "c.md"]);
+
+        let docs = list(vec![filter("priority", PropertyOp::Missing, None)], None);
+        assert_eq!(names(&docs), vec!["c.md"]);
+
+        let docs = list(
+            vec![
+                filter("priority", PropertyOp::Exists, None),
+                filter("status", PropertyOp::Exists, None),
+            ],
+            None,
+        );
+        assert_eq!(names(&docs), vec!["a.md"]);
+
+        // Docs without the property sort last in either direction.
+        let sort = |descending| {
+            Some(PropertySort {
+                key: "priority".into(),
+                descending,
+            })
+        };
+        assert_eq!(
+            names(&list(vec![], sort(false))),
+            vec!["b.md", "a.md", "c.md"]
+        );
+        assert_eq!(
+            names(&list(vec![], sort(true))),
+            vec!["a.md", "b.md", "c.md"]
+        );
+
+        assert!(ctx
+            .list_docs_with(
+                "project",
+                &ListDocsOptions {
+                    property_filters: vec![filter("priority", PropertyOp::Lt, None)],
+                    ..Default::default()
+                },
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_properties_follow_renames_moves_and_trash() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("archive", None).unwrap();
+        ctx.set_doc_property(
+            "project/a.md",
+            "status",
+            &PropertyValue::String("done".into()),
+        )
+        .unwrap();
+        ctx.set_folder_property("project", "owner", &PropertyValue::String("ana".into()))
+            .unwrap();
+
+        ctx.rename_doc("project/a.md", "renamed.md").unwrap();
+        ctx.move_doc("project/renamed.md", "archive").unwrap();
+        assert_eq!(
+            ctx.get_doc_property("archive/renamed.md", "status")
+                .unwrap(),
+            Some(PropertyValue::String("done".into()))
+        );
+
+        ctx.move_doc("archive/renamed.md", "project").unwrap();
+        ctx.move_folder("project", "archive").unwrap();
+        assert_eq!(
+            ctx.get_folder_property("archive/project", "owner").unwrap(),
+            Some(PropertyValue::String("ana".into()))
+        );
+        assert_eq!(
+            ctx.get_doc_property("archive/project/renamed.md", "status")
+                .unwrap(),
+            Some(PropertyValue::String("done".into()))
+        );
+
+        ctx.remove_folder("archive/project", true).unwrap();
+        let id = ctx.list_trash().unwrap()[0].id;
+        ctx.restore_from_trash(id, ConflictPolicy::Fail).unwrap();
+        assert_eq!(
+            ctx.get_folder_property("archive/project", "owner").unwrap(),
+            Some(PropertyValue::String("ana".into()))
+        );
+        assert_eq!(
+            ctx.get_doc_property("archive/project/renamed.md", "status")
+                .unwrap(),
+            Some(PropertyValue::String("done".into()))
+        );
+    }
+
+    #[test]
+    fn test_manifest_includes_properties() {
+        let (ctx, _temp) = create_test_context();
+        ctx.set_doc_property(
+            "project/b.md",
+            "status",
+            &PropertyValue::String("draft".into()),
+        )
+        .unwrap();
+
+        let entries = ctx
+            .generate_manifest_with("project", &ManifestOptions::default())
+            .unwrap();
+        let b = entries.iter().find(|e| e.doc_name == "b.md").unwrap();
+        assert_eq!(
+            b.properties["status"],
+            PropertyValue::String("draft".into())
+        );
+        assert!(entries
+            .iter()
+            .find(|e| e.doc_name == "a.md")
+            .unwrap()
+            .properties
+            .is_empty());
+
+        let json = serde_json::to_value(b).unwrap();
+        assert_eq!(
+            json["properties"]["status"],
+            serde_json::json!({ "type": "string", "value": "draft" })
+        );
+    }
+}
+
+#[cfg(test)]
+mod access_tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{
+        DocAccessOptions, EnvOverrides, FullTextSearchOptions, ManifestOptions, ManifestSort,
+        OpenContext, ReadDocOptions,
+    };
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("notes", None).unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            ctx.create_doc("notes", name, None).unwrap();
+            ctx.save_doc_content(&format!("notes/{name}"), "shared words", None)
+                .unwrap();
+        }
+
+        (ctx, temp_dir)
+    }
+
+    fn read(ctx: &OpenContext, path: &str, source: &str) {
+        ctx.get_doc_content_with(
+            path,
+            &ReadDocOptions {
+                source: Some(source.into()),
+            },
+        )
+        .unwrap();
+    }
+
+    fn paths<T>(docs: &[T], rel_path: impl Fn(&T) -> &str) -> Vec<&str> {
+        docs.iter().map(rel_path).collect()
+    }
+
+    #[test]
+    fn test_recent_and_frequent_docs() {
+        let (ctx, _temp) = create_test_context();
+        read(&ctx, "notes/a.md", "cli");
+        read(&ctx, "notes/a.md", "MCP");
+        read(&ctx, "notes/a.md", "mcp");
+        read(&ctx, "notes/b.md", "desktop");
+        ctx.record_doc_access("notes/c.md", Some("desktop"))
+            .unwrap();
+
+        let recent = ctx.recent_docs(&DocAccessOptions::default()).unwrap();
+        assert_eq!(
+            paths(&recent, |d| &d.doc.rel_path),
+            vec!["notes/c.md", "notes/b.md", "notes/a.md"]
+        );
+        assert_eq!(recent[2].access_count, 3);
+        assert!(recent[2].last_accessed_at.is_some());
+
+        let frequent = ctx.frequent_docs(&DocAccessOptions::default()).unwrap();
+        assert_eq!(
+            paths(&frequent, |d| &d.doc.rel_path),
+            vec!["notes/a.md", "notes/c.md", "notes/b.md"]
+        );
+
+        // Sources are matched case-insensitively.
+        let mcp = ctx
+            .frequent_docs(&DocAccessOptions {
+                source: Some("mcp".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(paths(&mcp, |d| &d.doc.rel_path), vec!["notes/a.md"]);
+        assert_eq!(mcp[0].access_count, 2);
+
+        let limited = ctx
+            .recent_docs(&DocAccessOptions {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(paths(&limited, |d| &d.doc.rel_path), vec!["notes/c.md"]);
+
+        let future = ctx
+            .recent_docs(&DocAccessOptions {
+                since: Some("2999-01-01".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(future.is_empty());
+        assert!(ctx
+            .recent_docs(&DocAccessOptions {
+                since: Some("yesterday".into()),
+                ..Default::default()
+            })
+            .is_err());
+
+        // Reads follow the doc through a rename.
+        ctx.rename_doc("notes/a.md", "renamed.md").unwrap();
+        let frequent = ctx.frequent_docs(&DocAccessOptions::default()).unwrap();
+        assert_eq!(frequent[0].doc.rel_path, "notes/renamed.md");
+        assert_eq!(frequent[0].access_count, 3);
+    }
+
+    #[test]
+    fn test_folder_filter_leaves_out_similarly_named_folders() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("my_notes", None).unwrap();
+        ctx.create_folder("my-notes", None).unwrap();
+        ctx.create_doc("my_notes", "a.md", None).unwrap();
+        ctx.create_doc("my-notes", "secret.md", None).unwrap();
+        read(&ctx, "my_notes/a.md", "cli");
+        read(&ctx, "my-notes/secret.md", "cli");
+        read(&ctx, "notes/a.md", "cli");
+
+        let recent = ctx
+            .recent_docs(&DocAccessOptions {
+                folder_path: Some("my_notes".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(paths(&recent, |d| &d.doc.rel_path), vec!["my_notes/a.md"]);
+    }
+
+    #[test]
+    fn test_pinned_docs() {
+        let (ctx, _temp) = create_test_context();
+        assert!(ctx.pin_doc("notes/c.md").unwrap());
+        assert!(ctx.pin_doc("notes/a.md").unwrap());
+        assert!(!ctx.pin_doc("notes/c.md").unwrap());
+        read(&ctx, "notes/a.md", "cli");
+
+        let pinned = ctx.pinned_docs().unwrap();
+        assert_eq!(
+            paths(&pinned, |d| &d.doc.rel_path),
+            vec!["notes/c.md", "notes/a.md"]
+        );
+        assert!(pinned.iter().all(|d| d.pinned));
+        assert_eq!(pinned[1].access_count, 1);
+        assert!(ctx.recent_docs(&DocAccessOptions::default()).unwrap()[0].pinned);
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "notes",
+                &ManifestOptions {
+                    pinned_first: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            paths(&manifest, |e| &e.rel_path),
+            vec!["notes/a.md", "notes/c.md", "notes/b.md"]
+        );
+
+        let hits = ctx
+            .full_text_search(
+                "shared",
+                &FullTextSearchOptions {
+                    pinned_first: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[2].rel_path, "notes/b.md");
+
+        assert!(ctx.unpin_doc("notes/c.md").unwrap());
+        assert!(!ctx.unpin_doc("notes/c.md").unwrap());
+        assert_eq!(
+            paths(&ctx.pinned_docs().unwrap(), |d| &d.doc.rel_path),
+            vec!["notes/a.md"]
+        );
+        ctx.remove_doc("notes/a.md").unwrap();
+        assert!(ctx.pinned_docs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_manifest_sorts_by_last_access() {
+        let (ctx, _temp) = create_test_context();
+        read(&ctx, "notes/b.md", "cli");
+        read(&ctx, "notes/c.md", "cli");
+        read(&ctx, "notes/b.md", "cli");
+
+        let manifest = ctx
+            .generate_manifest_with(
+                "notes",
+                &ManifestOptions {
+                    sort: ManifestSort::LastAccessed,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            paths(&manifest, |e| &e.rel_path),
+            vec!["notes/b.md", "notes/c.md", "notes/a.md"]
+        );
+    }
+
+    #[test]
+    fn test_reading_does_not_touch_updated_at() {
+        let (ctx, _temp) = create_test_context();
+        let before = ctx.find_doc("notes/a.md").unwrap().unwrap();
+        let file = fs::File::options()
+            .write(true)
+            .open(&before.abs_path)
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(3600))
+            .unwrap();
+
+        let content = ctx.get_doc_content_with_revision("notes/a.md").unwrap();
+        assert_eq!(content.content, "shared words");
+        let after = ctx.find_doc("notes/a.md").unwrap().unwrap();
+        assert_eq!(after.updated_at, before.updated_at);
+    }
+}
+
+#[cfg(test)]
+mod copy_tests {
+    use crate::{ConflictPolicy, EnvOverrides, OpenContext, PropertyValue};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("template", Some("Project template"))
+            .unwrap();
+        ctx.create_folder("template/design", Some("Design docs"))
+            .unwrap();
+        ctx.create_folder("work", None).unwrap();
+        ctx.create_doc("template", "readme.md", Some("Start here"))
+            .unwrap();
+        ctx.create_doc("template/design", "adr.md", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_copy_doc() {
+        let (ctx, _temp) = create_test_context();
+        ctx.save_doc_content("template/readme.md", "# Readme\n", None)
+            .unwrap();
+        ctx.add_tags("template/readme.md", &["intro".to_string()])
+            .unwrap();
+        ctx.set_doc_property(
+            "template/readme.md",
+            "status",
+            &PropertyValue::String("draft".into()),
+        )
+        .unwrap();
+        let original = ctx.find_doc("template/readme.md").unwrap().unwrap();
+
+        let copied = ctx
+            .copy_doc("template/readme.md", "work", None, ConflictPolicy::Fail)
+            .unwrap();
+        assert_eq!(copied.rel_path, "work/readme.md");
+        assert_ne!(copied.stable_id, original.stable_id);
+        let copy = ctx.find_doc("work/readme.md").unwrap().unwrap();
+        assert_eq!(copy.stable_id, copied.stable_id);
+        assert_eq!(copy.description, "Start here");
+        assert_eq!(ctx.get_doc_content("work/readme.md").unwrap(), "# Readme\n");
+        assert_eq!(ctx.get_doc_tags("work/readme.md").unwrap(), vec!["intro"]);
+        assert_eq!(
+            ctx.get_doc_property("work/readme.md", "status").unwrap(),
+            Some(PropertyValue::String("draft".into()))
+        );
+        assert_eq!(ctx.list_doc_versions(&copy.stable_id).unwrap().len(), 1);
+
+        // Name collisions fail or get a suffix.
+        assert!(ctx
+            .copy_doc("template/readme.md", "work", None, ConflictPolicy::Fail)
+            .is_err());
+        let duplicate = ctx
+            .copy_doc(
+                "template/readme.md",
+                "template",
+                None,
+                ConflictPolicy::Rename,
+            )
+            .unwrap();
+        assert_eq!(duplicate.rel_path, "template/readme-1.md");
+        let renamed = ctx
+            .copy_doc(
+                "template/readme.md",
+                "work",
+                Some("notes.md"),
+                ConflictPolicy::Fail,
+            )
+            .unwrap();
+        assert_eq!(renamed.rel_path, "work/notes.md");
+        assert!(ctx
+            .copy_doc(
+                "template/readme.md",
+                "work",
+                Some("a/b.md"),
+                ConflictPolicy::Fail
+            )
+            .is_err());
+        assert!(ctx
+            .copy_doc("template/readme.md", "missing", None, ConflictPolicy::Fail)
+            .is_err());
+    }
+
+    #[test]
+    fn test_copy_folder_leaves_out_similarly_named_folders() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("my_notes", None).unwrap();
+        ctx.create_folder("my-notes/inner", None).unwrap();
+        ctx.create_doc("my_notes", "a.md", None).unwrap();
+        ctx.create_doc("my-notes", "secret.md", None).unwrap();
+
+        let copied = ctx
+            .copy_folder("my_notes", "work", ConflictPolicy::Fail)
+            .unwrap();
+        let paths: Vec<&str> = copied.docs.iter().map(|d| d.rel_path.as_str()).collect();
+        assert_eq!(paths, vec!["work/my_notes/a.md"]);
+        assert!(ctx.find_folder("work/my_notes/inner").unwrap().is_none());
+        assert!(ctx.find_doc("work/my_notes/secret.md").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_copy_folder() {
+        let (ctx, _temp) = create_test_context();
+        let adr = ctx.find_doc("template/design/adr.md").unwrap().unwrap();
+        ctx.save_doc_content(
+            "template/readme.md",
+            &format!("See [ADR](oc://doc/{})\n", adr.stable_id),
+            None,
+        )
+        .unwrap();
+        ctx.save_template("meeting", "# Meeting\n").unwrap();
+        ctx.set_folder_template("template/design", Some("meeting"))
+            .unwrap();
+        ctx.set_folder_property(
+            "template",
+            "kind",
+            &PropertyValue::String("template".into()),
+        )
+        .unwrap();
+
+        let copied = ctx
+            .copy_folder("template", "work", ConflictPolicy::Fail)
+            .unwrap();
+        assert_eq!(copied.rel_path, "work/template");
+        let paths: Vec<&str> = copied.docs.iter().map(|d| d.rel_path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["work/template/design/adr.md", "work/template/readme.md"]
+        );
+
+        let folder = ctx.find_folder("work/template").unwrap().unwrap();
+        assert_eq!(folder.description, "Project template");
+        assert_eq!(
+            ctx.find_folder("work/template/design")
+                .unwrap()
+                .unwrap()
+                .description,
+            "Design docs"
+        );
+        assert_eq!(
+            ctx.get_folder_template("work/template/design")
+                .unwrap()
+                .as_deref(),
+            Some("meeting")
+        );
+        assert_eq!(
+            ctx.get_folder_property("work/template", "kind").unwrap(),
+            Some(PropertyValue::String("template".into()))
+        );
+
+        // Links inside the folder point at the copies.
+        let adr_copy = ctx
+            .find_doc("work/template/design/adr.md")
+            .unwrap()
+            .unwrap();
+        assert_ne!(adr_copy.stable_id, adr.stable_id);
+        assert_eq!(
+            ctx.get_doc_content("work/template/readme.md").unwrap(),
+            format!("See [ADR](oc://doc/{})\n", adr_copy.stable_id)
+        );
+        assert_eq!(ctx.get_backlinks(&adr_copy.stable_id).unwrap().len(), 1);
+        assert_eq!(
+            ctx.get_doc_content("template/readme.md").unwrap(),
+            format!("See [ADR](oc://doc/{})\n", adr.stable_id)
+        );
+
+        // Top level, with a suffix on collision.
+        let top = ctx
+            .copy_folder("template", "", ConflictPolicy::Rename)
+            .unwrap();
+        assert_eq!(top.rel_path, "template-1");
+        assert_eq!(ctx.list_docs("template-1", true).unwrap().len(), 2);
+        assert!(ctx
+            .copy_folder("template", "", ConflictPolicy::Fail)
+            .is_err());
+        assert!(ctx
+            .copy_folder("template", "template/design", ConflictPolicy::Fail)
+            .is_err());
+    }
+
+    #[cfg(feature = "search")]
+    #[test]
+    fn test_copy_emits_created_events() {
+        use crate::events::{create_event_bus, DocEvent, Event, FolderEvent};
+
+        let (ctx, _temp) = create_test_context();
+        let bus = create_event_bus();
+        let mut rx = bus.subscribe();
+        let ctx = ctx.with_event_bus(bus);
+
+        ctx.copy_folder("template", "work", ConflictPolicy::Fail)
+            .unwrap();
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert!(matches!(
+            &events[0],
+            Event::Folder(FolderEvent::Created { rel_path }) if rel_path == "work/template"
+        ));
+        let created: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Doc(DocEvent::Created { rel_path }) => Some(rel_path.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            created,
+            vec!["work/template/design/adr.md", "work/template/readme.md"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod link_tests {
+    use crate::{EnvOverrides, LinkKind, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("notes", None).unwrap();
+        ctx.create_folder("archive", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_outgoing_and_backlinks() {
+        let (ctx, _temp) = create_test_context();
+        let target = ctx.create_doc("notes", "target.md", None).unwrap();
+        let source = ctx.create_doc("notes", "source.md", None).unwrap();
+
+        let content = format!(
+            "See [Target](oc://doc/{}?path=notes%2Ftarget.md) and [[notes/target]].\n",
+            target.stable_id
+        );
+        ctx.save_doc_content("notes/source.md", &content, None)
+            .unwrap();
+
+        let outgoing = ctx.get_outgoing_links(&source.stable_id).unwrap();
+        assert_eq!(outgoing.len(), 2);
+        assert_eq!(outgoing[0].kind, LinkKind::Oc);
+        assert_eq!(outgoing[1].kind, LinkKind::Wiki);
+        assert!(outgoing
+            .iter()
+            .all(|l| l.resolved_path.as_deref() == Some("notes/target.md")));
+
+        let backlinks = ctx.get_backlinks(&target.stable_id).unwrap();
+        assert_eq!(backlinks.len(), 2);
+        assert!(backlinks.iter().all(|l| l.source_path == "notes/source.md"));
+
+        // Saving again replaces the previous links
+        ctx.save_doc_content("notes/source.md", "No links here.", None)
+            .unwrap();
+        assert!(ctx.get_backlinks(&target.stable_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_links_in_code_blocks_are_ignored() {
+        let (ctx, _temp) = create_test_context();
+        let source = ctx.create_doc("notes", "source.md", None).unwrap();
+        ctx.save_doc_content("notes/source.md", "```\n[[notes/other]]\n```\n", None)
+            .unwrap();
+        assert!(ctx
+            .get_outgoing_links(&source.stable_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_link_to_doc_created_later() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("notes", "source.md", None).unwrap();
+        ctx.save_doc_content("notes/source.md", "[[notes/later]]", None)
+            .unwrap();
+        assert_eq!(ctx.find_broken_links().unwrap().len(), 1);
+
+        let later = ctx.create_doc("notes", "later.md", None).unwrap();
+        assert!(ctx.find_broken_links().unwrap().is_empty());
+        assert_eq!(ctx.get_backlinks(&later.stable_id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rename_breaks_path_links_but_not_stable_id_links() {
+        let (ctx, _temp) = create_test_context();
+        let target = ctx.create_doc("notes", "target.md", None).unwrap();
+        ctx.create_doc("notes", "source.md", None).unwrap();
+        let content = format!("[A](oc://doc/{}) [[notes/target]]", target.stable_id);
+        ctx.save_doc_content("notes/source.md", &content, None)
+            .unwrap();
+
+        ctx.rename_doc("notes/target.md", "renamed.md").unwrap();
+
+        let broken = ctx.find_broken_links().unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].kind, LinkKind::Wiki);
+        assert_eq!(broken[0].target_path.as_deref(), Some("notes/target.md"));
+        assert_eq!(broken[0].resolved_path.as_deref(), Some("notes/renamed.md"));
+
+        // Backlinks follow the stable id
+        assert_eq!(ctx.get_backlinks(&target.stable_id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rewrite_links_after_move() {
+        let (ctx, _temp) = create_test_context();
+        let target = ctx.create_doc("notes", "target.md", None).unwrap();
+        ctx.create_doc("notes", "source.md", None).unwrap();
+        let content = format!(
+            "[A](oc://doc/{}?path=notes%2Ftarget.md)\n[[notes/target|Target]]\n[[notes/target.md#intro]]\n",
+            target.stable_id
+        );
+        ctx.save_doc_content("notes/source.md", &content, None)
+            .unwrap();
+
+        let moved = ctx.move_doc("notes/target.md", "archive").unwrap();
+        let result = ctx.rewrite_links(&moved.old_path, &moved.new_path).unwrap();
+        assert_eq!(result.updated_docs, vec!["notes/source.md"]);
+        assert_eq!(result.rewritten_links, 3);
+
+        let updated = ctx.get_doc_content("notes/source.md").unwrap();
+        assert_eq!(
+            updated,
+            format!(
+                "[A](oc://doc/{}?path=archive%2Ftarget.md)\n[[archive/target|Target]]\n[[archive/target.md#intro]]\n",
+                target.stable_id
+            )
+        );
+        assert!(ctx.find_broken_links().unwrap().is_empty());
+
+        // The rewrite is recorded in the source doc's history
+        let source = ctx.get_doc_meta("notes/source.md").unwrap();
+        let versions = ctx.list_doc_versions(&source.stable_id).unwrap();
+        assert_eq!(versions[0].source.as_deref(), Some("link-rewrite"));
+    }
+
+    #[test]
+    fn test_rewrite_keeps_oc_link_anchor() {
+        let (ctx, _temp) = create_test_context();
+        let target = ctx.create_doc("notes", "target.md", None).unwrap();
+        ctx.create_doc("notes", "source.md", None).unwrap();
+        let link =
+            |path: &str| format!("[Intro](oc://doc/{}?path={path}#intro)\n", target.stable_id);
+        ctx.save_doc_content("notes/source.md", &link("notes%2Ftarget.md"), None)
+            .unwrap();
+
+        let moved = ctx.move_doc("notes/target.md", "archive").unwrap();
+        let result = ctx.rewrite_links(&moved.old_path, &moved.new_path).unwrap();
+        assert_eq!(result.rewritten_links, 1);
+        assert_eq!(
+            ctx.get_doc_content("notes/source.md").unwrap(),
+            link("archive%2Ftarget.md")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_links_after_folder_rename() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("archive", "a.md", None).unwrap();
+        ctx.create_doc("notes", "source.md", None).unwrap();
+        ctx.save_doc_content("notes/source.md", "[[archive/a]]", None)
+            .unwrap();
+
+        let renamed = ctx.rename_folder("archive", "old").unwrap();
+        let result = ctx
+            .rewrite_links(&renamed.old_path, &renamed.new_path)
+            .unwrap();
+        assert_eq!(result.rewritten_links, 1);
+        assert_eq!(ctx.get_doc_content("notes/source.md").unwrap(), "[[old/a]]");
+    }
+
+    #[test]
+    fn test_links_in_fenced_code_are_ignored() {
+        let (ctx, _temp) = create_test_context();
+        let target = ctx.create_doc("notes", "target.md", None).unwrap();
+        ctx.create_doc("notes", "source.md", None).unwrap();
+        ctx.create_doc("notes", "example.md", None).unwrap();
+        let content = "See [[notes/target]].\n\n```md\n[[notes/target]]\n```\n";
+        ctx.save_doc_content("notes/source.md", content, None)
+            .unwrap();
+        ctx.save_doc_content("notes/example.md", "~~~\n[[notes/target]]\n~~~\n", None)
+            .unwrap();
+        assert_eq!(ctx.get_backlinks(&target.stable_id).unwrap().len(), 1);
+
+        let moved = ctx.move_doc("notes/target.md", "archive").unwrap();
+        let result = ctx.rewrite_links(&moved.old_path, &moved.new_path).unwrap();
+        assert_eq!(result.updated_docs, vec!["notes/source.md"]);
+        assert_eq!(result.rewritten_links, 1);
+        assert_eq!(
+            ctx.get_doc_content("notes/source.md").unwrap(),
+            "See [[archive/target]].\n\n```md\n[[notes/target]]\n```\n"
+        );
+        assert_eq!(
+            ctx.get_doc_content("notes/example.md").unwrap(),
+            "~~~\n[[notes/target]]\n~~~\n"
+        );
+    }
+
+    #[test]
+    fn test_rebuild_links_picks_up_external_edits() {
+        let (ctx, _temp) = create_test_context();
+        let target = ctx.create_doc("notes", "target.md", None).unwrap();
+        let source = ctx.create_doc("notes", "source.md", None).unwrap();
+        std::fs::write(&source.abs_path, "[[notes/target]]").unwrap();
+        assert!(ctx.get_backlinks(&target.stable_id).unwrap().is_empty());
+
+        assert_eq!(ctx.rebuild_links().unwrap(), 1);
+        assert_eq!(ctx.get_backlinks(&target.stable_id).unwrap().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod frontmatter_tests {
+    use crate::{parse_frontmatter, split_frontmatter, EnvOverrides, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("docs", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    const DOC: &str = "---\ntitle: Payments ADR\ntags: [decision, payments]\nstatus: accepted\ndescription: Old summary\n---\n# Body\n\nText.\n";
+
+    #[test]
+    fn test_split_frontmatter() {
+        let (yaml, body, lines) = split_frontmatter(DOC);
+        assert!(yaml.unwrap().contains("title: Payments ADR"));
+        assert_eq!(body, "# Body\n\nText.\n");
+        assert_eq!(lines, 6);
+
+        let (yaml, body, lines) = split_frontmatter("# No frontmatter\n");
+        assert!(yaml.is_none());
+        assert_eq!(body, "# No frontmatter\n");
+        assert_eq!(lines, 0);
+
+        // An unterminated block is not frontmatter
+        let (yaml, _, _) = split_frontmatter("---\ntitle: x\n");
+        assert!(yaml.is_none());
+    }
+
+    #[test]
+    fn test_parse_frontmatter() {
+        let fields = parse_frontmatter(DOC).unwrap();
+        assert_eq!(fields["title"], "Payments ADR");
+        assert_eq!(fields["tags"], serde_json::json!(["decision", "payments"]));
+        assert!(parse_frontmatter("---\n: : bad\n  - [\n---\n").is_none());
+    }
+
+    #[test]
+    fn test_get_doc_meta_exposes_frontmatter() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("docs", "adr.md", None).unwrap();
+        ctx.save_doc_content("docs/adr.md", DOC, None).unwrap();
+
+        let meta = ctx.get_doc_meta("docs/adr.md").unwrap();
+        let fields = meta.frontmatter.unwrap();
+        assert_eq!(fields["status"], "accepted");
+
+        // Sync is off by default
+        assert_eq!(meta.description, "");
+    }
+
+    #[test]
+    fn test_description_sync_from_frontmatter() {
+        let (ctx, _temp) = create_test_context();
+        let ctx = ctx.with_frontmatter_sync(true);
+        ctx.create_doc("docs", "adr.md", None).unwrap();
+        ctx.save_doc_content("docs/adr.md", DOC, None).unwrap();
+
+        let meta = ctx.get_doc_meta("docs/adr.md").unwrap();
+        assert_eq!(meta.description, "Old summary");
+
+        // An explicit description wins
+        ctx.save_doc_content("docs/adr.md", DOC, Some("Explicit"))
+            .unwrap();
+        assert_eq!(
+            ctx.get_doc_meta("docs/adr.md").unwrap().description,
+            "Explicit"
+        );
+    }
+
+    #[test]
+    fn test_description_sync_to_frontmatter() {
+        let (ctx, _temp) = create_test_context();
+        let ctx = ctx.with_frontmatter_sync(true);
+        ctx.create_doc("docs", "adr.md", None).unwrap();
+        ctx.create_doc("docs", "plain.md", None).unwrap();
+        ctx.save_doc_content("docs/adr.md", DOC, None).unwrap();
+        ctx.save_doc_content("docs/plain.md", "# Plain\n", None)
+            .unwrap();
+
+        ctx.set_doc_description("docs/adr.md", "New summary: v2")
+            .unwrap();
+        let content = ctx.get_doc_content("docs/adr.md").unwrap();
+        let fields = parse_frontmatter(&content).unwrap();
+        assert_eq!(fields["description"], "New summary: v2");
+        assert_eq!(fields["title"], "Payments ADR");
+        assert!(content.ends_with("# Body\n\nText.\n"));
+
+        // Docs without frontmatter are left alone
+        ctx.set_doc_description("docs/plain.md", "Plain summary")
+            .unwrap();
+        assert_eq!(ctx.get_doc_content("docs/plain.md").unwrap(), "# Plain\n");
+        assert_eq!(
+            ctx.get_doc_meta("docs/plain.md").unwrap().description,
+            "Plain summary"
+        );
+    }
+
+    #[test]
+    fn test_description_added_to_frontmatter_without_key() {
+        let (ctx, _temp) = create_test_context();
+        let ctx = ctx.with_frontmatter_sync(true);
+        ctx.create_doc("docs", "adr.md", None).unwrap();
+        ctx.save_doc_content("docs/adr.md", "---\ntitle: T\n---\nBody\n", None)
+            .unwrap();
+
+        ctx.set_doc_description("docs/adr.md", "Summary").unwrap();
+        assert_eq!(
+            ctx.get_doc_content("docs/adr.md").unwrap(),
+            "---\ntitle: T\ndescription: Summary\n---\nBody\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod revision_tests {
+    use crate::{CoreError, EnvOverrides, OpenContext, SaveDocOptions};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("docs", None).unwrap();
+        ctx.create_doc("docs", "note.md", None).unwrap();
+        ctx.save_doc_content("docs/note.md", "v1", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    fn expecting(revision: &str) -> SaveDocOptions {
+        SaveDocOptions {
+            expected_revision: Some(revision.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_with_matching_revision() {
+        let (ctx, _temp) = create_test_context();
+
+        let read = ctx.get_doc_content_with_revision("docs/note.md").unwrap();
+        assert_eq!(read.content, "v1");
+        assert_eq!(
+            ctx.get_doc_meta("docs/note.md").unwrap().revision,
+            Some(read.revision.clone())
+        );
+
+        let saved = ctx
+            .save_doc_content_with("docs/note.md", "v2", expecting(&read.revision))
+            .unwrap();
+        assert_ne!(saved.revision, read.revision);
+
+        // The returned revision chains into the next save
+        ctx.save_doc_content_with("docs/note.md", "v3", expecting(&saved.revision))
+            .unwrap();
+        assert_eq!(ctx.get_doc_content("docs/note.md").unwrap(), "v3");
+    }
+
+    #[test]
+    fn test_stale_revision_is_rejected() {
+        let (ctx, _temp) = create_test_context();
+
+        let read = ctx.get_doc_content_with_revision("docs/note.md").unwrap();
+        ctx.save_doc_content("docs/note.md", "other writer", None)
+            .unwrap();
+
+        let err = ctx
+            .save_doc_content_with("docs/note.md", "mine", expecting(&read.revision))
+            .unwrap_err();
+        match err {
+            CoreError::Conflict {
+                rel_path,
+                current_revision,
+            } => {
+                assert_eq!(rel_path, "docs/note.md");
+                assert_eq!(
+                    current_revision,
+                    ctx.get_doc_content_with_revision("docs/note.md")
+                        .unwrap()
+                        .revision
+                );
+            }
+            other => panic!("expected conflict, got {other:?}"),
+        }
+        assert_eq!(ctx.get_doc_content("docs/note.md").unwrap(), "other writer");
+        // The rejected save is not recorded in history
+        assert_eq!(
+            ctx.list_doc_versions(&ctx.get_doc_meta("docs/note.md").unwrap().stable_id)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_external_edit_is_detected() {
+        let (ctx, _temp) = create_test_context();
+
+        let read = ctx.get_doc_content_with_revision("docs/note.md").unwrap();
+        let abs_path = ctx.get_doc_meta("docs/note.md").unwrap().abs_path;
+        fs::write(&abs_path, "edited on disk").unwrap();
+
+        let err = ctx
+            .save_doc_content_with("docs/note.md", "mine", expecting(&read.revision))
+            .unwrap_err();
+        assert!(matches!(err, CoreError::Conflict { .. }));
+    }
+
+    #[test]
+    fn test_save_with_updated_at_revision() {
+        let (ctx, _temp) = create_test_context();
+
+        let meta = ctx.get_doc_meta("docs/note.md").unwrap();
+        ctx.save_doc_content_with("docs/note.md", "v2", expecting(&meta.updated_at))
+            .unwrap();
+
+        let err = ctx
+            .save_doc_content_with("docs/note.md", "v3", expecting("1999-01-01T00:00:00.000Z"))
+            .unwrap_err();
+        assert!(matches!(err, CoreError::Conflict { .. }));
+    }
+}
+
+#[cfg(test)]
+mod atomic_tests {
+    use crate::atomic::{self, fail_at};
+    use crate::{EnvOverrides, OpenContext};
+    use std::fs;
+    use tempfile::TempDir;
+
+    const FAIL_POINTS: [&str; 3] = ["db.update", "fs.rename", "db.commit"];
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("project", None).unwrap();
+        ctx.create_folder("project/sub", None).unwrap();
+        ctx.create_folder("archive", None).unwrap();
+        ctx.create_doc("project", "a.md", None).unwrap();
+        ctx.create_doc("project/sub", "b.md", None).unwrap();
+        ctx.save_doc_content("project/a.md", "A", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    /// Disk and catalog both still show the original layout
+    fn assert_unchanged(ctx: &OpenContext, temp: &TempDir) {
+        let root = temp.path().join("contexts");
+        assert!(root.join("project/a.md").is_file());
+        assert!(root.join("project/sub/b.md").is_file());
+        assert!(!root.join("renamed").exists());
+        assert!(!root.join("archive/project").exists());
+        assert!(!root.join("archive/a.md").exists());
+        assert!(!root.join("project/c.md").exists());
+
+        assert_eq!(ctx.get_doc_content("project/a.md").unwrap(), "A");
+        assert!(ctx.get_doc_meta("project/sub/b.md").is_ok());
+        let folders: Vec<String> = ctx
+            .list_folders(true)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.rel_path)
+            .collect();
+        assert_eq!(folders, vec!["archive", "project", "project/sub"]);
+    }
+
+    #[test]
+    fn test_rename_folder_rolls_back() {
+        for point in FAIL_POINTS {
+            let (ctx, temp) = create_test_context();
+            {
+                let _guard = fail_at(point);
+                assert!(ctx.rename_folder("project", "renamed").is_err());
+            }
+            assert_unchanged(&ctx, &temp);
+
+            // The same call succeeds once the failure is gone
+            ctx.rename_folder("project", "renamed").unwrap();
+            assert!(ctx.get_doc_meta("renamed/sub/b.md").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_move_folder_rolls_back() {
+        for point in FAIL_POINTS {
+            let (ctx, temp) = create_test_context();
+            {
+                let _guard = fail_at(point);
+                assert!(ctx.move_folder("project", "archive").is_err());
+            }
+            assert_unchanged(&ctx, &temp);
+
+            ctx.move_folder("project", "archive").unwrap();
+            assert!(ctx.get_doc_meta("archive/project/a.md").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_move_doc_rolls_back() {
+        for point in FAIL_POINTS {
+            let (ctx, temp) = create_test_context();
+            {
+                let _guard = fail_at(point);
+                assert!(ctx.move_doc("project/a.md", "archive").is_err());
+            }
+            assert_unchanged(&ctx, &temp);
+
+            ctx.move_doc("project/a.md", "archive").unwrap();
+            assert_eq!(ctx.get_doc_content("archive/a.md").unwrap(), "A");
+        }
+    }
+
+    #[test]
+    fn test_rename_doc_rolls_back() {
+        for point in FAIL_POINTS {
+            let (ctx, temp) = create_test_context();
+            {
+                let _guard = fail_at(point);
+                assert!(ctx.rename_doc("project/a.md", "c.md").is_err());
+            }
+            assert_unchanged(&ctx, &temp);
+        }
+    }
+
+    #[test]
+    fn test_rollback_removes_created_directories() {
+        let temp = TempDir::new().unwrap();
+        let from = temp.path().join("a.md");
+        let to = temp.path().join("x/y/a.md");
+        fs::write(&from, "A").unwrap();
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let _guard = fail_at("db.commit");
+        let result = atomic::commit_with_renames(&conn, &[(from.clone(), to)], |_| Ok(()));
+        assert!(result.is_err());
+        assert!(from.is_file());
+        assert!(!temp.path().join("x").exists());
+    }
+
+    #[test]
+    fn test_failed_save_keeps_previous_content() {
+        let (ctx, temp) = create_test_context();
+        {
+            let _guard = fail_at("fs.write");
+            assert!(ctx.save_doc_content("project/a.md", "B", None).is_err());
+        }
+        assert_eq!(ctx.get_doc_content("project/a.md").unwrap(), "A");
+        // No temp files left next to the doc
+        let names: Vec<String> = fs::read_dir(temp.path().join("contexts/project"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(names.iter().all(|n| !n.contains(".tmp-")), "{names:?}");
+    }
+
+    #[cfg(feature = "search")]
+    #[test]
+    fn test_no_events_emitted_on_failure() {
+        use crate::events::create_event_bus;
+
+        let (ctx, _temp) = create_test_context();
+        let bus = create_event_bus();
+        let mut rx = bus.subscribe();
+        let ctx = ctx.with_event_bus(bus);
+
+        for point in FAIL_POINTS {
+            let _guard = fail_at(point);
+            assert!(ctx.rename_folder("project", "renamed").is_err());
+            assert!(ctx.move_doc("project/a.md", "archive").is_err());
+        }
+        assert!(rx.try_recv().is_err());
+
+        ctx.move_doc("project/a.md", "archive").unwrap();
+        assert!(rx.try_recv().is_ok());
+    }
+}
+
+#[cfg(test)]
+mod fulltext_tests {
+    use crate::{EnvOverrides, FullTextSearchOptions, OpenContext};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("project", None).unwrap();
+        ctx.create_folder("archive", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    fn search(ctx: &OpenContext, query: &str) -> Vec<String> {
+        ctx.full_text_search(query, &FullTextSearchOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.rel_path)
+            .collect()
+    }
+
+    #[test]
+    fn test_search_content_with_snippet() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("project", "payments.md", None).unwrap();
+        ctx.create_doc("project", "other.md", None).unwrap();
+        ctx.save_doc_content(
+            "project/payments.md",
+            "# Payments\n\nRetries use exponential backoff with jitter.",
+            None,
+        )
+        .unwrap();
+        ctx.save_doc_content("project/other.md", "Unrelated notes.", None)
+            .unwrap();
+
+        let hits = ctx
+            .full_text_search("Backoff", &FullTextSearchOptions::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].rel_path, "project/payments.md");
+        assert!(hits[0].snippet.contains("<mark>backoff</mark>"));
+        assert!(hits[0].score > 0.0);
+
+        // All terms must match
+        assert!(search(&ctx, "backoff unrelated").is_empty());
+    }
+
+    #[test]
+    fn test_search_cjk() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("project", "zh.md", None).unwrap();
+        ctx.create_doc("project", "ja.md", None).unwrap();
+        ctx.save_doc_content("project/zh.md", "我们的支付系统使用重试机制。", None)
+            .unwrap();
+        ctx.save_doc_content("project/ja.md", "決済システムの設計", None)
+            .unwrap();
+
+        assert_eq!(search(&ctx, "支付"), vec!["project/zh.md"]);
+        assert_eq!(search(&ctx, "重试机制"), vec!["project/zh.md"]);
+        assert_eq!(search(&ctx, "システム"), vec!["project/ja.md"]);
+        // Characters must be adjacent
+        assert!(search(&ctx, "支统").is_empty());
+
+        let hits = ctx
+            .full_text_search("支付", &FullTextSearchOptions::default())
+            .unwrap();
+        assert!(
+            hits[0].snippet.contains("<mark>支付</mark>"),
+            "{}",
+            hits[0].snippet
+        );
+        assert!(!hits[0].snippet.contains('\u{200B}'));
+    }
+
+    #[test]
+    fn test_index_follows_mutations() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("project", "note.md", Some("Quarterly planning"))
+            .unwrap();
+        assert_eq!(search(&ctx, "quarterly"), vec!["project/note.md"]);
+
+        ctx.save_doc_content("project/note.md", "first draft", None)
+            .unwrap();
+        ctx.save_doc_content("project/note.md", "second draft", None)
+            .unwrap();
+        assert!(search(&ctx, "first").is_empty());
+        assert_eq!(search(&ctx, "second"), vec!["project/note.md"]);
+
+        ctx.rename_doc("project/note.md", "roadmap.md").unwrap();
+        assert_eq!(search(&ctx, "roadmap"), vec!["project/roadmap.md"]);
+        assert_eq!(search(&ctx, "second"), vec!["project/roadmap.md"]);
+
+        ctx.move_doc("project/roadmap.md", "archive").unwrap();
+        assert_eq!(search(&ctx, "second"), vec!["archive/roadmap.md"]);
+
+        ctx.set_doc_description("archive/roadmap.md", "Yearly goals")
+            .unwrap();
+        assert!(search(&ctx, "quarterly").is_empty());
+        assert_eq!(search(&ctx, "yearly"), vec!["archive/roadmap.md"]);
+
+        ctx.remove_doc("archive/roadmap.md").unwrap();
+        assert!(search(&ctx, "second").is_empty());
+    }
+
+    #[test]
+    fn test_folder_filter_leaves_out_similarly_named_folders() {
+        let (ctx, _temp) = create_test_context();
+        for folder in ["a_b", "axb", "a_b/inner"] {
+            ctx.create_folder(folder, None).unwrap();
+            ctx.create_doc(folder, "doc.md", None).unwrap();
+            ctx.save_doc_content(&format!("{folder}/doc.md"), "shared term", None)
+                .unwrap();
+        }
+
+        let mut paths: Vec<String> = ctx
+            .full_text_search(
+                "shared",
+                &FullTextSearchOptions {
+                    folder_path: Some("a_b".into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.rel_path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["a_b/doc.md", "a_b/inner/doc.md"]);
+    }
+
+    #[test]
+    fn test_folder_filter_and_limit() {
+        let (ctx, _temp) = create_test_context();
+        for (folder, name) in [
+            ("project", "a.md"),
+            ("project", "b.md"),
+            ("archive", "c.md"),
+        ] {
+            ctx.create_doc(folder, name, None).unwrap();
+            ctx.save_doc_content(&format!("{folder}/{name}"), "shared term", None)
+                .unwrap();
+        }
+
+        let hits = ctx
+            .full_text_search(
+                "shared",
+                &FullTextSearchOptions {
+                    folder_path: Some("project".into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|h| h.rel_path.starts_with("project/")));
+
+        let hits = ctx
+            .full_text_search(
+                "shared",
+                &FullTextSearchOptions {
+                    limit: Some(1),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_query_syntax_is_literal() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_doc("project", "a.md", None).unwrap();
+        ctx.save_doc_content("project/a.md", "cats OR dogs", None)
+            .unwrap();
+
+        assert!(ctx
+            .full_text_search("  ", &FullTextSearchOptions::default())
+            .is_err());
+        // FTS5 operators and quotes are searched as text, not parsed
+        assert!(search(&ctx, "\"unbalanced").is_empty());
+        assert_eq!(search(&ctx, "OR dogs"), vec!["project/a.md"]);
+        assert!(search(&ctx, "NEAR(cats").is_empty());
+    }
+
+    #[test]
+    fn test_existing_docs_are_backfilled() {
+        let (ctx, temp) = create_test_context();
+        ctx.create_doc("project", "old.md", None).unwrap();
+        let abs_path = ctx.get_doc_meta("project/old.md").unwrap().abs_path;
+        fs::write(&abs_path, "legacy content").unwrap();
+        ctx.with_conn(|conn| {
+            conn.execute("DELETE FROM docs_fts", [])?;
+            Ok(())
+        })
+        .unwrap();
+        drop(ctx);
+
+        let base_path = temp.path().to_path_buf();
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .unwrap();
+        assert_eq!(search(&ctx, "legacy"), vec!["project/old.md"]);
+
+        // Edits made outside OpenContext are picked up by a rebuild
+        fs::write(&abs_path, "edited externally").unwrap();
+        assert_eq!(ctx.rebuild_full_text_index().unwrap(), 1);
+        assert_eq!(search(&ctx, "externally"), vec!["project/old.md"]);
+    }
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use crate::{ConflictPolicy, EnvOverrides, OpenContext};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_context_at(base_path: &std::path::Path) -> OpenContext {
+        OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.to_path_buf()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context")
+    }
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let ctx = create_context_at(temp_dir.path());
+
+        ctx.create_folder("project", Some("Payments project"))
+            .unwrap();
+        ctx.create_folder("project/adr", Some("Decisions")).unwrap();
+        ctx.create_doc("project", "readme.md", Some("Overview"))
+            .unwrap();
+        ctx.create_doc("project/adr", "001.md", Some("Use Postgres"))
+            .unwrap();
+        let adr = ctx.get_doc_meta("project/adr/001.md").unwrap();
+        ctx.save_doc_content(
+            "project/readme.md",
+            &format!("See [ADR](oc://doc/{}).", adr.stable_id),
+            None,
+        )
+        .unwrap();
+        ctx.save_doc_content("project/adr/001.md", "# Use Postgres", None)
+            .unwrap();
+        ctx.add_tags("project/adr/001.md", &["decision".to_string()])
+            .unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_export_leaves_out_similarly_named_folders() {
+        let (ctx, temp) = create_test_context();
+        ctx.create_folder("my_notes/sub", None).unwrap();
+        ctx.create_folder("my-notes/inner", None).unwrap();
+        ctx.create_doc("my_notes", "a.md", None).unwrap();
+        ctx.create_doc("my-notes", "secret.md", None).unwrap();
+
+        let bundle = temp.path().join("my_notes.tar");
+        let exported = ctx.export_bundle("my_notes", &bundle).unwrap();
+        assert_eq!(exported.folder_count, 1);
+        assert_eq!(exported.doc_count, 1);
+
+        let other = TempDir::new().unwrap();
+        let target = create_context_at(other.path());
+        let imported = target
+            .import_bundle(&bundle, "", ConflictPolicy::Fail)
+            .unwrap();
+        assert_eq!(imported.docs.len(), 1);
+        assert!(target.get_doc_meta("my_notes/a.md").is_ok());
+        let folders: Vec<String> = target
+            .list_folders(true)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.rel_path)
+            .collect();
+        assert_eq!(folders, vec!["my_notes", "my_notes/sub"]);
+    }
+
+    #[test]
+    fn test_roundtrip_to_another_library() {
+        let (ctx, temp) = create_test_context();
+        let bundle = temp.path().join("out/project.tar");
+        let exported = ctx.export_bundle("project", &bundle).unwrap();
+        assert_eq!(exported.folder_count, 1);
+        assert_eq!(exported.doc_count, 2);
+
+        let other = TempDir::new().unwrap();
+        let target = create_context_at(other.path());
+        target.create_folder("shared", None).unwrap();
+        let imported = target
+            .import_bundle(&bundle, "shared", ConflictPolicy::Fail)
+            .unwrap();
+        assert_eq!(imported.rel_path, "shared/project");
+        assert_eq!(imported.docs.len(), 2);
+        assert_eq!(imported.reassigned_stable_ids, 0);
+
+        let source_adr = ctx.get_doc_meta("project/adr/001.md").unwrap();
+        let adr = target.get_doc_meta("shared/project/adr/001.md").unwrap();
+        assert_eq!(adr.stable_id, source_adr.stable_id);
+        assert_eq!(adr.description, "Use Postgres");
+        assert_eq!(adr.created_at, source_adr.created_at);
+        assert!(adr.abs_path.starts_with(other.path()));
+        assert_eq!(
+            target.get_doc_tags("shared/project/adr/001.md").unwrap(),
+            vec!["decision"]
+        );
+        let folders = target.list_folders(true).unwrap();
+        let project = folders
+            .iter()
+            .find(|f| f.rel_path == "shared/project")
+            .unwrap();
+        assert_eq!(project.description, "Payments project");
+
+        // Stable-id links keep resolving in the new library
+        let backlinks = target.get_backlinks(&adr.stable_id).unwrap();
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].source_path, "shared/project/readme.md");
+        assert_eq!(
+            target
+                .list_doc_versions(&adr.stable_id)
+                .unwrap()
+                .first()
+                .and_then(|v| v.source.clone())
+                .as_deref(),
+            Some("import")
+        );
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let (ctx, temp) = create_test_context();
+        let bundle = temp.path().join("project.tar");
+        ctx.export_bundle("project", &bundle).unwrap();
+        ctx.create_folder("copy", None).unwrap();
+        ctx.import_bundle(&bundle, "copy", ConflictPolicy::Fail)
+            .unwrap();
+
+        // Importing the same bundle again: fail leaves everything as is
+        let err = ctx
+            .import_bundle(&bundle, "copy", ConflictPolicy::Fail)
+            .unwrap_err();
+        assert!(err.to_string().contains("copy/project/readme.md"));
+        assert_eq!(ctx.list_docs("copy/project", true).unwrap().len(), 2);
+
+        let imported = ctx
+            .import_bundle(&bundle, "copy", ConflictPolicy::Rename)
+            .unwrap();
+        assert_eq!(imported.renamed.len(), 2);
+        assert!(imported
+            .docs
+            .contains(&"copy/project/readme-1.md".to_string()));
+        // Ids were in use, so the copies got new ones and links were remapped
+        assert_eq!(imported.reassigned_stable_ids, 2);
+        let adr_copy = ctx.get_doc_meta("copy/project/adr/001-1.md").unwrap();
+        let readme = ctx.get_doc_content("copy/project/readme-1.md").unwrap();
+        assert!(readme.contains(&adr_copy.stable_id));
+    }
+
+    #[test]
+    fn test_rejects_unsafe_paths() {
+        let (ctx, temp) = create_test_context();
+        let bundle = temp.path().join("evil.tar");
+
+        let manifest = serde_json::json!({
+            "format": "opencontext-bundle",
+            "version": 1,
+            "exported_at": "2024-01-01T00:00:00.000Z",
+            "root_name": "evil",
+            "folders": [],
+            "docs": [{
+                "rel_path": "../../escape.md",
+                "stable_id": "x",
+                "created_at": "2024-01-01T00:00:00.000Z",
+                "updated_at": "2024-01-01T00:00:00.000Z"
+            }]
+        })
+        .to_string();
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "manifest.json", manifest.as_bytes())
+            .unwrap();
+        fs::write(&bundle, builder.into_inner().unwrap()).unwrap();
+
+        let err = ctx
+            .import_bundle(&bundle, "project", ConflictPolicy::Fail)
+            .unwrap_err();
+        assert!(err.to_string().contains("unsafe path"));
+        assert!(!temp.path().join("escape.md").exists());
+
+        fs::write(&bundle, b"not a tar").unwrap();
+        assert!(ctx
+            .import_bundle(&bundle, "project", ConflictPolicy::Fail)
+            .is_err());
+    }
+}
+
+#[cfg(test)]
+mod workspace_tests {
+    use crate::{WorkspaceManager, DEFAULT_WORKSPACE};
+    use tempfile::TempDir;
+
+    fn create_manager() -> (WorkspaceManager, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let manager = WorkspaceManager::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        (manager, temp_dir)
+    }
+
+    #[test]
+    fn test_default_workspace_uses_base_root() {
+        let (manager, temp) = create_manager();
+        let workspaces = manager.list().unwrap();
+        assert_eq!(workspaces.len(), 1);
+        assert_eq!(workspaces[0].name, DEFAULT_WORKSPACE);
+        assert!(workspaces[0].active);
+
+        let ctx = manager.open(DEFAULT_WORKSPACE).unwrap();
+        let info = ctx.env_info();
+        assert_eq!(info.contexts_root, temp.path().join("contexts"));
+        assert_eq!(info.db_path, temp.path().join("opencontext.db"));
+    }
+
+    #[test]
+    fn test_workspaces_are_isolated() {
+        let (manager, temp) = create_manager();
+        manager.create("work").unwrap();
+        manager.create("personal").unwrap();
+
+        let work = manager.open("work").unwrap();
+        let personal = manager.open("personal").unwrap();
+        assert_eq!(
+            work.env_info().db_path,
+            temp.path().join("workspaces/work/opencontext.db")
+        );
+
+        work.create_folder("clients", None).unwrap();
+        assert!(personal.list_folders(true).unwrap().is_empty());
+        assert_eq!(work.list_folders(true).unwrap().len(), 1);
+
+        let names: Vec<String> = manager
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|w| w.name)
+            .collect();
+        assert_eq!(names, vec!["default", "personal", "work"]);
+    }
+
+    #[test]
+    fn test_switch_and_delete() {
+        let (manager, _temp) = create_manager();
+        manager.create("work").unwrap();
+        assert!(manager.switch("missing").is_err());
+
+        manager.switch("work").unwrap();
+        assert_eq!(manager.active().unwrap(), "work");
+        // Persisted for the next session
+        let reopened = WorkspaceManager::new(Some(manager.base_root().to_path_buf())).unwrap();
+        assert_eq!(reopened.active().unwrap(), "work");
+
+        assert!(manager.delete("work").is_err());
+        assert!(manager.delete(DEFAULT_WORKSPACE).is_err());
+        manager.switch(DEFAULT_WORKSPACE).unwrap();
+        manager.delete("work").unwrap();
+        assert!(manager.get("work").is_err());
+        assert_eq!(manager.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        let (manager, _temp) = create_manager();
+        for name in ["", "Work", "../escape", "a/b", "with space"] {
+            assert!(manager.create(name).is_err(), "{name:?}");
+        }
+        assert!(manager.create(DEFAULT_WORKSPACE).is_err());
+        manager.create("work").unwrap();
+        assert!(manager.create("work").is_err());
+    }
+}
+
+#[cfg(test)]
+mod context_pack_tests {
+    use crate::{
+        ContextPackOptions, ContextPackSource, EnvOverrides, OpenContext, PackInclusion,
+        PackStrategy,
+    };
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        (ctx, temp_dir)
+    }
+
+    fn long_body(title: &str, lines: usize) -> String {
+        let mut body = format!("# {title}\n\nIntro paragraph for {title}.\n\n## Details\n\n");
+        for i in 0..lines {
+            body.push_str(&format!("Line {i} of {title} with some filler text.\n"));
+        }
+        body
+    }
+
+    /// Three docs: a.md (small), b.md (large), c.md (small)
+    fn setup(ctx: &OpenContext) {
+        ctx.create_folder("proj", None).unwrap();
+        ctx.create_doc("proj", "a.md", Some("First")).unwrap();
+        ctx.create_doc("proj", "b.md", Some("Second")).unwrap();
+        ctx.create_doc("proj", "c.md", None).unwrap();
+        ctx.save_doc_content("proj/a.md", "---\ntitle: A\n---\n# A\n\nShort doc.\n", None)
+            .unwrap();
+        ctx.save_doc_content("proj/b.md", &long_body("B", 200), None)
+            .unwrap();
+        ctx.save_doc_content("proj/c.md", &long_body("C", 5), None)
+            .unwrap();
+    }
+
+    fn inclusions(pack: &crate::ContextPack) -> Vec<PackInclusion> {
+        pack.docs.iter().map(|d| d.inclusion).collect()
+    }
+
+    #[test]
+    fn test_pack_fits_everything() {
+        let (ctx, _temp) = create_test_context();
+        setup(&ctx);
+        let a = ctx.get_doc_meta("proj/a.md").unwrap();
+
+        let pack = ctx
+            .build_context_pack(
+                &ContextPackSource::Folder("proj".into()),
+                &ContextPackOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(inclusions(&pack), vec![PackInclusion::Full; 3]);
+        assert!(pack
+            .content
+            .starts_with("# Context pack: proj\n\n## proj/a.md\n"));
+        assert!(pack
+            .content
+            .contains(&format!("- stable_id: {}\n", a.stable_id)));
+        assert!(pack.content.contains("- description: First\n"));
+        // Frontmatter is left out of the bundle
+        assert!(!pack.content.contains("title: A"));
+        assert!(pack.content.contains("Line 199 of B"));
+    }
+
+    #[test]
+    fn test_pack_truncate_strategy() {
+        let (ctx, _temp) = create_test_context();
+        setup(&ctx);
+
+        let pack = ctx
+            .build_context_pack(
+                &ContextPackSource::Folder("proj".into()),
+                &ContextPackOptions {
+                    token_budget: Some(400),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            inclusions(&pack),
+            vec![
+                PackInclusion::Full,
+                PackInclusion::Truncated,
+                PackInclusion::Omitted
+            ]
+        );
+        assert!(pack.estimated_tokens <= 400);
+        assert!(pack.content.contains("…(truncated)"));
+        assert!(!pack.content.contains("Line 199 of B"));
+        assert!(!pack.content.contains("## proj/c.md"));
+    }
+
+    #[test]
+    fn test_pack_summarize_strategy() {
+        let (ctx, _temp) = create_test_context();
+        setup(&ctx);
+
+        let pack = ctx
+            .build_context_pack(
+                &ContextPackSource::Folder("proj".into()),
+                &ContextPackOptions {
+                    token_budget: Some(400),
+                    strategy: PackStrategy::Summarize,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            inclusions(&pack),
+            vec![
+                PackInclusion::Full,
+                PackInclusion::Summary,
+                PackInclusion::Full
+            ]
+        );
+        assert!(pack.estimated_tokens <= 400);
+        assert!(pack.content.contains("Intro paragraph for B."));
+        assert!(pack.content.contains("Outline:\n- B\n  - Details"));
+        assert!(pack.content.contains("Line 4 of C"));
+    }
+
+    #[test]
+    fn test_pack_by_stable_ids() {
+        let (ctx, _temp) = create_test_context();
+        setup(&ctx);
+        let a = ctx.get_doc_meta("proj/a.md").unwrap().stable_id;
+        let c = ctx.get_doc_meta("proj/c.md").unwrap().stable_id;
+
+        let pack = ctx
+            .build_context_pack(
+                &ContextPackSource::StableIds(vec![c.clone(), a.clone(), c.clone()]),
+                &ContextPackOptions::default(),
+            )
+            .unwrap();
+        let paths: Vec<&str> = pack.docs.iter().map(|d| d.rel_path.as_str()).collect();
+        assert_eq!(paths, vec!["proj/c.md", "proj/a.md"]);
+        assert!(pack.content.find("## proj/c.md") < pack.content.find("## proj/a.md"));
+
+        assert!(ctx
+            .build_context_pack(
+                &ContextPackSource::StableIds(vec!["missing".into()]),
+                &ContextPackOptions::default(),
+            )
+            .is_err());
+        assert!(ctx
+            .build_context_pack(
+                &ContextPackSource::Folder("proj".into()),
+                &ContextPackOptions {
+                    token_budget: Some(0),
+                    ..Default::default()
+                },
+            )
+            .is_err());
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use std::collections::HashMap;
+
+    use crate::{EnvOverrides, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_template_crud() {
+        let (ctx, temp) = create_test_context();
+        assert!(ctx.list_templates().unwrap().is_empty());
+
+        let info = ctx.save_template("adr", "# {{title}}\n").unwrap();
+        assert_eq!(info.abs_path, temp.path().join("templates/adr.md"));
+        ctx.save_template("meeting", "# Meeting {{date}}\n")
+            .unwrap();
+        let names: Vec<String> = ctx
+            .list_templates()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["adr", "meeting"]);
+        assert_eq!(ctx.get_template("adr").unwrap(), "# {{title}}\n");
+
+        assert!(ctx.save_template("../escape", "x").is_err());
+        assert!(ctx.get_template("missing").is_err());
+        ctx.delete_template("meeting").unwrap();
+        assert_eq!(ctx.list_templates().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_create_doc_from_template_substitutes_vars() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("decisions", None).unwrap();
+        ctx.save_template(
+            "adr",
+            "# {{title}}\n\nDate: {{ date }}\nStatus: {{status}}\nOwner: {{owner}}\nIn {{folder}}\n",
+        )
+        .unwrap();
+
+        let vars = HashMap::from([("status".to_string(), "proposed".to_string())]);
+        let created = ctx
+            .create_doc_from_template("decisions", "0001-use-rust.md", Some("adr"), &vars)
+            .unwrap();
+        let content = ctx.get_doc_content(&created.rel_path).unwrap();
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            content,
+            format!(
+                "# 0001-use-rust\n\nDate: {today}\nStatus: proposed\nOwner: {{{{owner}}}}\nIn decisions\n"
+            )
+        );
+        // Template content is searchable like any saved doc
+        let hits = ctx
+            .full_text_search("proposed", &Default::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+
+        // A missing template leaves no empty doc behind
+        assert!(ctx
+            .create_doc_from_template("decisions", "other.md", Some("missing"), &vars)
+            .is_err());
+        assert!(ctx.get_doc_meta("decisions/other.md").is_err());
+    }
+
+    #[test]
+    fn test_folder_default_template() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("meetings", None).unwrap();
+        ctx.create_folder("meetings/2024", None).unwrap();
+        ctx.create_folder("notes", None).unwrap();
+        ctx.save_template("meeting", "# {{title}}\n\n## Attendees\n")
+            .unwrap();
+
+        assert!(ctx
+            .set_folder_template("meetings", Some("missing"))
+            .is_err());
+        ctx.set_folder_template("meetings", Some("meeting"))
+            .unwrap();
+        assert_eq!(
+            ctx.get_folder_template("meetings/2024").unwrap().as_deref(),
+            Some("meeting")
+        );
+        assert_eq!(ctx.get_folder_template("notes").unwrap(), None);
+
+        // create_doc applies the inherited default
+        let created = ctx
+            .create_doc("meetings/2024", "standup.md", Some("Daily"))
+            .unwrap();
+        assert_eq!(created.description, "Daily");
+        assert_eq!(
+            ctx.get_doc_content("meetings/2024/standup.md").unwrap(),
+            "# standup\n\n## Attendees\n"
+        );
+        ctx.create_doc("notes", "plain.md", None).unwrap();
+        assert_eq!(ctx.get_doc_content("notes/plain.md").unwrap(), "");
+
+        // and so does create_doc_from_template without a template
+        ctx.create_doc_from_template("meetings", "retro.md", None, &HashMap::new())
+            .unwrap();
+        assert!(ctx
+            .get_doc_content("meetings/retro.md")
+            .unwrap()
+            .starts_with("# retro"));
+        assert!(ctx
+            .create_doc_from_template("notes", "x.md", None, &HashMap::new())
+            .is_err());
+
+        // Deleting the template clears it as a default
+        ctx.delete_template("meeting").unwrap();
+        assert_eq!(ctx.get_folder_template("meetings").unwrap(), None);
+        ctx.set_folder_template("meetings", None).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod idea_tests {
+    use crate::{AddIdeaOptions, EnvOverrides, IdeaDateRange, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_add_idea_writes_thread_doc() {
+        let (ctx, _temp) = create_test_context();
+        let idea = ctx.add_idea("", "Try  a Rust   core!").unwrap();
+        assert_eq!(idea.idea_box, "inbox");
+        assert!(idea.rel_path.starts_with(".ideas/inbox/"));
+        let name = idea.rel_path.rsplit('/').next().unwrap();
+        assert!(name.contains("-try-a-rust-core-"), "{name}");
+
+        let content = ctx.get_doc_content(&idea.rel_path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "[//]: # (idea:id={} created_at={})\nTry  a Rust   core!",
+                idea.id, idea.created_at
+            )
+        );
+
+        let reply = ctx
+            .add_idea_with(
+                "",
+                "Agreed",
+                &AddIdeaOptions {
+                    thread_path: Some(idea.rel_path.clone()),
+                    is_ai: true,
+                },
+            )
+            .unwrap();
+        assert_eq!(reply.rel_path, idea.rel_path);
+        let content = ctx.get_doc_content(&idea.rel_path).unwrap();
+        assert!(content.contains(" is_ai=true)\nAgreed"));
+        assert_eq!(
+            ctx.list_ideas(None, &IdeaDateRange::default())
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(ctx.add_idea("inbox", "   ").is_err());
+        assert!(ctx.add_idea("../x", "text").is_err());
+        assert!(ctx.add_idea("2024", "text").is_err());
+    }
+
+    #[test]
+    fn test_list_ideas_filters_by_box_and_date() {
+        let (ctx, _temp) = create_test_context();
+        // A thread written by the desktop app before boxes existed.
+        ctx.create_folder(".ideas/2023/05", None).unwrap();
+        ctx.create_doc(".ideas/2023/05", "20230501-old-abc.md", None)
+            .unwrap();
+        ctx.save_doc_content(
+            ".ideas/2023/05/20230501-old-abc.md",
+            "[//]: # (idea:id=old1 created_at=2023-05-01T08:00:00.000Z)\nFirst\n\n\
+             [//]: # (idea:id=old2 created_at=2023-05-03T08:00:00.000Z)\nSecond",
+            None,
+        )
+        .unwrap();
+        ctx.add_idea("work", "Ship it").unwrap();
+
+        let all = ctx.list_ideas(None, &IdeaDateRange::default()).unwrap();
+        let ids: Vec<&str> = all.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids[1..], ["old2", "old1"]);
+        assert_eq!(all[0].idea_box, "work");
+
+        let inbox = ctx
+            .list_ideas(Some("inbox"), &IdeaDateRange::default())
+            .unwrap();
+        assert_eq!(inbox.len(), 2);
+        assert_eq!(inbox[0].text, "Second");
+
+        let range = IdeaDateRange {
+            from: Some("2023-05-02".into()),
+            to: Some("2023-05-31".into()),
+        };
+        let ranged = ctx.list_ideas(None, &range).unwrap();
+        assert_eq!(ranged.len(), 1);
+        assert_eq!(ranged[0].id, "old2");
+    }
+
+    #[test]
+    fn test_update_delete_and_move_idea() {
+        let (ctx, _temp) = create_test_context();
+        let first = ctx.add_idea("inbox", "First").unwrap();
+        let second = ctx
+            .add_idea_with(
+                "inbox",
+                "Second",
+                &AddIdeaOptions {
+                    thread_path: Some(first.rel_path.clone()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let updated = ctx.update_idea(&first.id, "First, revised").unwrap();
+        assert_eq!(updated.created_at, first.created_at);
+        assert_eq!(ctx.get_idea(&first.id).unwrap().text, "First, revised");
+
+        let moved = ctx.move_idea(&second.id, "work").unwrap();
+        assert_eq!(moved.id, second.id);
+        assert_eq!(moved.created_at, second.created_at);
+        assert!(moved.rel_path.starts_with(".ideas/work/"));
+        let remaining = ctx.get_doc_content(&first.rel_path).unwrap();
+        assert!(!remaining.contains("Second"));
+
+        // Deleting the last idea of a thread removes the thread doc.
+        ctx.delete_idea(&moved.id).unwrap();
+        assert!(ctx.find_doc(&moved.rel_path).unwrap().is_none());
+        ctx.delete_idea(&first.id).unwrap();
+        assert!(ctx.find_doc(&first.rel_path).unwrap().is_none());
+        assert!(ctx.get_idea(&first.id).is_err());
+        assert!(ctx.update_idea("missing", "x").is_err());
+    }
+}
+
+#[cfg(all(test, feature = "watch"))]
+mod watcher_tests {
+    use std::{fs, sync::Arc, thread, time::Duration};
+
+    use crate::events::{create_event_bus, DocEvent, Event, FolderEvent};
+    use crate::watcher::{FsChanges, FsWatcher, WatcherOptions};
+    use crate::{EnvOverrides, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context")
+        .with_event_bus(create_event_bus());
+
+        (ctx, temp_dir)
+    }
+
+    fn touched(paths: &[&str]) -> FsChanges {
+        FsChanges {
+            touched: paths.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_self_writes_are_ignored() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("notes", None).unwrap();
+        ctx.create_doc("notes", "a.md", None).unwrap();
+        ctx.save_doc_content("notes/a.md", "# A\n", None).unwrap();
+        let mut rx = ctx.event_bus().unwrap().subscribe();
+
+        ctx.apply_fs_changes(&touched(&["notes", "notes/a.md"]))
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_external_edit_create_and_delete() {
+        let (ctx, temp) = create_test_context();
+        let root = temp.path().join("contexts");
+        ctx.create_folder("notes", None).unwrap();
+        let created = ctx.create_doc("notes", "a.md", None).unwrap();
+        let mut rx = ctx.event_bus().unwrap().subscribe();
+
+        fs::write(root.join("notes/a.md"), "edited in vim\n").unwrap();
+        fs::write(root.join("notes/b.md"), "new file\n").unwrap();
+        ctx.apply_fs_changes(&touched(&["notes/a.md", "notes/b.md"]))
+            .unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Doc(DocEvent::Updated { rel_path }) if rel_path == "notes/a.md"
+        ));
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Doc(DocEvent::Created { rel_path }) if rel_path == "notes/b.md"
+        ));
+        let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+        assert_eq!(versions[0].source.as_deref(), Some("external"));
+        assert!(ctx.find_doc("notes/b.md").unwrap().is_some());
+
+        fs::remove_file(root.join("notes/b.md")).unwrap();
+        ctx.apply_fs_changes(&touched(&["notes/b.md"])).unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Doc(DocEvent::Deleted { rel_path }) if rel_path == "notes/b.md"
+        ));
+        assert!(ctx.find_doc("notes/b.md").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_external_renames_keep_stable_ids() {
+        let (ctx, temp) = create_test_context();
+        let root = temp.path().join("contexts");
+        ctx.create_folder("notes", None).unwrap();
+        ctx.create_folder("archive", None).unwrap();
+        let created = ctx.create_doc("notes", "a.md", None).unwrap();
+        let mut rx = ctx.event_bus().unwrap().subscribe();
+
+        fs::rename(root.join("notes/a.md"), root.join("notes/b.md")).unwrap();
+        fs::rename(root.join("notes/b.md"), root.join("archive/b.md")).unwrap();
+        ctx.apply_fs_changes(&FsChanges {
+            renames: vec![
+                ("notes/a.md".into(), "notes/b.md".into()),
+                ("notes/b.md".into(), "archive/b.md".into()),
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Doc(DocEvent::Renamed { .. })
+        ));
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Doc(DocEvent::Moved { .. })
+        ));
+        let doc = ctx.find_doc("archive/b.md").unwrap().unwrap();
+        assert_eq!(doc.stable_id, created.stable_id);
+
+        fs::rename(root.join("archive"), root.join("old")).unwrap();
+        ctx.apply_fs_changes(&FsChanges {
+            renames: vec![("archive".into(), "old".into())],
+            ..Default::default()
+        })
+        .unwrap();
+        match rx.try_recv().unwrap() {
+            Event::Folder(FolderEvent::Renamed { affected_docs, .. }) => {
+                assert_eq!(
+                    affected_docs,
+                    vec![("archive/b.md".to_string(), "old/b.md".to_string())]
+                );
+            }
+            other => panic!("Unexpected event: {other:?}"),
+        }
+        let doc = ctx.find_doc("old/b.md").unwrap().unwrap();
+        assert_eq!(doc.stable_id, created.stable_id);
+        assert_eq!(doc.abs_path, root.join("old/b.md"));
+
+        fs::remove_dir_all(root.join("old")).unwrap();
+        ctx.apply_fs_changes(&touched(&["old"])).unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Folder(FolderEvent::Deleted { removed_docs, .. }) if removed_docs == ["old/b.md"]
+        ));
+        assert!(ctx.find_doc("old/b.md").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_external_folder_changes_leave_similarly_named_folders() {
+        let (ctx, temp) = create_test_context();
+        let root = temp.path().join("contexts");
+        for folder in ["a_b", "axb"] {
+            ctx.create_folder(folder, None).unwrap();
+            ctx.create_doc(folder, "doc.md", None).unwrap();
+            ctx.save_doc_content(&format!("{folder}/doc.md"), "shared term\n", None)
+                .unwrap();
+        }
+        let sibling = ctx.find_doc("axb/doc.md").unwrap().unwrap();
+        let mut rx = ctx.event_bus().unwrap().subscribe();
+
+        fs::rename(root.join("a_b"), root.join("c_d")).unwrap();
+        ctx.apply_fs_changes(&FsChanges {
+            renames: vec![("a_b".into(), "c_d".into())],
+            ..Default::default()
+        })
+        .unwrap();
+        match rx.try_recv().unwrap() {
+            Event::Folder(FolderEvent::Renamed { affected_docs, .. }) => {
+                assert_eq!(
+                    affected_docs,
+                    vec![("a_b/doc.md".to_string(), "c_d/doc.md".to_string())]
+                );
+            }
+            other => panic!("Unexpected event: {other:?}"),
+        }
+        let unchanged = ctx.find_doc("axb/doc.md").unwrap().unwrap();
+        assert_eq!(unchanged.id, sibling.id);
+        assert_eq!(unchanged.abs_path, root.join("axb/doc.md"));
+
+        // `cxd` matches `c_d/%` under LIKE
+        fs::rename(root.join("axb"), root.join("cxd")).unwrap();
+        ctx.apply_fs_changes(&FsChanges {
+            renames: vec![("axb".into(), "cxd".into())],
+            ..Default::default()
+        })
+        .unwrap();
+        rx.try_recv().unwrap();
+        fs::remove_dir_all(root.join("c_d")).unwrap();
+        ctx.apply_fs_changes(&touched(&["c_d"])).unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            Event::Folder(FolderEvent::Deleted { removed_docs, .. }) if removed_docs == ["c_d/doc.md"]
+        ));
+        assert!(ctx.find_doc("cxd/doc.md").unwrap().is_some());
+        let hits = ctx
+            .full_text_search("shared", &crate::FullTextSearchOptions::default())
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        let fts_rows: i64 = ctx
+            .with_conn(|conn| {
+                Ok(conn.query_row("SELECT COUNT(*) FROM docs_fts", [], |row| row.get(0))?)
+            })
+            .unwrap();
+        assert_eq!(fts_rows, 1);
+    }
+
+    #[test]
+    fn test_watcher_picks_up_external_edit() {
+        let (ctx, temp) = create_test_context();
+        let root = temp.path().join("contexts");
+        ctx.create_folder("notes", None).unwrap();
+        let created = ctx.create_doc("notes", "a.md", None).unwrap();
+        let ctx = Arc::new(ctx);
+        let watcher = FsWatcher::start_with(
+            ctx.clone(),
+            WatcherOptions {
+                debounce: Duration::from_millis(50),
+            },
+        )
+        .unwrap();
+
+        fs::write(root.join("notes/a.md"), "external\n").unwrap();
+        fs::create_dir(root.join("inbox")).unwrap();
+        fs::write(root.join("inbox/new.md"), "hello\n").unwrap();
+        let mut synced = false;
+        for _ in 0..50 {
+            thread::sleep(Duration::from_millis(100));
+            let versions = ctx.list_doc_versions(&created.stable_id).unwrap();
+            if !versions.is_empty() && ctx.find_doc("inbox/new.md").unwrap().is_some() {
+                synced = true;
+                break;
+            }
+        }
+        watcher.stop();
+        assert!(synced, "watcher did not sync external changes");
+    }
+}
+
+#[cfg(test)]
+mod attachment_tests {
+    use crate::{AttachmentGcOptions, EnvOverrides, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("notes", None).unwrap();
+        ctx.create_folder("notes/deep", None).unwrap();
+        ctx.create_folder("archive", None).unwrap();
+        ctx.create_doc("notes", "design.md", None).unwrap();
+        ctx.create_doc("notes", "other.md", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    #[test]
+    fn test_add_and_reference_attachment() {
+        let (ctx, _temp) = create_test_context();
+        let added = ctx
+            .add_attachment("notes/design.md", b"png-bytes", "../My Diagram (v2).PNG")
+            .unwrap();
+        let attachment = &added.attachment;
+        assert_eq!(attachment.file_name, "My-Diagram--v2-.PNG");
+        assert_eq!(attachment.mime_type, "image/png");
+        assert_eq!(attachment.size, 9);
+        assert_eq!(attachment.ref_count, 0);
+        assert_eq!(std::fs::read(&attachment.abs_path).unwrap(), b"png-bytes");
+        assert_eq!(added.link, format!("../{}", attachment.rel_path));
+        assert_eq!(
+            added.markdown,
+            format!("![My-Diagram--v2-.PNG]({})", added.link)
+        );
+        assert!(ctx.add_attachment("notes/design.md", b"x", "..").is_err());
+
+        ctx.save_doc_content(
+            "notes/design.md",
+            &format!("# Design\n\n{}\n", added.markdown),
+            None,
+        )
+        .unwrap();
+        ctx.save_doc_content(
+            "notes/other.md",
+            &format!("See [the diagram](<./{}>).\n", added.link),
+            None,
+        )
+        .unwrap();
+
+        let listed = ctx.list_attachments("notes/design.md").unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].ref_count, 2);
+        // Linked from other.md without being added to it
+        let listed = ctx.list_attachments("notes/other.md").unwrap();
+        assert_eq!(listed[0].attachment_id, attachment.attachment_id);
+
+        // Code blocks do not count as references.
+        ctx.save_doc_content(
+            "notes/other.md",
+            &format!("```\n{}\n```\n", added.markdown),
+            None,
+        )
+        .unwrap();
+        assert!(ctx.list_attachments("notes/other.md").unwrap().is_empty());
+        assert_eq!(
+            ctx.list_attachments("notes/design.md").unwrap()[0].ref_count,
+            1
+        );
+    }
+
+    #[test]
+    fn test_moves_rewrite_attachment_links() {
+        let (ctx, _temp) = create_test_context();
+        let added = ctx
+            .add_attachment("notes/design.md", b"svg", "flow.svg")
+            .unwrap();
+        let rel_path = added.attachment.rel_path.clone();
+        let content = format!(
+            "{}\n\n```\n{}\n```\n[site](https://example.com/.assets/x.png)\n",
+            added.markdown, added.markdown
+        );
+        ctx.save_doc_content("notes/design.md", &content, None)
+            .unwrap();
+
+        ctx.move_doc("notes/design.md", "notes/deep").unwrap();
+        let moved = ctx.get_doc_content("notes/deep/design.md").unwrap();
+        assert_eq!(
+            moved,
+            format!(
+                "![flow.svg](../../{rel_path})\n\n```\n{}\n```\n[site](https://example.com/.assets/x.png)\n",
+                added.markdown
+            )
+        );
+
+        ctx.rename_folder("notes", "docs").unwrap();
+        assert_eq!(ctx.get_doc_content("docs/deep/design.md").unwrap(), moved);
+
+        ctx.move_folder("docs/deep", "archive").unwrap();
+        let content = ctx.get_doc_content("archive/deep/design.md").unwrap();
+        assert!(content.starts_with(&format!("![flow.svg](../../{rel_path})")));
+
+        ctx.move_doc("archive/deep/design.md", "archive").unwrap();
+        let content = ctx.get_doc_content("archive/design.md").unwrap();
+        assert!(content.starts_with(&format!("![flow.svg](../{rel_path})")));
+        assert_eq!(
+            ctx.list_attachments("archive/design.md").unwrap()[0].ref_count,
+            1
+        );
+
+        let copied = ctx
+            .copy_doc(
+                "archive/design.md",
+                "archive/deep",
+                None,
+                Default::default(),
+            )
+            .unwrap();
+        let content = ctx.get_doc_content(&copied.rel_path).unwrap();
+        assert!(content.starts_with(&format!("![flow.svg](../../{rel_path})")));
+        assert_eq!(
+            ctx.list_attachments("archive/design.md").unwrap()[0].ref_count,
+            2
+        );
+    }
+
+    #[test]
+    fn test_gc_attachments() {
+        let (ctx, _temp) = create_test_context();
+        let kept = ctx
+            .add_attachment("notes/design.md", b"a", "kept.png")
+            .unwrap();
+        let orphan = ctx
+            .add_attachment("notes/design.md", b"b", "orphan.pdf")
+            .unwrap();
+        assert_eq!(orphan.markdown, format!("[orphan.pdf]({})", orphan.link));
+        ctx.save_doc_content("notes/design.md", &kept.markdown, None)
+            .unwrap();
+
+        let old_only = ctx
+            .gc_attachments(&AttachmentGcOptions {
+                older_than: Some("2000-01-01".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(old_only.is_empty());
+
+        let dry = ctx
+            .gc_attachments(&AttachmentGcOptions {
+                dry_run: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(dry.len(), 1);
+        assert!(orphan.attachment.abs_path.exists());
+
+        let collected = ctx.gc_attachments(&Default::default()).unwrap();
+        assert_eq!(collected.len(), 1);
+        assert_eq!(collected[0].attachment_id, orphan.attachment.attachment_id);
+        assert!(!orphan.attachment.abs_path.parent().unwrap().exists());
+        assert!(kept.attachment.abs_path.exists());
+
+        // A trashed doc keeps its attachments until the trash is purged.
+        ctx.remove_doc("notes/design.md").unwrap();
+        assert!(ctx.gc_attachments(&Default::default()).unwrap().is_empty());
+        ctx.purge_trash(None).unwrap();
+        let collected = ctx.gc_attachments(&Default::default()).unwrap();
+        assert_eq!(collected.len(), 1);
+        assert!(!kept.attachment.abs_path.exists());
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use crate::{
+        AuditLogOptions, AuditTarget, ConflictPolicy, EnvOverrides, OpenContext, SaveDocOptions,
+    };
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context")
+        .with_source("cli");
+
+        ctx.create_folder("notes", None).unwrap();
+        ctx.create_doc("notes", "design.md", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    fn actions(entries: &[crate::AuditEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.action.as_str()).collect()
+    }
+
+    #[test]
+    fn test_records_mutations_with_source() {
+        let (ctx, _temp) = create_test_context();
+        ctx.save_doc_content("notes/design.md", "# Design", None)
+            .unwrap();
+        ctx.set_doc_description("notes/design.md", "The design")
+            .unwrap();
+
+        let entries = ctx.audit_log(&AuditLogOptions::default()).unwrap();
+        assert_eq!(
+            actions(&entries),
+            [
+                "set_doc_description",
+                "save_doc_content",
+                "create_doc",
+                "create_folder"
+            ]
+        );
+        assert!(entries.iter().all(|e| e.source.as_deref() == Some("cli")));
+        let stable_id = ctx.get_doc_meta("notes/design.md").unwrap().stable_id;
+        assert_eq!(entries[0].target, AuditTarget::Doc);
+        assert_eq!(entries[0].stable_id.as_deref(), Some(stable_id.as_str()));
+        assert_eq!(entries[3].target, AuditTarget::Folder);
+        assert_eq!(entries[3].path, "notes");
+    }
+
+    #[test]
+    fn test_save_source_overrides_context_source() {
+        let (ctx, _temp) = create_test_context();
+        ctx.save_doc_content_with(
+            "notes/design.md",
+            "hello",
+            SaveDocOptions {
+                source: Some("MCP".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let entries = ctx.audit_log(&AuditLogOptions::default()).unwrap();
+        assert_eq!(entries[0].source.as_deref(), Some("mcp"));
+        assert_eq!(entries[1].source.as_deref(), Some("cli"));
+
+        let mcp = ctx
+            .audit_log(&AuditLogOptions {
+                source: Some("mcp".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(actions(&mcp), ["save_doc_content"]);
+    }
+
+    #[test]
+    fn test_doc_history_follows_folder_moves() {
+        let (ctx, _temp) = create_test_context();
+        let stable_id = ctx.get_doc_meta("notes/design.md").unwrap().stable_id;
+        ctx.rename_folder("notes", "specs").unwrap();
+        ctx.rename_doc("specs/design.md", "plan.md").unwrap();
+        ctx.create_folder("other", None).unwrap();
+        ctx.copy_doc("specs/plan.md", "other", None, ConflictPolicy::Fail)
+            .unwrap();
+
+        let history = ctx
+            .audit_log(&AuditLogOptions {
+                doc: Some("specs/plan.md".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            actions(&history),
+            ["copy_doc", "rename_doc", "rename_folder", "create_doc"]
+        );
+        let folder = &history[2];
+        assert_eq!(folder.path, "notes");
+        assert_eq!(folder.new_path.as_deref(), Some("specs"));
+        assert_eq!(folder.docs.len(), 1);
+        assert_eq!(folder.docs[0].stable_id, stable_id);
+        assert_eq!(folder.docs[0].path, "notes/design.md");
+        assert_eq!(folder.docs[0].new_path.as_deref(), Some("specs/design.md"));
+
+        let by_id = ctx
+            .audit_log(&AuditLogOptions {
+                doc: Some(stable_id),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_id.len(), history.len());
+    }
+
+    #[test]
+    fn test_folder_time_and_limit_filters() {
+        let (ctx, _temp) = create_test_context();
+        ctx.create_folder("other", None).unwrap();
+        ctx.create_doc("other", "x.md", None).unwrap();
+        ctx.remove_folder("notes", true).unwrap();
+
+        let notes = ctx
+            .audit_log(&AuditLogOptions {
+                folder_path: Some("notes".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            actions(&notes),
+            ["remove_folder", "create_doc", "create_folder"]
+        );
+        assert_eq!(notes[0].docs[0].path, "notes/design.md");
+
+        let future = ctx
+            .audit_log(&AuditLogOptions {
+                since: Some("2999-01-01".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(future.is_empty());
+        let past = ctx
+            .audit_log(&AuditLogOptions {
+                until: Some("2000-01-01".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(past.is_empty());
+
+        let latest = ctx
+            .audit_log(&AuditLogOptions {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(actions(&latest), ["remove_folder"]);
+        assert!(ctx
+            .audit_log(&AuditLogOptions {
+                limit: Some(0),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_records_trash_restore_and_purge() {
+        let (ctx, _temp) = create_test_context();
+        let stable_id = ctx.get_doc_meta("notes/design.md").unwrap().stable_id;
+        ctx.remove_doc("notes/design.md").unwrap();
+        let id = ctx.list_trash().unwrap()[0].id;
+        ctx.restore_from_trash(id, ConflictPolicy::Fail).unwrap();
+        ctx.create_folder("notes/sub", None).unwrap();
+        ctx.create_doc("notes/sub", "x.md", None).unwrap();
+        ctx.remove_folder("notes", true).unwrap();
+        let id = ctx.list_trash().unwrap()[0].id;
+        ctx.restore_from_trash(id, ConflictPolicy::Fail).unwrap();
+        ctx.remove_doc("notes/sub/x.md").unwrap();
+        assert_eq!(ctx.purge_trash(None).unwrap(), 1);
+
+        let entries = ctx.audit_log(&AuditLogOptions::default()).unwrap();
+        assert_eq!(
+            actions(&entries)[..3],
+            ["purge_trash", "remove_doc", "restore_from_trash"]
+        );
+        let purged = &entries[0];
+        assert_eq!(purged.target, AuditTarget::Doc);
+        assert_eq!(purged.path, "notes/sub/x.md");
+        assert_eq!(purged.source.as_deref(), Some("cli"));
+        let restored_folder = &entries[2];
+        assert_eq!(restored_folder.target, AuditTarget::Folder);
+        assert_eq!(restored_folder.path, "notes");
+        assert_eq!(restored_folder.new_path.as_deref(), Some("notes"));
+        assert_eq!(restored_folder.docs.len(), 2);
+
+        let history = ctx
+            .audit_log(&AuditLogOptions {
+                doc: Some(stable_id),
+                action: Some("restore_from_trash".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].target, AuditTarget::Doc);
+        assert_eq!(history[1].new_path.as_deref(), Some("notes/design.md"));
+    }
+
+    #[test]
+    fn test_records_bundle_import() {
+        let (ctx, temp) = create_test_context();
+        let bundle = temp.path().join("notes.tar");
+        ctx.export_bundle("notes", &bundle).unwrap();
+        ctx.create_folder("imported", None).unwrap();
+        ctx.import_bundle(&bundle, "imported", ConflictPolicy::Fail)
+            .unwrap();
+
+        let entries = ctx.audit_log(&AuditLogOptions::default()).unwrap();
+        assert_eq!(entries[0].action, "import_bundle");
+        assert_eq!(entries[0].target, AuditTarget::Folder);
+        assert_eq!(entries[0].path, "imported/notes");
+        assert_eq!(entries[0].source.as_deref(), Some("cli"));
+        assert_eq!(entries[0].docs.len(), 1);
+        assert_eq!(entries[0].docs[0].path, "imported/notes/design.md");
+
+        let history = ctx
+            .audit_log(&AuditLogOptions {
+                doc: Some("imported/notes/design.md".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(actions(&history), ["import_bundle"]);
+    }
+
+    #[test]
+    fn test_audit_log_is_append_only() {
+        let (ctx, _temp) = create_test_context();
+        let result = ctx.with_conn(|conn| {
+            conn.execute("UPDATE audit_log SET source = 'forged'", [])?;
+            Ok(())
+        });
+        assert!(result.is_err());
+        let result = ctx.with_conn(|conn| {
+            conn.execute("DELETE FROM audit_log", [])?;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(ctx.audit_log(&AuditLogOptions::default()).unwrap().len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod uri_tests {
+    use crate::{EnvOverrides, OpenContext};
+    use tempfile::TempDir;
+
+    const DESIGN: &str = "---\ntitle: Design\n---\n# Design\n\nIntro.\n\n## Goals\n\nFast.\nSmall.\n\n### `oc://` URIs\n\nCitable.\n\n## Non-goals\n\nNone.\n\n# Appendix\n\n## Goals\n\nAgain.\n";
+
+    fn create_test_context() -> (OpenContext, TempDir, String) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("notes", None).unwrap();
+        let doc = ctx.create_doc("notes", "design.md", None).unwrap();
+        ctx.save_doc_content("notes/design.md", DESIGN, None)
+            .unwrap();
+
+        (ctx, temp_dir, doc.stable_id)
+    }
+
+    #[test]
+    fn test_make_uri_uses_heading_path_anchor() {
+        let (ctx, _temp, stable_id) = create_test_context();
+        assert_eq!(
+            ctx.make_uri("notes/design.md", None).unwrap(),
+            format!("oc://doc/{stable_id}")
+        );
+        assert_eq!(
+            ctx.make_uri("notes/design.md", Some("Goals")).unwrap(),
+            format!("oc://doc/{stable_id}#design/goals")
+        );
+        assert_eq!(
+            ctx.make_uri("notes/design.md", Some("Appendix > Goals"))
+                .unwrap(),
+            format!("oc://doc/{stable_id}#appendix/goals")
+        );
+        assert_eq!(
+            ctx.make_uri("notes/design.md", Some("oc:// URIs")).unwrap(),
+            format!("oc://doc/{stable_id}#design/goals/oc-uris")
+        );
+        assert!(ctx.make_uri("notes/design.md", Some("Missing")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_section() {
+        let (ctx, _temp, stable_id) = create_test_context();
+        let resolved = ctx
+            .resolve_uri(&format!("oc://doc/{stable_id}#design/goals"))
+            .unwrap();
+        assert_eq!(resolved.doc.rel_path, "notes/design.md");
+        assert_eq!(resolved.heading_path.as_deref(), Some("Design > Goals"));
+        assert_eq!((resolved.start_line, resolved.end_line), (8, 15));
+        assert_eq!(
+            resolved.content,
+            "## Goals\n\nFast.\nSmall.\n\n### `oc://` URIs\n\nCitable."
+        );
+
+        // A bare heading slug picks the first match; the full path does not
+        let bare = ctx
+            .resolve_uri(&format!("oc://doc/{stable_id}#goals"))
+            .unwrap();
+        assert_eq!(bare.uri, format!("oc://doc/{stable_id}#design/goals"));
+        let appendix = ctx
+            .resolve_uri(&format!("oc://doc/{stable_id}#appendix/goals"))
+            .unwrap();
+        assert_eq!(appendix.content, "## Goals\n\nAgain.");
+
+        assert!(ctx
+            .resolve_uri(&format!("oc://doc/{stable_id}#missing"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_resolve_lines_and_whole_doc() {
+        let (ctx, _temp, stable_id) = create_test_context();
+        let resolved = ctx
+            .resolve_uri(&format!("oc://doc/{stable_id}?lines=10-11"))
+            .unwrap();
+        assert_eq!(resolved.content, "Fast.\nSmall.");
+        assert_eq!(resolved.uri, format!("oc://doc/{stable_id}?lines=10-11"));
+
+        let clamped = ctx
+            .resolve_uri(&format!("oc://doc/{stable_id}?lines=25-99"))
+            .unwrap();
+        assert_eq!(clamped.content, "Again.");
+        assert_eq!(clamped.end_line, 25);
+
+        let whole = ctx.resolve_uri(&format!("oc://doc/{stable_id}")).unwrap();
+        assert_eq!(whole.content, DESIGN.trim_end());
+        assert!(whole.anchor.is_none());
+        assert!(whole.doc.revision.is_some());
+    }
+
+    #[test]
+    fn test_uri_survives_move() {
+        let (ctx, _temp, stable_id) = create_test_context();
+        let uri = ctx.make_uri("notes/design.md", Some("Non-goals")).unwrap();
+        ctx.create_folder("archive", None).unwrap();
+        ctx.move_doc("notes/design.md", "archive").unwrap();
+        let resolved = ctx.resolve_uri(&uri).unwrap();
+        assert_eq!(resolved.doc.rel_path, "archive/design.md");
+        assert_eq!(resolved.content, "## Non-goals\n\nNone.");
+        assert_eq!(resolved.doc.stable_id, stable_id);
+    }
+
+    #[test]
+    fn test_anchor_links_and_path_hint() {
+        let (ctx, _temp, stable_id) = create_test_context();
+        ctx.create_doc("notes", "other.md", None).unwrap();
+        let uri = ctx.make_uri("notes/design.md", Some("Goals")).unwrap();
+        ctx.save_doc_content("notes/other.md", &format!("See [goals]({uri})."), None)
+            .unwrap();
+        let backlinks = ctx.get_backlinks(&stable_id).unwrap();
+        assert_eq!(backlinks.len(), 1);
+
+        let hinted = ctx
+            .resolve_uri("oc://doc/?path=notes/design.md#non-goals")
+            .unwrap();
+        assert_eq!(hinted.doc.stable_id, stable_id);
+        assert_eq!(hinted.heading_path.as_deref(), Some("Design > Non-goals"));
+    }
+
+    #[test]
+    fn test_rejects_malformed_uris() {
+        let (ctx, _temp, stable_id) = create_test_context();
+        for uri in [
+            "https://example.com".to_string(),
+            "oc://doc/".to_string(),
+            "oc://folder/notes".to_string(),
+            format!("oc://doc/{stable_id}?lines=0-3"),
+            format!("oc://doc/{stable_id}?lines=5-2"),
+            format!("oc://doc/{stable_id}?lines=a-b"),
+            format!("oc://doc/{stable_id}?lines=99-100"),
+            format!("oc://doc/{stable_id}?page=2"),
+            format!("oc://doc/{stable_id}?lines=1-2#goals"),
+            "oc://doc/unknown-id".to_string(),
+        ] {
+            assert!(ctx.resolve_uri(&uri).is_err(), "{uri} should be rejected");
+        }
+    }
+}
+
+#[cfg(test)]
+mod path_safety_tests {
+    use crate::{paths, ConflictPolicy, EnvOverrides, OpenContext};
+    use tempfile::TempDir;
+
+    fn create_test_context() -> (OpenContext, TempDir) {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let base_path = temp_dir.path().to_path_buf();
+
+        let ctx = OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+        .expect("Failed to initialize context");
+
+        ctx.create_folder("notes", None).unwrap();
+        ctx.create_doc("notes", "a.md", None).unwrap();
+
+        (ctx, temp_dir)
+    }
+
+    const HOSTILE_PATHS: &[&str] = &[
+        "..",
+        "../escape",
+        "notes/../../escape",
+        "notes/./a.md",
+        "..\\..\\escape",
+        "notes\\..\\..\\escape",
+        "/etc/passwd",
+        "\\etc\\passwd",
+        "//server/share",
+        "\\\\server\\share\\x",
+        "C:\\Windows\\win.ini",
+        "c:/windows",
+        "C:escape",
+        "notes/a\0.md",
+        "notes/a\n.md",
+        "notes/\u{1b}[31m.md",
+        "notes/\u{7f}.md",
+        "CON",
+        "notes/nul.md",
+        "notes/Aux.txt",
+        "notes/com1",
+        "notes/LPT9.md",
+        "notes/trailing.",
+        "notes/trailing /a.md",
+        ".trash/x.md",
+        ".assets/id/x.png",
+        ".TRASH/x.md",
+    ];
+
+    #[test]
+    fn test_clean_rel_path_rejects_hostile_input() {
+        for input in HOSTILE_PATHS {
+            let result = paths::clean_rel_path(input, "Document path");
+            assert!(result.is_err(), "{input:?} should be rejected: {result:?}");
+        }
+    }
+
+    #[test]
+    fn test_clean_rel_path_keeps_ordinary_names() {
+        for (input, expected) in [
+            ("notes/a.md", "notes/a.md"),
+            ("  notes//deep///b.md ", "notes/deep/b.md"),
+            ("notes\\b.md", "notes/b.md"),
+            ("notes/console.md", "notes/console.md"),
+            ("notes/com10.md", "notes/com10.md"),
+            ("notes/..hidden/x.md", "notes/..hidden/x.md"),
+            ("notes/v1.2 release.md", "notes/v1.2 release.md"),
+            (".ideas/inbox/2024.md", ".ideas/inbox/2024.md"),
+            ("notes/.trash/x.md", "notes/.trash/x.md"),
+            ("笔记/设计.md", "笔记/设计.md"),
+        ] {
+            assert_eq!(
+                paths::clean_rel_path(input, "Document path").unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_entry_points_reject_hostile_paths() {
+        let (ctx, temp) = create_test_context();
+        for input in HOSTILE_PATHS {
+            assert!(ctx.create_folder(input, None).is_err(), "{input:?}");
+            assert!(ctx.create_doc(input, "x.md", None).is_err(), "{input:?}");
+            assert!(ctx.get_doc_content(input).is_err(), "{input:?}");
+            assert!(ctx.get_doc_meta(input).is_err(), "{input:?}");
+            assert!(ctx.save_doc_content(input, "x", None).is_err(), "{input:?}");
+            assert!(ctx.remove_doc(input).is_err(), "{input:?}");
+            assert!(ctx.move_doc("notes/a.md", input).is_err(), "{input:?}");
+            assert!(ctx.move_folder("notes", input).is_err(), "{input:?}");
+            assert!(ctx.list_docs(input, false).is_err(), "{input:?}");
+            assert!(ctx
+                .copy_doc("notes/a.md", input, None, ConflictPolicy::Rename)
+                .is_err());
+        }
+        assert!(!temp.path().join("escape").exists());
+        assert_eq!(ctx.get_doc_content("notes/a.md").unwrap(), "");
+    }
+
+    #[test]
+    fn test_names_must_be_single_safe_segments() {
+        let (ctx, _temp) = create_test_context();
+        for name in [
+            "..", ".", "a/b", "a\\b", "C:x", "nul", "CON.md", "x\0.md", "x\r.md", "x.md.",
+        ] {
+            assert!(ctx.create_doc("notes", name, None).is_err(), "{name:?}");
+            assert!(ctx.rename_doc("notes/a.md", name).is_err(), "{name:?}");
+            assert!(ctx.rename_folder("notes", name).is_err(), "{name:?}");
+            assert!(ctx
+                .copy_doc("notes/a.md", "notes", Some(name), ConflictPolicy::Rename)
+                .is_err());
+        }
+        assert!(ctx.find_doc("notes/a.md").unwrap().is_some());
+        assert!(ctx.find_folder("notes").unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_folder_cannot_be_used_to_escape() {
+        use std::os::unix::fs::symlink;
+
+        let (ctx, temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+        let outside = temp.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        symlink(&outside, root.join("notes/link")).unwrap();
+
+        assert!(ctx.create_folder("notes/link", None).is_err());
+        assert!(ctx.create_folder("notes/link/sub", None).is_err());
+        ctx.create_folder("notes/other", None).unwrap();
+        assert!(ctx.rename_folder("notes/other", "link").is_err());
+        ctx.create_folder("link", None).unwrap();
+        assert!(ctx.move_folder("link", "notes").is_err());
+        assert!(std::fs::read_dir(&outside).unwrap().next().is_none());
+
+        // A catalogued folder swapped for a symlink after the fact
+        ctx.create_folder("dest", None).unwrap();
+        std::fs::remove_dir(root.join("dest")).unwrap();
+        symlink(&outside, root.join("dest")).unwrap();
+        assert!(ctx.create_doc("dest", "x.md", None).is_err());
+        assert!(ctx.move_doc("notes/a.md", "dest").is_err());
+        assert!(ctx
+            .copy_doc("notes/a.md", "dest", None, ConflictPolicy::Fail)
+            .is_err());
+        assert!(std::fs::read_dir(&outside).unwrap().next().is_none());
+        assert!(ctx.find_doc("notes/a.md").unwrap().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_doc_is_not_read_or_written() {
+        use std::os::unix::fs::symlink;
+
+        let (ctx, temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+        let secret = temp.path().join("secret.txt");
+        std::fs::write(&secret, "top secret").unwrap();
+        std::fs::remove_file(root.join("notes/a.md")).unwrap();
+        symlink(&secret, root.join("notes/a.md")).unwrap();
+
+        assert!(ctx.get_doc_content("notes/a.md").is_err());
+        assert!(ctx.get_doc_meta("notes/a.md").is_err());
+        assert!(ctx.save_doc_content("notes/a.md", "pwned", None).is_err());
+        assert!(ctx
+            .copy_doc("notes/a.md", "notes", Some("b.md"), ConflictPolicy::Fail)
+            .is_err());
+        assert_eq!(std::fs::read_to_string(&secret).unwrap(), "top secret");
+
+        // Dangling links could point anywhere once created
+        std::fs::remove_file(root.join("notes/a.md")).unwrap();
+        symlink(temp.path().join("missing.txt"), root.join("notes/a.md")).unwrap();
+        assert!(ctx.save_doc_content("notes/a.md", "pwned", None).is_err());
+        assert!(!temp.path().join("missing.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_inside_root_are_allowed() {
+        use std::os::unix::fs::symlink;
+
+        let (ctx, _temp) = create_test_context();
+        let root = ctx.env_info().contexts_root;
+        ctx.create_folder("shared", None).unwrap();
+        symlink(root.join("shared"), root.join("notes/alias")).unwrap();
+        ctx.create_folder("notes/alias", None).unwrap();
+        ctx.create_doc("notes/alias", "x.md", None).unwrap();
+        ctx.save_doc_content("notes/alias/x.md", "hi", None)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("shared/x.md")).unwrap(),
+            "hi"
+        );
+    }
+}
+
+#[cfg(test)]
+mod migration_tests {
+    use std::fs;
+
+    use rusqlite::Connection;
+
+    use crate::migrations::{apply_migrations, schema_version, Migration};
+    use crate::{
+        AuditLogOptions, CoreError, EnvOverrides, FullTextSearchOptions, OpenContext,
+        PropertyValue, SCHEMA_VERSION,
+    };
+    use tempfile::TempDir;
+
+    const LEGACY_STABLE_ID: &str = "6f1c0e9a-4b7d-4c2e-9a51-0d3f2b8e7c11";
+
+    /// Historical catalogs, oldest first, with the number of migrations whose
+    /// tables they already contain.
+    const FIXTURES: &[(&str, u32)] = &[
+        (include_str!("../tests/fixtures/schema/v0_legacy.sql"), 0),
+        (
+            include_str!("../tests/fixtures/schema/v1_folders_and_docs.sql"),
+            1,
+        ),
+        (
+            include_str!("../tests/fixtures/schema/v2_doc_versions.sql"),
+            2,
+        ),
+        (include_str!("../tests/fixtures/schema/v3_trash.sql"), 3),
+        (include_str!("../tests/fixtures/schema/v4_doc_tags.sql"), 4),
+        (include_str!("../tests/fixtures/schema/v5_doc_links.sql"), 5),
+        (include_str!("../tests/fixtures/schema/v6_docs_fts.sql"), 6),
+        (
+            include_str!("../tests/fixtures/schema/v7_doc_access.sql"),
+            7,
+        ),
+        (
+            include_str!("../tests/fixtures/schema/v8_folder_templates.sql"),
+            8,
+        ),
+        (
+            include_str!("../tests/fixtures/schema/v9_properties.sql"),
+            9,
+        ),
+        (
+            include_str!("../tests/fixtures/schema/v10_doc_pins.sql"),
+            10,
+        ),
+        (
+            include_str!("../tests/fixtures/schema/v11_attachments.sql"),
+            11,
+        ),
+        (
+            include_str!("../tests/fixtures/schema/v12_audit_log.sql"),
+            12,
+        ),
+    ];
+
+    fn open_context(temp: &TempDir) -> crate::CoreResult<OpenContext> {
+        let base_path = temp.path().to_path_buf();
+        OpenContext::initialize(EnvOverrides {
+            base_root: Some(base_path.clone()),
+            contexts_root: Some(base_path.join("contexts")),
+            db_path: Some(base_path.join("test.db")),
+        })
+    }
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = ?1",
+            [name],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn test_fresh_database_is_at_latest_version() {
+        let temp = TempDir::new().unwrap();
+        let ctx = open_context(&temp).unwrap();
+        ctx.with_conn(|conn| {
+            assert_eq!(schema_version(conn)?, SCHEMA_VERSION);
+            Ok(())
+        })
+        .unwrap();
+        drop(ctx);
+        // Reopening has nothing left to apply.
+        open_context(&temp).unwrap();
+    }
+
+    #[test]
+    fn test_upgrades_every_historical_schema() {
+        for (sql, tables) in FIXTURES {
+            let temp = TempDir::new().unwrap();
+            let conn = Connection::open(temp.path().join("test.db")).unwrap();
+            conn.execute_batch(sql).unwrap();
+            // Point the catalog at this temp dir, as if it had been created here.
+            let contexts = temp.path().join("contexts");
+            conn.execute(
+                "UPDATE docs SET abs_path = ?1 || '/' || rel_path",
+                [contexts.to_string_lossy()],
+            )
+            .unwrap();
+            drop(conn);
+            let notes = temp.path().join("contexts/notes");
+            fs::create_dir_all(&notes).unwrap();
+            fs::write(notes.join("a.md"), "# A\n\nmigration fixture body\n").unwrap();
+
+            let ctx =
+                open_context(&temp).unwrap_or_else(|e| panic!("fixture with {tables} tables: {e}"));
+            ctx.with_conn(|conn| {
+                assert_eq!(schema_version(conn)?, SCHEMA_VERSION);
+                for table in [
+                    "doc_versions",
+                    "trash",
+                    "doc_tags",
+                    "doc_links",
+                    "docs_fts",
+                    "doc_access",
+                    "folder_templates",
+                    "doc_properties",
+                    "folder_properties",
+                    "doc_pins",
+                    "attachments",
+                    "attachment_refs",
+                    "audit_log",
+                    "audit_log_docs",
+                ] {
+                    assert!(table_exists(conn, table), "{table} missing");
+                }
+                Ok(())
+            })
+            .unwrap();
+
+            let doc = ctx.find_doc("notes/a.md").unwrap().unwrap();
+            assert_eq!(doc.description, "First doc");
+            if *tables == 0 {
+                assert_eq!(doc.stable_id.len(), 36);
+            } else {
+                assert_eq!(doc.stable_id, LEGACY_STABLE_ID);
+            }
+            if *tables >= 2 {
+                assert_eq!(ctx.list_doc_versions(&doc.stable_id).unwrap().len(), 1);
+            }
+            if *tables >= 4 {
+                assert_eq!(ctx.get_doc_tags("notes/a.md").unwrap(), vec!["rust"]);
+            }
+            if *tables >= 8 {
+                assert_eq!(
+                    ctx.get_folder_template("notes").unwrap().as_deref(),
+                    Some("meeting")
+                );
+            }
+            if *tables >= 9 {
+                assert_eq!(
+                    ctx.get_doc_property("notes/a.md", "status").unwrap(),
+                    Some(PropertyValue::String("draft".into()))
+                );
+                assert_eq!(
+                    ctx.get_folder_property("notes", "owner").unwrap(),
+                    Some(PropertyValue::String("platform".into()))
+                );
+            }
+            if *tables >= 10 {
+                let pinned = ctx.pinned_docs().unwrap();
+                assert_eq!(pinned.len(), 1);
+                assert_eq!(pinned[0].doc.rel_path, "notes/a.md");
+            }
+            if *tables >= 11 {
+                let attachments = ctx.list_attachments("notes/a.md").unwrap();
+                assert_eq!(attachments.len(), 1);
+                assert_eq!(attachments[0].file_name, "diagram.png");
+                assert_eq!(attachments[0].ref_count, 1);
+            }
+            if *tables >= 12 {
+                let history = ctx
+                    .audit_log(&AuditLogOptions {
+                        doc: Some("notes/a.md".into()),
+                        ..Default::default()
+                    })
+                    .unwrap();
+                assert_eq!(history.len(), 1);
+                assert_eq!(history[0].action, "create_doc");
+                assert_eq!(history[0].source.as_deref(), Some("cli"));
+            }
+            let hits = ctx
+                .full_text_search("fixture", &FullTextSearchOptions::default())
+                .unwrap();
+            assert_eq!(hits.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let temp = TempDir::new().unwrap();
+        let conn = Connection::open(temp.path().join("test.db")).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        drop(conn);
+        match open_context(&temp) {
+            Err(CoreError::Message(msg)) => assert!(msg.contains("newer"), "{msg}"),
+            Err(other) => panic!("Unexpected error: {other}"),
+            Ok(_) => panic!("Opened a database from a newer version"),
+        }
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                name: "create_a",
+                up: |tx| {
+                    tx.execute_batch("CREATE TABLE a (id INTEGER)")?;
+                    Ok(())
+                },
+            },
+            Migration {
+                version: 2,
+                name: "create_b_then_fail",
+                up: |tx| {
+                    tx.execute_batch("CREATE TABLE b (id INTEGER)")?;
+                    Err(CoreError::Message("boom".into()))
+                },
+            },
+        ];
+        let err = apply_migrations(&mut conn, &migrations).unwrap_err();
+        assert!(err.to_string().contains("create_b_then_fail"));
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        assert!(table_exists(&conn, "a"));
+        assert!(!table_exists(&conn, "b"));
+    }
 }
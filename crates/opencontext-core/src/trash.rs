@@ -0,0 +1,636 @@
+//! Trash bin for removed documents and folders.
+//!
+//! `remove_doc` and `remove_folder` move files into `<contexts_root>/.trash`
+//! instead of deleting them. The catalog metadata (stable ids, descriptions,
+//! timestamps, tags, properties) is kept in the `trash` table so a restore brings back the
+//! exact same documents.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit::AuditedDoc,
+    fulltext, generate_stable_id, manifest, now_iso, parent_rel_path,
+    properties::{doc_properties, folder_properties, set_property},
+    tags::doc_tags,
+    CoreError, CoreResult, Doc, Folder, OpenContext, Properties,
+};
+
+#[cfg(feature = "search")]
+use crate::events::{DocEvent, FolderEvent};
+
+/// Directory (relative to `contexts_root`) that holds trashed files
+pub(crate) const TRASH_DIR: &str = ".trash";
+
+/// What to do when the target path already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Return an error
+    #[default]
+    Fail,
+    /// Pick a free name by appending a numeric suffix (`name-1.md`)
+    Rename,
+}
+
+/// Kind of item in the trash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    Doc,
+    Folder,
+}
+
+impl TrashKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TrashKind::Doc => "doc",
+            TrashKind::Folder => "folder",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        if value == "folder" {
+            TrashKind::Folder
+        } else {
+            TrashKind::Doc
+        }
+    }
+}
+
+/// An item in the trash bin
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashEntry {
+    pub id: i64,
+    pub kind: TrashKind,
+    /// Path the item had before it was removed
+    pub original_path: String,
+    pub description: String,
+    /// Stable id of a trashed document (`None` for folders)
+    pub stable_id: Option<String>,
+    /// Number of documents contained in this entry
+    pub doc_count: usize,
+    pub deleted_at: String,
+}
+
+/// Result of restoring a trash entry
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashRestored {
+    pub id: i64,
+    pub kind: TrashKind,
+    pub original_path: String,
+    /// Path the item was restored to (differs on a renamed conflict)
+    pub rel_path: String,
+    /// Paths of the documents brought back
+    pub restored_docs: Vec<String>,
+}
+
+/// Catalog metadata preserved for a trashed folder/doc tree.
+/// Paths are relative to the trashed item itself ("" is the item root).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TrashPayload {
+    folders: Vec<TrashedFolder>,
+    docs: Vec<TrashedDoc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrashedFolder {
+    rel_path: String,
+    description: String,
+    created_at: String,
+    #[serde(default)]
+    properties: Properties,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrashedDoc {
+    rel_path: String,
+    description: String,
+    stable_id: String,
+    created_at: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    properties: Properties,
+}
+
+impl OpenContext {
+    /// List items in the trash, most recently removed first.
+    pub fn list_trash(&self) -> CoreResult<Vec<TrashEntry>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, kind, original_path, description, payload, deleted_at, trash_path
+                 FROM trash ORDER BY deleted_at DESC, id DESC",
+            )?;
+            let rows = stmt
+                .query_map([], |row| row_to_trash(row).map(|(entry, _, _)| entry))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// Restore a trashed doc or folder to its original path.
+    ///
+    /// Missing parent folders are recreated. If the original path is taken,
+    /// `policy` decides between failing and restoring under a suffixed name.
+    pub fn restore_from_trash(&self, id: i64, policy: ConflictPolicy) -> CoreResult<TrashRestored> {
+        let (entry, trash_path, payload) = self.find_trash_entry(id)?;
+        let trash_abs = self.trash_root().join(&trash_path);
+
+        let parent = parent_rel_path(&entry.original_path);
+        let original_name = entry
+            .original_path
+            .split('/')
+            .next_back()
+            .unwrap_or(&entry.original_path)
+            .to_string();
+        let taken = |rel: &str| -> CoreResult<bool> {
+            let exists_in_catalog = match entry.kind {
+                TrashKind::Doc => self.find_doc(rel)?.is_some(),
+                TrashKind::Folder => self.find_folder(rel)?.is_some(),
+            };
+            Ok(exists_in_catalog || self.contexts_root.join(rel).exists())
+        };
+        let join_parent = |name: &str| match parent.as_deref() {
+            Some(p) => format!("{p}/{name}"),
+            None => name.to_string(),
+        };
+
+        let mut rel_path = entry.original_path.clone();
+        if taken(&rel_path)? {
+            match policy {
+                ConflictPolicy::Fail => {
+                    return Err(CoreError::Message(format!(
+                        "Cannot restore: \"{rel_path}\" already exists."
+                    )));
+                }
+                ConflictPolicy::Rename => {
+                    let mut n = 1;
+                    loop {
+                        let candidate = join_parent(&suffixed_name(&original_name, n));
+                        if !taken(&candidate)? {
+                            rel_path = candidate;
+                            break;
+                        }
+                        n += 1;
+                    }
+                }
+            }
+        }
+
+        if let Some(parent) = parent.as_deref() {
+            self.ensure_folder_record(parent)?;
+        }
+        let target_abs = self.contained_path(&rel_path)?;
+        if let Some(dir) = target_abs.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::rename(&trash_abs, &target_abs)?;
+
+        let ts = now_iso();
+        let result = self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut folders = payload.folders.clone();
+            // Parents before children
+            folders.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+            for folder in &folders {
+                let folder_rel = join_rel(&rel_path, &folder.rel_path);
+                let name = folder_rel.split('/').next_back().unwrap_or(&folder_rel);
+                tx.execute(
+                    "INSERT INTO folders (parent_id, name, rel_path, abs_path, description, created_at, updated_at)
+                     VALUES (
+                        (SELECT id FROM folders WHERE rel_path = ?1),
+                        ?2, ?3, ?4, ?5, ?6, ?7
+                     )",
+                    params![
+                        parent_rel_path(&folder_rel),
+                        name,
+                        folder_rel,
+                        self.contexts_root.join(&folder_rel).to_string_lossy(),
+                        folder.description,
+                        folder.created_at,
+                        ts
+                    ],
+                )?;
+                let folder_id = tx.last_insert_rowid();
+                for (key, value) in &folder.properties {
+                    set_property(&tx, "folder_properties", "folder_id", folder_id, key, value)?;
+                }
+            }
+            let mut restored = Vec::new();
+            for doc in &payload.docs {
+                let doc_rel = join_rel(&rel_path, &doc.rel_path);
+                let folder_rel = parent_rel_path(&doc_rel).unwrap_or_default();
+                let name = doc_rel.split('/').next_back().unwrap_or(&doc_rel);
+                // Keep the original stable id unless another doc claimed it meanwhile.
+                let in_use: Option<i64> = tx
+                    .query_row(
+                        "SELECT id FROM docs WHERE stable_id = ?1",
+                        [&doc.stable_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let stable_id = if in_use.is_some() {
+                    generate_stable_id(&tx)?
+                } else {
+                    doc.stable_id.clone()
+                };
+                tx.execute(
+                    "INSERT INTO docs (folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at)
+                     VALUES ((SELECT id FROM folders WHERE rel_path = ?1), ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        folder_rel,
+                        name,
+                        doc_rel,
+                        self.contexts_root.join(&doc_rel).to_string_lossy(),
+                        doc.description,
+                        stable_id,
+                        doc.created_at,
+                        ts
+                    ],
+                )?;
+                let doc_id = tx.last_insert_rowid();
+                fulltext::index_doc_file(&tx, doc_id)?;
+                for tag in &doc.tags {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO doc_tags (doc_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                        params![doc_id, tag, ts],
+                    )?;
+                }
+                for (key, value) in &doc.properties {
+                    set_property(&tx, "doc_properties", "doc_id", doc_id, key, value)?;
+                }
+                restored.push(AuditedDoc {
+                    stable_id,
+                    path: join_rel(&entry.original_path, &doc.rel_path),
+                    new_path: Some(doc_rel),
+                });
+            }
+            tx.execute("DELETE FROM trash WHERE id = ?1", params![id])?;
+            tx.commit()?;
+            Ok(restored)
+        });
+        let restored = match result {
+            Ok(restored) => restored,
+            Err(err) => {
+                // Put the files back so the trash entry still points at them.
+                let _ = fs::rename(&target_abs, &trash_abs);
+                return Err(err);
+            }
+        };
+        // Remove the now-empty per-entry trash directory.
+        if let Some(dir) = trash_abs.parent() {
+            let _ = fs::remove_dir(dir);
+        }
+        match (entry.kind, restored.first()) {
+            (TrashKind::Doc, Some(doc)) => self.audit_doc(
+                "restore_from_trash",
+                None,
+                &doc.path,
+                doc.new_path.as_deref(),
+                &doc.stable_id,
+            )?,
+            _ => self.audit_folder(
+                "restore_from_trash",
+                &entry.original_path,
+                Some(&rel_path),
+                &restored,
+            )?,
+        }
+        let restored_docs: Vec<String> = restored
+            .into_iter()
+            .filter_map(|doc| doc.new_path)
+            .collect();
+
+        #[cfg(feature = "search")]
+        {
+            if entry.kind == TrashKind::Folder {
+                self.emit_folder_event(FolderEvent::Created {
+                    rel_path: rel_path.clone(),
+                });
+            }
+            for doc_rel in &restored_docs {
+                self.emit_doc_event(DocEvent::Created {
+                    rel_path: doc_rel.clone(),
+                });
+            }
+        }
+
+        Ok(TrashRestored {
+            id,
+            kind: entry.kind,
+            original_path: entry.original_path,
+            rel_path,
+            restored_docs,
+        })
+    }
+
+    /// Permanently delete trash entries removed before `older_than`
+    /// (an RFC 3339 timestamp or `YYYY-MM-DD` date), or every entry when
+    /// `None`.
+    ///
+    /// Returns the number of purged entries.
+    pub fn purge_trash(&self, older_than: Option<&str>) -> CoreResult<usize> {
+        let older_than = older_than
+            .map(|value| manifest::normalize_since("older_than", value))
+            .transpose()?;
+        let entries: Vec<(TrashEntry, String, TrashPayload)> = self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, kind, original_path, description, payload, deleted_at, trash_path
+                 FROM trash WHERE ?1 IS NULL OR deleted_at < ?1",
+            )?;
+            let rows = stmt
+                .query_map([older_than], row_to_trash)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })?;
+
+        for (entry, trash_path, payload) in &entries {
+            let trash_abs = self.trash_root().join(trash_path);
+            if let Some(dir) = trash_abs.parent() {
+                if dir.exists() {
+                    fs::remove_dir_all(dir)?;
+                }
+            }
+            self.with_conn(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                for doc in &payload.docs {
+                    let live: Option<i64> = tx
+                        .query_row(
+                            "SELECT id FROM docs WHERE stable_id = ?1",
+                            [&doc.stable_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    if live.is_none() {
+                        tx.execute(
+                            "DELETE FROM doc_versions WHERE stable_id = ?1",
+                            [&doc.stable_id],
+                        )?;
+                        tx.execute(
+                            "DELETE FROM doc_links WHERE source_stable_id = ?1",
+                            [&doc.stable_id],
+                        )?;
+                        tx.execute(
+                            "DELETE FROM attachment_refs WHERE source_stable_id = ?1",
+                            [&doc.stable_id],
+                        )?;
+                    }
+                }
+                tx.execute("DELETE FROM trash WHERE id = ?1", params![entry.id])?;
+                tx.commit()?;
+                Ok(())
+            })?;
+            match (entry.kind, payload.docs.first()) {
+                (TrashKind::Doc, Some(doc)) => self.audit_doc(
+                    "purge_trash",
+                    None,
+                    &entry.original_path,
+                    None,
+                    &doc.stable_id,
+                )?,
+                _ => {
+                    let docs: Vec<AuditedDoc> = payload
+                        .docs
+                        .iter()
+                        .map(|doc| AuditedDoc {
+                            stable_id: doc.stable_id.clone(),
+                            path: join_rel(&entry.original_path, &doc.rel_path),
+                            new_path: None,
+                        })
+                        .collect();
+                    self.audit_folder("purge_trash", &entry.original_path, None, &docs)?
+                }
+            }
+        }
+        Ok(entries.len())
+    }
+
+    /// Move a document into the trash and drop its catalog row.
+    pub(crate) fn trash_doc(&self, doc: &Doc) -> CoreResult<()> {
+        if !doc.abs_path.exists() {
+            // Nothing left to preserve on disk.
+            return self.with_conn(|conn| {
+                conn.execute("DELETE FROM docs WHERE id = ?1", params![doc.id])?;
+                Ok(())
+            });
+        }
+        let (tags, properties) =
+            self.with_conn(|conn| Ok((doc_tags(conn, doc.id)?, doc_properties(conn, doc.id)?)))?;
+        let payload = TrashPayload {
+            folders: vec![],
+            docs: vec![TrashedDoc {
+                rel_path: String::new(),
+                description: doc.description.clone(),
+                stable_id: doc.stable_id.clone(),
+                created_at: doc.created_at.clone(),
+                tags,
+                properties,
+            }],
+        };
+        self.move_to_trash(
+            TrashKind::Doc,
+            &doc.rel_path,
+            &doc.name,
+            &doc.abs_path,
+            &doc.description,
+            &payload,
+            |tx| {
+                tx.execute("DELETE FROM docs WHERE id = ?1", params![doc.id])?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Move a folder tree into the trash and drop its catalog rows.
+    pub(crate) fn trash_folder(&self, folder: &Folder) -> CoreResult<()> {
+        let prefix = format!("{}/", folder.rel_path);
+        let payload = self.with_conn(|conn| {
+            let mut payload = TrashPayload::default();
+            let mut stmt = conn.prepare(
+                "SELECT rel_path, description, created_at, id FROM folders WHERE substr(rel_path, 1, length(?1)) = ?1 ORDER BY rel_path",
+            )?;
+            payload.folders.push(TrashedFolder {
+                rel_path: String::new(),
+                description: folder.description.clone(),
+                created_at: folder.created_at.clone(),
+                properties: folder_properties(conn, folder.id)?,
+            });
+            for row in stmt.query_map([&prefix], |row| {
+                Ok((
+                    TrashedFolder {
+                        rel_path: row.get(0)?,
+                        description: row.get(1)?,
+                        created_at: row.get(2)?,
+                        properties: Properties::new(),
+                    },
+                    row.get::<_, i64>(3)?,
+                ))
+            })? {
+                let (mut f, folder_id) = row?;
+                f.rel_path = f.rel_path[prefix.len()..].to_string();
+                f.properties = folder_properties(conn, folder_id)?;
+                payload.folders.push(f);
+            }
+            let mut stmt = conn.prepare(
+                "SELECT rel_path, description, stable_id, created_at, id FROM docs WHERE substr(rel_path, 1, length(?1)) = ?1 ORDER BY rel_path",
+            )?;
+            for row in stmt.query_map([&prefix], |row| {
+                Ok((
+                    TrashedDoc {
+                        rel_path: row.get(0)?,
+                        description: row.get(1)?,
+                        stable_id: row.get(2)?,
+                        created_at: row.get(3)?,
+                        tags: Vec::new(),
+                        properties: Properties::new(),
+                    },
+                    row.get::<_, i64>(4)?,
+                ))
+            })? {
+                let (mut d, doc_id) = row?;
+                d.rel_path = d.rel_path[prefix.len()..].to_string();
+                d.tags = doc_tags(conn, doc_id)?;
+                d.properties = doc_properties(conn, doc_id)?;
+                payload.docs.push(d);
+            }
+            Ok(payload)
+        })?;
+
+        let delete_rows = |tx: &rusqlite::Transaction<'_>| -> CoreResult<()> {
+            tx.execute(
+                "DELETE FROM docs WHERE substr(rel_path, 1, length(?1)) = ?1",
+                params![prefix],
+            )?;
+            tx.execute(
+                "DELETE FROM folders WHERE substr(rel_path, 1, length(?1)) = ?1",
+                params![prefix],
+            )?;
+            tx.execute("DELETE FROM docs WHERE folder_id = ?1", params![folder.id])?;
+            tx.execute("DELETE FROM folders WHERE id = ?1", params![folder.id])?;
+            Ok(())
+        };
+
+        if !folder.abs_path.exists() {
+            return self.with_conn(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                delete_rows(&tx)?;
+                tx.commit()?;
+                Ok(())
+            });
+        }
+        self.move_to_trash(
+            TrashKind::Folder,
+            &folder.rel_path,
+            &folder.name,
+            &folder.abs_path,
+            &folder.description,
+            &payload,
+            delete_rows,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn move_to_trash<F>(
+        &self,
+        kind: TrashKind,
+        rel_path: &str,
+        name: &str,
+        abs_path: &Path,
+        description: &str,
+        payload: &TrashPayload,
+        delete_rows: F,
+    ) -> CoreResult<()>
+    where
+        F: FnOnce(&rusqlite::Transaction<'_>) -> CoreResult<()>,
+    {
+        let payload_json =
+            serde_json::to_string(payload).map_err(|e| CoreError::Message(e.to_string()))?;
+        let bucket = self.with_conn(generate_stable_id)?;
+        let trash_path = format!("{bucket}/{name}");
+        let trash_abs = self.trash_root().join(&trash_path);
+        if let Some(dir) = trash_abs.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::rename(abs_path, &trash_abs)?;
+
+        let ts = now_iso();
+        let result = self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO trash (kind, original_path, trash_path, description, payload, deleted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![kind.as_str(), rel_path, trash_path, description, payload_json, ts],
+            )?;
+            delete_rows(&tx)?;
+            tx.commit()?;
+            Ok(())
+        });
+        if result.is_err() {
+            // Put the files back so disk and catalog stay in sync.
+            let _ = fs::rename(&trash_abs, abs_path);
+            if let Some(dir) = trash_abs.parent() {
+                let _ = fs::remove_dir(dir);
+            }
+        }
+        result
+    }
+
+    fn find_trash_entry(&self, id: i64) -> CoreResult<(TrashEntry, String, TrashPayload)> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT id, kind, original_path, description, payload, deleted_at, trash_path
+                 FROM trash WHERE id = ?1",
+                params![id],
+                row_to_trash,
+            )
+            .optional()?
+            .ok_or_else(|| CoreError::Message(format!("Trash entry {id} not found.")))
+        })
+    }
+
+    fn trash_root(&self) -> PathBuf {
+        self.contexts_root.join(TRASH_DIR)
+    }
+}
+
+fn row_to_trash(row: &rusqlite::Row<'_>) -> rusqlite::Result<(TrashEntry, String, TrashPayload)> {
+    let id: i64 = row.get(0)?;
+    let kind = TrashKind::parse(&row.get::<_, String>(1)?);
+    let original_path: String = row.get(2)?;
+    let payload: TrashPayload = serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default();
+    let entry = TrashEntry {
+        id,
+        kind,
+        original_path,
+        description: row.get(3)?,
+        stable_id: match kind {
+            TrashKind::Doc => payload.docs.first().map(|d| d.stable_id.clone()),
+            TrashKind::Folder => None,
+        },
+        doc_count: payload.docs.len(),
+        deleted_at: row.get(5)?,
+    };
+    Ok((entry, row.get(6)?, payload))
+}
+
+fn join_rel(base: &str, rel: &str) -> String {
+    if rel.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base}/{rel}")
+    }
+}
+
+/// `notes.md` -> `notes-1.md`, `project` -> `project-1`
+pub(crate) fn suffixed_name(name: &str, n: usize) -> String {
+    match name.rfind('.') {
+        Some(idx) if idx > 0 => format!("{}-{}{}", &name[..idx], n, &name[idx..]),
+        _ => format!("{name}-{n}"),
+    }
+}
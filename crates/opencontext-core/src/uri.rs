@@ -0,0 +1,227 @@
+//! `oc://` URIs that cite a doc, one of its sections or a line range.
+//!
+//! `oc://doc/<stable_id>` names a doc. A `#<anchor>` fragment narrows it to
+//! a section, where the anchor is the slugged heading path (`#design/goals`)
+//! or just the slug of the heading (`#goals`). A `?lines=10-40` query
+//! narrows it to lines of the file instead. Stable ids survive renames and
+//! moves, so the URIs do too. The editor's `?path=<rel_path>` hint is only
+//! used when the stable id is missing.
+
+use serde::Serialize;
+
+use crate::{
+    doc_not_found,
+    headings::{self, Section},
+    normalize_doc_path, CoreError, CoreResult, Doc, OpenContext,
+};
+
+const DOC_URI_PREFIX: &str = "oc://doc/";
+
+/// A doc location resolved from an `oc://` URI
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedUri {
+    /// Canonical form of the URI, with the full anchor of the section
+    pub uri: String,
+    pub doc: Doc,
+    /// Anchor of the cited section
+    pub anchor: Option<String>,
+    /// Heading path of the cited section, e.g. "Design > Goals"
+    pub heading_path: Option<String>,
+    /// 1-based first line of the cited content
+    pub start_line: usize,
+    /// 1-based last line of the cited content
+    pub end_line: usize,
+    /// The cited lines, or the whole doc
+    pub content: String,
+}
+
+impl OpenContext {
+    /// Resolve an `oc://doc/<stable_id>[?lines=a-b][#anchor]` URI to the doc
+    /// meta and the cited content.
+    pub fn resolve_uri(&self, uri: &str) -> CoreResult<ResolvedUri> {
+        let parsed = parse_uri(uri)?;
+        let rel_path = match (&parsed.stable_id, &parsed.path) {
+            (Some(stable_id), _) => self.get_doc_by_stable_id(stable_id)?.rel_path,
+            (None, Some(path)) => path.clone(),
+            (None, None) => return Err(invalid_uri(uri, "expected oc://doc/<stable_id>")),
+        };
+        let doc = self.get_doc_meta(&rel_path)?;
+        let stable_id = doc.stable_id.clone();
+        let content = self.read_doc_content(&doc.rel_path)?;
+        let lines: Vec<&str> = content.lines().collect();
+
+        let (start_line, end_line, section) = match (&parsed.anchor, parsed.lines) {
+            (Some(anchor), _) => {
+                let section =
+                    find_section(&headings::sections(&content), anchor).ok_or_else(|| {
+                        CoreError::Message(format!(
+                            "Heading \"#{anchor}\" not found in \"{}\".",
+                            doc.rel_path
+                        ))
+                    })?;
+                (section.start_line, section.end_line, Some(section))
+            }
+            (None, Some((start, end))) => {
+                if start > lines.len() {
+                    return Err(CoreError::Message(format!(
+                        "Line {start} is past the end of \"{}\" ({} lines).",
+                        doc.rel_path,
+                        lines.len()
+                    )));
+                }
+                (start, end.min(lines.len()), None)
+            }
+            (None, None) => (1, lines.len().max(1), None),
+        };
+
+        let uri = match (&section, parsed.lines) {
+            (Some(section), _) => format!("{DOC_URI_PREFIX}{stable_id}#{}", section.anchor),
+            (None, Some(_)) => format!("{DOC_URI_PREFIX}{stable_id}?lines={start_line}-{end_line}"),
+            (None, None) => format!("{DOC_URI_PREFIX}{stable_id}"),
+        };
+        let content = if lines.is_empty() {
+            String::new()
+        } else {
+            lines[start_line - 1..end_line].join("\n")
+        };
+        Ok(ResolvedUri {
+            uri,
+            doc,
+            anchor: section.as_ref().map(|s| s.anchor.clone()),
+            heading_path: section.map(|s| s.heading_path),
+            start_line,
+            end_line,
+            content,
+        })
+    }
+
+    /// `oc://` URI of the doc at `doc_path`, or of its section under
+    /// `heading`: a heading title, heading path ("Design > Goals") or anchor.
+    pub fn make_uri(&self, doc_path: &str, heading: Option<&str>) -> CoreResult<String> {
+        let rel_doc_path = normalize_doc_path(Some(doc_path))?;
+        let doc = self
+            .find_doc(&rel_doc_path)?
+            .ok_or_else(|| doc_not_found(&rel_doc_path))?;
+        let Some(heading) = heading.map(str::trim).filter(|h| !h.is_empty()) else {
+            return Ok(format!("{DOC_URI_PREFIX}{}", doc.stable_id));
+        };
+        let content = self.read_doc_content(&doc.rel_path)?;
+        let sections = headings::sections(&content);
+        let section = sections
+            .iter()
+            .find(|s| s.heading_path == heading || s.title == heading)
+            .cloned()
+            .or_else(|| find_section(&sections, heading.trim_start_matches('#')))
+            .ok_or_else(|| {
+                CoreError::Message(format!(
+                    "Heading \"{heading}\" not found in \"{rel_doc_path}\"."
+                ))
+            })?;
+        Ok(format!(
+            "{DOC_URI_PREFIX}{}#{}",
+            doc.stable_id, section.anchor
+        ))
+    }
+}
+
+struct ParsedUri {
+    stable_id: Option<String>,
+    path: Option<String>,
+    anchor: Option<String>,
+    lines: Option<(usize, usize)>,
+}
+
+fn parse_uri(uri: &str) -> CoreResult<ParsedUri> {
+    let uri = uri.trim();
+    let rest = uri
+        .get(..DOC_URI_PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(DOC_URI_PREFIX))
+        .map(|_| &uri[DOC_URI_PREFIX.len()..])
+        .ok_or_else(|| invalid_uri(uri, "expected oc://doc/<stable_id>"))?;
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (stable_id, query) = match rest.split_once('?') {
+        Some((id, query)) => (id, Some(query)),
+        None => (rest, None),
+    };
+    let stable_id = stable_id.trim_matches('/').trim();
+    if stable_id.contains('/') {
+        return Err(invalid_uri(uri, "expected oc://doc/<stable_id>"));
+    }
+
+    let mut lines = None;
+    let mut path = None;
+    for pair in query.into_iter().flat_map(|q| q.split('&')) {
+        match pair.split_once('=') {
+            Some(("lines", range)) => lines = Some(parse_line_range(uri, range)?),
+            Some(("path", value)) => {
+                let value = urlencoding::decode(value)
+                    .map_err(|_| invalid_uri(uri, "path is not valid UTF-8"))?;
+                path = Some(normalize_doc_path(Some(&value))?);
+            }
+            _ if pair.is_empty() => {}
+            _ => return Err(invalid_uri(uri, &format!("unknown parameter \"{pair}\""))),
+        }
+    }
+    let anchor = match fragment {
+        Some(fragment) => {
+            let decoded = urlencoding::decode(fragment)
+                .map_err(|_| invalid_uri(uri, "anchor is not valid UTF-8"))?;
+            Some(decoded.trim().to_string()).filter(|a| !a.is_empty())
+        }
+        None => None,
+    };
+    if anchor.is_some() && lines.is_some() {
+        return Err(invalid_uri(
+            uri,
+            "use either a #heading or ?lines, not both",
+        ));
+    }
+    if stable_id.is_empty() && path.is_none() {
+        return Err(invalid_uri(uri, "expected oc://doc/<stable_id>"));
+    }
+    Ok(ParsedUri {
+        stable_id: (!stable_id.is_empty()).then(|| stable_id.to_string()),
+        path,
+        anchor,
+        lines,
+    })
+}
+
+/// `a-b` or a single line `a`, 1-based and inclusive
+fn parse_line_range(uri: &str, range: &str) -> CoreResult<(usize, usize)> {
+    let parse = |n: &str| n.trim().parse::<usize>().ok().filter(|n| *n > 0);
+    let parsed = match range.split_once('-') {
+        Some((start, end)) => parse(start).zip(parse(end)),
+        None => parse(range).map(|n| (n, n)),
+    };
+    match parsed {
+        Some((start, end)) if start <= end => Ok((start, end)),
+        _ => Err(invalid_uri(
+            uri,
+            "lines must be a range like 10-40 of positive line numbers",
+        )),
+    }
+}
+
+/// The section with this full anchor, or else the first whose last heading
+/// has this slug.
+fn find_section(sections: &[Section], anchor: &str) -> Option<Section> {
+    let anchor = anchor.trim().to_lowercase();
+    sections
+        .iter()
+        .find(|s| s.anchor == anchor)
+        .or_else(|| {
+            let slug = headings::slugify(anchor.rsplit('/').next().unwrap_or_default());
+            sections
+                .iter()
+                .find(|s| s.anchor.rsplit('/').next() == Some(slug.as_str()))
+        })
+        .cloned()
+}
+
+fn invalid_uri(uri: &str, reason: &str) -> CoreError {
+    CoreError::Message(format!("Invalid oc:// URI \"{uri}\": {reason}."))
+}
@@ -0,0 +1,461 @@
+//! Filesystem watcher for edits made outside OpenContext.
+//!
+//! `OpenContext` mutation methods emit events themselves, but a file changed
+//! in an editor, by `git pull` or by a script does not. `FsWatcher` watches
+//! `contexts_root`, debounces the raw notifications, brings the catalog up
+//! to date and emits the matching `DocEvent`/`FolderEvent`s, so
+//! `IndexSyncService` keeps the search index fresh.
+//!
+//! Self-writes need no bookkeeping: a change is only applied where the
+//! catalog disagrees with the disk, and OpenContext updates the catalog as
+//! part of its own writes. A doc whose content matches its latest recorded
+//! version is left alone.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Event as NotifyEvent, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use rusqlite::{params, OptionalExtension};
+
+use crate::{
+    events::{DocEvent, FolderEvent},
+    fulltext, history, now_iso, parent_rel_path, parse_frontmatter, CoreError, CoreResult, Doc,
+    OpenContext, ReconcileOptions, SaveDocOptions,
+};
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+/// How often the worker checks whether it was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options for `FsWatcher::start_with`
+#[derive(Debug, Clone)]
+pub struct WatcherOptions {
+    /// Quiet period after the last notification before a batch is applied
+    pub debounce: Duration,
+}
+
+impl Default for WatcherOptions {
+    fn default() -> Self {
+        Self {
+            debounce: DEFAULT_DEBOUNCE,
+        }
+    }
+}
+
+/// Watches `contexts_root` until stopped or dropped.
+pub struct FsWatcher {
+    watcher: Option<RecommendedWatcher>,
+    worker: Option<JoinHandle<()>>,
+    running: Arc<AtomicBool>,
+}
+
+impl FsWatcher {
+    /// Start watching the contexts root of `ctx` with default options.
+    pub fn start(ctx: Arc<OpenContext>) -> CoreResult<Self> {
+        Self::start_with(ctx, WatcherOptions::default())
+    }
+
+    pub fn start_with(ctx: Arc<OpenContext>, options: WatcherOptions) -> CoreResult<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        watcher
+            .watch(&ctx.contexts_root, RecursiveMode::Recursive)
+            .map_err(watch_error)?;
+        let running = Arc::new(AtomicBool::new(true));
+        let worker_running = running.clone();
+        let worker = thread::Builder::new()
+            .name("opencontext-watcher".into())
+            .spawn(move || run(ctx, receiver, options.debounce, worker_running))?;
+        Ok(Self {
+            watcher: Some(watcher),
+            worker: Some(worker),
+            running,
+        })
+    }
+
+    /// Stop watching. Changes still waiting for their debounce are dropped.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.watcher.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for FsWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Relative paths changed in one debounced batch
+#[derive(Debug, Default)]
+pub(crate) struct FsChanges {
+    /// `(from, to)` pairs reported as a single rename
+    pub(crate) renames: Vec<(String, String)>,
+    /// Paths created, modified or removed
+    pub(crate) touched: Vec<String>,
+}
+
+fn run(
+    ctx: Arc<OpenContext>,
+    receiver: Receiver<notify::Result<NotifyEvent>>,
+    debounce: Duration,
+    running: Arc<AtomicBool>,
+) {
+    let root = ctx.contexts_root.clone();
+    let canonical_root = fs::canonicalize(&root).ok();
+    let mut batch = Vec::new();
+    let mut deadline: Option<Instant> = None;
+    while running.load(Ordering::SeqCst) {
+        let timeout = deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+            .unwrap_or(POLL_INTERVAL)
+            .min(POLL_INTERVAL);
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                batch.push(event);
+                deadline = Some(Instant::now() + debounce);
+            }
+            Ok(Err(e)) => log::warn!("[Watcher] {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            deadline = None;
+            let changes = collect_changes(&root, canonical_root.as_deref(), batch.drain(..));
+            if let Err(e) = ctx.apply_fs_changes(&changes) {
+                log::warn!("[Watcher] Failed to apply changes: {}", e);
+            }
+        }
+    }
+}
+
+fn collect_changes(
+    root: &Path,
+    canonical_root: Option<&Path>,
+    events: impl IntoIterator<Item = NotifyEvent>,
+) -> FsChanges {
+    let rel = |path: &PathBuf| {
+        relative_path(root, path)
+            .or_else(|| canonical_root.and_then(|r| relative_path(r, path)))
+            .filter(|p| is_watched_path(p))
+    };
+    let mut changes = FsChanges::default();
+    for event in events {
+        match event.kind {
+            EventKind::Access(_) => {}
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                match (rel(&event.paths[0]), rel(&event.paths[1])) {
+                    (Some(from), Some(to)) => changes.renames.push((from, to)),
+                    (Some(path), None) | (None, Some(path)) => changes.touched.push(path),
+                    (None, None) => {}
+                }
+            }
+            _ => changes.touched.extend(event.paths.iter().filter_map(&rel)),
+        }
+    }
+    // Rename halves are reported on their own as well; the pair covers them.
+    let renamed: HashSet<String> = changes
+        .renames
+        .iter()
+        .flat_map(|(from, to)| [from.clone(), to.clone()])
+        .collect();
+    let mut seen = HashSet::new();
+    changes
+        .touched
+        .retain(|p| !renamed.contains(p) && seen.insert(p.clone()));
+    changes
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Hidden entries are not part of the catalog, except for the `.ideas` tree.
+/// This also skips the temp files of atomic writes.
+fn is_watched_path(rel_path: &str) -> bool {
+    rel_path
+        .split('/')
+        .enumerate()
+        .all(|(i, part)| !part.starts_with('.') || (i == 0 && part == crate::IDEAS_ROOT))
+}
+
+/// Docs are `.md` files inside a folder; loose files in the root are ignored.
+fn is_doc_path(rel_path: &str) -> bool {
+    rel_path.ends_with(".md") && rel_path.contains('/')
+}
+
+impl OpenContext {
+    /// Bring the catalog in line with a batch of filesystem changes and
+    /// emit events for everything that was out of date.
+    pub(crate) fn apply_fs_changes(&self, changes: &FsChanges) -> CoreResult<()> {
+        let mut touched = Vec::new();
+        for (from, to) in &changes.renames {
+            if !self.apply_external_rename(from, to)? {
+                touched.push(from.clone());
+                touched.push(to.clone());
+            } else if !self.contexts_root.join(to).exists() {
+                // Renamed again or deleted later in the same batch
+                touched.push(to.clone());
+            }
+        }
+        touched.extend(changes.touched.iter().cloned());
+
+        let mut needs_reconcile = false;
+        for rel_path in &touched {
+            let abs_path = self.contexts_root.join(rel_path);
+            match fs::metadata(&abs_path) {
+                Ok(meta) if meta.is_dir() => {
+                    needs_reconcile |= self.find_folder(rel_path)?.is_none();
+                }
+                Ok(meta) if meta.is_file() && is_doc_path(rel_path) => {
+                    match self.find_doc(rel_path)? {
+                        Some(doc) => self.apply_external_edit(&doc)?,
+                        None => needs_reconcile = true,
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => self.apply_external_removal(rel_path)?,
+            }
+        }
+        // New folders can arrive with content already inside (a copy, a
+        // checkout), so registration goes through a full reconcile.
+        if needs_reconcile {
+            self.reconcile(ReconcileOptions::default())?;
+        }
+        Ok(())
+    }
+
+    /// Record an edited doc. Returns early when the content matches the
+    /// latest recorded version, which covers OpenContext's own saves.
+    fn apply_external_edit(&self, doc: &Doc) -> CoreResult<()> {
+        // A doc swapped for a symlink out of contexts_root is not read
+        if self.ensure_contained(&doc.abs_path).is_err() {
+            return Ok(());
+        }
+        let Ok(content) = fs::read_to_string(&doc.abs_path) else {
+            return Ok(());
+        };
+        let hash = history::content_hash(&content);
+        let latest_hash: Option<String> = self.with_conn(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT content_hash FROM doc_versions WHERE stable_id = ?1 ORDER BY id DESC LIMIT 1",
+                    [&doc.stable_id],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })?;
+        let unchanged = match latest_hash {
+            Some(latest) => latest == hash,
+            None => content.is_empty(),
+        };
+        if unchanged {
+            return Ok(());
+        }
+
+        self.record_doc_version(
+            doc,
+            None,
+            &content,
+            &SaveDocOptions {
+                source: Some(history::EXTERNAL_SOURCE.into()),
+                ..Default::default()
+            },
+        )?;
+        self.index_doc_links(doc, &content)?;
+        let description = if self.frontmatter_sync {
+            parse_frontmatter(&content).and_then(|fields| {
+                fields
+                    .get(crate::frontmatter::DESCRIPTION_KEY)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            })
+        } else {
+            None
+        };
+        let ts = now_iso();
+        self.with_conn(|conn| {
+            match description.as_deref() {
+                Some(desc) => conn.execute(
+                    "UPDATE docs SET description = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![desc, ts, doc.id],
+                )?,
+                None => conn.execute(
+                    "UPDATE docs SET updated_at = ?1 WHERE id = ?2",
+                    params![ts, doc.id],
+                )?,
+            };
+            fulltext::index_doc_text(conn, doc.id, &content)?;
+            Ok(())
+        })?;
+        self.emit_doc_event(DocEvent::Updated {
+            rel_path: doc.rel_path.clone(),
+        });
+        Ok(())
+    }
+
+    /// Drop the catalog row of a doc or folder that is gone from disk.
+    fn apply_external_removal(&self, rel_path: &str) -> CoreResult<()> {
+        if let Some(doc) = self.find_doc(rel_path)? {
+            self.with_conn(|conn| {
+                conn.execute("DELETE FROM docs WHERE id = ?1", [doc.id])?;
+                Ok(())
+            })?;
+            self.emit_doc_event(DocEvent::Deleted {
+                rel_path: doc.rel_path,
+            });
+            return Ok(());
+        }
+        let Some(folder) = self.find_folder(rel_path)? else {
+            return Ok(());
+        };
+        if folder.rel_path.is_empty() {
+            return Ok(());
+        }
+        let removed_docs = self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let prefix = format!("{}/", folder.rel_path);
+            let mut stmt =
+                tx.prepare("SELECT rel_path FROM docs WHERE substr(rel_path, 1, length(?1)) = ?1")?;
+            let docs = stmt
+                .query_map([prefix], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            drop(stmt);
+            // Subfolders and their docs go with it through ON DELETE CASCADE,
+            // and the docs_fts_delete trigger drops their index rows.
+            tx.execute("DELETE FROM folders WHERE id = ?1", [folder.id])?;
+            tx.commit()?;
+            Ok(docs)
+        })?;
+        self.emit_folder_event(FolderEvent::Deleted {
+            rel_path: folder.rel_path,
+            removed_docs,
+        });
+        Ok(())
+    }
+
+    /// Follow a rename of a tracked doc or folder. Returns `false` when the
+    /// pair is not one (a self-rename, a temp file, an untracked target);
+    /// both paths are then handled as plain changes.
+    fn apply_external_rename(&self, from: &str, to: &str) -> CoreResult<bool> {
+        let to_abs = self.contexts_root.join(to);
+        let new_parent = parent_rel_path(to).unwrap_or_default();
+        let parent = self.find_folder(&new_parent)?;
+        if parent.is_none() && !new_parent.is_empty() {
+            return Ok(false);
+        }
+        let new_name = to.rsplit('/').next().unwrap_or(to);
+        let same_parent = parent_rel_path(from).unwrap_or_default() == new_parent;
+        let ts = now_iso();
+
+        if !to_abs.is_dir() && is_doc_path(to) && self.find_doc(to)?.is_none() {
+            let (Some(doc), Some(parent)) = (self.find_doc(from)?, parent) else {
+                return Ok(false);
+            };
+            self.with_conn(|conn| {
+                conn.execute(
+                    "UPDATE docs SET folder_id = ?1, name = ?2, rel_path = ?3, abs_path = ?4, updated_at = ?5
+                     WHERE id = ?6",
+                    params![parent.id, new_name, to, to_abs.to_string_lossy(), ts, doc.id],
+                )?;
+                fulltext::index_doc_fields(conn, doc.id)?;
+                Ok(())
+            })?;
+            let (old_path, new_path) = (from.to_string(), to.to_string());
+            self.emit_doc_event(if same_parent {
+                DocEvent::Renamed { old_path, new_path }
+            } else {
+                DocEvent::Moved { old_path, new_path }
+            });
+            return Ok(true);
+        }
+
+        if !to_abs.is_file() && self.find_folder(to)?.is_none() {
+            let Some(folder) = self.find_folder(from)?.filter(|f| !f.rel_path.is_empty()) else {
+                return Ok(false);
+            };
+            let affected_docs = self.with_conn(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                tx.execute(
+                    "UPDATE folders SET parent_id = (SELECT id FROM folders WHERE rel_path = ?1),
+                        name = ?2, rel_path = ?3, abs_path = ?4, updated_at = ?5
+                     WHERE id = ?6",
+                    params![new_parent, new_name, to, to_abs.to_string_lossy(), ts, folder.id],
+                )?;
+                let prefix = format!("{from}/");
+                let mut affected_docs = Vec::new();
+                for table in ["folders", "docs"] {
+                    let mut stmt = tx.prepare(&format!(
+                        "SELECT id, rel_path FROM {table} WHERE substr(rel_path, 1, length(?1)) = ?1"
+                    ))?;
+                    let rows = stmt
+                        .query_map([&prefix], |row| {
+                            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                        })?
+                        .collect::<Result<Vec<_>, _>>()?;
+                    for (id, old_rel) in rows {
+                        let new_rel = format!("{to}/{}", &old_rel[from.len() + 1..]);
+                        let new_abs = self.contexts_root.join(&new_rel);
+                        tx.execute(
+                            &format!(
+                                "UPDATE {table} SET rel_path = ?1, abs_path = ?2, updated_at = ?3 WHERE id = ?4"
+                            ),
+                            params![new_rel, new_abs.to_string_lossy(), ts, id],
+                        )?;
+                        if table == "docs" {
+                            affected_docs.push((old_rel, new_rel));
+                        }
+                    }
+                }
+                tx.commit()?;
+                Ok(affected_docs)
+            })?;
+            let (old_path, new_path) = (from.to_string(), to.to_string());
+            self.emit_folder_event(if same_parent {
+                FolderEvent::Renamed {
+                    old_path,
+                    new_path,
+                    affected_docs,
+                }
+            } else {
+                FolderEvent::Moved {
+                    old_path,
+                    new_path,
+                    affected_docs,
+                }
+            });
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+fn watch_error(err: notify::Error) -> CoreError {
+    CoreError::Message(format!("Failed to watch contexts root: {err}"))
+}
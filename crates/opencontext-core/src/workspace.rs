@@ -0,0 +1,259 @@
+//! Named workspaces inside one OpenContext installation.
+//!
+//! The `default` workspace is the original layout directly under the base
+//! root (`~/.opencontext` or `OPENCONTEXT_ROOT`). Every other workspace
+//! lives in `<base_root>/workspaces/<name>/` with its own `contexts/`,
+//! `opencontext.db`, `lancedb/` and index metadata. A workspace may carry
+//! its own `config.json`/`config.toml`; without one it uses the base
+//! root's search config.
+//!
+//! The active workspace is `OPENCONTEXT_WORKSPACE` when set, otherwise the
+//! one recorded in `<base_root>/workspaces.json` by `switch`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    atomic, default_base_root, env_path, CoreError, CoreResult, EnvOverrides, OpenContext,
+};
+
+/// Name of the workspace that uses the base root directly
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+const WORKSPACES_DIR: &str = "workspaces";
+const STATE_FILE: &str = "workspaces.json";
+
+/// A workspace and where its data lives
+#[derive(Debug, Clone, Serialize)]
+pub struct Workspace {
+    pub name: String,
+    /// Directory holding the workspace's contexts, database and indexes
+    pub root: PathBuf,
+    pub active: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspaceState {
+    active: Option<String>,
+}
+
+/// Lists, creates, switches and deletes workspaces under one base root
+#[derive(Debug, Clone)]
+pub struct WorkspaceManager {
+    base_root: PathBuf,
+}
+
+impl WorkspaceManager {
+    /// Manage workspaces under `base_root`, or the default base root
+    /// (`OPENCONTEXT_ROOT`, then `~/.opencontext`) when `None`.
+    pub fn new(base_root: Option<PathBuf>) -> CoreResult<Self> {
+        let base_root = base_root
+            .or_else(|| env_path("OPENCONTEXT_ROOT"))
+            .or_else(default_base_root)
+            .ok_or_else(|| CoreError::Message("Unable to resolve user home directory".into()))?;
+        Ok(Self { base_root })
+    }
+
+    pub fn base_root(&self) -> &Path {
+        &self.base_root
+    }
+
+    /// Name of the active workspace
+    pub fn active(&self) -> CoreResult<String> {
+        if let Ok(name) = env::var("OPENCONTEXT_WORKSPACE") {
+            if !name.trim().is_empty() {
+                return Ok(name.trim().to_string());
+            }
+        }
+        let state = self.read_state()?;
+        Ok(state
+            .active
+            .filter(|name| name == DEFAULT_WORKSPACE || self.workspace_root(name).is_dir())
+            .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string()))
+    }
+
+    /// The default workspace followed by the named ones, sorted by name
+    pub fn list(&self) -> CoreResult<Vec<Workspace>> {
+        let active = self.active()?;
+        let mut names = Vec::new();
+        let dir = self.base_root.join(WORKSPACES_DIR);
+        if dir.is_dir() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if entry.file_type()?.is_dir() && validate_name(&name).is_ok() {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        names.insert(0, DEFAULT_WORKSPACE.to_string());
+        Ok(names
+            .into_iter()
+            .map(|name| Workspace {
+                root: self.workspace_root(&name),
+                active: name == active,
+                name,
+            })
+            .collect())
+    }
+
+    pub fn get(&self, name: &str) -> CoreResult<Workspace> {
+        validate_name(name)?;
+        let root = self.workspace_root(name);
+        if name != DEFAULT_WORKSPACE && !root.is_dir() {
+            return Err(workspace_not_found(name));
+        }
+        Ok(Workspace {
+            active: self.active()? == name,
+            name: name.to_string(),
+            root,
+        })
+    }
+
+    /// Create an empty workspace
+    pub fn create(&self, name: &str) -> CoreResult<Workspace> {
+        validate_name(name)?;
+        let root = self.workspace_root(name);
+        if name == DEFAULT_WORKSPACE || root.exists() {
+            return Err(CoreError::Message(format!(
+                "Workspace \"{name}\" already exists."
+            )));
+        }
+        fs::create_dir_all(root.join("contexts"))?;
+        self.get(name)
+    }
+
+    /// Make `name` the active workspace for future sessions
+    pub fn switch(&self, name: &str) -> CoreResult<Workspace> {
+        self.get(name)?;
+        self.write_state(&WorkspaceState {
+            active: Some(name.to_string()),
+        })?;
+        self.get(name)
+    }
+
+    /// Delete a workspace and everything in it. The default workspace and
+    /// the active workspace cannot be deleted.
+    pub fn delete(&self, name: &str) -> CoreResult<()> {
+        let workspace = self.get(name)?;
+        if name == DEFAULT_WORKSPACE {
+            return Err(CoreError::Message(
+                "The default workspace cannot be deleted.".into(),
+            ));
+        }
+        if workspace.active {
+            return Err(CoreError::Message(format!(
+                "Workspace \"{name}\" is active. Switch to another workspace first."
+            )));
+        }
+        fs::remove_dir_all(&workspace.root)?;
+        Ok(())
+    }
+
+    /// Overrides that make `OpenContext::initialize` open `name`
+    pub fn overrides(&self, name: &str) -> CoreResult<EnvOverrides> {
+        let workspace = self.get(name)?;
+        if name == DEFAULT_WORKSPACE {
+            // Keep honoring OPENCONTEXT_CONTEXTS_ROOT / OPENCONTEXT_DB_PATH.
+            return Ok(EnvOverrides {
+                base_root: Some(workspace.root),
+                ..Default::default()
+            });
+        }
+        Ok(EnvOverrides {
+            contexts_root: Some(workspace.root.join("contexts")),
+            db_path: Some(workspace.root.join("opencontext.db")),
+            base_root: Some(workspace.root),
+        })
+    }
+
+    /// Open the catalog of `name`
+    pub fn open(&self, name: &str) -> CoreResult<OpenContext> {
+        OpenContext::initialize(self.overrides(name)?)
+    }
+
+    /// Search config for `name`: the workspace's own config file if it has
+    /// one, else the base root's, with index paths inside the workspace.
+    #[cfg(feature = "search")]
+    pub fn search_config(
+        &self,
+        name: &str,
+    ) -> crate::search::SearchResult<crate::search::SearchConfig> {
+        use crate::search::{SearchConfig, SearchError};
+
+        let workspace = self
+            .get(name)
+            .map_err(|e| SearchError::Config(e.to_string()))?;
+        if name == DEFAULT_WORKSPACE {
+            return SearchConfig::load_from(&workspace.root);
+        }
+        let own_config = workspace.root.join("config.json").exists()
+            || workspace.root.join("config.toml").exists();
+        let mut config = if own_config {
+            SearchConfig::load_from(&workspace.root)?
+        } else {
+            let mut config = SearchConfig::load_from(&self.base_root)?;
+            config.paths = Default::default();
+            config
+        };
+        config
+            .paths
+            .lancedb_path
+            .get_or_insert_with(|| workspace.root.join("lancedb"));
+        config
+            .paths
+            .index_metadata_path
+            .get_or_insert_with(|| workspace.root.join("index-metadata.json"));
+        Ok(config)
+    }
+
+    fn workspace_root(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_WORKSPACE {
+            self.base_root.clone()
+        } else {
+            self.base_root.join(WORKSPACES_DIR).join(name)
+        }
+    }
+
+    fn read_state(&self) -> CoreResult<WorkspaceState> {
+        let path = self.base_root.join(STATE_FILE);
+        if !path.exists() {
+            return Ok(WorkspaceState::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content).unwrap_or_default())
+    }
+
+    fn write_state(&self, state: &WorkspaceState) -> CoreResult<()> {
+        fs::create_dir_all(&self.base_root)?;
+        let json =
+            serde_json::to_string_pretty(state).map_err(|e| CoreError::Message(e.to_string()))?;
+        atomic::write_atomic(&self.base_root.join(STATE_FILE), &json)
+    }
+}
+
+/// Workspace names are lowercase letters, digits, `-` and `_`, up to 64
+/// characters, so they are safe as directory names everywhere.
+fn validate_name(name: &str) -> CoreResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::Message(format!(
+            "Invalid workspace name \"{name}\". Use lowercase letters, digits, \"-\" and \"_\"."
+        )))
+    }
+}
+
+fn workspace_not_found(name: &str) -> CoreError {
+    CoreError::Message(format!("Workspace \"{name}\" does not exist."))
+}
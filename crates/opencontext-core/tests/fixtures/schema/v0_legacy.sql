@@ -0,0 +1,29 @@
+-- Catalog written by the original Node implementation, before stable ids.
+-- Databases of this age were created without PRAGMA user_version, so it is 0.
+
+CREATE TABLE IF NOT EXISTS folders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    parent_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    rel_path TEXT NOT NULL UNIQUE,
+    abs_path TEXT NOT NULL,
+    description TEXT DEFAULT '',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS docs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    rel_path TEXT NOT NULL UNIQUE,
+    abs_path TEXT NOT NULL,
+    description TEXT DEFAULT '',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+INSERT INTO folders (id, parent_id, name, rel_path, abs_path, description, created_at, updated_at)
+    VALUES (1, NULL, 'notes', 'notes', '/legacy/contexts/notes', 'Team notes', '2024-01-02T03:04:05.000Z', '2024-01-02T03:04:05.000Z');
+INSERT INTO docs (id, folder_id, name, rel_path, abs_path, description, created_at, updated_at)
+    VALUES (1, 1, 'a.md', 'notes/a.md', '/legacy/contexts/notes/a.md', 'First doc', '2024-01-02T03:04:05.000Z', '2024-01-02T03:04:05.000Z');
@@ -0,0 +1,146 @@
+-- Schema up to the `doc_pins` table and the `doc_access` source index, with sample rows.
+-- Written by a versioned build, so user_version is 10.
+
+CREATE TABLE IF NOT EXISTS folders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    parent_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    rel_path TEXT NOT NULL UNIQUE,
+    abs_path TEXT NOT NULL,
+    description TEXT DEFAULT '',
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS docs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    rel_path TEXT NOT NULL UNIQUE,
+    abs_path TEXT NOT NULL,
+    description TEXT DEFAULT '',
+    stable_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_docs_stable_id ON docs(stable_id);
+
+CREATE TABLE IF NOT EXISTS doc_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stable_id TEXT NOT NULL,
+    rel_path TEXT NOT NULL,
+    content TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    author TEXT,
+    source TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_doc_versions_stable_id ON doc_versions(stable_id, id);
+
+CREATE TABLE IF NOT EXISTS trash (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    original_path TEXT NOT NULL,
+    trash_path TEXT NOT NULL,
+    description TEXT DEFAULT '',
+    payload TEXT NOT NULL,
+    deleted_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS doc_tags (
+    doc_id INTEGER NOT NULL REFERENCES docs(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (doc_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_doc_tags_tag ON doc_tags(tag);
+
+CREATE TABLE IF NOT EXISTS doc_links (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_stable_id TEXT NOT NULL,
+    target_stable_id TEXT,
+    target_path TEXT,
+    kind TEXT NOT NULL,
+    raw TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_doc_links_source ON doc_links(source_stable_id);
+CREATE INDEX IF NOT EXISTS idx_doc_links_target ON doc_links(target_stable_id);
+CREATE INDEX IF NOT EXISTS idx_doc_links_target_path ON doc_links(target_path);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS docs_fts USING fts5(
+    name,
+    description,
+    content,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS docs_fts_delete AFTER DELETE ON docs BEGIN
+    DELETE FROM docs_fts WHERE rowid = old.id;
+END;
+
+CREATE TABLE IF NOT EXISTS doc_access (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    doc_id INTEGER NOT NULL REFERENCES docs(id) ON DELETE CASCADE,
+    source TEXT,
+    accessed_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_doc_access_doc ON doc_access(doc_id);
+
+CREATE TABLE IF NOT EXISTS folder_templates (
+    folder_id INTEGER PRIMARY KEY REFERENCES folders(id) ON DELETE CASCADE,
+    template TEXT NOT NULL
+);
+
+CREATE TABLE doc_properties (
+    doc_id INTEGER NOT NULL REFERENCES docs(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value_type TEXT NOT NULL,
+    value,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (doc_id, key)
+);
+
+CREATE INDEX idx_doc_properties_key ON doc_properties(key, value);
+
+CREATE TABLE folder_properties (
+    folder_id INTEGER NOT NULL REFERENCES folders(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value_type TEXT NOT NULL,
+    value,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (folder_id, key)
+);
+
+CREATE TABLE doc_pins (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    doc_id INTEGER NOT NULL UNIQUE REFERENCES docs(id) ON DELETE CASCADE,
+    pinned_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_doc_access_source ON doc_access(source, accessed_at);
+
+INSERT INTO folders (id, parent_id, name, rel_path, abs_path, description, created_at, updated_at)
+    VALUES (1, NULL, 'notes', 'notes', '/legacy/contexts/notes', 'Team notes', '2024-01-02T03:04:05.000Z', '2024-01-02T03:04:05.000Z');
+INSERT INTO docs (id, folder_id, name, rel_path, abs_path, description, stable_id, created_at, updated_at)
+    VALUES (1, 1, 'a.md', 'notes/a.md', '/legacy/contexts/notes/a.md', 'First doc', '6f1c0e9a-4b7d-4c2e-9a51-0d3f2b8e7c11', '2024-01-02T03:04:05.000Z', '2024-01-02T03:04:05.000Z');
+INSERT INTO doc_versions (stable_id, rel_path, content, content_hash, author, source, created_at)
+    VALUES ('6f1c0e9a-4b7d-4c2e-9a51-0d3f2b8e7c11', 'notes/a.md', 'old body', 'hash', NULL, 'user', '2024-01-02T03:04:05.000Z');
+INSERT INTO trash (kind, original_path, trash_path, description, payload, deleted_at)
+    VALUES ('doc', 'notes/gone.md', '.trash/1/gone.md', '', '{}', '2024-01-02T03:04:05.000Z');
+INSERT INTO doc_tags (doc_id, tag, created_at) VALUES (1, 'rust', '2024-01-02T03:04:05.000Z');
+INSERT INTO doc_links (source_stable_id, target_stable_id, target_path, kind, raw)
+    VALUES ('6f1c0e9a-4b7d-4c2e-9a51-0d3f2b8e7c11', NULL, 'notes/missing.md', 'markdown', '[x](missing.md)');
+INSERT INTO doc_access (doc_id, source, accessed_at) VALUES (1, 'cli', '2024-01-02T03:04:05.000Z');
+INSERT INTO folder_templates (folder_id, template) VALUES (1, 'meeting');
+INSERT INTO doc_properties (doc_id, key, value_type, value, updated_at)
+    VALUES (1, 'status', 'string', 'draft', '2024-01-02T03:04:05.000Z');
+INSERT INTO folder_properties (folder_id, key, value_type, value, updated_at)
+    VALUES (1, 'owner', 'string', 'platform', '2024-01-02T03:04:05.000Z');
+INSERT INTO doc_pins (doc_id, pinned_at) VALUES (1, '2024-01-02T03:04:05.000Z');
+
+PRAGMA user_version = 10;
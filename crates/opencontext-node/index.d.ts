@@ -6,18 +6,39 @@
 export interface FolderOptions {
   path: string
   description?: string
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface RenameFolderOptions {
   path: string
   newName: string
+  /** Update path-based links in referring docs */
+  rewriteLinks?: boolean
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface MoveFolderOptions {
   path: string
   destFolderPath: string
+  /** Update path-based links in referring docs */
+  rewriteLinks?: boolean
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
+}
+export interface CopyFolderOptions {
+  path: string
+  /** Parent folder of the copy ("" for the top level) */
+  destFolderPath: string
+  /** "fail" (default) or "rename" */
+  conflictPolicy?: string
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface RemoveFolderOptions {
   path: string
   force?: boolean
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface ListFolderOptions {
   all?: boolean
@@ -25,53 +46,296 @@ export interface ListFolderOptions {
 export interface ListDocsOptions {
   folderPath: string
   recursive?: boolean
+  /** `{ key, op?, value? }` filters, all of which must match */
+  propertyFilters?: Array<any>
+  /** `{ key, descending? }` */
+  sortByProperty?: any
 }
 export interface CreateDocOptions {
   folderPath: string
   name: string
   description?: string
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface MoveDocOptions {
   docPath: string
   destFolderPath: string
+  /** Update path-based links in referring docs */
+  rewriteLinks?: boolean
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface RenameDocOptions {
   docPath: string
   newName: string
+  /** Update path-based links in referring docs */
+  rewriteLinks?: boolean
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
+}
+export interface CopyDocOptions {
+  docPath: string
+  destFolderPath: string
+  /** Name of the copy (defaults to the original name) */
+  newName?: string
+  /** "fail" (default) or "rename" */
+  conflictPolicy?: string
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface RemoveDocOptions {
   docPath: string
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface SetDescriptionOptions {
   docPath: string
   description: string
+  /** Client recorded in the audit log (desktop, cli, mcp, an agent name) */
+  source?: string
 }
 export interface SaveDocOptions {
   docPath: string
   content: string
   description?: string
+  author?: string
+  source?: string
+  /** Revision (content hash or `updatedAt`) the caller last read */
+  expectedRevision?: string
+}
+export interface DocVersionOptions {
+  stableId: string
+  versionId: number
+}
+export interface DiffDocVersionsOptions {
+  stableId: string
+  fromVersion: number
+  toVersion: number
+}
+export interface RestoreFromTrashOptions {
+  id: number
+  /** "fail" (default) or "rename" */
+  conflictPolicy?: string
+}
+export interface PurgeTrashOptions {
+  olderThan?: string
+}
+export interface TagsOptions {
+  docPath: string
+  tags: Array<string>
+}
+export interface ListDocsByTagOptions {
+  tags: Array<string>
+  /** "any" (default) or "all" */
+  tagMatch?: string
+  folderPath?: string
+}
+export interface SetDocPropertyOptions {
+  docPath: string
+  key: string
+  /** `{ type: "string" | "number" | "date" | "bool", value }` */
+  value: any
+}
+export interface SetFolderPropertyOptions {
+  folderPath: string
+  key: string
+  /** `{ type: "string" | "number" | "date" | "bool", value }` */
+  value: any
+}
+export interface DocAccessOptions {
+  limit?: number
+  /** Only count reads from this source */
+  source?: string
+  /** RFC 3339 timestamp or YYYY-MM-DD date */
+  since?: string
+  folderPath?: string
+}
+export interface AttachmentGcOptions {
+  /** RFC 3339 timestamp or YYYY-MM-DD date */
+  olderThan?: string
+  dryRun?: boolean
+}
+export interface AuditLogOptions {
+  /** Doc path or stable id */
+  doc?: string
+  folderPath?: string
+  source?: string
+  action?: string
+  /** RFC 3339 timestamp or YYYY-MM-DD date */
+  since?: string
+  /** RFC 3339 timestamp or YYYY-MM-DD date */
+  until?: string
+  limit?: number
 }
 export interface ManifestOptions {
   folderPath: string
   limit?: number
+  tags?: Array<string>
+  /** "any" (default) or "all" */
+  tagMatch?: string
+  /** "path" (default), "updated_at", "access_count" or "last_accessed" */
+  sort?: string
+  /** List pinned docs first */
+  pinnedFirst?: boolean
+  /** RFC 3339 timestamp or YYYY-MM-DD date */
+  updatedSince?: string
+  maxTokens?: number
+  maxChars?: number
+  /** Output of renderManifest: "json" (default), "markdown" or "llms_txt" */
+  format?: string
+}
+export interface SaveTemplateOptions {
+  name: string
+  content: string
+}
+export interface FolderTemplateOptions {
+  folderPath: string
+  /** Omit to clear the folder's default template */
+  template?: string
+}
+export interface CreateDocFromTemplateOptions {
+  folderPath: string
+  name: string
+  /** Defaults to the folder's default template */
+  template?: string
+  /** Values for `{{key}}` placeholders */
+  vars?: Record<string, string>
+}
+export interface AddIdeaOptions {
+  /** Defaults to "inbox" */
+  ideaBox?: string
+  text: string
+  /** Append to this thread instead of starting a new one */
+  threadPath?: string
+  isAi?: boolean
+}
+export interface ListIdeasOptions {
+  /** Omit to list every box */
+  ideaBox?: string
+  /** Inclusive YYYY-MM-DD bounds on the creation date */
+  from?: string
+  to?: string
+}
+export interface ContextPackOptions {
+  /** Pack every doc under this folder */
+  folderPath?: string
+  /** Or pack these docs, in this order */
+  stableIds?: Array<string>
+  /** Estimated token budget (default 8000) */
+  tokenBudget?: number
+  /** "truncate" (default) or "summarize" */
+  strategy?: string
+  /** Priority order in a folder: "path" (default), "updated_at" or "access_count" */
+  sort?: string
+}
+export interface RewriteLinksOptions {
+  oldPath: string
+  newPath: string
+}
+export interface ExportBundleOptions {
+  folderPath: string
+  bundlePath: string
+}
+export interface ImportBundleOptions {
+  bundlePath: string
+  destFolder: string
+  /** "fail" (default) or "rename" */
+  conflictPolicy?: string
+}
+export interface FullTextSearchOptions {
+  query: string
+  limit?: number
+  folderPath?: string
+  /** Rank pinned docs first */
+  pinnedFirst?: boolean
 }
 export declare function initEnvironment(): NapiResult
 export declare function listFolders(options?: ListFolderOptions | undefined | null): NapiResult
 export declare function createFolder(options: FolderOptions): NapiResult
 export declare function renameFolder(options: RenameFolderOptions): NapiResult
 export declare function moveFolder(options: MoveFolderOptions): NapiResult
+export declare function copyFolder(options: CopyFolderOptions): NapiResult
 export declare function removeFolder(options: RemoveFolderOptions): NapiResult
 export declare function listDocs(options: ListDocsOptions): NapiResult
 export declare function createDoc(options: CreateDocOptions): NapiResult
 export declare function moveDoc(options: MoveDocOptions): NapiResult
 export declare function renameDoc(options: RenameDocOptions): NapiResult
+export declare function copyDoc(options: CopyDocOptions): NapiResult
 export declare function removeDoc(options: RemoveDocOptions): NapiResult
 export declare function setDocDescription(options: SetDescriptionOptions): NapiResult
-export declare function getDocContent(docPath: string): NapiResult
+/** `source` names the reading client (desktop, cli, mcp, ...) */
+export declare function getDocContent(docPath: string, source?: string | undefined | null): NapiResult
+export declare function getDocContentWithRevision(docPath: string, source?: string | undefined | null): NapiResult
 export declare function getDocMeta(docPath: string): NapiResult
 export declare function getDocByStableId(stableId: string): NapiResult
+/** Doc meta and cited content of an `oc://doc/<stable_id>[?lines=a-b][#anchor]` URI */
+export declare function resolveUri(uri: string): NapiResult
+/** `heading` is a heading title, heading path ("Design > Goals") or anchor */
+export declare function makeUri(docPath: string, heading?: string | undefined | null): string
 export declare function saveDocContent(options: SaveDocOptions): NapiResult
+export declare function listDocVersions(stableId: string): NapiResult
+export declare function getDocVersion(options: DocVersionOptions): NapiResult
+export declare function diffDocVersions(options: DiffDocVersionsOptions): NapiResult
+export declare function restoreDocVersion(options: DocVersionOptions): NapiResult
+export declare function listTrash(): NapiResult
+export declare function restoreFromTrash(options: RestoreFromTrashOptions): NapiResult
+export declare function purgeTrash(options?: PurgeTrashOptions | undefined | null): NapiResult
+export declare function addTags(options: TagsOptions): NapiResult
+export declare function removeTags(options: TagsOptions): NapiResult
+export declare function getDocTags(docPath: string): NapiResult
+export declare function listTags(): NapiResult
+export declare function listDocsByTag(options: ListDocsByTagOptions): NapiResult
+export declare function setDocProperty(options: SetDocPropertyOptions): NapiResult
+export declare function getDocProperties(docPath: string): NapiResult
+export declare function deleteDocProperty(docPath: string, key: string): boolean
+export declare function setFolderProperty(options: SetFolderPropertyOptions): NapiResult
+export declare function getFolderProperties(folderPath: string): NapiResult
+export declare function deleteFolderProperty(folderPath: string, key: string): boolean
+export declare function recordDocAccess(docPath: string, source?: string | undefined | null): void
+export declare function recentDocs(options?: DocAccessOptions | undefined | null): NapiResult
+export declare function frequentDocs(options?: DocAccessOptions | undefined | null): NapiResult
+export declare function pinnedDocs(): NapiResult
+export declare function pinDoc(docPath: string): boolean
+export declare function unpinDoc(docPath: string): boolean
+export declare function addAttachment(docPath: string, data: Buffer, fileName: string): NapiResult
+export declare function listAttachments(docPath: string): NapiResult
+export declare function gcAttachments(options?: AttachmentGcOptions | undefined | null): NapiResult
+export declare function auditLog(options?: AuditLogOptions | undefined | null): NapiResult
+export declare function getOutgoingLinks(stableId: string): NapiResult
+export declare function getBacklinks(stableId: string): NapiResult
+export declare function findBrokenLinks(): NapiResult
+export declare function rebuildLinks(): NapiResult
+export declare function rewriteLinks(options: RewriteLinksOptions): NapiResult
+export declare function exportBundle(options: ExportBundleOptions): NapiResult
+export declare function importBundle(options: ImportBundleOptions): NapiResult
+export declare function fullTextSearch(options: FullTextSearchOptions): NapiResult
+export declare function rebuildFullTextIndex(): NapiResult
+export declare function listTemplates(): NapiResult
+export declare function getTemplate(name: string): string
+export declare function saveTemplate(options: SaveTemplateOptions): NapiResult
+export declare function deleteTemplate(name: string): void
+export declare function setFolderTemplate(options: FolderTemplateOptions): void
+export declare function getFolderTemplate(folderPath: string): string | null
+export declare function createDocFromTemplate(options: CreateDocFromTemplateOptions): NapiResult
+export declare function addIdea(options: AddIdeaOptions): NapiResult
+export declare function listIdeas(options?: ListIdeasOptions | undefined | null): NapiResult
+export declare function getIdea(id: string): NapiResult
+export declare function updateIdea(id: string, text: string): NapiResult
+export declare function deleteIdea(id: string): void
+export declare function moveIdea(id: string, toBox: string): NapiResult
+export declare function listWorkspaces(): NapiResult
+export declare function currentWorkspace(): NapiResult
+export declare function createWorkspace(name: string): NapiResult
+/**
+ * Make `name` the active workspace. Later calls use its catalog; the index
+ * sync service is stopped and Searcher/Indexer instances must be recreated.
+ */
+export declare function switchWorkspace(name: string): NapiResult
+export declare function deleteWorkspace(name: string): void
 export declare function generateManifest(options: ManifestOptions): NapiResult
+export declare function renderManifest(options: ManifestOptions): string
+export declare function buildContextPack(options: ContextPackOptions): NapiResult
 /** Search options passed from JavaScript */
 export interface SearchOptions {
   query: string
@@ -79,6 +343,7 @@ export interface SearchOptions {
   mode?: string
   aggregateBy?: string
   docType?: string
+  metadataFilter?: Record<string, string>
 }
 /** Load search config */
 export declare function loadSearchConfig(): any
@@ -89,9 +354,11 @@ export declare function loadSearchConfig(): any
  * updates the search index in batches at regular intervals.
  *
  * @param interval_secs - Interval in seconds between batch processing (default: 300 = 5 minutes)
+ * @param watch_files - Also watch the contexts directory so edits made outside
+ *   OpenContext (editors, git pulls) reach the catalog and the index
  * @returns true if started, false if already running
  */
-export declare function startIndexSync(intervalSecs?: number | undefined | null): Promise<boolean>
+export declare function startIndexSync(intervalSecs?: number | undefined | null, watchFiles?: boolean | undefined | null): Promise<boolean>
 /** Check if index sync service is running */
 export declare function isIndexSyncRunning(): boolean
 /**
@@ -310,25 +310,87 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { initEnvironment, listFolders, createFolder, renameFolder, moveFolder, removeFolder, listDocs, createDoc, moveDoc, renameDoc, removeDoc, setDocDescription, getDocContent, getDocMeta, getDocByStableId, saveDocContent, generateManifest, Searcher, Indexer, loadSearchConfig, startIndexSync, isIndexSyncRunning, getIndexSyncStatus } = nativeBinding
+const { initEnvironment, listFolders, createFolder, renameFolder, moveFolder, copyFolder, removeFolder, listDocs, createDoc, moveDoc, renameDoc, copyDoc, removeDoc, setDocDescription, getDocContent, getDocContentWithRevision, getDocMeta, getDocByStableId, resolveUri, makeUri, saveDocContent, listDocVersions, getDocVersion, diffDocVersions, restoreDocVersion, listTrash, restoreFromTrash, purgeTrash, addTags, removeTags, getDocTags, listTags, listDocsByTag, setDocProperty, getDocProperties, deleteDocProperty, setFolderProperty, getFolderProperties, deleteFolderProperty, recordDocAccess, recentDocs, frequentDocs, pinnedDocs, pinDoc, unpinDoc, addAttachment, listAttachments, gcAttachments, auditLog, getOutgoingLinks, getBacklinks, findBrokenLinks, rebuildLinks, rewriteLinks, exportBundle, importBundle, fullTextSearch, rebuildFullTextIndex, listTemplates, getTemplate, saveTemplate, deleteTemplate, setFolderTemplate, getFolderTemplate, createDocFromTemplate, addIdea, listIdeas, getIdea, updateIdea, deleteIdea, moveIdea, listWorkspaces, currentWorkspace, createWorkspace, switchWorkspace, deleteWorkspace, generateManifest, renderManifest, buildContextPack, Searcher, Indexer, loadSearchConfig, startIndexSync, isIndexSyncRunning, getIndexSyncStatus } = nativeBinding
 
 module.exports.initEnvironment = initEnvironment
 module.exports.listFolders = listFolders
 module.exports.createFolder = createFolder
 module.exports.renameFolder = renameFolder
 module.exports.moveFolder = moveFolder
+module.exports.copyFolder = copyFolder
 module.exports.removeFolder = removeFolder
 module.exports.listDocs = listDocs
 module.exports.createDoc = createDoc
 module.exports.moveDoc = moveDoc
 module.exports.renameDoc = renameDoc
+module.exports.copyDoc = copyDoc
 module.exports.removeDoc = removeDoc
 module.exports.setDocDescription = setDocDescription
 module.exports.getDocContent = getDocContent
+module.exports.getDocContentWithRevision = getDocContentWithRevision
 module.exports.getDocMeta = getDocMeta
 module.exports.getDocByStableId = getDocByStableId
+module.exports.resolveUri = resolveUri
+module.exports.makeUri = makeUri
 module.exports.saveDocContent = saveDocContent
+module.exports.listDocVersions = listDocVersions
+module.exports.getDocVersion = getDocVersion
+module.exports.diffDocVersions = diffDocVersions
+module.exports.restoreDocVersion = restoreDocVersion
+module.exports.listTrash = listTrash
+module.exports.restoreFromTrash = restoreFromTrash
+module.exports.purgeTrash = purgeTrash
+module.exports.addTags = addTags
+module.exports.removeTags = removeTags
+module.exports.getDocTags = getDocTags
+module.exports.listTags = listTags
+module.exports.listDocsByTag = listDocsByTag
+module.exports.setDocProperty = setDocProperty
+module.exports.getDocProperties = getDocProperties
+module.exports.deleteDocProperty = deleteDocProperty
+module.exports.setFolderProperty = setFolderProperty
+module.exports.getFolderProperties = getFolderProperties
+module.exports.deleteFolderProperty = deleteFolderProperty
+module.exports.recordDocAccess = recordDocAccess
+module.exports.recentDocs = recentDocs
+module.exports.frequentDocs = frequentDocs
+module.exports.pinnedDocs = pinnedDocs
+module.exports.pinDoc = pinDoc
+module.exports.unpinDoc = unpinDoc
+module.exports.addAttachment = addAttachment
+module.exports.listAttachments = listAttachments
+module.exports.gcAttachments = gcAttachments
+module.exports.auditLog = auditLog
+module.exports.getOutgoingLinks = getOutgoingLinks
+module.exports.getBacklinks = getBacklinks
+module.exports.findBrokenLinks = findBrokenLinks
+module.exports.rebuildLinks = rebuildLinks
+module.exports.rewriteLinks = rewriteLinks
+module.exports.exportBundle = exportBundle
+module.exports.importBundle = importBundle
+module.exports.fullTextSearch = fullTextSearch
+module.exports.rebuildFullTextIndex = rebuildFullTextIndex
+module.exports.listTemplates = listTemplates
+module.exports.getTemplate = getTemplate
+module.exports.saveTemplate = saveTemplate
+module.exports.deleteTemplate = deleteTemplate
+module.exports.setFolderTemplate = setFolderTemplate
+module.exports.getFolderTemplate = getFolderTemplate
+module.exports.createDocFromTemplate = createDocFromTemplate
+module.exports.addIdea = addIdea
+module.exports.listIdeas = listIdeas
+module.exports.getIdea = getIdea
+module.exports.updateIdea = updateIdea
+module.exports.deleteIdea = deleteIdea
+module.exports.moveIdea = moveIdea
+module.exports.listWorkspaces = listWorkspaces
+module.exports.currentWorkspace = currentWorkspace
+module.exports.createWorkspace = createWorkspace
+module.exports.switchWorkspace = switchWorkspace
+module.exports.deleteWorkspace = deleteWorkspace
 module.exports.generateManifest = generateManifest
+module.exports.renderManifest = renderManifest
+module.exports.buildContextPack = buildContextPack
 module.exports.Searcher = Searcher
 module.exports.Indexer = Indexer
 module.exports.loadSearchConfig = loadSearchConfig
@@ -1,21 +1,31 @@
 #![allow(clippy::needless_borrow)]
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use napi::bindgen_prelude::*;
 use napi::Result as NapiResult;
 use napi::{Env, JsUnknown};
 use napi_derive::napi;
 use once_cell::sync::Lazy;
-use once_cell::sync::OnceCell;
 use opencontext_core::events::{create_event_bus, SharedEventBus};
 use opencontext_core::search::{
     IndexSyncService, Indexer as RustIndexer, SearchConfig, SearchOptions as RustSearchOptions,
     Searcher as RustSearcher,
 };
-use opencontext_core::{CoreError, EnvOverrides, OpenContext};
+use opencontext_core::watcher::FsWatcher;
+use opencontext_core::{
+    AddIdeaOptions as CoreAddIdeaOptions, AttachmentGcOptions as CoreAttachmentGcOptions,
+    AuditLogOptions as CoreAuditLogOptions, ConflictPolicy,
+    ContextPackOptions as CoreContextPackOptions, ContextPackSource, CoreError, CoreResult,
+    DocAccessOptions as CoreDocAccessOptions, FullTextSearchOptions as CoreFullTextSearchOptions,
+    IdeaDateRange, LinkRewrite, ListDocsOptions as CoreListDocsOptions, ManifestFormat,
+    ManifestOptions as CoreManifestOptions, ManifestSort, OpenContext, PackStrategy, PropertyValue,
+    ReadDocOptions, RenameResult, SaveDocOptions as CoreSaveDocOptions, TagMatch, WorkspaceManager,
+    DEFAULT_IDEA_BOX,
+};
 use serde::Serialize;
 use tokio::sync::Mutex;
 
@@ -25,42 +35,127 @@ static EVENT_BUS: Lazy<SharedEventBus> = Lazy::new(create_event_bus);
 // Flag to track if IndexSyncService is running
 static INDEX_SYNC_RUNNING: AtomicBool = AtomicBool::new(false);
 
-static CONTEXT: OnceCell<OpenContext> = OnceCell::new();
+// Handle of the running IndexSyncService task, aborted on workspace switch
+static INDEX_SYNC_TASK: Lazy<std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
+// Filesystem watcher started by startIndexSync({ watchFiles }), dropped on workspace switch
+static FS_WATCHER: Lazy<std::sync::Mutex<Option<FsWatcher>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
 
-fn ctx() -> NapiResult<&'static OpenContext> {
-    CONTEXT.get_or_try_init(|| {
-        OpenContext::initialize(EnvOverrides::default())
-            .map(|ctx| ctx.with_event_bus(EVENT_BUS.clone()))
-            .map_err(to_napi_error)
+// Context of the active workspace, opened lazily and replaced on switch
+static CONTEXT: Lazy<RwLock<Option<Arc<OpenContext>>>> = Lazy::new(|| RwLock::new(None));
+
+fn workspaces() -> NapiResult<WorkspaceManager> {
+    convert(WorkspaceManager::new(None))
+}
+
+fn ctx() -> NapiResult<Arc<OpenContext>> {
+    if let Some(ctx) = CONTEXT.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return Ok(ctx.clone());
+    }
+    let mut slot = CONTEXT.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(ctx) = slot.as_ref() {
+        return Ok(ctx.clone());
+    }
+    let manager = workspaces()?;
+    let active = convert(manager.active())?;
+    let ctx = Arc::new(convert(manager.open(&active))?.with_event_bus(EVENT_BUS.clone()));
+    *slot = Some(ctx.clone());
+    Ok(ctx)
+}
+
+/// Context of the active workspace, attributing changes to `source` in the
+/// audit log when given
+fn ctx_as(source: Option<&str>) -> NapiResult<Arc<OpenContext>> {
+    let ctx = ctx()?;
+    Ok(match source {
+        Some(source) => Arc::new((*ctx).clone().with_source(source)),
+        None => ctx,
     })
 }
 
+/// Search config of the active workspace
+fn search_config() -> NapiResult<SearchConfig> {
+    let manager = workspaces()?;
+    let active = convert(manager.active())?;
+    manager.search_config(&active).map_err(search_error_to_napi)
+}
+
 fn to_napi_error(err: CoreError) -> napi::Error {
     napi::Error::from_reason(err.to_string())
 }
 
+/// Like `to_napi_error`, but throws revision conflicts as a JS error with
+/// `code: "CONFLICT"` and `currentRevision` so callers can re-read and retry.
+fn to_js_error(env: &Env, err: CoreError) -> napi::Error {
+    let CoreError::Conflict {
+        rel_path,
+        current_revision,
+    } = &err
+    else {
+        return to_napi_error(err);
+    };
+    let reason = err.to_string();
+    let thrown = env
+        .create_error(napi::Error::from_reason(reason.clone()))
+        .and_then(|mut js_err| {
+            js_err.set_named_property("code", env.create_string("CONFLICT")?)?;
+            js_err.set_named_property("relPath", env.create_string(rel_path)?)?;
+            js_err.set_named_property("currentRevision", env.create_string(current_revision)?)?;
+            env.throw(js_err)
+        });
+    match thrown {
+        Ok(()) => napi::Error::new(Status::PendingException, reason),
+        Err(e) => e,
+    }
+}
+
 #[napi(object)]
 pub struct FolderOptions {
     pub path: String,
     pub description: Option<String>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct RenameFolderOptions {
     pub path: String,
     pub new_name: String,
+    /// Update path-based links in referring docs
+    pub rewrite_links: Option<bool>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct MoveFolderOptions {
     pub path: String,
     pub dest_folder_path: String,
+    /// Update path-based links in referring docs
+    pub rewrite_links: Option<bool>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
+}
+
+#[napi(object)]
+pub struct CopyFolderOptions {
+    pub path: String,
+    /// Parent folder of the copy ("" for the top level)
+    pub dest_folder_path: String,
+    /// "fail" (default) or "rename"
+    pub conflict_policy: Option<String>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct RemoveFolderOptions {
     pub path: String,
     pub force: Option<bool>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
@@ -72,6 +167,10 @@ pub struct ListFolderOptions {
 pub struct ListDocsOptions {
     pub folder_path: String,
     pub recursive: Option<bool>,
+    /// `{ key, op?, value? }` filters, all of which must match
+    pub property_filters: Option<Vec<serde_json::Value>>,
+    /// `{ key, descending? }`
+    pub sort_by_property: Option<serde_json::Value>,
 }
 
 #[napi(object)]
@@ -79,29 +178,55 @@ pub struct CreateDocOptions {
     pub folder_path: String,
     pub name: String,
     pub description: Option<String>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct MoveDocOptions {
     pub doc_path: String,
     pub dest_folder_path: String,
+    /// Update path-based links in referring docs
+    pub rewrite_links: Option<bool>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct RenameDocOptions {
     pub doc_path: String,
     pub new_name: String,
+    /// Update path-based links in referring docs
+    pub rewrite_links: Option<bool>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
+}
+
+#[napi(object)]
+pub struct CopyDocOptions {
+    pub doc_path: String,
+    pub dest_folder_path: String,
+    /// Name of the copy (defaults to the original name)
+    pub new_name: Option<String>,
+    /// "fail" (default) or "rename"
+    pub conflict_policy: Option<String>,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct RemoveDocOptions {
     pub doc_path: String,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
 pub struct SetDescriptionOptions {
     pub doc_path: String,
     pub description: String,
+    /// Client recorded in the audit log (desktop, cli, mcp, an agent name)
+    pub source: Option<String>,
 }
 
 #[napi(object)]
@@ -109,12 +234,225 @@ pub struct SaveDocOptions {
     pub doc_path: String,
     pub content: String,
     pub description: Option<String>,
+    pub author: Option<String>,
+    pub source: Option<String>,
+    /// Revision (content hash or `updatedAt`) the caller last read
+    pub expected_revision: Option<String>,
+}
+
+#[napi(object)]
+pub struct DocVersionOptions {
+    pub stable_id: String,
+    pub version_id: i64,
+}
+
+#[napi(object)]
+pub struct DiffDocVersionsOptions {
+    pub stable_id: String,
+    pub from_version: i64,
+    pub to_version: i64,
+}
+
+#[napi(object)]
+pub struct RestoreFromTrashOptions {
+    pub id: i64,
+    /// "fail" (default) or "rename"
+    pub conflict_policy: Option<String>,
+}
+
+#[napi(object)]
+pub struct PurgeTrashOptions {
+    pub older_than: Option<String>,
+}
+
+#[napi(object)]
+pub struct TagsOptions {
+    pub doc_path: String,
+    pub tags: Vec<String>,
+}
+
+#[napi(object)]
+pub struct ListDocsByTagOptions {
+    pub tags: Vec<String>,
+    /// "any" (default) or "all"
+    pub tag_match: Option<String>,
+    pub folder_path: Option<String>,
+}
+
+#[napi(object)]
+pub struct SetDocPropertyOptions {
+    pub doc_path: String,
+    pub key: String,
+    /// `{ type: "string" | "number" | "date" | "bool", value }`
+    pub value: serde_json::Value,
+}
+
+#[napi(object)]
+pub struct SetFolderPropertyOptions {
+    pub folder_path: String,
+    pub key: String,
+    /// `{ type: "string" | "number" | "date" | "bool", value }`
+    pub value: serde_json::Value,
+}
+
+#[napi(object)]
+pub struct DocAccessOptions {
+    pub limit: Option<u32>,
+    /// Only count reads from this source
+    pub source: Option<String>,
+    /// RFC 3339 timestamp or YYYY-MM-DD date
+    pub since: Option<String>,
+    pub folder_path: Option<String>,
+}
+
+#[napi(object)]
+pub struct AttachmentGcOptions {
+    /// RFC 3339 timestamp or YYYY-MM-DD date
+    pub older_than: Option<String>,
+    pub dry_run: Option<bool>,
+}
+
+#[napi(object)]
+pub struct AuditLogOptions {
+    /// Doc path or stable id
+    pub doc: Option<String>,
+    pub folder_path: Option<String>,
+    pub source: Option<String>,
+    pub action: Option<String>,
+    /// RFC 3339 timestamp or YYYY-MM-DD date
+    pub since: Option<String>,
+    /// RFC 3339 timestamp or YYYY-MM-DD date
+    pub until: Option<String>,
+    pub limit: Option<u32>,
 }
 
 #[napi(object)]
 pub struct ManifestOptions {
     pub folder_path: String,
     pub limit: Option<u32>,
+    pub tags: Option<Vec<String>>,
+    /// "any" (default) or "all"
+    pub tag_match: Option<String>,
+    /// "path" (default), "updated_at", "access_count" or "last_accessed"
+    pub sort: Option<String>,
+    /// List pinned docs first
+    pub pinned_first: Option<bool>,
+    /// RFC 3339 timestamp or YYYY-MM-DD date
+    pub updated_since: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub max_chars: Option<u32>,
+    /// Output of renderManifest: "json" (default), "markdown" or "llms_txt"
+    pub format: Option<String>,
+}
+
+#[napi(object)]
+pub struct SaveTemplateOptions {
+    pub name: String,
+    pub content: String,
+}
+
+#[napi(object)]
+pub struct FolderTemplateOptions {
+    pub folder_path: String,
+    /// Omit to clear the folder's default template
+    pub template: Option<String>,
+}
+
+#[napi(object)]
+pub struct CreateDocFromTemplateOptions {
+    pub folder_path: String,
+    pub name: String,
+    /// Defaults to the folder's default template
+    pub template: Option<String>,
+    /// Values for `{{key}}` placeholders
+    pub vars: Option<HashMap<String, String>>,
+}
+
+#[napi(object)]
+pub struct AddIdeaOptions {
+    /// Defaults to "inbox"
+    pub idea_box: Option<String>,
+    pub text: String,
+    /// Append to this thread instead of starting a new one
+    pub thread_path: Option<String>,
+    pub is_ai: Option<bool>,
+}
+
+#[napi(object)]
+pub struct ListIdeasOptions {
+    /// Omit to list every box
+    pub idea_box: Option<String>,
+    /// Inclusive YYYY-MM-DD bounds on the creation date
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[napi(object)]
+pub struct ContextPackOptions {
+    /// Pack every doc under this folder
+    pub folder_path: Option<String>,
+    /// Or pack these docs, in this order
+    pub stable_ids: Option<Vec<String>>,
+    /// Estimated token budget (default 8000)
+    pub token_budget: Option<u32>,
+    /// "truncate" (default) or "summarize"
+    pub strategy: Option<String>,
+    /// Priority order in a folder: "path" (default), "updated_at" or "access_count"
+    pub sort: Option<String>,
+}
+
+#[napi(object)]
+pub struct RewriteLinksOptions {
+    pub old_path: String,
+    pub new_path: String,
+}
+
+#[napi(object)]
+pub struct ExportBundleOptions {
+    pub folder_path: String,
+    pub bundle_path: String,
+}
+
+#[napi(object)]
+pub struct ImportBundleOptions {
+    pub bundle_path: String,
+    pub dest_folder: String,
+    /// "fail" (default) or "rename"
+    pub conflict_policy: Option<String>,
+}
+
+#[napi(object)]
+pub struct FullTextSearchOptions {
+    pub query: String,
+    pub limit: Option<u32>,
+    pub folder_path: Option<String>,
+    /// Rank pinned docs first
+    pub pinned_first: Option<bool>,
+}
+
+/// Result of a rename/move, with the link rewrite when one was requested
+#[derive(Serialize)]
+struct RenameOutcome {
+    #[serde(flatten)]
+    result: RenameResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_rewrite: Option<LinkRewrite>,
+}
+
+fn with_link_rewrite(
+    ctx: &OpenContext,
+    result: RenameResult,
+    rewrite_links: Option<bool>,
+) -> CoreResult<RenameOutcome> {
+    let link_rewrite = if rewrite_links.unwrap_or(false) {
+        Some(ctx.rewrite_links(&result.old_path, &result.new_path)?)
+    } else {
+        None
+    };
+    Ok(RenameOutcome {
+        result,
+        link_rewrite,
+    })
 }
 
 #[napi]
@@ -132,28 +470,38 @@ pub fn list_folders(env: Env, options: Option<ListFolderOptions>) -> NapiResult<
 
 #[napi]
 pub fn create_folder(env: Env, options: FolderOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.create_folder(&options.path, options.description.as_deref()))?;
     to_js(env, &result)
 }
 
 #[napi]
 pub fn rename_folder(env: Env, options: RenameFolderOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.rename_folder(&options.path, &options.new_name))?;
+    let result = convert(with_link_rewrite(&ctx, result, options.rewrite_links))?;
     to_js(env, &result)
 }
 
 #[napi]
 pub fn move_folder(env: Env, options: MoveFolderOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.move_folder(&options.path, &options.dest_folder_path))?;
+    let result = convert(with_link_rewrite(&ctx, result, options.rewrite_links))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn copy_folder(env: Env, options: CopyFolderOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx_as(options.source.as_deref())?;
+    let policy = parse_conflict_policy(options.conflict_policy.as_deref())?;
+    let result = convert(ctx.copy_folder(&options.path, &options.dest_folder_path, policy))?;
     to_js(env, &result)
 }
 
 #[napi]
 pub fn remove_folder(env: Env, options: RemoveFolderOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.remove_folder(&options.path, options.force.unwrap_or(false)))?;
     to_js(env, &result)
 }
@@ -161,13 +509,23 @@ pub fn remove_folder(env: Env, options: RemoveFolderOptions) -> NapiResult<JsUnk
 #[napi]
 pub fn list_docs(env: Env, options: ListDocsOptions) -> NapiResult<JsUnknown> {
     let ctx = ctx()?;
-    let docs = convert(ctx.list_docs(&options.folder_path, options.recursive.unwrap_or(false)))?;
+    let core_options = CoreListDocsOptions {
+        recursive: options.recursive.unwrap_or(false),
+        property_filters: options
+            .property_filters
+            .unwrap_or_default()
+            .into_iter()
+            .map(parse_json)
+            .collect::<NapiResult<_>>()?,
+        sort_by_property: options.sort_by_property.map(parse_json).transpose()?,
+    };
+    let docs = convert(ctx.list_docs_with(&options.folder_path, &core_options))?;
     to_js(env, &docs)
 }
 
 #[napi]
 pub fn create_doc(env: Env, options: CreateDocOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let doc = convert(ctx.create_doc(
         &options.folder_path,
         &options.name,
@@ -178,37 +536,64 @@ pub fn create_doc(env: Env, options: CreateDocOptions) -> NapiResult<JsUnknown>
 
 #[napi]
 pub fn move_doc(env: Env, options: MoveDocOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.move_doc(&options.doc_path, &options.dest_folder_path))?;
+    let result = convert(with_link_rewrite(&ctx, result, options.rewrite_links))?;
     to_js(env, &result)
 }
 
 #[napi]
 pub fn rename_doc(env: Env, options: RenameDocOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.rename_doc(&options.doc_path, &options.new_name))?;
+    let result = convert(with_link_rewrite(&ctx, result, options.rewrite_links))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn copy_doc(env: Env, options: CopyDocOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx_as(options.source.as_deref())?;
+    let policy = parse_conflict_policy(options.conflict_policy.as_deref())?;
+    let result = convert(ctx.copy_doc(
+        &options.doc_path,
+        &options.dest_folder_path,
+        options.new_name.as_deref(),
+        policy,
+    ))?;
     to_js(env, &result)
 }
 
 #[napi]
 pub fn remove_doc(env: Env, options: RemoveDocOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.remove_doc(&options.doc_path))?;
     to_js(env, &result)
 }
 
 #[napi]
 pub fn set_doc_description(env: Env, options: SetDescriptionOptions) -> NapiResult<JsUnknown> {
-    let ctx = ctx()?;
+    let ctx = ctx_as(options.source.as_deref())?;
     let result = convert(ctx.set_doc_description(&options.doc_path, &options.description))?;
     to_js(env, &result)
 }
 
+/// `source` names the reading client (desktop, cli, mcp, ...)
 #[napi]
-pub fn get_doc_content(doc_path: String) -> NapiResult<String> {
+pub fn get_doc_content(doc_path: String, source: Option<String>) -> NapiResult<String> {
     let ctx = ctx()?;
-    let content = convert(ctx.get_doc_content(&doc_path))?;
-    Ok(content)
+    let content = convert(ctx.get_doc_content_with(&doc_path, &ReadDocOptions { source }))?;
+    Ok(content.content)
+}
+
+#[napi]
+pub fn get_doc_content_with_revision(
+    env: Env,
+    doc_path: String,
+    source: Option<String>,
+) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let content = convert(ctx.get_doc_content_with(&doc_path, &ReadDocOptions { source }))?;
+    to_js(env, &content)
 }
 
 #[napi]
@@ -225,25 +610,604 @@ pub fn get_doc_by_stable_id(env: Env, stable_id: String) -> NapiResult<JsUnknown
     to_js(env, &doc)
 }
 
+/// Doc meta and cited content of an `oc://doc/<stable_id>[?lines=a-b][#anchor]` URI
+#[napi]
+pub fn resolve_uri(env: Env, uri: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let resolved = convert(ctx.resolve_uri(&uri))?;
+    to_js(env, &resolved)
+}
+
+/// `heading` is a heading title, heading path ("Design > Goals") or anchor
+#[napi]
+pub fn make_uri(doc_path: String, heading: Option<String>) -> NapiResult<String> {
+    let ctx = ctx()?;
+    convert(ctx.make_uri(&doc_path, heading.as_deref()))
+}
+
 #[napi]
 pub fn save_doc_content(env: Env, options: SaveDocOptions) -> NapiResult<JsUnknown> {
     let ctx = ctx()?;
-    let result = convert(ctx.save_doc_content(
-        &options.doc_path,
-        &options.content,
-        options.description.as_deref(),
+    let result = ctx
+        .save_doc_content_with(
+            &options.doc_path,
+            &options.content,
+            CoreSaveDocOptions {
+                description: options.description,
+                author: options.author,
+                source: options.source,
+                expected_revision: options.expected_revision,
+            },
+        )
+        .map_err(|err| to_js_error(&env, err))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn list_doc_versions(env: Env, stable_id: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let versions = convert(ctx.list_doc_versions(&stable_id))?;
+    to_js(env, &versions)
+}
+
+#[napi]
+pub fn get_doc_version(env: Env, options: DocVersionOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let version = convert(ctx.get_doc_version(&options.stable_id, options.version_id))?;
+    to_js(env, &version)
+}
+
+#[napi]
+pub fn diff_doc_versions(env: Env, options: DiffDocVersionsOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let diff = convert(ctx.diff_doc_versions(
+        &options.stable_id,
+        options.from_version,
+        options.to_version,
     ))?;
+    to_js(env, &diff)
+}
+
+#[napi]
+pub fn restore_doc_version(env: Env, options: DocVersionOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let result = convert(ctx.restore_doc_version(&options.stable_id, options.version_id))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn list_trash(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let entries = convert(ctx.list_trash())?;
+    to_js(env, &entries)
+}
+
+#[napi]
+pub fn restore_from_trash(env: Env, options: RestoreFromTrashOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let policy = parse_conflict_policy(options.conflict_policy.as_deref())?;
+    let result = convert(ctx.restore_from_trash(options.id, policy))?;
     to_js(env, &result)
 }
 
+#[napi]
+pub fn purge_trash(env: Env, options: Option<PurgeTrashOptions>) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let older_than = options.and_then(|o| o.older_than);
+    let purged = convert(ctx.purge_trash(older_than.as_deref()))?;
+    to_js(env, &purged)
+}
+
+#[napi]
+pub fn add_tags(env: Env, options: TagsOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let tags = convert(ctx.add_tags(&options.doc_path, &options.tags))?;
+    to_js(env, &tags)
+}
+
+#[napi]
+pub fn remove_tags(env: Env, options: TagsOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let tags = convert(ctx.remove_tags(&options.doc_path, &options.tags))?;
+    to_js(env, &tags)
+}
+
+#[napi]
+pub fn get_doc_tags(env: Env, doc_path: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let tags = convert(ctx.get_doc_tags(&doc_path))?;
+    to_js(env, &tags)
+}
+
+#[napi]
+pub fn list_tags(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let tags = convert(ctx.list_tags())?;
+    to_js(env, &tags)
+}
+
+#[napi]
+pub fn list_docs_by_tag(env: Env, options: ListDocsByTagOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let mode = parse_tag_match(options.tag_match.as_deref())?;
+    let docs = convert(ctx.list_docs_by_tag(&options.tags, mode, options.folder_path.as_deref()))?;
+    to_js(env, &docs)
+}
+
+#[napi]
+pub fn set_doc_property(env: Env, options: SetDocPropertyOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let value: PropertyValue = parse_json(options.value)?;
+    let properties = convert(ctx.set_doc_property(&options.doc_path, &options.key, &value))?;
+    to_js(env, &properties)
+}
+
+#[napi]
+pub fn get_doc_properties(env: Env, doc_path: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let properties = convert(ctx.get_doc_properties(&doc_path))?;
+    to_js(env, &properties)
+}
+
+#[napi]
+pub fn delete_doc_property(doc_path: String, key: String) -> NapiResult<bool> {
+    let ctx = ctx()?;
+    convert(ctx.delete_doc_property(&doc_path, &key))
+}
+
+#[napi]
+pub fn set_folder_property(env: Env, options: SetFolderPropertyOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let value: PropertyValue = parse_json(options.value)?;
+    let properties = convert(ctx.set_folder_property(&options.folder_path, &options.key, &value))?;
+    to_js(env, &properties)
+}
+
+#[napi]
+pub fn get_folder_properties(env: Env, folder_path: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let properties = convert(ctx.get_folder_properties(&folder_path))?;
+    to_js(env, &properties)
+}
+
+#[napi]
+pub fn delete_folder_property(folder_path: String, key: String) -> NapiResult<bool> {
+    let ctx = ctx()?;
+    convert(ctx.delete_folder_property(&folder_path, &key))
+}
+
+#[napi]
+pub fn record_doc_access(doc_path: String, source: Option<String>) -> NapiResult<()> {
+    let ctx = ctx()?;
+    convert(ctx.record_doc_access(&doc_path, source.as_deref()))
+}
+
+#[napi]
+pub fn recent_docs(env: Env, options: Option<DocAccessOptions>) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let docs = convert(ctx.recent_docs(&core_access_options(options)))?;
+    to_js(env, &docs)
+}
+
+#[napi]
+pub fn frequent_docs(env: Env, options: Option<DocAccessOptions>) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let docs = convert(ctx.frequent_docs(&core_access_options(options)))?;
+    to_js(env, &docs)
+}
+
+#[napi]
+pub fn pinned_docs(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let docs = convert(ctx.pinned_docs())?;
+    to_js(env, &docs)
+}
+
+#[napi]
+pub fn pin_doc(doc_path: String) -> NapiResult<bool> {
+    let ctx = ctx()?;
+    convert(ctx.pin_doc(&doc_path))
+}
+
+#[napi]
+pub fn unpin_doc(doc_path: String) -> NapiResult<bool> {
+    let ctx = ctx()?;
+    convert(ctx.unpin_doc(&doc_path))
+}
+
+#[napi]
+pub fn add_attachment(
+    env: Env,
+    doc_path: String,
+    data: Buffer,
+    file_name: String,
+) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let added = convert(ctx.add_attachment(&doc_path, &data, &file_name))?;
+    to_js(env, &added)
+}
+
+#[napi]
+pub fn list_attachments(env: Env, doc_path: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let attachments = convert(ctx.list_attachments(&doc_path))?;
+    to_js(env, &attachments)
+}
+
+#[napi]
+pub fn gc_attachments(env: Env, options: Option<AttachmentGcOptions>) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let options = options
+        .map(|options| CoreAttachmentGcOptions {
+            older_than: options.older_than,
+            dry_run: options.dry_run.unwrap_or(false),
+        })
+        .unwrap_or_default();
+    let collected = convert(ctx.gc_attachments(&options))?;
+    to_js(env, &collected)
+}
+
+#[napi]
+pub fn audit_log(env: Env, options: Option<AuditLogOptions>) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let options = options
+        .map(|options| CoreAuditLogOptions {
+            doc: options.doc,
+            folder_path: options.folder_path,
+            source: options.source,
+            action: options.action,
+            since: options.since,
+            until: options.until,
+            limit: options.limit.map(|limit| limit as usize),
+        })
+        .unwrap_or_default();
+    let entries = convert(ctx.audit_log(&options))?;
+    to_js(env, &entries)
+}
+
+#[napi]
+pub fn get_outgoing_links(env: Env, stable_id: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let links = convert(ctx.get_outgoing_links(&stable_id))?;
+    to_js(env, &links)
+}
+
+#[napi]
+pub fn get_backlinks(env: Env, stable_id: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let links = convert(ctx.get_backlinks(&stable_id))?;
+    to_js(env, &links)
+}
+
+#[napi]
+pub fn find_broken_links(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let links = convert(ctx.find_broken_links())?;
+    to_js(env, &links)
+}
+
+#[napi]
+pub fn rebuild_links(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let count = convert(ctx.rebuild_links())?;
+    to_js(env, &count)
+}
+
+#[napi]
+pub fn rewrite_links(env: Env, options: RewriteLinksOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let result = convert(ctx.rewrite_links(&options.old_path, &options.new_path))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn export_bundle(env: Env, options: ExportBundleOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let result =
+        convert(ctx.export_bundle(&options.folder_path, &PathBuf::from(options.bundle_path)))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn import_bundle(env: Env, options: ImportBundleOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let policy = parse_conflict_policy(options.conflict_policy.as_deref())?;
+    let result = convert(ctx.import_bundle(
+        &PathBuf::from(options.bundle_path),
+        &options.dest_folder,
+        policy,
+    ))?;
+    to_js(env, &result)
+}
+
+#[napi]
+pub fn full_text_search(env: Env, options: FullTextSearchOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let hits = convert(ctx.full_text_search(
+        &options.query,
+        &CoreFullTextSearchOptions {
+            limit: options.limit.map(|v| v as usize),
+            folder_path: options.folder_path,
+            pinned_first: options.pinned_first.unwrap_or(false),
+        },
+    ))?;
+    to_js(env, &hits)
+}
+
+#[napi]
+pub fn rebuild_full_text_index(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let count = convert(ctx.rebuild_full_text_index())?;
+    to_js(env, &count)
+}
+
+#[napi]
+pub fn list_templates(env: Env) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let templates = convert(ctx.list_templates())?;
+    to_js(env, &templates)
+}
+
+#[napi]
+pub fn get_template(name: String) -> NapiResult<String> {
+    let ctx = ctx()?;
+    convert(ctx.get_template(&name))
+}
+
+#[napi]
+pub fn save_template(env: Env, options: SaveTemplateOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let template = convert(ctx.save_template(&options.name, &options.content))?;
+    to_js(env, &template)
+}
+
+#[napi]
+pub fn delete_template(name: String) -> NapiResult<()> {
+    let ctx = ctx()?;
+    convert(ctx.delete_template(&name))
+}
+
+#[napi]
+pub fn set_folder_template(options: FolderTemplateOptions) -> NapiResult<()> {
+    let ctx = ctx()?;
+    convert(ctx.set_folder_template(&options.folder_path, options.template.as_deref()))
+}
+
+#[napi]
+pub fn get_folder_template(folder_path: String) -> NapiResult<Option<String>> {
+    let ctx = ctx()?;
+    convert(ctx.get_folder_template(&folder_path))
+}
+
+#[napi]
+pub fn create_doc_from_template(
+    env: Env,
+    options: CreateDocFromTemplateOptions,
+) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let doc = convert(ctx.create_doc_from_template(
+        &options.folder_path,
+        &options.name,
+        options.template.as_deref(),
+        &options.vars.unwrap_or_default(),
+    ))?;
+    to_js(env, &doc)
+}
+
+#[napi]
+pub fn add_idea(env: Env, options: AddIdeaOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let idea = convert(ctx.add_idea_with(
+        options.idea_box.as_deref().unwrap_or(DEFAULT_IDEA_BOX),
+        &options.text,
+        &CoreAddIdeaOptions {
+            thread_path: options.thread_path,
+            is_ai: options.is_ai.unwrap_or(false),
+        },
+    ))?;
+    to_js(env, &idea)
+}
+
+#[napi]
+pub fn list_ideas(env: Env, options: Option<ListIdeasOptions>) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let (idea_box, from, to) = match options {
+        Some(o) => (o.idea_box, o.from, o.to),
+        None => (None, None, None),
+    };
+    let ideas = convert(ctx.list_ideas(idea_box.as_deref(), &IdeaDateRange { from, to }))?;
+    to_js(env, &ideas)
+}
+
+#[napi]
+pub fn get_idea(env: Env, id: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let idea = convert(ctx.get_idea(&id))?;
+    to_js(env, &idea)
+}
+
+#[napi]
+pub fn update_idea(env: Env, id: String, text: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let idea = convert(ctx.update_idea(&id, &text))?;
+    to_js(env, &idea)
+}
+
+#[napi]
+pub fn delete_idea(id: String) -> NapiResult<()> {
+    let ctx = ctx()?;
+    convert(ctx.delete_idea(&id))
+}
+
+#[napi]
+pub fn move_idea(env: Env, id: String, to_box: String) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let idea = convert(ctx.move_idea(&id, &to_box))?;
+    to_js(env, &idea)
+}
+
+#[napi]
+pub fn list_workspaces(env: Env) -> NapiResult<JsUnknown> {
+    let workspaces = convert(workspaces()?.list())?;
+    to_js(env, &workspaces)
+}
+
+#[napi]
+pub fn current_workspace(env: Env) -> NapiResult<JsUnknown> {
+    let manager = workspaces()?;
+    let workspace = convert(manager.get(&convert(manager.active())?))?;
+    to_js(env, &workspace)
+}
+
+#[napi]
+pub fn create_workspace(env: Env, name: String) -> NapiResult<JsUnknown> {
+    let workspace = convert(workspaces()?.create(&name))?;
+    to_js(env, &workspace)
+}
+
+/// Make `name` the active workspace. Later calls use its catalog; the index
+/// sync service is stopped and Searcher/Indexer instances must be recreated.
+#[napi]
+pub fn switch_workspace(env: Env, name: String) -> NapiResult<JsUnknown> {
+    let manager = workspaces()?;
+    let ctx = convert(manager.open(&name))?.with_event_bus(EVENT_BUS.clone());
+    let workspace = convert(manager.switch(&name))?;
+    *CONTEXT.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(ctx));
+    if let Some(handle) = INDEX_SYNC_TASK
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    {
+        handle.abort();
+        INDEX_SYNC_RUNNING.store(false, Ordering::SeqCst);
+    }
+    FS_WATCHER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    to_js(env, &workspace)
+}
+
+#[napi]
+pub fn delete_workspace(name: String) -> NapiResult<()> {
+    convert(workspaces()?.delete(&name))
+}
+
 #[napi]
 pub fn generate_manifest(env: Env, options: ManifestOptions) -> NapiResult<JsUnknown> {
     let ctx = ctx()?;
-    let manifest =
-        convert(ctx.generate_manifest(&options.folder_path, options.limit.map(|v| v as usize)))?;
+    let manifest_options = core_manifest_options(&options)?;
+    let manifest = convert(ctx.generate_manifest_with(&options.folder_path, &manifest_options))?;
     to_js(env, &manifest)
 }
 
+#[napi]
+pub fn render_manifest(options: ManifestOptions) -> NapiResult<String> {
+    let ctx = ctx()?;
+    let manifest_options = core_manifest_options(&options)?;
+    let format = match options.format.as_deref() {
+        None | Some("json") => ManifestFormat::Json,
+        Some("markdown") => ManifestFormat::Markdown,
+        Some("llms_txt") => ManifestFormat::LlmsTxt,
+        Some(other) => {
+            return Err(napi::Error::from_reason(format!(
+                "Unknown manifest format \"{other}\"."
+            )))
+        }
+    };
+    convert(ctx.render_manifest(&options.folder_path, &manifest_options, format))
+}
+
+fn core_manifest_options(options: &ManifestOptions) -> NapiResult<CoreManifestOptions> {
+    Ok(CoreManifestOptions {
+        limit: options.limit.map(|v| v as usize),
+        tags: options.tags.clone().unwrap_or_default(),
+        tag_match: parse_tag_match(options.tag_match.as_deref())?,
+        sort: parse_manifest_sort(options.sort.as_deref())?,
+        pinned_first: options.pinned_first.unwrap_or(false),
+        updated_since: options.updated_since.clone(),
+        max_tokens: options.max_tokens.map(|v| v as usize),
+        max_chars: options.max_chars.map(|v| v as usize),
+    })
+}
+
+#[napi]
+pub fn build_context_pack(env: Env, options: ContextPackOptions) -> NapiResult<JsUnknown> {
+    let ctx = ctx()?;
+    let source = match (options.folder_path, options.stable_ids) {
+        (Some(folder_path), None) => ContextPackSource::Folder(folder_path),
+        (None, Some(stable_ids)) => ContextPackSource::StableIds(stable_ids),
+        _ => {
+            return Err(napi::Error::from_reason(
+                "Pass exactly one of folderPath or stableIds.",
+            ))
+        }
+    };
+    let strategy = match options.strategy.as_deref() {
+        None | Some("truncate") => PackStrategy::Truncate,
+        Some("summarize") => PackStrategy::Summarize,
+        Some(other) => {
+            return Err(napi::Error::from_reason(format!(
+                "Unknown context pack strategy \"{other}\"."
+            )))
+        }
+    };
+    let pack = convert(ctx.build_context_pack(
+        &source,
+        &CoreContextPackOptions {
+            token_budget: options.token_budget.map(|v| v as usize),
+            strategy,
+            sort: parse_manifest_sort(options.sort.as_deref())?,
+        },
+    ))?;
+    to_js(env, &pack)
+}
+
+fn parse_manifest_sort(value: Option<&str>) -> NapiResult<ManifestSort> {
+    match value {
+        None | Some("path") => Ok(ManifestSort::Path),
+        Some("updated_at") => Ok(ManifestSort::UpdatedAt),
+        Some("access_count") => Ok(ManifestSort::AccessCount),
+        Some("last_accessed") => Ok(ManifestSort::LastAccessed),
+        Some(other) => Err(napi::Error::from_reason(format!(
+            "Unknown manifest sort \"{other}\"."
+        ))),
+    }
+}
+
+fn core_access_options(options: Option<DocAccessOptions>) -> CoreDocAccessOptions {
+    match options {
+        Some(options) => CoreDocAccessOptions {
+            limit: options.limit.map(|v| v as usize),
+            source: options.source,
+            since: options.since,
+            folder_path: options.folder_path,
+        },
+        None => CoreDocAccessOptions::default(),
+    }
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> NapiResult<T> {
+    serde_json::from_value(value).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+fn parse_tag_match(value: Option<&str>) -> NapiResult<TagMatch> {
+    match value {
+        None | Some("any") => Ok(TagMatch::Any),
+        Some("all") => Ok(TagMatch::All),
+        Some(other) => Err(napi::Error::from_reason(format!(
+            "Unknown tag match \"{other}\"."
+        ))),
+    }
+}
+
+fn parse_conflict_policy(value: Option<&str>) -> NapiResult<ConflictPolicy> {
+    match value {
+        None | Some("fail") => Ok(ConflictPolicy::Fail),
+        Some("rename") => Ok(ConflictPolicy::Rename),
+        Some(other) => Err(napi::Error::from_reason(format!(
+            "Unknown conflict policy \"{other}\"."
+        ))),
+    }
+}
+
 fn to_js<T: Serialize>(env: Env, value: &T) -> NapiResult<JsUnknown> {
     env.to_js_value(value)
 }
@@ -266,6 +1230,7 @@ pub struct SearchOptions {
     pub mode: Option<String>,
     pub aggregate_by: Option<String>,
     pub doc_type: Option<String>,
+    pub metadata_filter: Option<HashMap<String, String>>,
 }
 
 impl From<SearchOptions> for RustSearchOptions {
@@ -290,6 +1255,7 @@ impl From<SearchOptions> for RustSearchOptions {
             mode,
             aggregate_by,
             doc_type: opts.doc_type,
+            metadata_filter: opts.metadata_filter,
         }
     }
 }
@@ -306,7 +1272,7 @@ impl Searcher {
     /// contexts_root is optional - if not provided, uses default from environment
     #[napi(factory)]
     pub async fn create() -> Result<Searcher> {
-        let config = SearchConfig::load().map_err(search_error_to_napi)?;
+        let config = search_config()?;
         let searcher = RustSearcher::new(config)
             .await
             .map_err(search_error_to_napi)?;
@@ -345,7 +1311,7 @@ impl Indexer {
         let oc_ctx = ctx()?;
         let contexts_root = PathBuf::from(&oc_ctx.env_info().contexts_root);
 
-        let config = SearchConfig::load().map_err(search_error_to_napi)?;
+        let config = search_config()?;
         let indexer = RustIndexer::new(config, contexts_root)
             .await
             .map_err(search_error_to_napi)?;
@@ -429,7 +1395,7 @@ impl Indexer {
 /// Load search config
 #[napi]
 pub fn load_search_config() -> Result<serde_json::Value> {
-    let config = SearchConfig::load().map_err(search_error_to_napi)?;
+    let config = search_config()?;
 
     serde_json::to_value(&config).map_err(|e| napi::Error::from_reason(e.to_string()))
 }
@@ -442,31 +1408,53 @@ pub fn load_search_config() -> Result<serde_json::Value> {
 /// updates the search index in batches at regular intervals.
 ///
 /// @param interval_secs - Interval in seconds between batch processing (default: 300 = 5 minutes)
+/// @param watch_files - Also watch the contexts directory so edits made outside
+///   OpenContext (editors, git pulls) reach the catalog and the index
 /// @returns true if started, false if already running
 #[napi]
-pub async fn start_index_sync(interval_secs: Option<u32>) -> Result<bool> {
+pub async fn start_index_sync(
+    interval_secs: Option<u32>,
+    watch_files: Option<bool>,
+) -> Result<bool> {
     // Check if already running
     if INDEX_SYNC_RUNNING.swap(true, Ordering::SeqCst) {
         return Ok(false); // Already running
     }
 
-    let oc_ctx = ctx()?;
+    // Resolve everything that can fail before starting the watcher, so an
+    // error leaves nothing running.
+    let (oc_ctx, config) = match ctx().and_then(|oc_ctx| Ok((oc_ctx, search_config()?))) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            INDEX_SYNC_RUNNING.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+    if watch_files.unwrap_or(false) {
+        let watcher = match FsWatcher::start(oc_ctx.clone()) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                INDEX_SYNC_RUNNING.store(false, Ordering::SeqCst);
+                return Err(to_napi_error(e));
+            }
+        };
+        *FS_WATCHER.lock().unwrap_or_else(|e| e.into_inner()) = Some(watcher);
+    }
     let contexts_root = PathBuf::from(&oc_ctx.env_info().contexts_root);
 
-    let config = SearchConfig::load().map_err(search_error_to_napi)?;
-
     let interval = interval_secs.unwrap_or(300) as u64;
     let sync_service = IndexSyncService::new(config, contexts_root).with_interval(interval);
 
     let event_bus = EVENT_BUS.clone();
 
     // Spawn the sync service in a background task
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
         if let Err(e) = sync_service.start(event_bus).await {
             log::error!("[IndexSync] Service error: {}", e);
         }
         INDEX_SYNC_RUNNING.store(false, Ordering::SeqCst);
     });
+    *INDEX_SYNC_TASK.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
 
     Ok(true)
 }
@@ -483,5 +1471,6 @@ pub fn is_index_sync_running() -> bool {
 pub fn get_index_sync_status() -> serde_json::Value {
     serde_json::json!({
         "running": INDEX_SYNC_RUNNING.load(Ordering::SeqCst),
+        "watching": FS_WATCHER.lock().unwrap_or_else(|e| e.into_inner()).is_some(),
     })
 }
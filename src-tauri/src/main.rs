@@ -6,19 +6,59 @@ use opencontext_core::events::{create_event_bus, SharedEventBus};
 use opencontext_core::search::{
     IndexStats, IndexSyncService, Indexer, SearchConfig, SearchOptions, SearchResults, Searcher,
 };
-use opencontext_core::{EnvOverrides, OpenContext};
+use opencontext_core::watcher::FsWatcher;
+use opencontext_core::{
+    AccessedDoc, AddIdeaOptions as CoreAddIdeaOptions, Attachment, AttachmentAdded,
+    AttachmentGcOptions, AuditEntry, AuditLogOptions as CoreAuditLogOptions, ConflictPolicy,
+    ContextPack, ContextPackOptions as CoreContextPackOptions, ContextPackSource, CoreError,
+    DocAccessOptions as CoreDocAccessOptions, DocCopied, DocCreated, FolderCopied,
+    FullTextSearchOptions as CoreFullTextSearchOptions, Idea, IdeaDateRange,
+    ListDocsOptions as CoreListDocsOptions, ManifestFormat, ManifestOptions as CoreManifestOptions,
+    ManifestSort, OpenContext, PackStrategy, Properties, PropertyFilter, PropertySort,
+    PropertyValue, ReadDocOptions, RenameResult, ResolvedUri, SaveDocOptions as CoreSaveDocOptions,
+    TagMatch, TemplateInfo, Workspace, WorkspaceManager,
+};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Mutex;
-use tauri::{Emitter, State};
+use tauri::{Emitter, Manager, State};
 use tokio::sync::Mutex as AsyncMutex;
 
 struct AppState {
     ctx: Mutex<OpenContext>,
     searcher: AsyncMutex<Option<Searcher>>,
     indexer: AsyncMutex<Option<Indexer>>,
-    search_config: SearchConfig,
-    #[allow(dead_code)]
+    search_config: Mutex<SearchConfig>,
+    event_bus: SharedEventBus,
+    workspaces: WorkspaceManager,
+    index_sync: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    fs_watcher: Mutex<Option<FsWatcher>>,
+}
+
+/// Start the index sync service for the workspace at `contexts_root`
+fn spawn_index_sync(
+    config: SearchConfig,
+    contexts_root: std::path::PathBuf,
+    event_bus: SharedEventBus,
+) -> tauri::async_runtime::JoinHandle<()> {
+    // Use tauri::async_runtime::spawn which works with Tauri's runtime management
+    tauri::async_runtime::spawn(async move {
+        let sync_service = IndexSyncService::new(config, contexts_root);
+        if let Err(e) = sync_service.start(event_bus).await {
+            log::error!("[IndexSync] Service error: {}", e);
+        }
+    })
+}
+
+/// Watch the contexts directory of workspace `name` for external edits.
+/// The watcher keeps its own handle on the workspace catalog.
+fn start_fs_watcher(
+    workspaces: &WorkspaceManager,
+    name: &str,
     event_bus: SharedEventBus,
+) -> Result<FsWatcher, CoreError> {
+    let ctx = workspaces.open(name)?.with_event_bus(event_bus);
+    FsWatcher::start(std::sync::Arc::new(ctx))
 }
 
 // Tauri command 返回结果类型
@@ -28,296 +68,1296 @@ fn map_err<E: std::fmt::Display>(e: E) -> String {
     e.to_string()
 }
 
-// ===== Folder Commands =====
+// ===== Folder Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListFoldersOptions {
+    all: Option<bool>,
+}
+
+#[tauri::command]
+fn list_folders(
+    state: State<AppState>,
+    options: Option<ListFoldersOptions>,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let folders = ctx
+        .list_folders(options.and_then(|o| o.all).unwrap_or(false))
+        .map_err(map_err)?;
+    serde_json::to_value(&folders).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateFolderOptions {
+    path: String,
+    description: Option<String>,
+}
+
+#[tauri::command]
+fn create_folder(
+    state: State<AppState>,
+    options: CreateFolderOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let folder = ctx
+        .create_folder(&options.path, options.description.as_deref())
+        .map_err(map_err)?;
+    serde_json::to_value(&folder).map_err(map_err)
+}
+
+/// Serialize a rename/move result, rewriting links to the old path first
+/// when requested.
+fn with_link_rewrite(
+    ctx: &OpenContext,
+    result: RenameResult,
+    rewrite_links: bool,
+) -> CmdResult<serde_json::Value> {
+    let mut value = serde_json::to_value(&result).map_err(map_err)?;
+    if rewrite_links {
+        let rewrite = ctx
+            .rewrite_links(&result.old_path, &result.new_path)
+            .map_err(map_err)?;
+        value["link_rewrite"] = serde_json::to_value(&rewrite).map_err(map_err)?;
+    }
+    Ok(value)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameFolderOptions {
+    path: String,
+    new_name: String,
+    /// Update path-based links in referring docs
+    #[serde(default)]
+    rewrite_links: bool,
+}
+
+#[tauri::command]
+fn rename_folder(
+    state: State<AppState>,
+    options: RenameFolderOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let folder = ctx
+        .rename_folder(&options.path, &options.new_name)
+        .map_err(map_err)?;
+    with_link_rewrite(&ctx, folder, options.rewrite_links)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveFolderOptions {
+    path: String,
+    dest_folder_path: String,
+    /// Update path-based links in referring docs
+    #[serde(default)]
+    rewrite_links: bool,
+}
+
+#[tauri::command]
+fn move_folder(state: State<AppState>, options: MoveFolderOptions) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let folder = ctx
+        .move_folder(&options.path, &options.dest_folder_path)
+        .map_err(map_err)?;
+    with_link_rewrite(&ctx, folder, options.rewrite_links)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyFolderOptions {
+    path: String,
+    dest_folder_path: String,
+    #[serde(default)]
+    conflict_policy: ConflictPolicy,
+}
+
+#[tauri::command]
+fn copy_folder(state: State<AppState>, options: CopyFolderOptions) -> CmdResult<FolderCopied> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.copy_folder(
+        &options.path,
+        &options.dest_folder_path,
+        options.conflict_policy,
+    )
+    .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoveFolderOptions {
+    path: String,
+    force: Option<bool>,
+}
+
+#[tauri::command]
+fn remove_folder(state: State<AppState>, options: RemoveFolderOptions) -> CmdResult<bool> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.remove_folder(&options.path, options.force.unwrap_or(false))
+        .map_err(map_err)?;
+    Ok(true)
+}
+
+// ===== Document Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListDocsOptions {
+    folder_path: String,
+    recursive: Option<bool>,
+    #[serde(default)]
+    property_filters: Vec<PropertyFilter>,
+    sort_by_property: Option<PropertySort>,
+}
+
+#[tauri::command]
+fn list_docs(state: State<AppState>, options: ListDocsOptions) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let docs = ctx
+        .list_docs_with(
+            &options.folder_path,
+            &CoreListDocsOptions {
+                recursive: options.recursive.unwrap_or(false),
+                property_filters: options.property_filters,
+                sort_by_property: options.sort_by_property,
+            },
+        )
+        .map_err(map_err)?;
+    serde_json::to_value(&docs).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDocOptions {
+    folder_path: String,
+    name: String,
+    description: Option<String>,
+}
+
+#[tauri::command]
+fn create_doc(state: State<AppState>, options: CreateDocOptions) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .create_doc(
+            &options.folder_path,
+            &options.name,
+            options.description.as_deref(),
+        )
+        .map_err(map_err)?;
+    serde_json::to_value(&doc).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveDocOptions {
+    doc_path: String,
+    dest_folder_path: String,
+    /// Update path-based links in referring docs
+    #[serde(default)]
+    rewrite_links: bool,
+}
+
+#[tauri::command]
+fn move_doc(state: State<AppState>, options: MoveDocOptions) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .move_doc(&options.doc_path, &options.dest_folder_path)
+        .map_err(map_err)?;
+    with_link_rewrite(&ctx, doc, options.rewrite_links)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameDocOptions {
+    doc_path: String,
+    new_name: String,
+    /// Update path-based links in referring docs
+    #[serde(default)]
+    rewrite_links: bool,
+}
+
+#[tauri::command]
+fn rename_doc(state: State<AppState>, options: RenameDocOptions) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .rename_doc(&options.doc_path, &options.new_name)
+        .map_err(map_err)?;
+    with_link_rewrite(&ctx, doc, options.rewrite_links)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyDocOptions {
+    doc_path: String,
+    dest_folder_path: String,
+    new_name: Option<String>,
+    #[serde(default)]
+    conflict_policy: ConflictPolicy,
+}
+
+#[tauri::command]
+fn copy_doc(state: State<AppState>, options: CopyDocOptions) -> CmdResult<DocCopied> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.copy_doc(
+        &options.doc_path,
+        &options.dest_folder_path,
+        options.new_name.as_deref(),
+        options.conflict_policy,
+    )
+    .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoveDocOptions {
+    doc_path: String,
+}
+
+#[tauri::command]
+fn remove_doc(state: State<AppState>, options: RemoveDocOptions) -> CmdResult<bool> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.remove_doc(&options.doc_path).map_err(map_err)?;
+    Ok(true)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetDescriptionOptions {
+    doc_path: String,
+    description: String,
+}
+
+#[tauri::command]
+fn set_doc_description(
+    state: State<AppState>,
+    options: SetDescriptionOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .set_doc_description(&options.doc_path, &options.description)
+        .map_err(map_err)?;
+    serde_json::to_value(&doc).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetDocContentOptions {
+    path: String,
+}
+
+#[derive(Serialize)]
+struct DocContentResponse {
+    content: String,
+    revision: String,
+}
+
+#[tauri::command]
+fn get_doc_content(
+    state: State<AppState>,
+    options: GetDocContentOptions,
+) -> CmdResult<DocContentResponse> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .get_doc_content_with(
+            &options.path,
+            &ReadDocOptions {
+                source: Some("desktop".into()),
+            },
+        )
+        .map_err(map_err)?;
+    Ok(DocContentResponse {
+        content: doc.content,
+        revision: doc.revision,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveDocOptions {
+    path: String,
+    content: String,
+    description: Option<String>,
+    author: Option<String>,
+    source: Option<String>,
+    expected_revision: Option<String>,
+}
+
+/// Revision conflicts are returned as `{ code: "conflict", message, relPath,
+/// currentRevision }` so the editor can reload; other errors stay strings.
+fn save_error(err: CoreError) -> serde_json::Value {
+    match &err {
+        CoreError::Conflict {
+            rel_path,
+            current_revision,
+        } => serde_json::json!({
+            "code": "conflict",
+            "message": err.to_string(),
+            "relPath": rel_path,
+            "currentRevision": current_revision,
+        }),
+        _ => serde_json::Value::String(err.to_string()),
+    }
+}
+
+#[tauri::command]
+fn save_doc_content(
+    state: State<AppState>,
+    options: SaveDocOptions,
+) -> Result<serde_json::Value, serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(|e| map_err(e).into())?;
+    let doc = ctx
+        .save_doc_content_with(
+            &options.path,
+            &options.content,
+            CoreSaveDocOptions {
+                description: options.description,
+                author: options.author,
+                source: options.source.or_else(|| Some("desktop".to_string())),
+                expected_revision: options.expected_revision,
+            },
+        )
+        .map_err(save_error)?;
+    serde_json::to_value(&doc).map_err(|e| map_err(e).into())
+}
+
+// ===== Version History Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListDocVersionsOptions {
+    stable_id: String,
+}
+
+#[tauri::command]
+fn list_doc_versions(
+    state: State<AppState>,
+    options: ListDocVersionsOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let versions = ctx.list_doc_versions(&options.stable_id).map_err(map_err)?;
+    serde_json::to_value(&versions).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocVersionOptions {
+    stable_id: String,
+    version_id: i64,
+}
+
+#[tauri::command]
+fn get_doc_version(
+    state: State<AppState>,
+    options: DocVersionOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let version = ctx
+        .get_doc_version(&options.stable_id, options.version_id)
+        .map_err(map_err)?;
+    serde_json::to_value(&version).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffDocVersionsOptions {
+    stable_id: String,
+    from_version: i64,
+    to_version: i64,
+}
+
+#[tauri::command]
+fn diff_doc_versions(
+    state: State<AppState>,
+    options: DiffDocVersionsOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let diff = ctx
+        .diff_doc_versions(&options.stable_id, options.from_version, options.to_version)
+        .map_err(map_err)?;
+    serde_json::to_value(&diff).map_err(map_err)
+}
+
+#[tauri::command]
+fn restore_doc_version(
+    state: State<AppState>,
+    options: DocVersionOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .restore_doc_version(&options.stable_id, options.version_id)
+        .map_err(map_err)?;
+    serde_json::to_value(&doc).map_err(map_err)
+}
+
+// ===== Trash Commands =====
+
+#[tauri::command]
+fn list_trash(state: State<AppState>) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let entries = ctx.list_trash().map_err(map_err)?;
+    serde_json::to_value(&entries).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreFromTrashOptions {
+    id: i64,
+    #[serde(default)]
+    conflict_policy: ConflictPolicy,
+}
+
+#[tauri::command]
+fn restore_from_trash(
+    state: State<AppState>,
+    options: RestoreFromTrashOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let restored = ctx
+        .restore_from_trash(options.id, options.conflict_policy)
+        .map_err(map_err)?;
+    serde_json::to_value(&restored).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PurgeTrashOptions {
+    older_than: Option<String>,
+}
+
+#[tauri::command]
+fn purge_trash(state: State<AppState>, options: PurgeTrashOptions) -> CmdResult<usize> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.purge_trash(options.older_than.as_deref())
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetDocByIdOptions {
+    stable_id: String,
+}
+
+#[tauri::command]
+fn get_doc_by_id(
+    state: State<AppState>,
+    options: GetDocByIdOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx
+        .get_doc_by_stable_id(&options.stable_id)
+        .map_err(map_err)?;
+    serde_json::to_value(&doc).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetDocMetaOptions {
+    path: String,
+}
+
+#[tauri::command]
+fn get_doc_meta(
+    state: State<AppState>,
+    options: GetDocMetaOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let doc = ctx.get_doc_meta(&options.path).map_err(map_err)?;
+    serde_json::to_value(&doc).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveUriOptions {
+    uri: String,
+}
+
+#[tauri::command]
+fn resolve_uri(state: State<AppState>, options: ResolveUriOptions) -> CmdResult<ResolvedUri> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.resolve_uri(&options.uri).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MakeUriOptions {
+    doc_path: String,
+    /// Heading title, heading path ("Design > Goals") or anchor
+    heading: Option<String>,
+}
+
+#[tauri::command]
+fn make_uri(state: State<AppState>, options: MakeUriOptions) -> CmdResult<String> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.make_uri(&options.doc_path, options.heading.as_deref())
+        .map_err(map_err)
+}
+
+// ===== Tag Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TagsOptions {
+    doc_path: String,
+    tags: Vec<String>,
+}
+
+#[tauri::command]
+fn add_tags(state: State<AppState>, options: TagsOptions) -> CmdResult<Vec<String>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.add_tags(&options.doc_path, &options.tags)
+        .map_err(map_err)
+}
+
+#[tauri::command]
+fn remove_tags(state: State<AppState>, options: TagsOptions) -> CmdResult<Vec<String>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.remove_tags(&options.doc_path, &options.tags)
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetDocTagsOptions {
+    doc_path: String,
+}
+
+#[tauri::command]
+fn get_doc_tags(state: State<AppState>, options: GetDocTagsOptions) -> CmdResult<Vec<String>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.get_doc_tags(&options.doc_path).map_err(map_err)
+}
+
+#[tauri::command]
+fn list_tags(state: State<AppState>) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let tags = ctx.list_tags().map_err(map_err)?;
+    serde_json::to_value(&tags).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListDocsByTagOptions {
+    tags: Vec<String>,
+    #[serde(default)]
+    tag_match: TagMatch,
+    folder_path: Option<String>,
+}
+
+#[tauri::command]
+fn list_docs_by_tag(
+    state: State<AppState>,
+    options: ListDocsByTagOptions,
+) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let docs = ctx
+        .list_docs_by_tag(
+            &options.tags,
+            options.tag_match,
+            options.folder_path.as_deref(),
+        )
+        .map_err(map_err)?;
+    serde_json::to_value(&docs).map_err(map_err)
+}
+
+// ===== Property Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetDocPropertyOptions {
+    doc_path: String,
+    key: String,
+    value: PropertyValue,
+}
+
+#[tauri::command]
+fn set_doc_property(
+    state: State<AppState>,
+    options: SetDocPropertyOptions,
+) -> CmdResult<Properties> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.set_doc_property(&options.doc_path, &options.key, &options.value)
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocPropertiesOptions {
+    doc_path: String,
+}
+
+#[tauri::command]
+fn get_doc_properties(
+    state: State<AppState>,
+    options: DocPropertiesOptions,
+) -> CmdResult<Properties> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.get_doc_properties(&options.doc_path).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteDocPropertyOptions {
+    doc_path: String,
+    key: String,
+}
+
+#[tauri::command]
+fn delete_doc_property(
+    state: State<AppState>,
+    options: DeleteDocPropertyOptions,
+) -> CmdResult<bool> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.delete_doc_property(&options.doc_path, &options.key)
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetFolderPropertyOptions {
+    folder_path: String,
+    key: String,
+    value: PropertyValue,
+}
+
+#[tauri::command]
+fn set_folder_property(
+    state: State<AppState>,
+    options: SetFolderPropertyOptions,
+) -> CmdResult<Properties> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.set_folder_property(&options.folder_path, &options.key, &options.value)
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FolderPropertiesOptions {
+    folder_path: String,
+}
+
+#[tauri::command]
+fn get_folder_properties(
+    state: State<AppState>,
+    options: FolderPropertiesOptions,
+) -> CmdResult<Properties> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.get_folder_properties(&options.folder_path)
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteFolderPropertyOptions {
+    folder_path: String,
+    key: String,
+}
+
+#[tauri::command]
+fn delete_folder_property(
+    state: State<AppState>,
+    options: DeleteFolderPropertyOptions,
+) -> CmdResult<bool> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.delete_folder_property(&options.folder_path, &options.key)
+        .map_err(map_err)
+}
+
+// ===== Access Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocAccessOptions {
+    limit: Option<usize>,
+    source: Option<String>,
+    since: Option<String>,
+    folder_path: Option<String>,
+}
+
+impl DocAccessOptions {
+    fn to_core(&self) -> CoreDocAccessOptions {
+        CoreDocAccessOptions {
+            limit: self.limit,
+            source: self.source.clone(),
+            since: self.since.clone(),
+            folder_path: self.folder_path.clone(),
+        }
+    }
+}
+
+#[tauri::command]
+fn recent_docs(state: State<AppState>, options: DocAccessOptions) -> CmdResult<Vec<AccessedDoc>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.recent_docs(&options.to_core()).map_err(map_err)
+}
+
+#[tauri::command]
+fn frequent_docs(state: State<AppState>, options: DocAccessOptions) -> CmdResult<Vec<AccessedDoc>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.frequent_docs(&options.to_core()).map_err(map_err)
+}
+
+#[tauri::command]
+fn pinned_docs(state: State<AppState>) -> CmdResult<Vec<AccessedDoc>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.pinned_docs().map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PinDocOptions {
+    doc_path: String,
+}
+
+#[tauri::command]
+fn pin_doc(state: State<AppState>, options: PinDocOptions) -> CmdResult<bool> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.pin_doc(&options.doc_path).map_err(map_err)
+}
+
+#[tauri::command]
+fn unpin_doc(state: State<AppState>, options: PinDocOptions) -> CmdResult<bool> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.unpin_doc(&options.doc_path).map_err(map_err)
+}
+
+// ===== Attachment Commands =====
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddAttachmentOptions {
+    doc_path: String,
+    data: Vec<u8>,
+    file_name: String,
+}
+
+#[tauri::command]
+fn add_attachment(
+    state: State<AppState>,
+    options: AddAttachmentOptions,
+) -> CmdResult<AttachmentAdded> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.add_attachment(&options.doc_path, &options.data, &options.file_name)
+        .map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListAttachmentsOptions {
+    doc_path: String,
+}
+
+#[tauri::command]
+fn list_attachments(
+    state: State<AppState>,
+    options: ListAttachmentsOptions,
+) -> CmdResult<Vec<Attachment>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.list_attachments(&options.doc_path).map_err(map_err)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcAttachmentsOptions {
+    older_than: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[tauri::command]
+fn gc_attachments(
+    state: State<AppState>,
+    options: GcAttachmentsOptions,
+) -> CmdResult<Vec<Attachment>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.gc_attachments(&AttachmentGcOptions {
+        older_than: options.older_than,
+        dry_run: options.dry_run,
+    })
+    .map_err(map_err)
+}
+
+// ===== Audit Commands =====
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct AuditLogOptions {
+    /// Doc path or stable id
+    doc: Option<String>,
+    folder_path: Option<String>,
+    source: Option<String>,
+    action: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+}
+
+#[tauri::command]
+fn audit_log(
+    state: State<AppState>,
+    options: Option<AuditLogOptions>,
+) -> CmdResult<Vec<AuditEntry>> {
+    let options = options.unwrap_or_default();
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.audit_log(&CoreAuditLogOptions {
+        doc: options.doc,
+        folder_path: options.folder_path,
+        source: options.source,
+        action: options.action,
+        since: options.since,
+        until: options.until,
+        limit: options.limit,
+    })
+    .map_err(map_err)
+}
+
+// ===== Link Commands =====
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ListFoldersOptions {
-    all: Option<bool>,
+struct StableIdOptions {
+    stable_id: String,
 }
 
 #[tauri::command]
-fn list_folders(
+fn get_outgoing_links(
     state: State<AppState>,
-    options: Option<ListFoldersOptions>,
+    options: StableIdOptions,
 ) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let folders = ctx
-        .list_folders(options.and_then(|o| o.all).unwrap_or(false))
+    let links = ctx
+        .get_outgoing_links(&options.stable_id)
         .map_err(map_err)?;
-    serde_json::to_value(&folders).map_err(map_err)
+    serde_json::to_value(&links).map_err(map_err)
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct CreateFolderOptions {
-    path: String,
-    description: Option<String>,
+#[tauri::command]
+fn get_backlinks(state: State<AppState>, options: StableIdOptions) -> CmdResult<serde_json::Value> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    let links = ctx.get_backlinks(&options.stable_id).map_err(map_err)?;
+    serde_json::to_value(&links).map_err(map_err)
 }
 
 #[tauri::command]
-fn create_folder(
-    state: State<AppState>,
-    options: CreateFolderOptions,
-) -> CmdResult<serde_json::Value> {
+fn find_broken_links(state: State<AppState>) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let folder = ctx
-        .create_folder(&options.path, options.description.as_deref())
-        .map_err(map_err)?;
-    serde_json::to_value(&folder).map_err(map_err)
+    let links = ctx.find_broken_links().map_err(map_err)?;
+    serde_json::to_value(&links).map_err(map_err)
+}
+
+#[tauri::command]
+fn rebuild_links(state: State<AppState>) -> CmdResult<usize> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.rebuild_links().map_err(map_err)
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct RenameFolderOptions {
-    path: String,
-    new_name: String,
+struct RewriteLinksOptions {
+    old_path: String,
+    new_path: String,
 }
 
 #[tauri::command]
-fn rename_folder(
+fn rewrite_links(
     state: State<AppState>,
-    options: RenameFolderOptions,
+    options: RewriteLinksOptions,
 ) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let folder = ctx
-        .rename_folder(&options.path, &options.new_name)
+    let result = ctx
+        .rewrite_links(&options.old_path, &options.new_path)
         .map_err(map_err)?;
-    serde_json::to_value(&folder).map_err(map_err)
+    serde_json::to_value(&result).map_err(map_err)
 }
 
+// ===== Bundle Commands =====
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct MoveFolderOptions {
-    path: String,
-    dest_folder_path: String,
+struct ExportBundleOptions {
+    folder_path: String,
+    bundle_path: String,
 }
 
 #[tauri::command]
-fn move_folder(state: State<AppState>, options: MoveFolderOptions) -> CmdResult<serde_json::Value> {
+fn export_bundle(
+    state: State<AppState>,
+    options: ExportBundleOptions,
+) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let folder = ctx
-        .move_folder(&options.path, &options.dest_folder_path)
+    let result = ctx
+        .export_bundle(&options.folder_path, Path::new(&options.bundle_path))
         .map_err(map_err)?;
-    serde_json::to_value(&folder).map_err(map_err)
+    serde_json::to_value(&result).map_err(map_err)
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct RemoveFolderOptions {
-    path: String,
-    force: Option<bool>,
+struct ImportBundleOptions {
+    bundle_path: String,
+    dest_folder: String,
+    #[serde(default)]
+    conflict_policy: ConflictPolicy,
 }
 
 #[tauri::command]
-fn remove_folder(state: State<AppState>, options: RemoveFolderOptions) -> CmdResult<bool> {
+fn import_bundle(
+    state: State<AppState>,
+    options: ImportBundleOptions,
+) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    ctx.remove_folder(&options.path, options.force.unwrap_or(false))
+    let result = ctx
+        .import_bundle(
+            Path::new(&options.bundle_path),
+            &options.dest_folder,
+            options.conflict_policy,
+        )
         .map_err(map_err)?;
-    Ok(true)
+    serde_json::to_value(&result).map_err(map_err)
 }
 
-// ===== Document Commands =====
+// ===== Full-Text Search Commands =====
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ListDocsOptions {
-    folder_path: String,
-    recursive: Option<bool>,
+struct FullTextSearchOptions {
+    query: String,
+    limit: Option<usize>,
+    folder_path: Option<String>,
+    #[serde(default)]
+    pinned_first: bool,
 }
 
 #[tauri::command]
-fn list_docs(state: State<AppState>, options: ListDocsOptions) -> CmdResult<serde_json::Value> {
+fn full_text_search(
+    state: State<AppState>,
+    options: FullTextSearchOptions,
+) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let docs = ctx
-        .list_docs(&options.folder_path, options.recursive.unwrap_or(false))
+    let hits = ctx
+        .full_text_search(
+            &options.query,
+            &CoreFullTextSearchOptions {
+                limit: options.limit,
+                folder_path: options.folder_path,
+                pinned_first: options.pinned_first,
+            },
+        )
         .map_err(map_err)?;
-    serde_json::to_value(&docs).map_err(map_err)
+    serde_json::to_value(&hits).map_err(map_err)
 }
 
+#[tauri::command]
+fn rebuild_full_text_index(state: State<AppState>) -> CmdResult<usize> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.rebuild_full_text_index().map_err(map_err)
+}
+
+// ===== Manifest Commands =====
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct CreateDocOptions {
+struct ManifestOptions {
     folder_path: String,
-    name: String,
-    description: Option<String>,
+    limit: Option<u32>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    tag_match: TagMatch,
+    #[serde(default)]
+    sort: ManifestSort,
+    #[serde(default)]
+    pinned_first: bool,
+    updated_since: Option<String>,
+    max_tokens: Option<u32>,
+    max_chars: Option<u32>,
+}
+
+impl ManifestOptions {
+    fn to_core(&self) -> CoreManifestOptions {
+        CoreManifestOptions {
+            limit: self.limit.map(|v| v as usize),
+            tags: self.tags.clone(),
+            tag_match: self.tag_match,
+            sort: self.sort,
+            pinned_first: self.pinned_first,
+            updated_since: self.updated_since.clone(),
+            max_tokens: self.max_tokens.map(|v| v as usize),
+            max_chars: self.max_chars.map(|v| v as usize),
+        }
+    }
 }
 
 #[tauri::command]
-fn create_doc(state: State<AppState>, options: CreateDocOptions) -> CmdResult<serde_json::Value> {
+fn generate_manifest(
+    state: State<AppState>,
+    options: ManifestOptions,
+) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx
-        .create_doc(
-            &options.folder_path,
-            &options.name,
-            options.description.as_deref(),
-        )
+    let manifest = ctx
+        .generate_manifest_with(&options.folder_path, &options.to_core())
         .map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    serde_json::to_value(&manifest).map_err(map_err)
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct MoveDocOptions {
-    doc_path: String,
-    dest_folder_path: String,
+#[tauri::command]
+fn render_manifest(
+    state: State<AppState>,
+    options: ManifestOptions,
+    format: Option<ManifestFormat>,
+) -> CmdResult<String> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.render_manifest(
+        &options.folder_path,
+        &options.to_core(),
+        format.unwrap_or_default(),
+    )
+    .map_err(map_err)
 }
 
+// ===== Template Commands =====
+
 #[tauri::command]
-fn move_doc(state: State<AppState>, options: MoveDocOptions) -> CmdResult<serde_json::Value> {
+fn list_templates(state: State<AppState>) -> CmdResult<Vec<TemplateInfo>> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx
-        .move_doc(&options.doc_path, &options.dest_folder_path)
-        .map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    ctx.list_templates().map_err(map_err)
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RenameDocOptions {
-    doc_path: String,
-    new_name: String,
+#[tauri::command]
+fn get_template(state: State<AppState>, name: String) -> CmdResult<String> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.get_template(&name).map_err(map_err)
 }
 
 #[tauri::command]
-fn rename_doc(state: State<AppState>, options: RenameDocOptions) -> CmdResult<serde_json::Value> {
+fn save_template(state: State<AppState>, name: String, content: String) -> CmdResult<TemplateInfo> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx
-        .rename_doc(&options.doc_path, &options.new_name)
-        .map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    ctx.save_template(&name, &content).map_err(map_err)
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RemoveDocOptions {
-    doc_path: String,
+#[tauri::command]
+fn delete_template(state: State<AppState>, name: String) -> CmdResult<()> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.delete_template(&name).map_err(map_err)
 }
 
 #[tauri::command]
-fn remove_doc(state: State<AppState>, options: RemoveDocOptions) -> CmdResult<bool> {
+fn set_folder_template(
+    state: State<AppState>,
+    folder_path: String,
+    template: Option<String>,
+) -> CmdResult<()> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    ctx.remove_doc(&options.doc_path).map_err(map_err)?;
-    Ok(true)
+    ctx.set_folder_template(&folder_path, template.as_deref())
+        .map_err(map_err)
+}
+
+#[tauri::command]
+fn get_folder_template(state: State<AppState>, folder_path: String) -> CmdResult<Option<String>> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.get_folder_template(&folder_path).map_err(map_err)
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct SetDescriptionOptions {
-    doc_path: String,
-    description: String,
+struct CreateDocFromTemplateOptions {
+    folder_path: String,
+    name: String,
+    template: Option<String>,
+    #[serde(default)]
+    vars: std::collections::HashMap<String, String>,
 }
 
 #[tauri::command]
-fn set_doc_description(
+fn create_doc_from_template(
     state: State<AppState>,
-    options: SetDescriptionOptions,
-) -> CmdResult<serde_json::Value> {
+    options: CreateDocFromTemplateOptions,
+) -> CmdResult<DocCreated> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx
-        .set_doc_description(&options.doc_path, &options.description)
-        .map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    ctx.create_doc_from_template(
+        &options.folder_path,
+        &options.name,
+        options.template.as_deref(),
+        &options.vars,
+    )
+    .map_err(map_err)
 }
 
+// ===== Idea Commands =====
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct GetDocContentOptions {
-    path: String,
+struct AddIdeaOptions {
+    idea_box: Option<String>,
+    text: String,
+    thread_path: Option<String>,
+    #[serde(default)]
+    is_ai: bool,
 }
 
-#[derive(Serialize)]
-struct DocContentResponse {
-    content: String,
+#[tauri::command]
+fn add_idea(state: State<AppState>, options: AddIdeaOptions) -> CmdResult<Idea> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.add_idea_with(
+        options.idea_box.as_deref().unwrap_or_default(),
+        &options.text,
+        &CoreAddIdeaOptions {
+            thread_path: options.thread_path,
+            is_ai: options.is_ai,
+        },
+    )
+    .map_err(map_err)
 }
 
 #[tauri::command]
-fn get_doc_content(
+fn list_ideas(
     state: State<AppState>,
-    options: GetDocContentOptions,
-) -> CmdResult<DocContentResponse> {
+    idea_box: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+) -> CmdResult<Vec<Idea>> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let content = ctx.get_doc_content(&options.path).map_err(map_err)?;
-    Ok(DocContentResponse { content })
+    ctx.list_ideas(idea_box.as_deref(), &IdeaDateRange { from, to })
+        .map_err(map_err)
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SaveDocOptions {
-    path: String,
-    content: String,
-    description: Option<String>,
+#[tauri::command]
+fn get_idea(state: State<AppState>, id: String) -> CmdResult<Idea> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.get_idea(&id).map_err(map_err)
 }
 
 #[tauri::command]
-fn save_doc_content(
-    state: State<AppState>,
-    options: SaveDocOptions,
-) -> CmdResult<serde_json::Value> {
+fn update_idea(state: State<AppState>, id: String, text: String) -> CmdResult<Idea> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx
-        .save_doc_content(
-            &options.path,
-            &options.content,
-            options.description.as_deref(),
-        )
-        .map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    ctx.update_idea(&id, &text).map_err(map_err)
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct GetDocByIdOptions {
-    stable_id: String,
+#[tauri::command]
+fn delete_idea(state: State<AppState>, id: String) -> CmdResult<()> {
+    let ctx = state.ctx.lock().map_err(map_err)?;
+    ctx.delete_idea(&id).map_err(map_err)
 }
 
 #[tauri::command]
-fn get_doc_by_id(
-    state: State<AppState>,
-    options: GetDocByIdOptions,
-) -> CmdResult<serde_json::Value> {
+fn move_idea(state: State<AppState>, id: String, to_box: String) -> CmdResult<Idea> {
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx
-        .get_doc_by_stable_id(&options.stable_id)
-        .map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    ctx.move_idea(&id, &to_box).map_err(map_err)
 }
 
+// ===== Context Pack Commands =====
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct GetDocMetaOptions {
-    path: String,
+struct ContextPackOptions {
+    folder_path: Option<String>,
+    stable_ids: Option<Vec<String>>,
+    token_budget: Option<u32>,
+    #[serde(default)]
+    strategy: PackStrategy,
+    #[serde(default)]
+    sort: ManifestSort,
 }
 
 #[tauri::command]
-fn get_doc_meta(
+fn build_context_pack(
     state: State<AppState>,
-    options: GetDocMetaOptions,
-) -> CmdResult<serde_json::Value> {
+    options: ContextPackOptions,
+) -> CmdResult<ContextPack> {
+    let source = match (options.folder_path, options.stable_ids) {
+        (Some(folder_path), None) => ContextPackSource::Folder(folder_path),
+        (None, Some(stable_ids)) => ContextPackSource::StableIds(stable_ids),
+        _ => return Err("Pass exactly one of folderPath or stableIds.".to_string()),
+    };
     let ctx = state.ctx.lock().map_err(map_err)?;
-    let doc = ctx.get_doc_meta(&options.path).map_err(map_err)?;
-    serde_json::to_value(&doc).map_err(map_err)
+    ctx.build_context_pack(
+        &source,
+        &CoreContextPackOptions {
+            token_budget: options.token_budget.map(|v| v as usize),
+            strategy: options.strategy,
+            sort: options.sort,
+        },
+    )
+    .map_err(map_err)
 }
 
-// ===== Manifest Command =====
+// ===== Workspace Commands =====
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ManifestOptions {
-    folder_path: String,
-    limit: Option<u32>,
+#[tauri::command]
+fn list_workspaces(state: State<AppState>) -> CmdResult<Vec<Workspace>> {
+    state.workspaces.list().map_err(map_err)
 }
 
 #[tauri::command]
-fn generate_manifest(
-    state: State<AppState>,
-    options: ManifestOptions,
-) -> CmdResult<serde_json::Value> {
-    let ctx = state.ctx.lock().map_err(map_err)?;
-    let manifest = ctx
-        .generate_manifest(&options.folder_path, options.limit.map(|v| v as usize))
-        .map_err(map_err)?;
-    serde_json::to_value(&manifest).map_err(map_err)
+fn current_workspace(state: State<AppState>) -> CmdResult<Workspace> {
+    let name = state.workspaces.active().map_err(map_err)?;
+    state.workspaces.get(&name).map_err(map_err)
+}
+
+#[tauri::command]
+fn create_workspace(state: State<AppState>, name: String) -> CmdResult<Workspace> {
+    state.workspaces.create(&name).map_err(map_err)
+}
+
+/// Make `name` the active workspace and reopen the catalog, search config
+/// and index sync service for it.
+#[tauri::command]
+async fn switch_workspace(state: State<'_, AppState>, name: String) -> CmdResult<Workspace> {
+    let new_ctx = state
+        .workspaces
+        .open(&name)
+        .map_err(map_err)?
+        .with_source("desktop")
+        .with_event_bus(state.event_bus.clone());
+    let new_config = state.workspaces.search_config(&name).map_err(map_err)?;
+    let workspace = state.workspaces.switch(&name).map_err(map_err)?;
+    let contexts_root = new_ctx.env_info().contexts_root.clone();
+
+    // Drop cached search handles so they are rebuilt for the new workspace
+    *state.searcher.lock().await = None;
+    *state.indexer.lock().await = None;
+    *state.ctx.lock().map_err(map_err)? = new_ctx;
+    *state.search_config.lock().map_err(map_err)? = new_config.clone();
+
+    let mut index_sync = state.index_sync.lock().map_err(map_err)?;
+    if let Some(handle) = index_sync.take() {
+        handle.abort();
+    }
+    *index_sync = Some(spawn_index_sync(
+        new_config,
+        contexts_root,
+        state.event_bus.clone(),
+    ));
+
+    let mut fs_watcher = state.fs_watcher.lock().map_err(map_err)?;
+    if fs_watcher.take().is_some() {
+        *fs_watcher = Some(
+            start_fs_watcher(&state.workspaces, &name, state.event_bus.clone()).map_err(map_err)?,
+        );
+    }
+    Ok(workspace)
+}
+
+/// Turn the filesystem watcher for the active workspace on or off
+#[tauri::command]
+fn set_fs_watcher(state: State<AppState>, enabled: bool) -> CmdResult<bool> {
+    let mut fs_watcher = state.fs_watcher.lock().map_err(map_err)?;
+    if !enabled {
+        fs_watcher.take();
+    } else if fs_watcher.is_none() {
+        let active = state.workspaces.active().map_err(map_err)?;
+        *fs_watcher = Some(
+            start_fs_watcher(&state.workspaces, &active, state.event_bus.clone())
+                .map_err(map_err)?,
+        );
+    }
+    Ok(fs_watcher.is_some())
+}
+
+#[tauri::command]
+fn delete_workspace(state: State<AppState>, name: String) -> CmdResult<()> {
+    state.workspaces.delete(&name).map_err(map_err)
 }
 
 // ===== Environment Info Command =====
@@ -326,7 +1366,7 @@ fn generate_manifest(
 fn get_env_info(state: State<AppState>) -> CmdResult<serde_json::Value> {
     let ctx = state.ctx.lock().map_err(map_err)?;
     let base_info = ctx.env_info();
-    let config = &state.search_config;
+    let config = state.search_config.lock().map_err(map_err)?.clone();
 
     // Mask API key for security (show only last 4 chars)
     let masked_api_key = config.embedding.api_key.as_ref().map(|key| {
@@ -340,6 +1380,7 @@ fn get_env_info(state: State<AppState>) -> CmdResult<serde_json::Value> {
     let info = serde_json::json!({
         "contexts_root": base_info.contexts_root,
         "db_path": base_info.db_path,
+        "templates_root": base_info.templates_root,
         "embedding_model": config.embedding.model,
         "embedding_api_base": config.embedding.api_base,
         "api_key_masked": masked_api_key,
@@ -865,7 +1906,7 @@ async fn semantic_search(
 
     // Initialize searcher if not already done
     if searcher_guard.is_none() {
-        let searcher = Searcher::new(state.search_config.clone())
+        let searcher = Searcher::new(state.search_config.lock().map_err(map_err)?.clone())
             .await
             .map_err(map_err)?;
         *searcher_guard = Some(searcher);
@@ -912,9 +1953,12 @@ async fn build_search_index(
 
     // Initialize indexer if not already done
     if indexer_guard.is_none() {
-        let indexer = Indexer::new(state.search_config.clone(), contexts_root)
-            .await
-            .map_err(map_err)?;
+        let indexer = Indexer::new(
+            state.search_config.lock().map_err(map_err)?.clone(),
+            contexts_root,
+        )
+        .await
+        .map_err(map_err)?;
         *indexer_guard = Some(indexer);
     }
 
@@ -930,7 +1974,12 @@ async fn build_search_index(
         .map_err(map_err)?;
 
     // Save index metadata with last update time
-    let metadata_path = state.search_config.paths.get_index_metadata_path();
+    let metadata_path = state
+        .search_config
+        .lock()
+        .map_err(map_err)?
+        .paths
+        .get_index_metadata_path();
     let metadata = serde_json::json!({
         "lastFullBuild": std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -969,9 +2018,12 @@ async fn get_index_status(state: State<'_, AppState>) -> CmdResult<IndexStatus>
     let mut indexer_guard = state.indexer.lock().await;
 
     if indexer_guard.is_none() {
-        let indexer = Indexer::new(state.search_config.clone(), contexts_root)
-            .await
-            .map_err(map_err)?;
+        let indexer = Indexer::new(
+            state.search_config.lock().map_err(map_err)?.clone(),
+            contexts_root,
+        )
+        .await
+        .map_err(map_err)?;
         *indexer_guard = Some(indexer);
     }
 
@@ -982,7 +2034,12 @@ async fn get_index_status(state: State<'_, AppState>) -> CmdResult<IndexStatus>
     // Try to read last update time from index-metadata.json
     // Prefer lastUpdated (any update), fallback to lastFullBuild (full rebuild only)
     let last_updated = {
-        let metadata_path = state.search_config.paths.get_index_metadata_path();
+        let metadata_path = state
+            .search_config
+            .lock()
+            .map_err(map_err)?
+            .paths
+            .get_index_metadata_path();
         if metadata_path.exists() {
             std::fs::read_to_string(&metadata_path)
                 .ok()
@@ -1015,9 +2072,12 @@ async fn clean_search_index(state: State<'_, AppState>) -> CmdResult<bool> {
     let mut indexer_guard = state.indexer.lock().await;
 
     if indexer_guard.is_none() {
-        let indexer = Indexer::new(state.search_config.clone(), contexts_root)
-            .await
-            .map_err(map_err)?;
+        let indexer = Indexer::new(
+            state.search_config.lock().map_err(map_err)?.clone(),
+            contexts_root,
+        )
+        .await
+        .map_err(map_err)?;
         *indexer_guard = Some(indexer);
     }
 
@@ -1031,12 +2091,18 @@ fn main() {
     // Create event bus for document lifecycle events
     let event_bus = create_event_bus();
 
-    // Initialize OpenContext with event bus
-    let ctx = OpenContext::initialize(EnvOverrides::default())
+    // Initialize OpenContext for the active workspace with event bus
+    let workspaces = WorkspaceManager::new(None).expect("failed to resolve OpenContext root");
+    let workspace = workspaces
+        .active()
+        .expect("failed to read active workspace");
+    let ctx = workspaces
+        .open(&workspace)
         .expect("failed to initialize OpenContext core")
+        .with_source("desktop")
         .with_event_bus(event_bus.clone());
 
-    let search_config = SearchConfig::load().unwrap_or_default();
+    let search_config = workspaces.search_config(&workspace).unwrap_or_default();
     let contexts_root = ctx.env_info().contexts_root.clone();
 
     // Clone for setup hook
@@ -1050,8 +2116,11 @@ fn main() {
             ctx: Mutex::new(ctx),
             searcher: AsyncMutex::new(None),
             indexer: AsyncMutex::new(None),
-            search_config,
+            search_config: Mutex::new(search_config),
             event_bus,
+            workspaces,
+            index_sync: Mutex::new(None),
+            fs_watcher: Mutex::new(None),
         })
         .setup(move |app| {
             // Create Edit menu with predefined items for macOS
@@ -1081,13 +2150,11 @@ fn main() {
             }
 
             // Start index sync service in background
-            // Use tauri::async_runtime::spawn which works with Tauri's runtime management
-            tauri::async_runtime::spawn(async move {
-                let sync_service = IndexSyncService::new(sync_config, sync_contexts_root);
-                if let Err(e) = sync_service.start(sync_event_bus).await {
-                    log::error!("[IndexSync] Service error: {}", e);
-                }
-            });
+            let handle = spawn_index_sync(sync_config, sync_contexts_root, sync_event_bus);
+            let state = app.state::<AppState>();
+            if let Ok(mut index_sync) = state.index_sync.lock() {
+                *index_sync = Some(handle);
+            }
 
             Ok(())
         })
@@ -1097,20 +2164,95 @@ fn main() {
             create_folder,
             rename_folder,
             move_folder,
+            copy_folder,
             remove_folder,
             // Document commands
             list_docs,
             create_doc,
             get_doc_by_id,
             get_doc_meta,
+            resolve_uri,
+            make_uri,
             move_doc,
             rename_doc,
+            copy_doc,
             remove_doc,
             set_doc_description,
             get_doc_content,
             save_doc_content,
+            // Version history commands
+            list_doc_versions,
+            get_doc_version,
+            diff_doc_versions,
+            restore_doc_version,
+            // Trash commands
+            list_trash,
+            restore_from_trash,
+            purge_trash,
+            // Tag commands
+            add_tags,
+            remove_tags,
+            get_doc_tags,
+            list_tags,
+            list_docs_by_tag,
+            // Property commands
+            set_doc_property,
+            get_doc_properties,
+            delete_doc_property,
+            set_folder_property,
+            get_folder_properties,
+            delete_folder_property,
+            // Access commands
+            recent_docs,
+            frequent_docs,
+            pinned_docs,
+            pin_doc,
+            unpin_doc,
+            // Attachment commands
+            add_attachment,
+            list_attachments,
+            gc_attachments,
+            // Audit commands
+            audit_log,
+            // Link commands
+            get_outgoing_links,
+            get_backlinks,
+            find_broken_links,
+            rebuild_links,
+            rewrite_links,
+            // Bundle commands
+            export_bundle,
+            import_bundle,
+            // Full-text search commands
+            full_text_search,
+            rebuild_full_text_index,
+            // Template commands
+            list_templates,
+            get_template,
+            save_template,
+            delete_template,
+            set_folder_template,
+            get_folder_template,
+            create_doc_from_template,
+            // Idea commands
+            add_idea,
+            list_ideas,
+            get_idea,
+            update_idea,
+            delete_idea,
+            move_idea,
+            // Context pack commands
+            build_context_pack,
+            // Workspace commands
+            list_workspaces,
+            current_workspace,
+            create_workspace,
+            switch_workspace,
+            delete_workspace,
+            set_fs_watcher,
             // Utility commands
             generate_manifest,
+            render_manifest,
             get_env_info,
             save_config,
             // Search commands
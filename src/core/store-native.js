@@ -48,49 +48,53 @@ function listFolders(options = {}) {
 
 /**
  * Create a folder
- * @param {{ path: string, description?: string }} options
+ * @param {{ path: string, description?: string, source?: string }} options
  * @returns {{ rel_path: string, abs_path: string, description: string }}
  */
 function createFolder(options) {
   return handleResult(native.get().createFolder({
     path: options.path,
     description: options.description,
+    source: options.source,
   }));
 }
 
 /**
  * Rename a folder
- * @param {{ path: string, newName: string }} options
+ * @param {{ path: string, newName: string, source?: string }} options
  * @returns {{ old_path: string, new_path: string }}
  */
 function renameFolder(options) {
   return handleResult(native.get().renameFolder({
     path: options.path,
     newName: options.newName,
+    source: options.source,
   }));
 }
 
 /**
  * Move a folder
- * @param {{ path: string, destFolderPath: string }} options
+ * @param {{ path: string, destFolderPath: string, source?: string }} options
  * @returns {{ old_path: string, new_path: string }}
  */
 function moveFolder(options) {
   return handleResult(native.get().moveFolder({
     path: options.path,
     destFolderPath: options.destFolderPath,
+    source: options.source,
   }));
 }
 
 /**
  * Remove a folder
- * @param {{ path: string, force?: boolean }} options
+ * @param {{ path: string, force?: boolean, source?: string }} options
  * @returns {{ removed: string }}
  */
 function removeFolder(options) {
   const result = handleResult(native.get().removeFolder({
     path: options.path,
     force: options.force ?? false,
+    source: options.source,
   }));
   return { removed: result.rel_path };
 }
@@ -109,7 +113,7 @@ function listDocs(options) {
 
 /**
  * Create a document
- * @param {{ folderPath: string, name: string, description?: string }} options
+ * @param {{ folderPath: string, name: string, description?: string, source?: string }} options
  * @returns {{ rel_path: string, abs_path: string, description: string, stable_id: string }}
  */
 function createDoc(options) {
@@ -117,54 +121,59 @@ function createDoc(options) {
     folderPath: options.folderPath,
     name: options.name,
     description: options.description,
+    source: options.source,
   }));
 }
 
 /**
  * Move a document
- * @param {{ docPath: string, destFolderPath: string }} options
+ * @param {{ docPath: string, destFolderPath: string, source?: string }} options
  * @returns {{ old_path: string, new_path: string }}
  */
 function moveDoc(options) {
   return handleResult(native.get().moveDoc({
     docPath: options.docPath,
     destFolderPath: options.destFolderPath,
+    source: options.source,
   }));
 }
 
 /**
  * Rename a document
- * @param {{ docPath: string, newName: string }} options
+ * @param {{ docPath: string, newName: string, source?: string }} options
  * @returns {{ old_path: string, new_path: string }}
  */
 function renameDoc(options) {
   return handleResult(native.get().renameDoc({
     docPath: options.docPath,
     newName: options.newName,
+    source: options.source,
   }));
 }
 
 /**
  * Remove a document
- * @param {{ docPath: string }} options
+ * @param {{ docPath: string, source?: string }} options
  * @returns {{ removed: string }}
  */
 function removeDoc(options) {
   const result = handleResult(native.get().removeDoc({
     docPath: options.docPath,
+    source: options.source,
   }));
   return { removed: result.rel_path };
 }
 
 /**
  * Set document description
- * @param {{ docPath: string, description: string }} options
+ * @param {{ docPath: string, description: string, source?: string }} options
  * @returns {{ rel_path: string, description: string }}
  */
 function setDocDescription(options) {
   return handleResult(native.get().setDocDescription({
     docPath: options.docPath,
     description: options.description,
+    source: options.source,
   }));
 }
 
@@ -186,18 +195,37 @@ function getDocByStableId(stableId) {
   return handleResult(native.get().getDocByStableId(stableId));
 }
 
+/**
+ * Resolve an oc://doc/<stable_id>[?lines=a-b][#anchor] URI
+ * @param {string} uri
+ * @returns {{ uri: string, doc: Doc, anchor?: string, heading_path?: string, start_line: number, end_line: number, content: string }}
+ */
+function resolveUri(uri) {
+  return handleResult(native.get().resolveUri(uri));
+}
+
+/**
+ * Build the oc:// URI of a document or one of its sections
+ * @param {{ docPath: string, heading?: string }} options
+ * @returns {string}
+ */
+function makeUri(options) {
+  return handleResult(native.get().makeUri(options.docPath, options.heading));
+}
+
 /**
  * Get document content
  * @param {string} docPath
+ * @param {string} [source] - Reading client recorded with the access (desktop, cli, mcp, ...)
  * @returns {string}
  */
-function getDocContent(docPath) {
-  return handleResult(native.get().getDocContent(docPath));
+function getDocContent(docPath, source) {
+  return handleResult(native.get().getDocContent(docPath, source));
 }
 
 /**
  * Save document content
- * @param {{ docPath: string, content: string, description?: string }} options
+ * @param {{ docPath: string, content: string, description?: string, source?: string }} options
  * @returns {{ rel_path: string, abs_path: string }}
  */
 function saveDocContent(options) {
@@ -205,6 +233,7 @@ function saveDocContent(options) {
     docPath: options.docPath,
     content: options.content,
     description: options.description,
+    source: options.source,
   }));
 }
 
@@ -220,6 +249,23 @@ function generateManifest(options) {
   }));
 }
 
+/**
+ * Query the mutation audit log, newest first
+ * @param {{ doc?: string, folderPath?: string, source?: string, action?: string, since?: string, until?: string, limit?: number }} options
+ * @returns {Array<AuditEntry>}
+ */
+function auditLog(options = {}) {
+  return handleResult(native.get().auditLog({
+    doc: options.doc,
+    folderPath: options.folderPath,
+    source: options.source,
+    action: options.action,
+    since: options.since,
+    until: options.until,
+    limit: options.limit,
+  }));
+}
+
 module.exports = {
   // Availability checks
   isNativeAvailable,
@@ -240,7 +286,10 @@ module.exports = {
   setDocDescription,
   getDocMeta,
   getDocByStableId,
+  resolveUri,
+  makeUri,
   getDocContent,
   saveDocContent,
   generateManifest,
+  auditLog,
 };
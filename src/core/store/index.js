@@ -40,9 +40,12 @@ module.exports = {
   setDocDescription: nativeStore.setDocDescription,
   getDocMeta: nativeStore.getDocMeta,
   getDocByStableId: nativeStore.getDocByStableId,
+  resolveUri: nativeStore.resolveUri,
+  makeUri: nativeStore.makeUri,
   getDocContent: nativeStore.getDocContent,
   saveDocContent: nativeStore.saveDocContent,
   generateManifest: nativeStore.generateManifest,
+  auditLog: nativeStore.auditLog,
   
   // Constants
   DEFAULT_BASE_ROOT,
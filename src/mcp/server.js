@@ -66,7 +66,7 @@ server.registerTool(
     })
   },
   async ({ folder_path, doc_name, description }) => {
-    const result = store.createDoc({ folderPath: folder_path, name: doc_name, description: description || '' });
+    const result = store.createDoc({ folderPath: folder_path, name: doc_name, description: description || '', source: 'mcp' });
     return toToolResponse(result);
   }
 );
@@ -81,7 +81,7 @@ server.registerTool(
     })
   },
   async ({ doc_path, description }) => {
-    const result = store.setDocDescription({ docPath: doc_path, description });
+    const result = store.setDocDescription({ docPath: doc_path, description, source: 'mcp' });
     return toToolResponse(result);
   }
 );
@@ -166,6 +166,31 @@ server.registerTool(
   }
 );
 
+// ===== P1: oc_resolve_uri =====
+server.registerTool(
+  'oc_resolve_uri',
+  {
+    description: 'Resolve an oc:// URI to the document and the exact content it cites: the whole doc, a section (oc://doc/<stable_id>#<heading-anchor>) or a line range (oc://doc/<stable_id>?lines=10-40).',
+    inputSchema: z.object({
+      uri: z.string().min(1).describe('URI such as oc://doc/<stable_id>#design/goals or oc://doc/<stable_id>?lines=10-40')
+    })
+  },
+  async ({ uri }) => {
+    const resolved = store.resolveUri(uri);
+    return toToolResponse({
+      uri: resolved.uri,
+      stable_id: resolved.doc.stable_id,
+      rel_path: resolved.doc.rel_path,
+      abs_path: resolved.doc.abs_path,
+      description: resolved.doc.description || '',
+      heading_path: resolved.heading_path || null,
+      start_line: resolved.start_line,
+      end_line: resolved.end_line,
+      content: resolved.content
+    });
+  }
+);
+
 // ===== P1: oc_get_link =====
 server.registerTool(
   'oc_get_link',
@@ -173,16 +198,17 @@ server.registerTool(
     description: 'Get the stable link (oc://doc/<stable_id>) for a document. Use this when citing documents.',
     inputSchema: z.object({
       doc_path: z.string().min(1).describe('Document path relative to contexts/, e.g. "Product/opencontext/agentic/guide"'),
-      label: z.string().optional().describe('Optional label for the markdown link (defaults to filename without extension)')
+      label: z.string().optional().describe('Optional label for the markdown link (defaults to filename without extension)'),
+      heading: z.string().optional().describe('Optional heading to cite: a title, a heading path like "Design > Goals", or an anchor')
     })
   },
-  async ({ doc_path, label }) => {
+  async ({ doc_path, label, heading }) => {
     const doc = store.getDocMeta({ docPath: doc_path });
     if (!doc.stable_id) {
       throw new Error('stable_id not found. Run `oc init` to ensure schema migration has completed.');
     }
     const linkLabel = label || path.basename(doc.rel_path).replace(/\.md$/i, '');
-    const url = `oc://doc/${doc.stable_id}`;
+    const url = heading ? store.makeUri({ docPath: doc_path, heading }) : `oc://doc/${doc.stable_id}`;
     return toToolResponse({
       stable_id: doc.stable_id,
       url,
@@ -204,7 +230,7 @@ server.registerTool(
     })
   },
   async ({ folder_path, description }) => {
-    const result = store.createFolder({ path: folder_path, description: description || '' });
+    const result = store.createFolder({ path: folder_path, description: description || '', source: 'mcp' });
     return toToolResponse({
       rel_path: result.rel_path,
       abs_path: result.abs_path,
@@ -57,7 +57,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/folders', (req, res) => {
     try {
       const { path: folderPath, description } = req.body;
-      const result = createFolder({ path: folderPath, description });
+      const result = createFolder({ path: folderPath, description, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -67,7 +67,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/folders/rename', (req, res) => {
     try {
       const { path: folderPath, new_name } = req.body;
-      const result = renameFolder({ path: folderPath, newName: new_name });
+      const result = renameFolder({ path: folderPath, newName: new_name, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -77,7 +77,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/folders/move', (req, res) => {
     try {
       const { path: folderPath, dest_folder_path } = req.body || {};
-      const result = moveFolder({ path: folderPath, destFolderPath: dest_folder_path });
+      const result = moveFolder({ path: folderPath, destFolderPath: dest_folder_path, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -87,7 +87,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/folders/delete', (req, res) => {
     try {
       const { path: folderPath, force } = req.body;
-      const result = removeFolder({ path: folderPath, force });
+      const result = removeFolder({ path: folderPath, force, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -150,7 +150,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/docs', (req, res) => {
     try {
       const { folder_path, name, description } = req.body;
-      const result = createDoc({ folderPath: folder_path, name, description });
+      const result = createDoc({ folderPath: folder_path, name, description, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -160,7 +160,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/docs/move', (req, res) => {
     try {
       const { doc_path, dest_folder_path } = req.body;
-      const result = moveDoc({ docPath: doc_path, destFolderPath: dest_folder_path });
+      const result = moveDoc({ docPath: doc_path, destFolderPath: dest_folder_path, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -170,7 +170,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/docs/rename', (req, res) => {
     try {
       const { doc_path, new_name } = req.body;
-      const result = renameDoc({ docPath: doc_path, newName: new_name });
+      const result = renameDoc({ docPath: doc_path, newName: new_name, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -180,7 +180,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
   app.post('/api/docs/description', (req, res) => {
     try {
       const { doc_path, description } = req.body;
-      const result = setDocDescription({ docPath: doc_path, description });
+      const result = setDocDescription({ docPath: doc_path, description, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -206,7 +206,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
       if (!docPath || typeof content !== 'string') {
         return res.status(400).json({ error: 'Missing path or content' });
       }
-      const result = saveDocContent({ docPath, content, description });
+      const result = saveDocContent({ docPath, content, description, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });
@@ -219,7 +219,7 @@ async function createUiServer({ host = '127.0.0.1', port = 3222 }) {
       if (!docPath) {
         return res.status(400).json({ error: 'Missing path' });
       }
-      const result = removeDoc({ docPath });
+      const result = removeDoc({ docPath, source: 'web' });
       res.json(result);
     } catch (error) {
       res.status(400).json({ error: error.message });